@@ -0,0 +1,206 @@
+use anyhow::{Result, bail};
+use chrono::{Datelike, NaiveDate};
+use semver::Version;
+
+/// Pattern used when `[git] calver_pattern` is unset.
+pub const DEFAULT_PATTERN: &str = "YYYY.MM.PATCH";
+
+/// One dotted component of a CalVer pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Part {
+    /// Full year, e.g. 2026.
+    Year,
+    /// Year without the century, e.g. 26.
+    ShortYear,
+    Month,
+    /// ISO 8601 week of the year.
+    Week,
+    Day,
+    /// Counter reset whenever the date parts change.
+    Patch,
+}
+
+/// A three-part CalVer pattern such as `YYYY.MM.PATCH`. Versions stay valid semver, so
+/// components carry no leading zeros (`2026.1.0`, not `2026.01.0`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    parts: [Part; 3],
+}
+
+impl Pattern {
+    pub fn parse(raw: &str) -> Result<Self> {
+        let tokens: Vec<&str> = raw.split('.').collect();
+        if tokens.len() != 3 {
+            bail!(
+                "calver pattern `{}` must have three parts (e.g. {}) to stay valid semver",
+                raw,
+                DEFAULT_PATTERN
+            );
+        }
+        let mut parts = [Part::Patch; 3];
+        for (slot, token) in parts.iter_mut().zip(&tokens) {
+            *slot = match token.to_ascii_uppercase().as_str() {
+                "YYYY" => Part::Year,
+                "YY" => Part::ShortYear,
+                "MM" => Part::Month,
+                "WW" => Part::Week,
+                "DD" => Part::Day,
+                "PATCH" | "MICRO" => Part::Patch,
+                other => bail!(
+                    "unknown calver token `{}` in `{}` (expected YYYY, YY, MM, WW, DD, or PATCH)",
+                    other,
+                    raw
+                ),
+            };
+        }
+        if parts[..2].contains(&Part::Patch) {
+            bail!("PATCH may only be the last part of `{}`", raw);
+        }
+        if !matches!(parts[0], Part::Year | Part::ShortYear) {
+            bail!("calver pattern `{}` must start with YYYY or YY", raw);
+        }
+        Ok(Pattern { parts })
+    }
+
+    /// The version released on `today` after `current`: the date parts follow the calendar,
+    /// and PATCH counts up within the same period and resets to 0 when it rolls over.
+    pub fn next(&self, current: &Version, today: NaiveDate) -> Result<Version> {
+        // Week numbers belong to the ISO week-numbering year, which differs from the
+        // calendar year for a few days around New Year.
+        let year = if self.parts.contains(&Part::Week) {
+            today.iso_week().year()
+        } else {
+            today.year()
+        };
+        let date = |part: Part| -> u64 {
+            match part {
+                Part::Year => year as u64,
+                Part::ShortYear => (year % 100) as u64,
+                Part::Month => today.month() as u64,
+                Part::Week => today.iso_week().week() as u64,
+                Part::Day => today.day() as u64,
+                Part::Patch => 0,
+            }
+        };
+        let current_parts = [current.major, current.minor, current.patch];
+        let mut next = [0u64; 3];
+        for (index, part) in self.parts.iter().enumerate() {
+            next[index] = date(*part);
+        }
+        let same_period = self
+            .parts
+            .iter()
+            .enumerate()
+            .filter(|(_, part)| **part != Part::Patch)
+            .all(|(index, _)| next[index] == current_parts[index]);
+        if same_period {
+            if !current.pre.is_empty() {
+                // A pre-release of this period's version is finished by releasing it.
+                next[2] = current.patch;
+            } else if self.parts[2] == Part::Patch {
+                next[2] = current.patch + 1;
+            } else {
+                bail!(
+                    "{} was already released for this period; add PATCH to the calver pattern to release again",
+                    current
+                );
+            }
+        }
+        let version = Version::new(next[0], next[1], next[2]);
+        if version < *current {
+            bail!(
+                "calver {} would go backwards from {}; check the clock or the pattern",
+                version,
+                current
+            );
+        }
+        Ok(version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn rolls_over_by_period() {
+        let pattern = Pattern::parse(DEFAULT_PATTERN).unwrap();
+        let current = Version::new(2026, 9, 4);
+        assert_eq!(
+            pattern.next(&current, day(2026, 9, 30)).unwrap(),
+            Version::new(2026, 9, 5)
+        );
+        assert_eq!(
+            pattern.next(&current, day(2026, 10, 1)).unwrap(),
+            Version::new(2026, 10, 0)
+        );
+        assert_eq!(
+            pattern
+                .next(&Version::new(2026, 12, 2), day(2027, 1, 1))
+                .unwrap(),
+            Version::new(2027, 1, 0)
+        );
+        // Switching from semver to calver starts the calendar.
+        assert_eq!(
+            pattern
+                .next(&Version::new(1, 4, 0), day(2026, 3, 2))
+                .unwrap(),
+            Version::new(2026, 3, 0)
+        );
+        assert!(
+            pattern
+                .next(&Version::new(2027, 1, 0), day(2026, 12, 1))
+                .is_err()
+        );
+
+        let daily = Pattern::parse("YY.MM.DD").unwrap();
+        assert_eq!(
+            daily
+                .next(&Version::new(26, 3, 1), day(2026, 3, 2))
+                .unwrap(),
+            Version::new(26, 3, 2)
+        );
+        assert!(
+            daily
+                .next(&Version::new(26, 3, 2), day(2026, 3, 2))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn rejects_patterns_that_are_not_semver() {
+        assert!(Pattern::parse("YYYY.MM.DD.PATCH").is_err());
+        assert!(Pattern::parse("YYYY.PATCH.MM").is_err());
+        assert!(Pattern::parse("MM.YYYY.PATCH").is_err());
+        assert!(Pattern::parse("YYYY.0M.PATCH").is_err());
+        assert!(Pattern::parse("yyyy.ww.patch").is_ok());
+    }
+
+    #[test]
+    fn weekly_patterns_use_the_iso_week_year() {
+        let pattern = Pattern::parse("YYYY.WW.PATCH").unwrap();
+        assert_eq!(
+            pattern
+                .next(&Version::new(2025, 52, 3), day(2025, 12, 29))
+                .unwrap(),
+            Version::new(2026, 1, 0)
+        );
+        assert_eq!(
+            pattern
+                .next(&Version::new(2026, 52, 0), day(2027, 1, 1))
+                .unwrap(),
+            Version::new(2026, 53, 0)
+        );
+        let short = Pattern::parse("YY.WW.PATCH").unwrap();
+        assert_eq!(
+            short
+                .next(&Version::new(25, 52, 0), day(2025, 12, 29))
+                .unwrap(),
+            Version::new(26, 1, 0)
+        );
+    }
+}
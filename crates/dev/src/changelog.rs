@@ -0,0 +1,740 @@
+use std::collections::HashSet;
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use serde_json::Value;
+
+/// A commit subject split into its conventional-commit parts. Subjects that do not
+/// follow the convention keep `kind` empty and the whole subject as `description`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commit {
+    pub kind: String,
+    pub scope: Option<String>,
+    pub breaking: bool,
+    pub description: String,
+    /// Markdown link appended to the entry, e.g. `[#12](https://...)` for a PR.
+    pub link: Option<String>,
+    /// Commit (or PR merge commit) the entry comes from.
+    pub sha: Option<String>,
+    pub author: Option<String>,
+    /// PR number, from the PR itself or a `(#12)` / `Merge pull request #12` subject.
+    pub pr: Option<u64>,
+}
+
+/// Changelog sections in output order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    Breaking,
+    Features,
+    Fixes,
+    Other,
+}
+
+impl Section {
+    pub const ALL: [Section; 4] = [
+        Section::Breaking,
+        Section::Features,
+        Section::Fixes,
+        Section::Other,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Section::Breaking => "Breaking Changes",
+            Section::Features => "Features",
+            Section::Fixes => "Fixes",
+            Section::Other => "Other Changes",
+        }
+    }
+}
+
+/// Keep a Changelog categories in the order the format lists them.
+const CATEGORIES: [&str; 6] = [
+    "Added",
+    "Changed",
+    "Deprecated",
+    "Removed",
+    "Fixed",
+    "Security",
+];
+
+/// First line of a new `CHANGELOG.md`.
+pub const HEADER: &str = "# Changelog\n\nAll notable changes to this project are documented in this file.\n\n\
+                          The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.1.0/).\n";
+
+impl Commit {
+    /// Parse `type(scope)!: description`; a `BREAKING CHANGE:` (or `BREAKING-CHANGE:`)
+    /// footer in `body` also marks the commit as breaking.
+    pub fn parse(subject: &str, body: &str) -> Self {
+        let subject = subject.trim();
+        let footer_breaking = body.lines().any(|line| {
+            line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:")
+        });
+        let plain = || Commit {
+            kind: String::new(),
+            scope: None,
+            breaking: footer_breaking,
+            description: subject.to_owned(),
+            link: None,
+            sha: None,
+            author: None,
+            pr: None,
+        };
+
+        let Some((head, description)) = subject.split_once(": ") else {
+            return plain();
+        };
+        let (head, bang) = match head.strip_suffix('!') {
+            Some(head) => (head, true),
+            None => (head, false),
+        };
+        let (kind, scope) = match head.split_once('(') {
+            Some((kind, rest)) => match rest.strip_suffix(')') {
+                Some(scope) if !scope.is_empty() => (kind, Some(scope.to_owned())),
+                _ => return plain(),
+            },
+            None => (head, None),
+        };
+        if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return plain();
+        }
+
+        Commit {
+            kind: kind.to_ascii_lowercase(),
+            scope,
+            breaking: bang || footer_breaking,
+            description: description.trim().to_owned(),
+            link: None,
+            sha: None,
+            author: None,
+            pr: None,
+        }
+    }
+
+    pub fn section(&self) -> Section {
+        if self.breaking {
+            return Section::Breaking;
+        }
+        match self.kind.as_str() {
+            "feat" => Section::Features,
+            "fix" => Section::Fixes,
+            _ => Section::Other,
+        }
+    }
+
+    /// Keep a Changelog category, or `None` for housekeeping (`chore`, `ci`, `test`,
+    /// `style`, `build`) that stays out of the changelog file.
+    pub fn category(&self) -> Option<&'static str> {
+        if self.breaking {
+            return Some("Changed");
+        }
+        match self.kind.as_str() {
+            "feat" => Some("Added"),
+            "fix" => Some("Fixed"),
+            "chore" | "ci" | "test" | "style" | "build" => None,
+            _ => Some("Changed"),
+        }
+    }
+
+    /// Bullet text: `**scope:** description`, the same in every section.
+    pub fn entry(&self) -> String {
+        let mut out = String::new();
+        if let Some(scope) = &self.scope {
+            out.push_str(&format!("**{}:** ", scope));
+        }
+        out.push_str(&self.description);
+        if let Some(link) = &self.link {
+            out.push_str(&format!(" ({})", link));
+        }
+        out
+    }
+
+    /// Bullet text for the changelog file, where the category heading stands in for
+    /// the type and breaking changes are called out inline.
+    pub fn release_entry(&self) -> String {
+        let text = self.entry();
+        if self.breaking {
+            format!("**Breaking:** {}", text)
+        } else {
+            text
+        }
+    }
+}
+
+/// Commits in `range` (newest first), parsed as conventional commits. Non-empty
+/// `paths` limit the log to commits touching them.
+pub fn collect(range: &str, paths: &[&str]) -> Result<Vec<Commit>> {
+    Ok(log(range, paths, &["%H", "%an", "%s", "%b"])?
+        .into_iter()
+        .map(|fields| {
+            let mut commit = Commit::parse(&fields[2], &fields[3]);
+            commit.pr = pr_number(&fields[2]);
+            commit.sha = Some(fields[0].clone());
+            commit.author = Some(fields[1].clone());
+            commit
+        })
+        .collect())
+}
+
+/// Raw `(subject, body)` of each commit in `range`, newest first.
+pub fn messages(range: &str, paths: &[&str]) -> Result<Vec<(String, String)>> {
+    Ok(log(range, paths, &["%s", "%b"])?
+        .into_iter()
+        .map(|mut fields| {
+            let body = fields.pop().unwrap_or_default();
+            (fields.pop().unwrap_or_default(), body)
+        })
+        .collect())
+}
+
+/// `git log` placeholders (`%s`, `%H`, ...) for each commit in `range`, newest first,
+/// trimmed; commits with an empty subject are skipped. `%b`, if any, must come last.
+fn log(range: &str, paths: &[&str], placeholders: &[&str]) -> Result<Vec<Vec<String>>> {
+    let mut cmd = Command::new("git");
+    cmd.args([
+        "log",
+        range,
+        &format!("--pretty=format:{}%x1e", placeholders.join("%x1f")),
+    ]);
+    if !paths.is_empty() {
+        cmd.arg("--").args(paths);
+    }
+    let output = cmd
+        .output()
+        .with_context(|| format!("collecting commits for {}", range))?;
+    if !output.status.success() {
+        let code = output.status.code().unwrap_or(-1);
+        bail!("git log failed with status {}", code);
+    }
+    let subject = placeholders.iter().position(|field| *field == "%s");
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split('\x1e')
+        .map(|record| {
+            let mut fields: Vec<String> = record
+                .splitn(placeholders.len(), '\x1f')
+                .map(|field| field.trim().to_owned())
+                .collect();
+            fields.resize(placeholders.len(), String::new());
+            fields
+        })
+        .filter(|fields| subject.is_none_or(|index| !fields[index].is_empty()))
+        .collect())
+}
+
+/// PR number from a squash-merge `subject (#12)` or a `Merge pull request #12 from ...`
+/// subject.
+fn pr_number(subject: &str) -> Option<u64> {
+    let digits = match subject.strip_prefix("Merge pull request #") {
+        Some(rest) => rest.split(' ').next()?,
+        None => subject.trim_end().strip_suffix(')')?.rsplit_once("(#")?.1,
+    };
+    digits.parse().ok()
+}
+
+/// PRs merged into `base` whose merge commit falls in `range` (restricted to `paths`
+/// when given), as entries built from PR titles and labels and linked to the PR.
+pub fn collect_merged_prs(range: &str, base: &str, paths: &[&str]) -> Result<Vec<Commit>> {
+    let mut cmd = Command::new("git");
+    cmd.args(["rev-list", range]);
+    if !paths.is_empty() {
+        cmd.arg("--").args(paths);
+    }
+    let output = cmd
+        .output()
+        .with_context(|| format!("listing commits for {}", range))?;
+    if !output.status.success() {
+        let code = output.status.code().unwrap_or(-1);
+        bail!("git rev-list failed with status {}", code);
+    }
+    let in_range: HashSet<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_owned)
+        .collect();
+
+    let prs = fetch_all(|limit| merged_prs(base, limit))?;
+    Ok(from_pull_requests(&prs, &in_range))
+}
+
+/// `gh pr list` has no offset, so re-run `fetch` with a larger limit until it returns
+/// fewer PRs than asked for; a full page may have cut off older ones.
+fn fetch_all(mut fetch: impl FnMut(usize) -> Result<Value>) -> Result<Value> {
+    let mut limit = 500;
+    loop {
+        let prs = fetch(limit)?;
+        if prs.as_array().map_or(0, Vec::len) < limit {
+            return Ok(prs);
+        }
+        limit *= 4;
+    }
+}
+
+fn merged_prs(base: &str, limit: usize) -> Result<Value> {
+    let output = Command::new("gh")
+        .args([
+            "pr",
+            "list",
+            "--state",
+            "merged",
+            "--base",
+            base,
+            "--limit",
+            &limit.to_string(),
+            "--json",
+            "number,title,labels,mergeCommit,url,author",
+        ])
+        .output()
+        .context("running gh pr list (the `prs` changelog source requires the GitHub CLI)")?;
+    if !output.status.success() {
+        bail!(
+            "gh pr list failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    serde_json::from_slice(&output.stdout).context("parsing gh output")
+}
+
+fn from_pull_requests(prs: &Value, in_range: &HashSet<String>) -> Vec<Commit> {
+    prs.as_array()
+        .into_iter()
+        .flatten()
+        .filter(|pr| {
+            pr["mergeCommit"]["oid"]
+                .as_str()
+                .is_some_and(|oid| in_range.contains(oid))
+        })
+        .map(|pr| {
+            let labels: Vec<String> = pr["labels"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|label| label["name"].as_str())
+                .map(str::to_ascii_lowercase)
+                .collect();
+            let has = |names: &[&str]| labels.iter().any(|label| names.contains(&label.as_str()));
+            let mut entry = Commit::parse(pr["title"].as_str().unwrap_or(""), "");
+            // Labels fill in what a free-form PR title does not say.
+            if has(&["breaking", "breaking change", "breaking-change"]) {
+                entry.breaking = true;
+            }
+            if entry.kind.is_empty() {
+                if has(&["bug", "fix", "bugfix"]) {
+                    entry.kind = "fix".to_string();
+                } else if has(&["feature", "enhancement", "feat"]) {
+                    entry.kind = "feat".to_string();
+                }
+            }
+            let number = pr["number"].as_u64().unwrap_or_default();
+            entry.link = Some(match pr["url"].as_str() {
+                Some(url) => format!("[#{}]({})", number, url),
+                None => format!("#{}", number),
+            });
+            entry.pr = Some(number);
+            entry.sha = pr["mergeCommit"]["oid"].as_str().map(str::to_owned);
+            entry.author = pr["author"]["login"].as_str().map(str::to_owned);
+            entry
+        })
+        .collect()
+}
+
+/// `dev version changelog --format json` output.
+#[derive(Serialize)]
+struct JsonChangelog<'a> {
+    range: &'a str,
+    entries: Vec<JsonEntry<'a>>,
+}
+
+/// One entry of `dev version changelog --format json`.
+#[derive(Serialize)]
+struct JsonEntry<'a> {
+    #[serde(rename = "type")]
+    kind: Option<&'a str>,
+    scope: Option<&'a str>,
+    subject: &'a str,
+    breaking: bool,
+    section: &'static str,
+    sha: Option<&'a str>,
+    author: Option<&'a str>,
+    pr: Option<u64>,
+}
+
+/// `{"range": ..., "entries": [...]}` for release dashboards and bots, entries newest
+/// first with the same section as the Markdown output.
+pub fn render_json(range: &str, commits: &[Commit]) -> Result<String> {
+    let entries: Vec<JsonEntry> = commits
+        .iter()
+        .map(|commit| JsonEntry {
+            kind: (!commit.kind.is_empty()).then_some(commit.kind.as_str()),
+            scope: commit.scope.as_deref(),
+            subject: &commit.description,
+            breaking: commit.breaking,
+            section: commit.section().title(),
+            sha: commit.sha.as_deref(),
+            author: commit.author.as_deref(),
+            pr: commit.pr,
+        })
+        .collect();
+    serde_json::to_string_pretty(&JsonChangelog { range, entries }).context("serializing changelog")
+}
+
+/// Markdown with one `###` heading per non-empty section.
+pub fn render(commits: &[Commit]) -> String {
+    let mut out = String::new();
+    for section in Section::ALL {
+        let entries: Vec<String> = commits
+            .iter()
+            .filter(|commit| commit.section() == section)
+            .map(Commit::entry)
+            .collect();
+        if entries.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("### {}\n\n", section.title()));
+        for entry in entries {
+            out.push_str(&format!("- {}\n", entry));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// A Keep a Changelog release body: hand-written `notes` (the old `## [Unreleased]`
+/// content) merged with `commits` under `### Added`, `### Changed`, `### Fixed`, ...
+pub fn render_release(notes: &str, commits: &[Commit]) -> String {
+    let mut preamble = Vec::new();
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+    for line in notes.lines() {
+        if let Some(title) = line.strip_prefix("### ") {
+            groups.push((title.trim().to_owned(), Vec::new()));
+        } else if let Some((_, lines)) = groups.last_mut() {
+            if !line.trim().is_empty() {
+                lines.push(line.to_owned());
+            }
+        } else {
+            preamble.push(line);
+        }
+    }
+    for category in CATEGORIES {
+        let entries: Vec<String> = commits
+            .iter()
+            .filter(|commit| commit.category() == Some(category))
+            .map(|commit| format!("- {}", commit.release_entry()))
+            .collect();
+        if entries.is_empty() {
+            continue;
+        }
+        match groups
+            .iter_mut()
+            .find(|(title, _)| title.eq_ignore_ascii_case(category))
+        {
+            Some((_, lines)) => lines.extend(entries),
+            None => groups.push((category.to_owned(), entries)),
+        }
+    }
+    groups.sort_by_key(|(title, _)| {
+        CATEGORIES
+            .iter()
+            .position(|category| category.eq_ignore_ascii_case(title))
+            .unwrap_or(CATEGORIES.len())
+    });
+
+    let mut out = String::new();
+    let preamble = preamble.join("\n");
+    if !preamble.trim().is_empty() {
+        out.push_str(preamble.trim());
+        out.push_str("\n\n");
+    }
+    for (title, lines) in groups.iter().filter(|(_, lines)| !lines.is_empty()) {
+        out.push_str(&format!("### {}\n\n{}\n\n", title, lines.join("\n")));
+    }
+    if out.is_empty() {
+        out.push_str("No notable changes.\n\n");
+    }
+    out
+}
+
+/// Move the `## [Unreleased]` notes of `content` into a new `## {heading}` release
+/// rendered with `commits`, leaving an empty Unreleased section above it. `links` set
+/// (or replace) link reference definitions, e.g. `[Unreleased]: <compare url>`.
+pub fn add_release(
+    content: &str,
+    heading: &str,
+    commits: &[Commit],
+    links: &[(String, String)],
+) -> String {
+    let (body, references) = split_references(content);
+    let lines: Vec<&str> = body.lines().collect();
+    let is_release = |line: &&str| line.starts_with("## ");
+    let unreleased = lines.iter().position(|line| {
+        is_release(line)
+            && line[3..]
+                .trim()
+                .trim_matches(['[', ']'])
+                .eq_ignore_ascii_case("unreleased")
+    });
+    let (before, notes, after) = match unreleased {
+        Some(index) => {
+            let end = lines[index + 1..]
+                .iter()
+                .position(is_release)
+                .map_or(lines.len(), |offset| index + 1 + offset);
+            (&lines[..index], &lines[index + 1..end], &lines[end..])
+        }
+        None => {
+            let first = lines.iter().position(is_release).unwrap_or(lines.len());
+            (&lines[..first], &[][..], &lines[first..])
+        }
+    };
+
+    let mut out = before.join("\n").trim_end().to_owned();
+    if out.is_empty() {
+        out.push_str(HEADER.trim_end());
+    }
+    out.push_str("\n\n## [Unreleased]\n\n");
+    out.push_str(&format!("## {}\n\n", heading));
+    out.push_str(&render_release(&notes.join("\n"), commits));
+    let after = after.join("\n");
+    if !after.trim().is_empty() {
+        out.push_str(after.trim());
+        out.push('\n');
+    }
+    let mut definitions: Vec<String> = links
+        .iter()
+        .map(|(label, url)| format!("[{}]: {}", label, url))
+        .collect();
+    definitions.extend(references.into_iter().filter(|line| {
+        !links
+            .iter()
+            .any(|(label, _)| line.starts_with(&format!("[{}]:", label)))
+    }));
+    let mut out = out.trim_end().to_owned();
+    if !definitions.is_empty() {
+        out.push_str(&format!("\n\n{}", definitions.join("\n")));
+    }
+    out.push('\n');
+    out
+}
+
+/// `content` without the release section headed by `tag` and its `[tag]:` link, or
+/// `None` when there is no such section. `links` replace existing definitions with the
+/// same label (the `[Unreleased]` comparison, which started at `tag`).
+pub fn remove_release(content: &str, tag: &str, links: &[(String, String)]) -> Option<String> {
+    let (body, references) = split_references(content);
+    let lines: Vec<&str> = body.lines().collect();
+    let start = lines.iter().position(|line| {
+        line.starts_with("## ") && line.split([' ', '[', ']']).any(|token| token == tag)
+    })?;
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| line.starts_with("## "))
+        .map_or(lines.len(), |offset| start + 1 + offset);
+
+    let mut out = lines[..start].join("\n").trim_end().to_owned();
+    let rest = lines[end..].join("\n");
+    if !rest.trim().is_empty() {
+        out.push_str("\n\n");
+        out.push_str(rest.trim());
+    }
+    let own = format!("[{}]:", tag);
+    let definitions: Vec<String> = references
+        .into_iter()
+        .filter(|line| !line.starts_with(&own))
+        .map(|line| {
+            links
+                .iter()
+                .find(|(label, _)| line.starts_with(&format!("[{}]:", label)))
+                .map_or(line, |(label, url)| format!("[{}]: {}", label, url))
+        })
+        .collect();
+    if !definitions.is_empty() {
+        out.push_str(&format!("\n\n{}", definitions.join("\n")));
+    }
+    out.push('\n');
+    Some(out)
+}
+
+/// Split the trailing block of link reference definitions (`[label]: url`) off `content`.
+fn split_references(content: &str) -> (&str, Vec<String>) {
+    let lines: Vec<&str> = content.trim_end().lines().collect();
+    let is_reference = |line: &str| {
+        line.starts_with('[')
+            && line
+                .split_once("]: ")
+                .is_some_and(|(label, _)| !label.contains(']'))
+    };
+    let start = lines
+        .iter()
+        .rposition(|line| !line.trim().is_empty() && !is_reference(line))
+        .map_or(0, |index| index + 1);
+    let references: Vec<String> = lines[start..]
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.to_string())
+        .collect();
+    if references.is_empty() {
+        return (content, references);
+    }
+    let cut: usize = lines[..start].iter().map(|line| line.len() + 1).sum();
+    (&content[..cut.min(content.len())], references)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_conventional_subjects() {
+        let commit = Commit::parse("feat(api)!: drop v1 routes", "");
+        assert_eq!(commit.kind, "feat");
+        assert_eq!(commit.scope.as_deref(), Some("api"));
+        assert!(commit.breaking);
+        assert_eq!(commit.description, "drop v1 routes");
+
+        assert!(Commit::parse("fix: typo", "details\n\nBREAKING CHANGE: renamed flag").breaking);
+        let plain = Commit::parse("Merge branch 'main'", "");
+        assert_eq!(plain.kind, "");
+        assert_eq!(plain.description, "Merge branch 'main'");
+        assert_eq!(Commit::parse("feat(): empty scope", "").kind, "");
+    }
+
+    #[test]
+    fn render_groups_into_sections() {
+        let commits = vec![
+            Commit::parse("fix(env): keep comments", ""),
+            Commit::parse("feat: add dev env gen", ""),
+            Commit::parse("chore(ci): bump actions", ""),
+            Commit::parse("feat(config)!: require version", ""),
+            Commit::parse("Update README", ""),
+        ];
+        assert_eq!(
+            render(&commits),
+            "### Breaking Changes\n\n- **config:** require version\n\n\
+             ### Features\n\n- add dev env gen\n\n\
+             ### Fixes\n\n- **env:** keep comments\n\n\
+             ### Other Changes\n\n- **ci:** bump actions\n- Update README\n\n"
+        );
+        assert_eq!(render(&[]), "");
+    }
+
+    #[test]
+    fn merged_prs_are_fetched_past_a_full_page() {
+        let mut limits = Vec::new();
+        let prs = fetch_all(|limit| {
+            limits.push(limit);
+            Ok(Value::Array(vec![Value::Null; limit.min(2500)]))
+        })
+        .unwrap();
+        assert_eq!(limits, [500, 2000, 8000]);
+        assert_eq!(prs.as_array().unwrap().len(), 2500);
+    }
+
+    #[test]
+    fn pull_requests_use_titles_labels_and_links() {
+        let prs: Value = serde_json::from_str(
+            r#"[
+                {"number": 12, "title": "Handle empty config", "url": "https://github.com/o/r/pull/12",
+                 "labels": [{"name": "Bug"}], "mergeCommit": {"oid": "aaa"}, "author": {"login": "ada"}},
+                {"number": 13, "title": "feat(cli): add --json", "url": "https://github.com/o/r/pull/13",
+                 "labels": [{"name": "breaking"}], "mergeCommit": {"oid": "bbb"}},
+                {"number": 9, "title": "feat: older release", "url": "https://github.com/o/r/pull/9",
+                 "labels": [], "mergeCommit": {"oid": "zzz"}}
+            ]"#,
+        )
+        .unwrap();
+        let in_range: HashSet<String> = ["aaa", "bbb"].map(String::from).into();
+        let entries = from_pull_requests(&prs, &in_range);
+        assert_eq!(
+            render(&entries),
+            "### Breaking Changes\n\n- **cli:** add --json ([#13](https://github.com/o/r/pull/13))\n\n\
+             ### Fixes\n\n- Handle empty config ([#12](https://github.com/o/r/pull/12))\n\n"
+        );
+        assert_eq!(
+            (
+                entries[0].pr,
+                entries[0].sha.as_deref(),
+                entries[0].author.as_deref()
+            ),
+            (Some(12), Some("aaa"), Some("ada"))
+        );
+    }
+
+    #[test]
+    fn json_entries_carry_commit_metadata() {
+        assert_eq!(pr_number("feat: add --json (#42)"), Some(42));
+        assert_eq!(pr_number("Merge pull request #7 from o/feature"), Some(7));
+        assert_eq!(pr_number("fix: handle (#x)"), None);
+
+        let mut commit = Commit::parse("feat(cli)!: add --json (#42)", "");
+        commit.sha = Some("abc123".into());
+        commit.author = Some("Ada".into());
+        commit.pr = Some(42);
+        let plain = Commit::parse("Update README", "");
+        let json: Value =
+            serde_json::from_str(&render_json("v1.0.0..HEAD", &[commit, plain]).unwrap()).unwrap();
+        assert_eq!(json["range"], "v1.0.0..HEAD");
+        let entry = &json["entries"][0];
+        assert_eq!(entry["type"], "feat");
+        assert_eq!(entry["scope"], "cli");
+        assert_eq!(entry["subject"], "add --json (#42)");
+        assert_eq!(entry["breaking"], true);
+        assert_eq!(entry["section"], "Breaking Changes");
+        assert_eq!(entry["sha"], "abc123");
+        assert_eq!(entry["author"], "Ada");
+        assert_eq!(entry["pr"], 42);
+        assert!(json["entries"][1]["type"].is_null());
+    }
+
+    #[test]
+    fn release_moves_unreleased_notes_and_links() {
+        let content = "# Changelog\n\n## [Unreleased]\n\n### Fixed\n\n- Hand-written fix\n\n\
+                       ## [v1.0.0] - 2026-01-01\n\n- first\n\n\
+                       [Unreleased]: https://github.com/o/r/compare/v1.0.0...HEAD\n\
+                       [v1.0.0]: https://github.com/o/r/releases/tag/v1.0.0\n";
+        let commits = vec![
+            Commit::parse("fix(env): keep comments", ""),
+            Commit::parse("feat: add dev env gen", ""),
+            Commit::parse("chore(ci): bump actions", ""),
+            Commit::parse("refactor(config)!: require version", ""),
+        ];
+        let links = vec![
+            (
+                "Unreleased".to_string(),
+                "https://github.com/o/r/compare/v1.1.0...HEAD".to_string(),
+            ),
+            (
+                "v1.1.0".to_string(),
+                "https://github.com/o/r/compare/v1.0.0...v1.1.0".to_string(),
+            ),
+        ];
+        assert_eq!(
+            add_release(content, "[v1.1.0] - 2026-02-01", &commits, &links),
+            "# Changelog\n\n## [Unreleased]\n\n## [v1.1.0] - 2026-02-01\n\n\
+             ### Added\n\n- add dev env gen\n\n\
+             ### Changed\n\n- **Breaking:** **config:** require version\n\n\
+             ### Fixed\n\n- Hand-written fix\n- **env:** keep comments\n\n\
+             ## [v1.0.0] - 2026-01-01\n\n- first\n\n\
+             [Unreleased]: https://github.com/o/r/compare/v1.1.0...HEAD\n\
+             [v1.1.0]: https://github.com/o/r/compare/v1.0.0...v1.1.0\n\
+             [v1.0.0]: https://github.com/o/r/releases/tag/v1.0.0\n"
+        );
+
+        let released = add_release(content, "[v1.1.0] - 2026-02-01", &commits, &links);
+        let restore = vec![(
+            "Unreleased".to_string(),
+            "https://github.com/o/r/compare/v1.0.0...HEAD".to_string(),
+        )];
+        assert_eq!(
+            remove_release(&released, "v1.1.0", &restore).unwrap(),
+            "# Changelog\n\n## [Unreleased]\n\n## [v1.0.0] - 2026-01-01\n\n- first\n\n\
+             [Unreleased]: https://github.com/o/r/compare/v1.0.0...HEAD\n\
+             [v1.0.0]: https://github.com/o/r/releases/tag/v1.0.0\n"
+        );
+        assert_eq!(remove_release(&released, "v2.0.0", &restore), None);
+
+        let fresh = add_release("", "[v0.1.0] - 2026-02-01", &[], &[]);
+        assert!(fresh.starts_with(HEADER));
+        assert!(
+            fresh.ends_with("## [Unreleased]\n\n## [v0.1.0] - 2026-02-01\n\nNo notable changes.\n")
+        );
+    }
+}
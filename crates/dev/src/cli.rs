@@ -12,8 +12,17 @@ pub struct Cli {
     pub file: Option<PathBuf>,
     #[arg(long = "project", global = true)]
     pub project: Option<String>,
+    /// Run the verb or task in every configured project (same as `dev workspace run`)
+    #[arg(long = "all-projects", global = true, conflicts_with = "project")]
+    pub all_projects: bool,
     #[arg(short = 'l', long = "language")]
     pub language: Option<String>,
+    /// Apply `[profiles.<name>]` overrides (tasks, env requirements, git branches, ...)
+    #[arg(long = "profile", global = true)]
+    pub profile: Option<String>,
+    /// Override a `[vars]` entry for this run (repeatable)
+    #[arg(long = "set", global = true, value_name = "KEY=VALUE", value_parser = crate::vars::parse_assignment)]
+    pub set: Vec<(String, String)>,
     #[arg(short = 'n', long = "dry-run", global = true)]
     pub dry_run: bool,
     #[arg(short = 'v', long = "verbose", action = clap::ArgAction::Count)]
@@ -47,6 +56,16 @@ pub enum Command {
     All {
         verb: Verb,
     },
+    /// Manage monorepo projects in `[projects]`.
+    Project {
+        #[command(subcommand)]
+        command: ProjectCommand,
+    },
+    /// Run a verb or task across every project in `[projects]`.
+    Workspace {
+        #[command(subcommand)]
+        command: WorkspaceCommand,
+    },
     /// Install tooling and scaffolds for a language (defaults to configured language).
     Install(InstallArgs),
     /// Manage language defaults.
@@ -82,29 +101,71 @@ pub enum Command {
         #[arg(long = "no-deps", global = true)]
         no_deps: bool,
     },
-    /// Generate a Markdown code review overlay from git diffs.
+    /// Generate a Markdown, HTML or JSON code review overlay from git diffs.
     Review {
-        /// Path to the markdown file to write
+        /// Path to the report to write (default: `[review] output`, else review-report.md,
+        /// .html or .json)
         #[arg(long = "output")]
         output: Option<PathBuf>,
+        /// `md` for fenced overlays, `html` for a standalone page with highlighted,
+        /// collapsible files, `json` for per-file hunk records
+        /// (default: `[review] format`, else md)
+        #[arg(long, value_enum)]
+        format: Option<ReviewFormat>,
         /// Include unstaged working tree changes in the report
         #[arg(long = "include-working")]
         include_working: bool,
-        /// Compare current branch against main instead of showing staged changes
+        /// Leave unstaged changes out even when `[review] include_working` is set
+        #[arg(long = "no-include-working", conflicts_with = "include_working")]
+        no_include_working: bool,
+        /// Include new files git does not track yet, shown as fully added
+        #[arg(long = "include-untracked", conflicts_with = "commits")]
+        include_untracked: bool,
+        /// Annotate each hunk with `git blame` for its lines (last author, commit age)
+        #[arg(long)]
+        blame: bool,
+        /// Embed only N unchanged lines around each hunk instead of the whole file
+        #[arg(long, value_name = "N", conflicts_with = "diff_only")]
+        context: Option<usize>,
+        /// Show only the hunks, without the rest of the file (same as `--context 0`)
+        #[arg(long = "diff-only")]
+        diff_only: bool,
+        /// Compare current branch against the default branch (`[review] base`, else
+        /// origin/HEAD, else main) instead of showing staged changes
         #[arg(long = "main")]
         main: bool,
+        /// Compare current branch against REF (a branch, tag or SHA) instead of showing
+        /// staged changes
+        #[arg(long, value_name = "REF", conflicts_with_all = ["include_working", "main"])]
+        base: Option<String>,
+        /// Review each commit in RANGE (e.g. `main..HEAD`) as its own section, oldest first
+        #[arg(long, value_name = "RANGE", conflicts_with_all = ["include_working", "main", "base"])]
+        commits: Option<String>,
+        /// Split the Markdown report into numbered files (`review-report.1.md`, ...) of
+        /// at most N estimated tokens each, breaking between files where possible
+        #[arg(long, value_name = "N")]
+        max_tokens: Option<usize>,
+        /// Wrap the Markdown report in an LLM prompt template; `{{review}}` in the
+        /// template marks where the report goes (defaults to `[review] prompt`)
+        #[arg(long, value_name = "TEMPLATE")]
+        prompt: Option<PathBuf>,
+        /// Also copy the report to the system clipboard (wl-copy, xclip, xsel, pbcopy or clip)
+        #[arg(long, conflicts_with = "max_tokens")]
+        clipboard: bool,
     },
     /// Generate a directory structure map with file contents (for LLM context).
     Walk {
         /// Directory to map (default: current directory)
         #[arg(default_value = ".")]
         directory: PathBuf,
-        /// Output file path (default: manifest.md)
-        #[arg(short = 'o', long = "output", default_value = "manifest.md")]
-        output: PathBuf,
-        /// Output format
-        #[arg(long = "format", default_value = "markdown")]
-        format: String,
+        /// Output file path (default: manifest.md, manifest.json or manifest.yaml), or `-`
+        /// for stdout
+        #[arg(short = 'o', long = "output")]
+        output: Option<PathBuf>,
+        /// Output format: `markdown` for LLM context, `json` or `yaml` for a structured
+        /// tree of paths, sizes, modification times and contents
+        #[arg(long = "format", value_enum, default_value_t = WalkFormat::Markdown)]
+        format: WalkFormat,
         /// Maximum depth to traverse
         #[arg(long = "max-depth", default_value = "10")]
         max_depth: u32,
@@ -117,6 +178,53 @@ pub enum Command {
         /// Include hidden files
         #[arg(long = "include-hidden")]
         include_hidden: bool,
+        /// Number the lines of embedded file contents, so edits can cite them
+        #[arg(long = "line-numbers")]
+        line_numbers: bool,
+        /// Only list files matching this path glob (`src/**`, `*.rs`); repeatable
+        #[arg(long = "include", value_name = "GLOB")]
+        include: Vec<String>,
+        /// Leave out files and directories matching this path glob (`tests/**`); repeatable
+        #[arg(long = "exclude", value_name = "GLOB")]
+        exclude: Vec<String>,
+        /// Only list files added or changed since a previous manifest (per the hashes
+        /// `--record-hashes` kept for it), or since a git ref (committed, staged, modified and
+        /// untracked files)
+        #[arg(long = "since", value_name = "MANIFEST|REF")]
+        since: Option<String>,
+        /// Record each file's content hash next to the manifest (`<output>.hashes.json`), for
+        /// a later `--since <output>`
+        #[arg(long = "record-hashes")]
+        record_hashes: bool,
+        /// Also copy the manifest to the system clipboard
+        #[arg(long = "clipboard", conflicts_with = "split_size")]
+        clipboard: bool,
+        /// Keep the manifest within N estimated tokens by truncating or omitting the
+        /// contents of the largest files, listing what was cut
+        #[arg(long, value_name = "N")]
+        max_tokens: Option<usize>,
+        /// Write the manifest as numbered parts of at most this many bytes (`200k`) or
+        /// tokens (`8000t`), plus an index file listing them
+        #[arg(long, value_name = "BYTES|TOKENS", value_parser = crate::walk::SplitSize::parse)]
+        split_size: Option<crate::walk::SplitSize>,
+    },
+    /// Diagnose common project setup problems and optionally fix them.
+    Doctor(DoctorArgs),
+    /// Compare installed tool versions (node, pnpm, uv, rustc) with `.dev/lock.toml`.
+    CheckTools {
+        /// Record the current versions instead of checking them
+        #[arg(long = "update", default_value_t = false)]
+        update: bool,
+    },
+    /// Print a shell completion script for subcommands and flags (no config needed).
+    Completions {
+        #[arg(value_enum)]
+        shell: CompletionShell,
+    },
+    /// Diagnostics for the dev CLI itself.
+    Debug {
+        #[command(subcommand)]
+        command: DebugCommand,
     },
     /// Docker helpers for generating base/project containers.
     Docker {
@@ -127,6 +235,46 @@ pub enum Command {
     External(Vec<String>),
 }
 
+#[derive(Args, Debug)]
+pub struct DoctorArgs {
+    /// Apply fixes for detected problems and re-verify
+    #[arg(long = "fix", default_value_t = false)]
+    pub fix: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ProjectCommand {
+    /// Add a project, or update its chdir/language if it already exists.
+    Add {
+        name: String,
+        /// Directory relative to the repo root (where `.dev/` lives)
+        #[arg(long = "chdir")]
+        chdir: Option<String>,
+        #[arg(long = "language")]
+        language: Option<String>,
+    },
+    /// List configured projects.
+    List,
+    /// Remove a project.
+    Rm {
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum WorkspaceCommand {
+    /// Run a verb (fmt, lint, type, test, ...) or task in each project and report a pass/fail matrix.
+    Run {
+        target: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DebugCommand {
+    /// Report how long each startup phase takes (config discovery, parsing, env lookup).
+    Timings,
+}
+
 #[derive(Subcommand, Debug)]
 pub enum DockerCommand {
     /// Generate docker/Dockerfile.core, docker-compose.yml, and .env for the current project.
@@ -188,13 +336,13 @@ pub struct DockerInitArgs {
     #[arg(long = "force", default_value_t = false)]
     pub force: bool,
 
-    /// Base image to use in docker/Dockerfile.core
-    #[arg(long = "base-image", default_value = "nvcr.io/nvidia/pytorch:25.09-py3")]
-    pub base_image: String,
+    /// Base image to use in docker/Dockerfile.core (default: `[vars] base_image`, then nvcr.io/nvidia/pytorch:25.09-py3)
+    #[arg(long = "base-image")]
+    pub base_image: Option<String>,
 
-    /// Compose service name (default: core)
-    #[arg(long = "service", default_value = "core")]
-    pub service: String,
+    /// Compose service name (default: `[vars] service`, then core)
+    #[arg(long = "service")]
+    pub service: Option<String>,
 }
 
 /// Shared verb enumeration for consistent handling across languages.
@@ -252,6 +400,40 @@ pub enum GitCommand {
     BranchCreate(BranchCreate),
     BranchFinalize(BranchFinalize),
     ReleasePr(ReleasePr),
+    /// Compose a conventional commit from prompts or flags and commit the staged changes.
+    Commit(CommitArgs),
+    /// Publish a version tag as a release (GitHub, GitLab, or Gitea) with its changelog section and artifacts.
+    Release(ReleaseArgs),
+    /// Rebase the current branch onto its base and force-push it with lease.
+    Sync(SyncArgs),
+    /// Delete local branches already merged into the main/release branches, locally and on origin.
+    Cleanup(CleanupArgs),
+    /// Reverse the last recorded git flow (branch created, merge, deletion, tag) where it is safe.
+    Undo(UndoArgs),
+    /// Inspect pull requests for the current repository.
+    Pr {
+        #[command(subcommand)]
+        command: PrCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PrCommand {
+    /// Push the current branch and open a PR, filling `[git] pr_template` when configured.
+    Create(PrCreate),
+    /// Show the open PR for the current branch with its review state and CI checks.
+    Status {
+        #[arg(long, value_enum, default_value = "table")]
+        format: PrFormat,
+    },
+    /// List open PRs with their review state and overall CI status.
+    List {
+        #[arg(long, value_enum, default_value = "table")]
+        format: PrFormat,
+        /// Maximum number of PRs to show
+        #[arg(long, default_value_t = 30)]
+        limit: usize,
+    },
 }
 
 #[derive(Args, Debug)]
@@ -263,6 +445,9 @@ pub struct BranchCreate {
     pub push: bool,
     #[arg(long = "allow-dirty")]
     pub allow_dirty: bool,
+    /// Stash local changes (including untracked files) for the flow and re-apply them afterwards
+    #[arg(long, conflicts_with = "allow_dirty")]
+    pub autostash: bool,
 }
 
 #[derive(Args, Debug)]
@@ -271,10 +456,118 @@ pub struct BranchFinalize {
     pub name: Option<String>,
     #[arg(long = "into")]
     pub base: Option<String>,
+    /// Delete the branch locally and on origin after a local merge/rebase/squash
     #[arg(long)]
     pub delete: bool,
+    /// How to integrate the branch: open a PR (default), or merge/rebase/squash locally and push
+    #[arg(long, value_enum)]
+    pub strategy: Option<FinalizeStrategy>,
+    /// Shorthand for `--strategy squash`
+    #[arg(long, conflicts_with = "strategy")]
+    pub squash: bool,
+    /// Commit the synthesized squash message without opening the editor
+    #[arg(long = "no-edit")]
+    pub no_edit: bool,
+    /// Keep the source branch after a squash instead of deleting it locally
+    #[arg(long, conflicts_with = "delete")]
+    pub keep: bool,
+    /// Require green CI checks on the branch before a local merge (also `[git] require_checks`)
+    #[arg(long = "require-checks")]
+    pub require_checks: bool,
+    /// Skip the protected-branch and CI check guards
+    #[arg(long)]
+    pub force: bool,
     #[arg(long = "allow-dirty")]
     pub allow_dirty: bool,
+    /// Stash local changes (including untracked files) for the flow and re-apply them afterwards
+    #[arg(long, conflicts_with = "allow_dirty")]
+    pub autostash: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct CommitArgs {
+    /// Commit type (feat, fix, docs, ...); prompted for when omitted
+    #[arg(long = "type", short = 't')]
+    pub kind: Option<String>,
+    /// Optional scope, e.g. `env` in `feat(env): ...`
+    #[arg(long, short = 's')]
+    pub scope: Option<String>,
+    /// Short imperative summary; passing it skips the optional prompts
+    #[arg(long, short = 'm')]
+    pub subject: Option<String>,
+    /// Longer explanation after the header
+    #[arg(long)]
+    pub body: Option<String>,
+    /// Mark the commit as breaking with this `BREAKING CHANGE:` description
+    #[arg(long, value_name = "DESCRIPTION")]
+    pub breaking: Option<String>,
+    /// Run the `lint` pipeline before committing and abort if it fails
+    #[arg(long)]
+    pub lint: bool,
+    /// Sign the commit (`-S`); also enabled by `[git] sign = true`
+    #[arg(long)]
+    pub sign: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct PrCreate {
+    /// Branch to merge into (default: release-candidate)
+    #[arg(long = "into")]
+    pub base: Option<String>,
+    /// PR title (default: filled from the commits)
+    #[arg(long)]
+    pub title: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct SyncArgs {
+    /// Branch to rebase onto (default: the base recorded by branch-create, else release-candidate)
+    #[arg(long = "onto")]
+    pub base: Option<String>,
+    /// Rebase only; leave origin untouched
+    #[arg(long = "no-push")]
+    pub no_push: bool,
+    /// Stash local changes for the rebase and re-apply them afterwards (`git rebase --autostash`)
+    #[arg(long)]
+    pub autostash: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct CleanupArgs {
+    /// Branch merged branches are checked against (repeatable; default: main and release branches)
+    #[arg(long = "into", value_name = "BRANCH")]
+    pub bases: Vec<String>,
+    /// Delete every listed branch without prompting
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+    /// Keep the branches on origin
+    #[arg(long = "local-only")]
+    pub local_only: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct UndoArgs {
+    /// Show the recorded flows, newest first, instead of undoing
+    #[arg(long)]
+    pub list: bool,
+    /// Also rewrite branches that were already pushed (with `--force-with-lease`)
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ReleaseArgs {
+    /// Tag to publish (default: the latest version tag)
+    pub tag: Option<String>,
+    /// Create the release as a draft
+    #[arg(long)]
+    pub draft: bool,
+    /// Extra file to upload (repeatable; `*` allowed in the file name)
+    #[arg(long = "asset", value_name = "PATH")]
+    pub assets: Vec<String>,
+    /// Skip the artifacts declared in `[git] release_assets`
+    #[arg(long = "no-assets")]
+    pub no_assets: bool,
 }
 
 #[derive(Args, Debug)]
@@ -285,6 +578,15 @@ pub struct ReleasePr {
     pub to: Option<String>,
     #[arg(long = "no-open")]
     pub no_open: bool,
+    /// Request a review from this user or team (repeatable; default `[git] release_reviewers`)
+    #[arg(long = "reviewer", value_name = "HANDLE")]
+    pub reviewers: Vec<String>,
+    /// Apply this label (repeatable; default `[git] release_labels`)
+    #[arg(long = "label", value_name = "NAME")]
+    pub labels: Vec<String>,
+    /// Open the PR as a draft (also `[git] release_draft = true`)
+    #[arg(long)]
+    pub draft: bool,
 }
 
 #[derive(Subcommand, Debug)]
@@ -292,23 +594,87 @@ pub enum VersionCommand {
     Bump(VersionBump),
     Changelog(ChangelogArgs),
     Show,
+    /// Recommend the next bump from the conventional commits since the last tag.
+    Next(VersionNext),
+    /// Report Cargo/pnpm/uv workspace members whose versions or internal dependency
+    /// requirements are out of sync with the root version.
+    Check,
+    /// Check that every version file and `[git] version_patterns` location carries the
+    /// same version.
+    Verify,
+    /// Revert the last `dev version bump`: restore the previous version, drop its
+    /// changelog section, and delete its tag.
+    Rollback(VersionRollback),
+    /// Publish the tagged version to its package registries (cargo, npm, pnpm, uv, twine).
+    Publish(VersionPublish),
 }
 
 #[derive(Args, Debug)]
 pub struct VersionBump {
-    #[arg(value_enum)]
-    pub level: BumpLevel,
+    #[arg(value_enum, required_unless_present_any = ["custom", "strip_build"])]
+    pub level: Option<BumpLevel>,
     #[arg(long = "custom")]
     pub custom: Option<String>,
+    /// Append semver build metadata to the new version, e.g. `--build "$(git rev-parse --short HEAD)"`
+    #[arg(long, value_name = "META")]
+    pub build: Option<String>,
+    /// Release the current version without its build metadata (no increment)
+    #[arg(long = "strip-build", conflicts_with_all = ["level", "custom", "build"])]
+    pub strip_build: bool,
+    #[command(flatten)]
+    pub options: BumpOptions,
+}
+
+/// What a bump does besides editing the version files.
+#[derive(Args, Debug, Clone, Default)]
+pub struct BumpOptions {
     #[arg(long = "tag")]
     pub tag: bool,
     #[arg(long = "no-commit")]
     pub no_commit: bool,
     #[arg(long = "no-changelog")]
     pub no_changelog: bool,
+    /// Sign the release commit and tag (`-S` / `-s`); also enabled by `[git] sign = true`
+    #[arg(long)]
+    pub sign: bool,
+    /// Commit the bump on a new `release/vX.Y.Z` branch, push it, and open a PR into the
+    /// current branch instead of committing to it (tag after the PR merges)
+    #[arg(long, conflicts_with_all = ["tag", "no_commit"])]
+    pub pr: bool,
+    /// Satisfy `[git] require_ci` with green CI checks on GitHub for HEAD instead of
+    /// running the pipelines locally
+    #[arg(long = "remote-ci")]
+    pub remote_ci: bool,
 }
 
-#[derive(Copy, Clone, Debug, ValueEnum)]
+#[derive(Args, Debug)]
+pub struct VersionNext {
+    /// Perform the recommended bump instead of only printing it
+    #[arg(long)]
+    pub apply: bool,
+    #[command(flatten)]
+    pub options: BumpOptions,
+}
+
+#[derive(Args, Debug)]
+pub struct VersionPublish {
+    /// Publish with these tools only (default: `[git] publish`, else inferred from the
+    /// version files)
+    #[arg(long = "tool", value_name = "TOOL")]
+    pub tools: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct VersionRollback {
+    /// Also delete the tag on origin
+    #[arg(long)]
+    pub remote: bool,
+    /// Restore the files without committing
+    #[arg(long = "no-commit")]
+    pub no_commit: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
 pub enum BumpLevel {
     Major,
     Minor,
@@ -322,6 +688,25 @@ pub struct ChangelogArgs {
     pub since: Option<String>,
     #[arg(long = "unreleased")]
     pub unreleased: bool,
+    /// Build entries from commit subjects or from merged PR titles and labels
+    #[arg(long, value_enum)]
+    pub source: Option<ChangelogSource>,
+    /// `md` for release notes, `json` for structured entries (type, scope, subject, sha,
+    /// author, pr) other tooling can consume
+    #[arg(long, value_enum, default_value = "md")]
+    pub format: ChangelogFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangelogFormat {
+    Md,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangelogSource {
+    Commits,
+    Prs,
 }
 
 #[derive(Args, Debug)]
@@ -330,6 +715,10 @@ pub struct EnvArgs {
     #[arg(long = "raw", default_value_t = false)]
     pub raw: bool,
 
+    /// Layer .env.<NAME> on top for this run (wins over `dev env switch` when layered)
+    #[arg(long = "env-profile", value_name = "NAME", global = true)]
+    pub env_profile: Option<String>,
+
     #[command(subcommand)]
     pub command: Option<EnvCommand>,
 }
@@ -337,30 +726,61 @@ pub struct EnvArgs {
 #[derive(Subcommand, Debug)]
 pub enum EnvCommand {
     /// List all environment variables (default if no subcommand)
-    List,
+    List {
+        /// Show which file (.env, .env.local, .env.<profile>) each value came from
+        #[arg(long = "explain", default_value_t = false)]
+        explain: bool,
+        /// Show each key's description (the comment directly above it)
+        #[arg(long = "describe", default_value_t = false)]
+        describe: bool,
+    },
     /// Get a single environment variable value
     Get { key: String },
     /// Add or update an environment variable
-    Add { key: String, value: String },
+    Add {
+        key: String,
+        value: String,
+        /// Description written as a comment above the key
+        #[arg(long = "desc")]
+        desc: Option<String>,
+    },
     /// Remove an environment variable
     Rm { key: String },
+    /// Rename a key in place, keeping its position and comments
+    Rename {
+        old: String,
+        new: String,
+        /// Search tracked project files for the old name and list the matches
+        #[arg(long = "find-refs", default_value_t = false)]
+        find_refs: bool,
+    },
     /// List available environment profiles (.env.*)
     Profiles,
-    /// Switch to a different environment profile
-    Switch { profile: String },
+    /// Switch to a different environment profile (copies it over .env unless [env] layered)
+    Switch {
+        // Not `profile`: that id belongs to the global `--profile` flag.
+        #[arg(value_name = "PROFILE")]
+        name: String,
+    },
     /// Save current .env as a named profile
     Save { name: String },
     /// Validate .env against required keys in config
     Check,
     /// Initialize .env from .env.example if missing
     Init,
-    /// Generate .env.example from current .env (values stripped)
+    /// Generate .env.example from current .env (values stripped, descriptions kept)
     Template,
-    /// Show diff between .env and a reference file
+    /// Show diff between .env and a reference file or profile
     Diff {
-        /// Reference file to compare against (default: .env.example)
+        /// Reference file or profile name (`staging` -> .env.staging) to compare against
         #[arg(default_value = ".env.example")]
         reference: String,
+        /// Also compare values, not just which keys exist
+        #[arg(long = "values", default_value_t = false)]
+        values: bool,
+        /// Show values instead of masking them (with --values)
+        #[arg(long = "raw", default_value_t = false)]
+        raw: bool,
     },
     /// Interactively add missing keys from a reference file
     Sync {
@@ -368,6 +788,110 @@ pub enum EnvCommand {
         #[arg(default_value = ".env.example")]
         reference: String,
     },
+    /// Encrypt .env to .env.enc with age (or sops) so it can be committed
+    Encrypt {
+        /// age recipient (public key or recipients file); repeatable, default: [env] recipients
+        #[arg(short = 'r', long = "recipient")]
+        recipients: Vec<String>,
+        /// Use sops (keys from .sops.yaml) instead of age
+        #[arg(long = "sops", default_value_t = false)]
+        sops: bool,
+        /// Delete the plaintext .env afterwards; commands then read .env.enc directly
+        #[arg(long = "remove-plain", default_value_t = false)]
+        remove_plain: bool,
+    },
+    /// Generate a cryptographically random value and store it under KEY
+    Gen {
+        key: String,
+        /// Random bytes to draw (ignored for uuid)
+        #[arg(long = "length", default_value_t = 32)]
+        length: usize,
+        #[arg(long = "format", value_enum, default_value_t = EnvGenFormat::Hex)]
+        format: EnvGenFormat,
+    },
+    /// Sort keys within comment-headed groups, drop duplicates, and normalize quoting
+    Fmt {
+        /// File to format (default: .env), e.g. .env.example
+        file: Option<PathBuf>,
+        /// Report whether the file needs formatting without writing it
+        #[arg(long = "check", default_value_t = false)]
+        check: bool,
+    },
+    /// Merge several env files into one, settling conflicting values by --strategy
+    Merge {
+        #[arg(required = true, num_args = 2.., value_name = "FILE")]
+        files: Vec<PathBuf>,
+        /// File to write (default: .env)
+        #[arg(long = "out")]
+        out: Option<PathBuf>,
+        #[arg(long = "strategy", value_enum, default_value_t = EnvMergeStrategy::PreferLast)]
+        strategy: EnvMergeStrategy,
+    },
+    /// Watch .env (and its layers) and restart a compose service or re-run a task on change
+    Watch {
+        /// docker compose service to recreate so it picks up the new values
+        #[arg(long = "restart", value_name = "SERVICE", required_unless_present = "task")]
+        restart: Option<String>,
+        /// Task to re-run after each change
+        #[arg(long = "task", conflicts_with = "restart")]
+        task: Option<String>,
+        /// Seconds between checks
+        #[arg(long = "interval", default_value_t = 1)]
+        interval: u64,
+    },
+    /// Compare env lookups in project source with .env and [env] keys: report unused and missing
+    Audit,
+    /// Full-screen editor: masked values, add/edit/delete, search, required/optional keys
+    Edit,
+    /// List .env snapshots taken before add, rm, rename, gen, fmt, merge, switch, sync, import, edit, and decrypt
+    History,
+    /// Restore the n-th most recent .env snapshot (1 = newest)
+    Rollback { n: usize },
+    /// Run a command with .env (plus .env.<NAME> under --env-profile) in its environment
+    Exec {
+        /// Command and arguments, after `--`
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+    /// Print the env as shell commands, e.g. `eval "$(dev env export)"`
+    Export {
+        #[arg(long = "format", value_enum, default_value_t = EnvExportFormat::Sh)]
+        format: EnvExportFormat,
+    },
+    /// Merge keys from a JSON, YAML (incl. Kubernetes Secret), or dotenv file into .env
+    Import {
+        file: PathBuf,
+        /// Input format (default: from the file extension)
+        #[arg(long = "format", value_enum)]
+        format: Option<EnvImportFormat>,
+        /// Replace values of keys that already exist in .env
+        #[arg(long = "overwrite", default_value_t = false)]
+        overwrite: bool,
+    },
+    /// Fill .env from a secrets manager (default backend/path: [env.sync])
+    Pull {
+        #[arg(long = "backend", value_enum)]
+        backend: Option<SecretBackend>,
+        /// AWS secret id, Vault KV path, or 1Password `vault/item`
+        #[arg(long = "path")]
+        path: Option<String>,
+    },
+    /// Write .env values back to the secrets manager
+    Push {
+        #[arg(long = "backend", value_enum)]
+        backend: Option<SecretBackend>,
+        #[arg(long = "path")]
+        path: Option<String>,
+    },
+    /// Decrypt .env.enc back to .env
+    Decrypt {
+        /// age identity file (default: $DEV_AGE_IDENTITY, then ~/.config/age/keys.txt, then a prompt)
+        #[arg(short = 'i', long = "identity")]
+        identity: Option<PathBuf>,
+        /// Overwrite an existing .env
+        #[arg(long = "force", default_value_t = false)]
+        force: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -381,7 +905,70 @@ pub enum ConfigCommand {
         #[arg(long = "force", default_value_t = false)]
         force: bool,
     },
-    Reload,
+    /// Reparse config and reindex tasks; `--watch` keeps reloading on every change.
+    Reload {
+        /// Keep running and reload whenever the config (or shared config) changes
+        #[arg(long = "watch", default_value_t = false)]
+        watch: bool,
+        /// Seconds between checks in watch mode
+        #[arg(long = "interval", default_value_t = 1, requires = "watch")]
+        interval: u64,
+    },
+    /// Upgrade the config to the current schema version (and out of legacy locations).
+    Migrate {
+        /// Move `~/.dev/config.toml` to `$XDG_CONFIG_HOME/dev/config.toml` instead
+        #[arg(long = "xdg", default_value_t = false)]
+        xdg: bool,
+    },
+    /// Compare the active config with the generated template (or another file).
+    Diff {
+        /// Reference config to compare against (default: embedded example template)
+        #[arg()]
+        against: Option<PathBuf>,
+    },
+    /// Clone or update the shared team config in ~/.dev/shared/ (layered under project config).
+    Sync {
+        /// Git URL of the team config repository (defaults to the existing checkout's origin)
+        #[arg(long = "from")]
+        from: Option<String>,
+    },
+    /// Print the config file, or with --resolved the effective merged config.
+    Export {
+        /// Merge shared config, --profile overrides, and built-in pipelines, then normalize
+        #[arg(long = "resolved", default_value_t = false)]
+        resolved: bool,
+        #[arg(long = "format", value_enum, default_value_t = ExportFormat::Toml)]
+        format: ExportFormat,
+    },
+    /// Print the value at a dotted key path (e.g. `git.main_branch`).
+    Get {
+        key: String,
+    },
+    /// Set a dotted key path; values are parsed as TOML, falling back to a string.
+    Set {
+        key: String,
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+    },
+    /// Flag config smells (missing descriptions, needless shells, empty pipelines, duplicates).
+    Lint {
+        /// Apply mechanical fixes in place
+        #[arg(long = "fix", default_value_t = false)]
+        fix: bool,
+    },
+    /// Remove a task; references to it elsewhere block removal unless --force.
+    #[command(alias = "remove-task")]
+    Rm {
+        name: String,
+        /// Also drop references to the task from other tasks and pipelines
+        #[arg(long = "force", default_value_t = false)]
+        force: bool,
+    },
+    /// Rename a task and update every reference to it.
+    Rename {
+        old: String,
+        new: String,
+    },
     Add {
         #[arg()]
         name: Option<String>,
@@ -394,6 +981,88 @@ pub enum ConfigCommand {
     },
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Toml,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnvExportFormat {
+    Sh,
+    Fish,
+    Powershell,
+    Dotenv,
+    Json,
+    Yaml,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FinalizeStrategy {
+    Pr,
+    Merge,
+    Rebase,
+    Squash,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReviewFormat {
+    Md,
+    Html,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WalkFormat {
+    #[value(alias = "md")]
+    Markdown,
+    Json,
+    #[value(alias = "yml")]
+    Yaml,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrFormat {
+    Table,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnvGenFormat {
+    Hex,
+    Base64,
+    Uuid,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnvMergeStrategy {
+    PreferFirst,
+    PreferLast,
+    Interactive,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecretBackend {
+    Aws,
+    Vault,
+    #[value(name = "1password", alias = "op")]
+    OnePassword,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnvImportFormat {
+    Json,
+    Yaml,
+    Dotenv,
+}
+
 #[derive(Subcommand, Debug)]
 pub enum SetupCommand {
     /// Run default components with --skip-installed implied
@@ -0,0 +1,75 @@
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result, bail};
+
+/// Clipboard tools to try, in order, each with the arguments that make it read stdin.
+fn candidates(os: &str, wayland: bool) -> Vec<(&'static str, &'static [&'static str])> {
+    match os {
+        "macos" => vec![("pbcopy", &[])],
+        "windows" => vec![("clip", &[])],
+        _ => {
+            let mut tools: Vec<(&'static str, &'static [&'static str])> = vec![
+                ("xclip", &["-selection", "clipboard"]),
+                ("xsel", &["--clipboard", "--input"]),
+                // WSL: the Windows clipboard, reachable through interop.
+                ("clip.exe", &[]),
+            ];
+            if wayland {
+                tools.insert(0, ("wl-copy", &[]));
+            }
+            tools
+        }
+    }
+}
+
+/// Copies `text` to the system clipboard and returns the name of the tool that did it.
+pub fn copy(text: &str) -> Result<&'static str> {
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some_and(|value| !value.is_empty());
+    let tools = candidates(std::env::consts::OS, wayland);
+    for &(program, args) in &tools {
+        let mut child = match Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) if err.kind() == ErrorKind::NotFound => continue,
+            Err(err) => return Err(err).with_context(|| format!("failed to run {}", program)),
+        };
+        child
+            .stdin
+            .take()
+            .context("clipboard tool has no stdin")?
+            .write_all(text.as_bytes())
+            .with_context(|| format!("failed to write to {}", program))?;
+        let status = child.wait()?;
+        if !status.success() {
+            bail!("{} exited with {}", program, status);
+        }
+        return Ok(program);
+    }
+    let names: Vec<&str> = tools.iter().map(|(program, _)| *program).collect();
+    bail!("no clipboard tool found (tried {})", names.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_wl_copy_only_under_wayland() {
+        let names = |os, wayland| -> Vec<&str> {
+            candidates(os, wayland)
+                .into_iter()
+                .map(|(program, _)| program)
+                .collect()
+        };
+        assert_eq!(names("macos", true), ["pbcopy"]);
+        assert_eq!(names("windows", false), ["clip"]);
+        assert_eq!(names("linux", false), ["xclip", "xsel", "clip.exe"]);
+        assert_eq!(names("linux", true)[0], "wl-copy");
+    }
+}
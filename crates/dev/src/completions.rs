@@ -0,0 +1,165 @@
+use clap::CommandFactory;
+
+use crate::cli::{Cli, CompletionShell};
+
+/// One command in the tree: its `/`-joined path from `dev`, subcommands, and flags.
+struct Node {
+    path: String,
+    subcommands: Vec<(String, String)>,
+    flags: Vec<(String, String)>,
+}
+
+fn first_line(text: Option<&clap::builder::StyledStr>) -> String {
+    text.map(|text| text.to_string().lines().next().unwrap_or("").to_owned())
+        .unwrap_or_default()
+}
+
+/// Visible subcommands, leaving out clap's generated `help` tree.
+fn visible(command: &clap::Command) -> impl Iterator<Item = &clap::Command> {
+    command
+        .get_subcommands()
+        .filter(|sub| !sub.is_hide_set() && sub.get_name() != "help")
+}
+
+fn collect(command: &clap::Command, path: String, nodes: &mut Vec<Node>) {
+    let subcommands = visible(command)
+        .map(|sub| (sub.get_name().to_owned(), first_line(sub.get_about())))
+        .collect();
+    let flags = command
+        .get_arguments()
+        .filter(|arg| !arg.is_positional() && !arg.is_hide_set())
+        .flat_map(|arg| {
+            let help = first_line(arg.get_help());
+            let long = arg
+                .get_long()
+                .map(|long| (format!("--{}", long), help.clone()));
+            let short = arg
+                .get_short()
+                .map(|short| (format!("-{}", short), help.clone()));
+            long.into_iter().chain(short)
+        })
+        .collect();
+    nodes.push(Node {
+        path: path.clone(),
+        subcommands,
+        flags,
+    });
+    for sub in visible(command) {
+        collect(sub, format!("{}/{}", path, sub.get_name()), nodes);
+    }
+}
+
+/// Every command reachable from `dev`, built from the clap definitions so the scripts
+/// never drift from the CLI. Tasks are left out: listing them needs the config.
+fn nodes() -> Vec<Node> {
+    let mut command = Cli::command();
+    // Propagates global flags and adds `--help` to every subcommand.
+    command.build();
+    let mut nodes = Vec::new();
+    collect(&command, "dev".to_owned(), &mut nodes);
+    nodes
+}
+
+fn bash(nodes: &[Node]) -> String {
+    let paths: Vec<&str> = nodes
+        .iter()
+        .skip(1)
+        .map(|node| node.path.as_str())
+        .collect();
+    let mut out = String::from("_dev() {\n");
+    out.push_str("    local cur=\"${COMP_WORDS[COMP_CWORD]}\" path=dev word i words\n");
+    out.push_str("    for ((i = 1; i < COMP_CWORD; i++)); do\n");
+    out.push_str("        word=\"${COMP_WORDS[i]}\"\n");
+    out.push_str("        case \"$path/$word\" in\n");
+    out.push_str(&format!(
+        "            {}) path=\"$path/$word\" ;;\n",
+        paths.join("|")
+    ));
+    out.push_str("        esac\n    done\n    case \"$path\" in\n");
+    for node in nodes {
+        let words: Vec<&str> = node
+            .subcommands
+            .iter()
+            .chain(&node.flags)
+            .map(|(word, _)| word.as_str())
+            .collect();
+        out.push_str(&format!(
+            "        {}) words=\"{}\" ;;\n",
+            node.path,
+            words.join(" ")
+        ));
+    }
+    out.push_str("    esac\n    COMPREPLY=($(compgen -W \"$words\" -- \"$cur\"))\n}\n");
+    out.push_str("complete -F _dev dev\n");
+    out
+}
+
+fn fish(nodes: &[Node]) -> String {
+    let quote = |text: &str| format!("'{}'", text.replace('\\', r"\\").replace('\'', r"\'"));
+    let paths: Vec<String> = nodes.iter().skip(1).map(|node| quote(&node.path)).collect();
+    let mut out = String::from("function __dev_path\n    set -l path dev\n");
+    out.push_str("    for word in (commandline -opc)[2..-1]\n");
+    out.push_str(&format!(
+        "        if contains -- \"$path/$word\" {}\n            set path \"$path/$word\"\n        end\n",
+        paths.join(" ")
+    ));
+    out.push_str("    end\n    echo $path\nend\n\ncomplete -c dev -f\n");
+    for node in nodes {
+        let condition = quote(&format!("test (__dev_path) = {}", node.path));
+        for (name, about) in &node.subcommands {
+            out.push_str(&format!(
+                "complete -c dev -n {} -a {} -d {}\n",
+                condition,
+                quote(name),
+                quote(about)
+            ));
+        }
+        for (flag, help) in &node.flags {
+            let option = match flag.strip_prefix("--") {
+                Some(long) => format!("-l {}", long),
+                None => format!("-s {}", &flag[1..]),
+            };
+            out.push_str(&format!(
+                "complete -c dev -n {} {} -d {}\n",
+                condition,
+                option,
+                quote(help)
+            ));
+        }
+    }
+    out
+}
+
+/// The completion script for `shell`; needs neither the config nor the environment.
+pub fn script(shell: CompletionShell) -> String {
+    let nodes = nodes();
+    match shell {
+        CompletionShell::Bash => bash(&nodes),
+        // zsh runs the bash function through its compatibility layer.
+        CompletionShell::Zsh => format!(
+            "#compdef dev\nautoload -U +X bashcompinit && bashcompinit\n{}",
+            bash(&nodes)
+        ),
+        CompletionShell::Fish => fish(&nodes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts_cover_nested_subcommands_and_flags() {
+        let bash = script(CompletionShell::Bash);
+        assert!(bash.contains("|dev/git/pr|"));
+        assert!(!bash.contains("/help"));
+        assert!(bash.contains("        dev/walk) words=\""));
+        assert!(bash.contains("--split-size"));
+        assert!(bash.ends_with("complete -F _dev dev\n"));
+
+        let fish = script(CompletionShell::Fish);
+        assert!(fish.contains("complete -c dev -n 'test (__dev_path) = dev' -a 'walk'"));
+        assert!(fish.contains("complete -c dev -n 'test (__dev_path) = dev/walk' -l max-tokens"));
+        assert!(script(CompletionShell::Zsh).starts_with("#compdef dev\n"));
+    }
+}
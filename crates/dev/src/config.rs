@@ -1,18 +1,25 @@
 use std::collections::BTreeMap;
-use std::fmt::Write as _;
+use std::fmt::{self, Write as _};
 use std::fs;
+use std::path::Path;
 
-use anyhow::{Context, Result, bail};
-use camino::Utf8Path;
+use anyhow::{Context, Result, anyhow, bail};
+use camino::{Utf8Path, Utf8PathBuf};
 use serde::Deserialize;
 use toml::Value;
-use toml_edit::{Array, DocumentMut, Item, Table, Value as EditValue, value};
+use toml_edit::{Array, DocumentMut, ImDocument, Item, Table, Value as EditValue, value};
 
 use crate::scaffold;
 
-/// Root configuration document loaded from `~/.dev/config.toml` by default.
+/// Schema version written by `dev config migrate` and the generated template.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Root configuration document, discovered from `.dev/config.toml` or the user config
+/// (`$DEV_CONFIG`, `~/.config/dev/config.toml`, `~/.dev/config.toml`).
 #[derive(Debug, Deserialize)]
 pub struct DevConfig {
+    /// Schema version; configs without one predate versioning and are treated as `0`.
+    pub version: Option<u32>,
     pub default_language: Option<String>,
     pub default_project: Option<String>,
     pub projects: Option<BTreeMap<String, Project>>,
@@ -20,6 +27,168 @@ pub struct DevConfig {
     pub languages: Option<BTreeMap<String, Language>>,
     pub git: Option<GitConfig>,
     pub env: Option<EnvConfig>,
+    pub review: Option<ReviewConfig>,
+    /// Shared values (`[vars]`) substituted for `{{name}}` in tasks, docker init, and `.env` templates.
+    pub vars: Option<BTreeMap<String, toml::Value>>,
+    /// Named overlays (`[profiles.staging]`) applied on top of the config with `--profile`.
+    pub profiles: Option<BTreeMap<String, toml::Table>>,
+    /// Raw file contents, retained so later errors can point at the offending line.
+    #[serde(skip)]
+    pub source: Option<ConfigSource>,
+    /// Shared team config layered underneath this one, if any (see `dev config sync`).
+    #[serde(skip)]
+    pub shared: Option<Utf8PathBuf>,
+    /// Profile selected with `--profile`, already applied to the fields above.
+    #[serde(skip)]
+    pub profile: Option<String>,
+}
+
+/// Raw text of the loaded config files used to map values back to source spans.
+#[derive(Debug, Clone)]
+pub struct ConfigSource {
+    /// Highest precedence first: profile overrides before the base tables, the local
+    /// file before the shared one.
+    layers: Vec<SourceLayer>,
+}
+
+#[derive(Debug, Clone)]
+struct SourceLayer {
+    path: Utf8PathBuf,
+    raw: String,
+    /// Where this layer's values sit in its file: `["profiles", name]` for a profile.
+    prefix: Vec<String>,
+}
+
+/// One step of a path into the config document, e.g. `tasks` -> `build` -> `commands` -> `0`.
+#[derive(Clone, Copy, Debug)]
+pub enum ConfigKey<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+/// File/line/column of a config value along with the source line it sits on.
+#[derive(Debug)]
+pub struct SourceLocation {
+    pub path: Utf8PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub snippet: String,
+    width: usize,
+}
+
+impl ConfigSource {
+    pub fn new(path: &Utf8Path, raw: String) -> Self {
+        Self {
+            layers: vec![SourceLayer {
+                path: path.to_owned(),
+                raw,
+                prefix: Vec::new(),
+            }],
+        }
+    }
+
+    /// The layers [`load_with_profile`] merges: `shared` under `local`, and the
+    /// `[profiles.<profile>]` tables of both over them.
+    fn layered(local: SourceLayer, shared: Option<SourceLayer>, profile: Option<&str>) -> Self {
+        let base: Vec<SourceLayer> = [Some(local), shared].into_iter().flatten().collect();
+        let mut layers = Vec::new();
+        if let Some(name) = profile {
+            layers.extend(base.iter().map(|layer| SourceLayer {
+                prefix: vec!["profiles".to_owned(), name.to_owned()],
+                ..layer.clone()
+            }));
+        }
+        layers.extend(base);
+        Self { layers }
+    }
+
+    /// Locate the value at `keys` in the layer it came from: the first that has all of
+    /// them, else the one with the deepest match. Falls back to the nearest ancestor
+    /// that has a span.
+    pub fn locate(&self, keys: &[ConfigKey<'_>]) -> Option<SourceLocation> {
+        let mut best: Option<(usize, SourceLocation)> = None;
+        for layer in &self.layers {
+            let Some((depth, location)) = layer.locate(keys) else {
+                continue;
+            };
+            if depth == keys.len() {
+                return Some(location);
+            }
+            if best.as_ref().is_none_or(|(deepest, _)| depth > *deepest) {
+                best = Some((depth, location));
+            }
+        }
+        best.map(|(_, location)| location)
+    }
+}
+
+impl SourceLayer {
+    /// How many of `keys` this layer has, and where the deepest of them sits; `None`
+    /// when the layer lacks its prefix.
+    fn locate(&self, keys: &[ConfigKey<'_>]) -> Option<(usize, SourceLocation)> {
+        let doc = ImDocument::parse(self.raw.as_str()).ok()?;
+        let mut item = doc.as_item();
+        for name in &self.prefix {
+            item = item.get(name.as_str())?;
+        }
+        let mut span = item.span();
+        let mut depth = 0;
+        for key in keys {
+            let next = match key {
+                ConfigKey::Key(name) => item.get(*name),
+                ConfigKey::Index(idx) => item.get(*idx),
+            };
+            let Some(next) = next else {
+                break;
+            };
+            item = next;
+            depth += 1;
+            if let Some(found) = item.span() {
+                span = Some(found);
+            }
+        }
+
+        let span = span?;
+        let line_start = self.raw[..span.start].rfind('\n').map_or(0, |idx| idx + 1);
+        let line_end = self.raw[span.start..]
+            .find('\n')
+            .map_or(self.raw.len(), |idx| span.start + idx);
+        Some((
+            depth,
+            SourceLocation {
+                path: self.path.clone(),
+                line: self.raw[..span.start].matches('\n').count() + 1,
+                column: span.start - line_start + 1,
+                snippet: self.raw[line_start..line_end].trim_end_matches('\r').to_owned(),
+                width: span.end.min(line_end).saturating_sub(span.start).max(1),
+            },
+        ))
+    }
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let gutter = " ".repeat(self.line.to_string().len());
+        writeln!(f, "{}--> {}:{}:{}", gutter, self.path, self.line, self.column)?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", self.line, self.snippet)?;
+        write!(
+            f,
+            "{} | {}{}",
+            gutter,
+            " ".repeat(self.column - 1),
+            "^".repeat(self.width)
+        )
+    }
+}
+
+/// Render the location of `keys` as a suffix for error messages, or an empty
+/// string when the config was not loaded from a file.
+pub fn source_hint(source: Option<&ConfigSource>, keys: &[ConfigKey<'_>]) -> String {
+    source
+        .and_then(|source| source.locate(keys))
+        .map(|location| format!("\n{}", location))
+        .unwrap_or_default()
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -103,13 +272,29 @@ pub fn upsert_task_command(
 pub struct Project {
     pub chdir: Option<String>,
     pub language: Option<String>,
+    /// Version tag prefix for this project (e.g. `web-v` for `web-v1.2.0`).
+    pub tag_prefix: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Task {
+    /// Short human-readable summary shown by `dev list`.
+    pub description: Option<String>,
     pub commands: Vec<Value>,
     #[serde(default)]
     pub allow_fail: bool,
+    /// Environment variables set for every command in the task (and tasks it references).
+    #[serde(default)]
+    pub env: BTreeMap<String, ConfigValue>,
+}
+
+/// A config string that is either written inline or resolved at execution time
+/// via `{ secret = "NAME" }`, keeping credentials out of committed config files.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum ConfigValue {
+    Plain(String),
+    Secret { secret: String },
 }
 
 #[derive(Debug, Deserialize)]
@@ -130,24 +315,706 @@ pub struct Pipelines {
     pub ci: Option<Vec<String>>,
 }
 
+impl Pipelines {
+    /// Every verb paired with its configured task list, in CLI order.
+    pub fn entries(&self) -> [(&'static str, Option<&Vec<String>>); 7] {
+        [
+            ("fmt", self.fmt.as_ref()),
+            ("lint", self.lint.as_ref()),
+            ("type", self.type_check.as_ref()),
+            ("test", self.test.as_ref()),
+            ("fix", self.fix.as_ref()),
+            ("check", self.check.as_ref()),
+            ("ci", self.ci.as_ref()),
+        ]
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct GitConfig {
     pub main_branch: Option<String>,
     pub release_branch: Option<String>,
     pub version_file: Option<String>,
+    /// Manifests bumped together in one commit (monorepos); the first one is the source
+    /// of the current version. Takes precedence over `version_file`.
+    pub version_files: Option<Vec<String>>,
+    /// Other places the version appears (`VERSION`, `__init__.py`, Helm charts, docs),
+    /// bumped with the version files and checked by `dev version verify`.
+    pub version_patterns: Option<Vec<VersionPattern>>,
+    /// `semver` (default) or `calver`, which derives versions from the release date.
+    pub version_scheme: Option<String>,
+    /// CalVer layout, e.g. `YYYY.MM.PATCH` (the default) or `YY.WW.PATCH`.
+    pub calver_pattern: Option<String>,
     pub changelog: Option<String>,
+    /// Give each package in `version_files` its own changelog, from the commits touching its
+    /// directory, besides the aggregate root changelog.
+    pub package_changelogs: Option<bool>,
+    /// Hosting provider for PR and release flows: `github`, `gitlab`, or `gitea`
+    /// (guessed from the `origin` remote when unset).
+    pub provider: Option<String>,
+    /// Reviewers requested on release PRs when `--reviewer` is not given.
+    pub release_reviewers: Option<Vec<String>>,
+    /// Labels applied to release PRs when `--label` is not given.
+    pub release_labels: Option<Vec<String>>,
+    /// Open release PRs as drafts.
+    pub release_draft: Option<bool>,
+    /// Markdown template for PR bodies from `release-pr` and `dev git pr create`.
+    pub pr_template: Option<String>,
+    /// Files (`*` allowed in the file name) uploaded to the release by `dev git release`.
+    pub release_assets: Option<Vec<String>>,
+    /// Where `dev version` changelog entries come from: `commits` (default) or `prs`.
+    pub changelog_source: Option<String>,
+    /// Sign commits and tags made by `dev version bump` and `dev git commit`.
+    pub sign: Option<bool>,
+    /// Prefix for version tags (default `v`); projects can override it.
+    pub tag_prefix: Option<String>,
+    /// Full tag template, e.g. `{project}-v{version}`; takes precedence over `tag_prefix`.
+    pub tag_format: Option<String>,
+    /// Release commit message template, e.g. `chore(release): {version}` (`{tag}` also works).
+    pub release_commit: Option<String>,
+    /// Branches (`*` wildcards) that `branch-finalize` refuses to merge into locally.
+    pub protected_branches: Option<Vec<String>>,
+    /// Require green CI checks before `branch-finalize` merges locally.
+    pub require_checks: Option<bool>,
+    /// Default `dev git branch-finalize --strategy`: `pr`, `merge`, `rebase`, or `squash`.
+    pub finalize_strategy: Option<String>,
+    /// Pipelines (`check`, `test`, ...) that must pass before `dev version bump` writes
+    /// anything.
+    pub require_ci: Option<Vec<String>>,
+    /// Tools `dev version publish` runs: `cargo`, `npm`, `pnpm`, `uv`, or `twine`
+    /// (inferred from the version files when unset).
+    pub publish: Option<Vec<String>>,
+    /// File (e.g. `RELEASE.md`) `dev version bump` writes into each release commit with
+    /// the tag, source commit, locked tool versions, and `release_assets` checksums.
+    pub provenance: Option<String>,
+    pub hooks: Option<GitHooks>,
+}
+
+/// `[git.hooks]`: tasks run around `dev version bump`.
+#[derive(Debug, Deserialize)]
+pub struct GitHooks {
+    /// Run before anything is written; a failure aborts the bump.
+    pub pre_bump: Option<Vec<String>>,
+    /// Run once the version files and changelogs are written, before the provenance
+    /// record and the release commit: build release artifacts here.
+    pub post_write: Option<Vec<String>>,
+    /// Run after the release commit and tag.
+    pub post_bump: Option<Vec<String>>,
+}
+
+/// `[[git.version_patterns]]`: text in `file` where `{version}` stands for the version.
+#[derive(Debug, Deserialize)]
+pub struct VersionPattern {
+    pub file: String,
+    pub pattern: String,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct EnvConfig {
     pub required: Option<Vec<String>>,
     pub optional: Option<Vec<String>>,
+    /// age recipients (public keys or recipient files) used by `dev env encrypt`.
+    pub recipients: Option<Vec<String>>,
+    /// Key patterns (`*` wildcards, case-insensitive) whose values are masked in
+    /// listings; when unset every value is masked.
+    pub mask: Option<Vec<String>>,
+    /// Resolve `.env`, `.env.local`, then `.env.<profile>` at read time instead of
+    /// copying a profile over `.env` on `dev env switch`.
+    pub layered: Option<bool>,
+    pub sync: Option<EnvSyncConfig>,
+}
+
+impl EnvConfig {
+    /// Whether `key`'s value should be hidden when not running with `--raw`.
+    pub fn is_sensitive(&self, key: &str) -> bool {
+        match &self.mask {
+            Some(patterns) => patterns.iter().any(|pattern| wildcard_matches(pattern, key)),
+            None => true,
+        }
+    }
+}
+
+/// Match `key` against `pattern` ignoring ASCII case, where `*` stands for any run of characters.
+fn wildcard_matches(pattern: &str, key: &str) -> bool {
+    glob_matches(&pattern.to_ascii_uppercase(), &key.to_ascii_uppercase())
+}
+
+/// Case-sensitive match of `text` against `pattern`, where `*` stands for any run of characters.
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Gitignore-style match of a `/`-separated relative `path`: `*` and `?` stay within one
+/// segment, `**` spans any number of them, a pattern without a `/` matches the last
+/// segment at any depth, and a trailing `/` only matches directories.
+pub fn path_glob_matches(pattern: &str, path: &str, is_dir: bool) -> bool {
+    let (pattern, dir_only) = match pattern.strip_suffix('/') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    if dir_only && !is_dir {
+        return false;
+    }
+    let pattern = pattern.strip_prefix("./").unwrap_or(pattern);
+    let anchored = pattern.contains('/');
+    let pattern = pattern.trim_start_matches('/');
+    let mut pattern: Vec<&str> = pattern.split('/').collect();
+    if !anchored {
+        pattern.insert(0, "**");
+    }
+    let path: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+    segments_match(&pattern, &path)
+}
+
+fn segments_match(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| segments_match(rest, &path[skip..])),
+        Some((first, rest)) => {
+            !path.is_empty() && segment_matches(first.as_bytes(), path[0].as_bytes()) && segments_match(rest, &path[1..])
+        }
+    }
 }
 
-/// Load a configuration file from disk and deserialize it.
+fn segment_matches(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|skip| segment_matches(rest, &text[skip..])),
+        Some((b'?', rest)) => !text.is_empty() && segment_matches(rest, &text[1..]),
+        Some((c, rest)) => text.first() == Some(c) && segment_matches(rest, &text[1..]),
+    }
+}
+
+/// `[review]`: per-project defaults for `dev review`; flags on the command line win.
+#[derive(Debug, Default, Deserialize)]
+pub struct ReviewConfig {
+    /// Report path used when `--output` is not given.
+    pub output: Option<String>,
+    /// `md`, `html`, or `json`.
+    pub format: Option<String>,
+    /// Branch `--main` compares against, instead of detecting it from `origin/HEAD`.
+    pub base: Option<String>,
+    /// Gitignore-style path globs (`*`, `**`, `dir/`) left out of every report, e.g.
+    /// lockfiles; a file inside a matching directory is left out too.
+    pub exclude: Option<Vec<String>>,
+    /// Always include unstaged changes, as with `--include-working` (`--no-include-working`
+    /// turns it off for one run).
+    pub include_working: Option<bool>,
+    /// Prompt template file used when `--prompt` is not given.
+    pub prompt: Option<String>,
+    /// Text put before the report when there is no prompt template.
+    pub preamble: Option<String>,
+    /// Text put after the report when there is no prompt template.
+    pub epilogue: Option<String>,
+    /// Files larger than this many bytes (default 256 KiB) are summarised by size
+    /// instead of overlaid; 0 turns the limit off.
+    pub max_file_bytes: Option<u64>,
+}
+
+/// `[env.sync]`: where `dev env pull` / `dev env push` find secrets.
+#[derive(Debug, Deserialize)]
+pub struct EnvSyncConfig {
+    /// `aws`, `vault`, or `1password`.
+    pub backend: Option<String>,
+    pub path: Option<String>,
+    /// `.env` key -> remote field name; when set, only these keys are synced.
+    pub keys: Option<BTreeMap<String, String>>,
+}
+
+/// Load a configuration file from disk and deserialize it, layered on top of
+/// the shared team config from `~/.dev/shared/` when one has been synced.
 pub fn load_from_path(path: &Utf8Path) -> Result<DevConfig> {
+    load_with_profile(path, None)
+}
+
+/// Like [`load_from_path`], then overlay `[profiles.<profile>]` on the result.
+pub fn load_with_profile(path: &Utf8Path, profile: Option<&str>) -> Result<DevConfig> {
+    load_layered(path, shared_config_path()?.as_deref(), profile)
+}
+
+/// [`load_with_profile`] with the shared layer given explicitly, `None` for none.
+fn load_layered(path: &Utf8Path, shared: Option<&Utf8Path>, profile: Option<&str>) -> Result<DevConfig> {
+    let raw = fs::read_to_string(path).with_context(|| format!("reading config {}", path))?;
+    let (merged, shared) = layered_table(path, &raw, profile, shared)?;
+    let mut config: DevConfig = Value::Table(merged)
+        .try_into()
+        .with_context(|| format!("layering config {}", path))?;
+
+    let local = SourceLayer {
+        path: path.to_owned(),
+        raw,
+        prefix: Vec::new(),
+    };
+    config.shared = shared.as_ref().map(|layer| layer.path.clone());
+    config.source = Some(ConfigSource::layered(local, shared, profile));
+    config.profile = profile.map(str::to_owned);
+    Ok(config)
+}
+
+/// The effective config as `dev` sees it: shared, project, and profile layers
+/// merged (or the built-ins when there is no config file), then normalized to
+/// the current schema.
+pub fn export_resolved(path: Option<&Utf8Path>, profile: Option<&str>) -> Result<DocumentMut> {
+    let merged = match path {
+        Some(path) => {
+            let raw =
+                fs::read_to_string(path).with_context(|| format!("reading config {}", path))?;
+            layered_table(path, &raw, profile, shared_config_path()?.as_deref())?.0
+        }
+        // No config file: mirror what `dev` runs with, built-ins for detected languages.
+        None => {
+            let cwd = std::env::current_dir().context("reading current directory")?;
+            builtin_table(&detect_languages(&cwd))?
+        }
+    };
+    render_resolved(merged)
+}
+
+/// `merged` as [`export_resolved`] prints it.
+fn render_resolved(mut merged: toml::Table) -> Result<DocumentMut> {
+    // Profiles are already applied and versions differ per layer; migrate
+    // below re-stamps the version after normalizing legacy task shapes.
+    merged.remove("profiles");
+    merged.remove("version");
+
+    let mut doc: DocumentMut = toml::to_string(&merged)
+        .context("rendering resolved config")?
+        .parse()
+        .context("re-parsing resolved config")?;
+    migrate_document(&mut doc)?;
+    Ok(doc)
+}
+
+fn layered_table(
+    path: &Utf8Path,
+    raw: &str,
+    profile: Option<&str>,
+    shared_path: Option<&Utf8Path>,
+) -> Result<(toml::Table, Option<SourceLayer>)> {
+    let config = parse_checked(path, raw)?;
+
+    let mut shared = None;
+    let mut merged = toml::Table::new();
+    if let Some(shared_path) = shared_path.filter(|shared| !same_file(shared, path)).map(Utf8Path::to_owned) {
+        let shared_raw = fs::read_to_string(&shared_path)
+            .with_context(|| format!("reading shared config {}", shared_path))?;
+        // Validate each layer on its own first so errors keep their line numbers.
+        parse_checked(&shared_path, &shared_raw)?;
+        merged = toml::from_str(&shared_raw)
+            .with_context(|| format!("parsing shared config {}", shared_path))?;
+        shared = Some(SourceLayer {
+            path: shared_path,
+            raw: shared_raw,
+            prefix: Vec::new(),
+        });
+    }
+    let local: toml::Table =
+        toml::from_str(raw).with_context(|| format!("parsing config {}", path))?;
+    merge_layer(&mut merged, local, "");
+
+    if let Some(name) = profile {
+        let overrides = match merged.get("profiles").and_then(|profiles| profiles.get(name)) {
+            Some(Value::Table(overrides)) => overrides.clone(),
+            _ => {
+                let available = config
+                    .profiles
+                    .as_ref()
+                    .map(|profiles| profiles.keys().cloned().collect::<Vec<_>>().join(", "))
+                    .filter(|names| !names.is_empty())
+                    .unwrap_or_else(|| "<none>".to_owned());
+                bail!(
+                    "unknown profile `{}` in {} (available: {})",
+                    name,
+                    path,
+                    available
+                );
+            }
+        };
+        merge_layer(&mut merged, overrides, "");
+    }
+    Ok((merged, shared))
+}
+
+/// Load only the file at `path`, without shared, profile, or built-in layers.
+pub fn load_file(path: &Utf8Path) -> Result<DevConfig> {
     let raw = fs::read_to_string(path).with_context(|| format!("reading config {}", path))?;
-    toml::from_str(&raw).with_context(|| format!("parsing config {}", path))
+    let mut config = parse_checked(path, &raw)?;
+    config.source = Some(ConfigSource::new(path, raw));
+    Ok(config)
+}
+
+/// Config used when no config file exists: built-in pipelines for `languages`.
+pub fn builtin_config(languages: &[&str]) -> Result<DevConfig> {
+    Value::Table(builtin_table(languages)?)
+        .try_into()
+        .context("loading built-in pipelines")
+}
+
+fn builtin_table(languages: &[&str]) -> Result<toml::Table> {
+    let mut merged = toml::Table::new();
+    let languages: Vec<String> = languages.iter().map(|name| (*name).to_owned()).collect();
+    apply_builtin_pipelines(&mut merged, &languages)?;
+    Ok(merged)
+}
+
+/// Languages whose manifests are present in `dir`, in a stable order.
+pub fn detect_languages(dir: &Path) -> Vec<&'static str> {
+    const MANIFESTS: &[(&str, &[&str])] = &[
+        ("rust", &["Cargo.toml"]),
+        ("python", &["pyproject.toml", "setup.py", "requirements.txt"]),
+        ("typescript", &["package.json", "tsconfig.json"]),
+    ];
+    MANIFESTS
+        .iter()
+        .filter(|(_, files)| files.iter().any(|file| dir.join(file).exists()))
+        .map(|(language, _)| *language)
+        .collect()
+}
+
+/// Fill in built-in pipelines for `languages` wherever the config leaves a
+/// verb undefined, copying just the built-in tasks those pipelines need.
+fn apply_builtin_pipelines(merged: &mut toml::Table, languages: &[String]) -> Result<()> {
+    let raw = crate::templates::get_string("config/defaults.toml")?;
+    let defaults: toml::Table = toml::from_str(&raw).context("parsing built-in defaults")?;
+    let empty = toml::Table::new();
+    let builtin_tasks = defaults.get("tasks").and_then(Value::as_table).unwrap_or(&empty);
+
+    for language in languages {
+        let Some(builtin) = defaults
+            .get("languages")
+            .and_then(|languages| languages.get(language))
+            .and_then(|language| language.get("pipelines"))
+            .and_then(Value::as_table)
+        else {
+            continue;
+        };
+
+        for (verb, tasks) in builtin {
+            let pipelines = table_entry(table_entry(table_entry(merged, "languages"), language), "pipelines");
+            if pipelines.contains_key(verb) {
+                continue;
+            }
+            pipelines.insert(verb.clone(), tasks.clone());
+            for task in tasks.as_array().into_iter().flatten().filter_map(Value::as_str) {
+                copy_builtin_task(task, builtin_tasks, table_entry(merged, "tasks"));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn copy_builtin_task(name: &str, builtin: &toml::Table, tasks: &mut toml::Table) {
+    if tasks.contains_key(name) {
+        return;
+    }
+    let Some(task) = builtin.get(name) else {
+        return;
+    };
+    tasks.insert(name.to_owned(), task.clone());
+    let nested = task.get("commands").and_then(Value::as_array).into_iter().flatten();
+    for reference in nested.filter_map(Value::as_str) {
+        copy_builtin_task(reference, builtin, tasks);
+    }
+}
+
+fn table_entry<'a>(table: &'a mut toml::Table, key: &str) -> &'a mut toml::Table {
+    let entry = table
+        .entry(key.to_owned())
+        .or_insert_with(|| Value::Table(toml::Table::new()));
+    if !entry.is_table() {
+        *entry = Value::Table(toml::Table::new());
+    }
+    entry.as_table_mut().expect("entry was just made a table")
+}
+
+pub fn parse_checked(path: &Utf8Path, raw: &str) -> Result<DevConfig> {
+    let config: DevConfig =
+        toml::from_str(raw).with_context(|| format!("parsing config {}", path))?;
+    if let Some(version) = config.version
+        && version > CONFIG_VERSION
+    {
+        bail!(
+            "config {} declares version {} but this dev supports up to {}; upgrade dev",
+            path,
+            version,
+            CONFIG_VERSION
+        );
+    }
+    Ok(config)
+}
+
+/// Directory `dev config sync` clones the team config repository into.
+pub fn shared_config_dir() -> Result<Utf8PathBuf> {
+    let home = dirs::home_dir().ok_or_else(|| anyhow!("unable to determine home directory"))?;
+    Utf8PathBuf::from_path_buf(home.join(".dev").join("shared"))
+        .map_err(|_| anyhow!("home directory must be valid UTF-8"))
+}
+
+/// Config file inside the shared checkout: `config.toml` at the repo root, or
+/// `.dev/config.toml` for repos that mirror a project layout.
+pub fn shared_config_path() -> Result<Option<Utf8PathBuf>> {
+    let dir = shared_config_dir()?;
+    Ok([dir.join("config.toml"), dir.join(".dev").join("config.toml")]
+        .into_iter()
+        .find(|candidate| candidate.is_file()))
+}
+
+fn same_file(a: &Utf8Path, b: &Utf8Path) -> bool {
+    match (a.canonicalize_utf8(), b.canonicalize_utf8()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Overlay `local` onto `base`. Tables merge key by key, except individual
+/// tasks, which the project replaces wholesale rather than mixing fields.
+fn merge_layer(base: &mut toml::Table, local: toml::Table, prefix: &str) {
+    for (key, value) in local {
+        let atomic = prefix == "tasks";
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(existing)), Value::Table(incoming)) if !atomic => {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                merge_layer(existing, incoming, &path);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Upgrade a parsed config document in place to `CONFIG_VERSION`, returning a
+/// description of every change made. Untouched entries keep their comments.
+pub fn migrate_document(doc: &mut DocumentMut) -> Result<Vec<String>> {
+    let version = match doc.get("version") {
+        None => 0,
+        Some(item) => item
+            .as_integer()
+            .ok_or_else(|| anyhow!("config `version` must be an integer"))?,
+    };
+    if version > i64::from(CONFIG_VERSION) {
+        bail!(
+            "config declares version {} but this dev supports up to {}",
+            version,
+            CONFIG_VERSION
+        );
+    }
+
+    let mut changes = Vec::new();
+    if version < 1 {
+        migrate_task_shapes(doc, &mut changes)?;
+    }
+
+    if version != i64::from(CONFIG_VERSION) {
+        doc["version"] = value(i64::from(CONFIG_VERSION));
+        changes.push(format!("set version = {}", CONFIG_VERSION));
+    }
+
+    Ok(changes)
+}
+
+/// Version 0 configs accepted a few task shapes the loader no longer understands:
+/// bare `tasks.x = [...]` lists, a singular `command`, and a string `commands`.
+fn migrate_task_shapes(doc: &mut DocumentMut, changes: &mut Vec<String>) -> Result<()> {
+    let Some(tasks) = doc.get_mut("tasks").and_then(Item::as_table_like_mut) else {
+        return Ok(());
+    };
+
+    let names: Vec<String> = tasks.iter().map(|(name, _)| name.to_owned()).collect();
+    for name in names {
+        let Some(item) = tasks.get_mut(&name) else {
+            continue;
+        };
+
+        if let Some(commands) = item.as_array() {
+            let mut table = Table::new();
+            table.insert("commands", Item::Value(EditValue::Array(commands.clone())));
+            *item = Item::Table(table);
+            changes.push(format!("tasks.{}: wrapped bare command list in a table", name));
+            continue;
+        }
+
+        let Some(task) = item.as_table_like_mut() else {
+            bail!("task `{}` is neither a table nor a command list", name);
+        };
+
+        let decor = task.key("command").map(|key| key.leaf_decor().clone());
+        if let Some(command) = task.remove("command") {
+            if task.contains_key("commands") {
+                bail!("task `{}` defines both `command` and `commands`", name);
+            }
+            let argv = match command.as_value() {
+                Some(EditValue::Array(argv)) => argv.clone(),
+                Some(EditValue::String(raw)) => {
+                    let mut argv = Array::new();
+                    argv.push("bash");
+                    argv.push("-lc");
+                    argv.push(raw.value().as_str());
+                    argv
+                }
+                _ => bail!("task `{}` has unsupported `command` value", name),
+            };
+            let mut outer = Array::new();
+            outer.push(EditValue::Array(argv));
+            task.insert("commands", Item::Value(EditValue::Array(outer)));
+            if let (Some(decor), Some(mut key)) = (decor, task.key_mut("commands")) {
+                *key.leaf_decor_mut() = decor;
+            }
+            changes.push(format!("tasks.{}: replaced `command` with `commands`", name));
+        }
+
+        if let Some(reference) = task.get("commands").and_then(Item::as_str) {
+            let mut outer = Array::new();
+            outer.push(reference);
+            task.insert("commands", Item::Value(EditValue::Array(outer)));
+            changes.push(format!("tasks.{}: converted string `commands` to a list", name));
+        }
+    }
+
+    Ok(())
+}
+
+/// Keys that older configs may still carry, matched segment-wise (`*` = any name),
+/// with guidance on their replacement.
+const DEPRECATED_KEYS: &[(&str, &str)] = &[
+    ("tasks.*.command", "use `commands`; run `dev config migrate`"),
+];
+
+/// Structural differences between a config document and a reference (usually the
+/// embedded example template).
+#[derive(Debug, Default)]
+pub struct ConfigDiff {
+    pub missing_sections: Vec<String>,
+    pub missing_keys: Vec<String>,
+    pub extra_sections: Vec<String>,
+    pub deprecated: Vec<(String, &'static str)>,
+}
+
+impl ConfigDiff {
+    pub fn is_empty(&self) -> bool {
+        self.missing_sections.is_empty()
+            && self.missing_keys.is_empty()
+            && self.extra_sections.is_empty()
+            && self.deprecated.is_empty()
+    }
+}
+
+/// The embedded example config, as written by `dev config generate`.
+pub fn example_config() -> Result<String> {
+    crate::templates::get_string("config/example.config.toml")
+}
+
+/// Tables whose keys are names the user picks; their entries are compared by shape, as `*`.
+const NAMED_TABLES: &[&str] = &["tasks", "projects", "profiles", "languages", "profiles.*.tasks"];
+
+/// `path` with user-chosen names replaced by `*`, e.g. `tasks.build.commands` -> `tasks.*.commands`.
+fn schema_path(path: &str) -> String {
+    let mut schema = String::new();
+    for segment in path.split('.') {
+        let named = NAMED_TABLES.contains(&schema.as_str());
+        if !schema.is_empty() {
+            schema.push('.');
+        }
+        schema.push_str(if named { "*" } else { segment });
+    }
+    schema
+}
+
+pub fn diff_documents(active: &DocumentMut, reference: &DocumentMut) -> ConfigDiff {
+    let mut active_sections = Vec::new();
+    let mut active_keys = Vec::new();
+    collect_paths(active.as_table(), "", &mut active_sections, &mut active_keys);
+    let mut reference_sections = Vec::new();
+    let mut reference_keys = Vec::new();
+    collect_paths(reference.as_table(), "", &mut reference_sections, &mut reference_keys);
+
+    let mut diff = ConfigDiff::default();
+    for key in &active_keys {
+        if let Some((_, hint)) = DEPRECATED_KEYS
+            .iter()
+            .find(|(pattern, _)| key_matches(pattern, key))
+        {
+            diff.deprecated.push((key.clone(), hint));
+        }
+    }
+
+    let schema = |paths: Vec<String>| {
+        let mut seen = std::collections::HashSet::new();
+        let mut paths: Vec<String> = paths.iter().map(|path| schema_path(path)).collect();
+        paths.retain(|path| seen.insert(path.clone()));
+        paths
+    };
+    let (active_sections, active_keys) = (schema(active_sections), schema(active_keys));
+    let (reference_sections, reference_keys) = (schema(reference_sections), schema(reference_keys));
+    // Which fields a task or project sets is up to the user, so nothing under a named
+    // entry is reported missing; fields the reference does not know are still extra.
+    let optional = |path: &str| path.split('.').any(|segment| segment == "*");
+
+    for section in &reference_sections {
+        if !active_sections.contains(section) && !optional(section) {
+            diff.missing_sections.push(section.clone());
+        }
+    }
+    for key in &reference_keys {
+        // Keys inside a missing section are already covered by that section.
+        let parent = key.rsplit_once('.').map(|(parent, _)| parent);
+        let parent_present = parent.is_none_or(|parent| active_sections.iter().any(|s| s == parent));
+        if parent_present && !active_keys.contains(key) && !optional(key) {
+            diff.missing_keys.push(key.clone());
+        }
+    }
+    for section in &active_sections {
+        if !reference_sections.contains(section) {
+            diff.extra_sections.push(section.clone());
+        }
+    }
+    diff
+}
+
+fn collect_paths(table: &Table, prefix: &str, sections: &mut Vec<String>, keys: &mut Vec<String>) {
+    for (name, item) in table.iter() {
+        let path = if prefix.is_empty() {
+            name.to_owned()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+        match item {
+            Item::Table(child) => {
+                sections.push(path.clone());
+                collect_paths(child, &path, sections, keys);
+            }
+            _ => keys.push(path),
+        }
+    }
+}
+
+fn key_matches(pattern: &str, key: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('.').collect();
+    let key: Vec<&str> = key.split('.').collect();
+    pattern.len() == key.len()
+        && pattern
+            .iter()
+            .zip(&key)
+            .all(|(expected, actual)| *expected == "*" || expected == actual)
 }
 
 pub fn write_example_config(path: &Utf8Path, overwrite: bool) -> Result<()> {
@@ -176,12 +1043,349 @@ pub fn set_default_language(path: &Utf8Path, language: &str) -> Result<()> {
     fs::write(path, doc.to_string()).with_context(|| format!("writing config {}", path))
 }
 
+/// Remove `tasks.<name>` and its profile overrides. References to it from other tasks or pipelines make
+/// this fail unless `strip_refs` is set, in which case they are dropped too.
+/// Returns the locations whose references were removed.
+pub fn remove_task(doc: &mut DocumentMut, name: &str, strip_refs: bool) -> Result<Vec<String>> {
+    let defined = doc
+        .get("tasks")
+        .and_then(Item::as_table_like)
+        .is_some_and(|tasks| tasks.contains_key(name));
+    if !defined {
+        bail!("task `{}` is not defined", name);
+    }
+
+    let mut referenced = Vec::new();
+    visit_task_refs(doc.as_table_mut(), "", &mut |location, refs| {
+        if !refs.iter().any(|item| item.as_str() == Some(name)) {
+            return;
+        }
+        if !strip_refs {
+            referenced.push(location.to_owned());
+            return;
+        }
+        refs.retain(|item| item.as_str() != Some(name));
+        if refs.is_empty() {
+            referenced.push(format!("{} (now empty)", location));
+        } else {
+            referenced.push(location.to_owned());
+        }
+    });
+    if !referenced.is_empty() && !strip_refs {
+        bail!(
+            "task `{}` is still referenced by {}; pass --force to remove those references too",
+            name,
+            referenced.join(", ")
+        );
+    }
+
+    if let Some(tasks) = doc.get_mut("tasks").and_then(Item::as_table_like_mut) {
+        tasks.remove(name);
+    }
+    if let Some(profiles) = doc.get_mut("profiles").and_then(Item::as_table_like_mut) {
+        for (_, overrides) in profiles.iter_mut() {
+            if let Some(tasks) = overrides.get_mut("tasks").and_then(Item::as_table_like_mut) {
+                tasks.remove(name);
+            }
+        }
+    }
+    Ok(referenced)
+}
+
+/// Rename `tasks.<old>` to `tasks.<new>` (including profile overrides) and
+/// rewrite every reference to it. Returns the locations that were updated.
+pub fn rename_task(doc: &mut DocumentMut, old: &str, new: &str) -> Result<Vec<String>> {
+    let tasks = doc
+        .get_mut("tasks")
+        .and_then(Item::as_table_like_mut)
+        .ok_or_else(|| anyhow!("task `{}` is not defined", old))?;
+    if !tasks.contains_key(old) {
+        bail!("task `{}` is not defined", old);
+    }
+    if tasks.contains_key(new) {
+        bail!("task `{}` already exists", new);
+    }
+    rename_key(tasks, old, new);
+
+    let mut updated = Vec::new();
+    if let Some(profiles) = doc.get_mut("profiles").and_then(Item::as_table_like_mut) {
+        for (profile, overrides) in profiles.iter_mut() {
+            if let Some(tasks) = overrides.get_mut("tasks").and_then(Item::as_table_like_mut)
+                && tasks.contains_key(old)
+            {
+                rename_key(tasks, old, new);
+                updated.push(format!("profiles.{}.tasks.{}", profile.get(), new));
+            }
+        }
+    }
+
+    visit_task_refs(doc.as_table_mut(), "", &mut |location, refs| {
+        let mut touched = false;
+        for item in refs.iter_mut() {
+            if item.as_str() == Some(old) {
+                let decor = item.decor().clone();
+                *item = EditValue::from(new);
+                *item.decor_mut() = decor;
+                touched = true;
+            }
+        }
+        if touched {
+            updated.push(location.to_owned());
+        }
+    });
+    Ok(updated)
+}
+
+/// Re-key an entry, keeping its comments; `[tasks.x]` sections keep their place in the file.
+fn rename_key(table: &mut dyn toml_edit::TableLike, old: &str, new: &str) {
+    let Some(decor) = table.key(old).map(|key| key.leaf_decor().clone()) else {
+        return;
+    };
+    let Some(item) = table.remove(old) else {
+        return;
+    };
+    table.insert(new, item);
+    if let Some(mut key) = table.key_mut(new) {
+        *key.leaf_decor_mut() = decor;
+    }
+}
+
+/// Call `visit` with every array that can name tasks: task `commands` and
+/// language pipelines, at the top level and inside `[profiles.*]`.
+fn visit_task_refs(
+    root: &mut dyn toml_edit::TableLike,
+    prefix: &str,
+    visit: &mut dyn FnMut(&str, &mut Array),
+) {
+    if let Some(tasks) = root.get_mut("tasks").and_then(Item::as_table_like_mut) {
+        for (name, task) in tasks.iter_mut() {
+            if let Some(commands) = task.get_mut("commands").and_then(Item::as_array_mut) {
+                visit(&format!("{}tasks.{}.commands", prefix, name.get()), commands);
+            }
+        }
+    }
+    if let Some(languages) = root.get_mut("languages").and_then(Item::as_table_like_mut) {
+        for (language, spec) in languages.iter_mut() {
+            let Some(pipelines) = spec.get_mut("pipelines").and_then(Item::as_table_like_mut) else {
+                continue;
+            };
+            for (verb, tasks) in pipelines.iter_mut() {
+                if let Some(tasks) = tasks.as_array_mut() {
+                    let location =
+                        format!("{}languages.{}.pipelines.{}", prefix, language.get(), verb.get());
+                    visit(&location, tasks);
+                }
+            }
+        }
+    }
+    if prefix.is_empty()
+        && let Some(profiles) = root.get_mut("profiles").and_then(Item::as_table_like_mut)
+    {
+        for (profile, overrides) in profiles.iter_mut() {
+            if let Some(overrides) = overrides.as_table_like_mut() {
+                visit_task_refs(overrides, &format!("profiles.{}.", profile.get()), visit);
+            }
+        }
+    }
+}
+
+/// Create or update `[projects.<name>]`; only the fields given are changed.
+pub fn upsert_project(
+    doc: &mut DocumentMut,
+    name: &str,
+    chdir: Option<&str>,
+    language: Option<&str>,
+) -> Result<()> {
+    if !doc.contains_key("projects") {
+        let mut projects = Table::new();
+        projects.set_implicit(true);
+        doc.insert("projects", Item::Table(projects));
+    }
+    let projects = doc
+        .get_mut("projects")
+        .and_then(Item::as_table_like_mut)
+        .ok_or_else(|| anyhow!("config has non-table `projects` entry"))?;
+    if !projects.contains_key(name) {
+        projects.insert(name, Item::Table(Table::new()));
+    }
+    let project = projects
+        .get_mut(name)
+        .and_then(Item::as_table_like_mut)
+        .ok_or_else(|| anyhow!("project `{}` is not a table", name))?;
+    if let Some(chdir) = chdir {
+        project.insert("chdir", value(chdir));
+    }
+    if let Some(language) = language {
+        project.insert("language", value(language));
+    }
+    Ok(())
+}
+
+/// Remove `[projects.<name>]`, clearing `default_project` if it pointed there.
+/// Returns whether `default_project` was cleared.
+pub fn remove_project(doc: &mut DocumentMut, name: &str) -> Result<bool> {
+    let removed = doc
+        .get_mut("projects")
+        .and_then(Item::as_table_like_mut)
+        .and_then(|projects| projects.remove(name));
+    if removed.is_none() {
+        bail!("project `{}` is not defined", name);
+    }
+    if doc.get("default_project").and_then(Item::as_str) == Some(name) {
+        doc.remove("default_project");
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+/// Check that every `languages.*.pipelines.*` entry names a defined task, so a
+/// typo surfaces when the config loads rather than when that verb first runs.
+pub fn validate_pipeline_refs(config: &DevConfig) -> Result<()> {
+    let known: Vec<&str> = config.tasks.iter().flatten().map(|(name, _)| name.as_str()).collect();
+    let mut problems = Vec::new();
+    for (language, spec) in config.languages.iter().flatten() {
+        let Some(pipelines) = &spec.pipelines else {
+            continue;
+        };
+        for (verb, tasks) in pipelines.entries() {
+            for (idx, task) in tasks.into_iter().flatten().enumerate() {
+                if known.contains(&task.as_str()) {
+                    continue;
+                }
+                let suggestion = closest_name(task, &known)
+                    .map(|name| format!(" (did you mean `{}`?)", name))
+                    .unwrap_or_default();
+                let location = [
+                    ConfigKey::Key("languages"),
+                    ConfigKey::Key(language),
+                    ConfigKey::Key("pipelines"),
+                    ConfigKey::Key(verb),
+                    ConfigKey::Index(idx),
+                ];
+                problems.push(format!(
+                    "`{}` pipeline for language `{}` references unknown task `{}`{}{}",
+                    verb,
+                    language,
+                    task,
+                    suggestion,
+                    source_hint(config.source.as_ref(), &location)
+                ));
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        return Ok(());
+    }
+    let available = if known.is_empty() {
+        "no tasks are defined".to_owned()
+    } else {
+        format!("available tasks: {}", known.join(", "))
+    };
+    bail!("{}\n{}", problems.join("\n"), available)
+}
+
+/// Nearest known name within a small edit distance, for "did you mean" hints.
+fn closest_name<'a>(name: &str, known: &[&'a str]) -> Option<&'a str> {
+    let limit = (name.chars().count() / 3).max(2);
+    known
+        .iter()
+        .map(|candidate| (edit_distance(name, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Look up a dotted key path such as `languages.python.pipelines.test`.
+pub fn get_path<'a>(doc: &'a DocumentMut, path: &str) -> Option<&'a Item> {
+    let mut item = doc.as_item();
+    for segment in path.split('.') {
+        item = item.get(segment)?;
+    }
+    Some(item)
+}
+
+/// Set a dotted key path, creating intermediate tables as needed.
+pub fn set_path(doc: &mut DocumentMut, path: &str, new_value: EditValue) -> Result<()> {
+    let segments: Vec<&str> = path.split('.').collect();
+    if segments.iter().any(|segment| segment.is_empty()) {
+        bail!("invalid config key `{}`", path);
+    }
+    let (last, parents) = segments.split_last().expect("split always yields a segment");
+
+    let mut table = doc.as_table_mut() as &mut dyn toml_edit::TableLike;
+    for (depth, segment) in parents.iter().enumerate() {
+        if !table.contains_key(segment) {
+            let mut child = Table::new();
+            child.set_implicit(true);
+            table.insert(segment, Item::Table(child));
+        }
+        table = table
+            .get_mut(segment)
+            .and_then(Item::as_table_like_mut)
+            .ok_or_else(|| {
+                anyhow!(
+                    "`{}` is not a table; cannot set `{}`",
+                    segments[..=depth].join("."),
+                    path
+                )
+            })?;
+    }
+
+    match table.get_mut(last) {
+        // Keep the existing key's comments and formatting around the new value.
+        Some(Item::Value(existing)) => {
+            let decor = existing.decor().clone();
+            *existing = new_value;
+            *existing.decor_mut() = decor;
+        }
+        Some(Item::Table(_)) | Some(Item::ArrayOfTables(_)) => {
+            bail!("`{}` is a table; set one of its keys instead", path)
+        }
+        _ => {
+            table.insert(last, Item::Value(new_value));
+        }
+    }
+    Ok(())
+}
+
+/// Interpret a command-line value as TOML (`true`, `3`, `["a", "b"]`), falling
+/// back to a plain string so `dev config set git.main_branch main` just works.
+pub fn parse_cli_value(raw: &str) -> EditValue {
+    raw.parse::<EditValue>()
+        .map(|mut parsed| {
+            parsed.decor_mut().clear();
+            parsed
+        })
+        .unwrap_or_else(|_| EditValue::from(raw))
+}
+
 pub fn format_summary(config: &DevConfig) -> String {
     let mut out = String::new();
     let default_language = config.default_language.as_deref().unwrap_or("<none>");
     let task_count = config.tasks.as_ref().map(|t| t.len()).unwrap_or(0);
     let language_count = config.languages.as_ref().map(|l| l.len()).unwrap_or(0);
 
+    if let Some(shared) = &config.shared {
+        let _ = writeln!(out, "Shared config: {}", shared);
+    }
+    if let Some(profile) = &config.profile {
+        let _ = writeln!(out, "Profile: {}", profile);
+    }
     let _ = writeln!(out, "Default language: {}", default_language);
     let _ = writeln!(out, "Tasks defined: {}", task_count);
     let _ = writeln!(out, "Languages configured: {}", language_count);
@@ -223,27 +1427,448 @@ pub fn format_summary(config: &DevConfig) -> String {
 }
 
 fn collect_pipeline_names(pipelines: &Pipelines) -> Vec<&'static str> {
-    let mut names = Vec::new();
-    if pipelines.fmt.is_some() {
-        names.push("fmt");
+    pipelines
+        .entries()
+        .into_iter()
+        .filter(|(_, tasks)| tasks.is_some())
+        .map(|(verb, _)| verb)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrate_rewrites_legacy_task_shapes() {
+        let mut doc: DocumentMut = r#"# keep me
+default_language = "rust"
+
+[tasks]
+bare = [["cargo", "build"]]
+shell = { command = "cargo test" }
+
+[tasks.single]
+# task comment
+command = ["cargo", "fmt"]
+
+[tasks.alias]
+commands = "single"
+"#
+        .parse()
+        .unwrap();
+
+        let changes = migrate_document(&mut doc).unwrap();
+        assert_eq!(changes.len(), 5);
+
+        let rendered = doc.to_string();
+        assert!(rendered.contains("# keep me"));
+        assert!(rendered.contains("# task comment"));
+
+        let config: DevConfig = toml::from_str(&rendered).unwrap();
+        assert_eq!(config.version, Some(CONFIG_VERSION));
+        let tasks = config.tasks.unwrap();
+        assert_eq!(tasks["bare"].commands.len(), 1);
+        assert_eq!(tasks["single"].commands[0].as_array().unwrap().len(), 2);
+        assert_eq!(tasks["shell"].commands[0].as_array().unwrap()[2].as_str(), Some("cargo test"));
+        assert_eq!(tasks["alias"].commands[0].as_str(), Some("single"));
+    }
+
+    #[test]
+    fn env_mask_patterns_select_sensitive_keys() {
+        let env: EnvConfig =
+            toml::from_str(r#"mask = ["*_KEY", "*_SECRET", "PASSWORD*", "DB_*_URL"]"#).unwrap();
+        assert!(env.is_sensitive("API_KEY"));
+        assert!(env.is_sensitive("client_secret"));
+        assert!(env.is_sensitive("PASSWORD"));
+        assert!(env.is_sensitive("DB_PRIMARY_URL"));
+        assert!(!env.is_sensitive("KEY"));
+        assert!(!env.is_sensitive("DEBUG"));
+        assert!(!env.is_sensitive("DB_URL_BACKUP"));
+
+        let unset: EnvConfig = toml::from_str("").unwrap();
+        assert!(unset.is_sensitive("DEBUG"));
+    }
+
+    #[test]
+    fn path_globs_follow_gitignore_rules() {
+        assert!(path_glob_matches("src/**", "src/a/b.rs", false));
+        assert!(!path_glob_matches("src/**", "tests/src/b.rs", false));
+        assert!(path_glob_matches("*.rs", "src/a/b.rs", false));
+        assert!(!path_glob_matches("src/*.rs", "src/a/b.rs", false));
+        assert!(path_glob_matches("src/**/*.rs", "src/b.rs", false));
+        assert!(path_glob_matches("/docs", "docs", true));
+        assert!(!path_glob_matches("/docs", "site/docs", true));
+        assert!(path_glob_matches("docs", "site/docs", true));
+        assert!(path_glob_matches("build/", "build", true));
+        assert!(!path_glob_matches("build/", "build", false));
+        assert!(path_glob_matches("v?.txt", "v1.txt", false));
+        assert!(!path_glob_matches("v?.txt", "v10.txt", false));
+    }
+
+    #[test]
+    fn locate_points_at_nested_command_argument() {
+        let raw = "default_language = \"rust\"\n\n[tasks.build]\ncommands = [[\"cargo\", 1]]\n";
+        let source = ConfigSource::new(Utf8Path::new("config.toml"), raw.to_owned());
+        let location = source
+            .locate(&[
+                ConfigKey::Key("tasks"),
+                ConfigKey::Key("build"),
+                ConfigKey::Key("commands"),
+                ConfigKey::Index(0),
+                ConfigKey::Index(1),
+            ])
+            .unwrap();
+        assert_eq!((location.line, location.column), (4, 23));
+        assert_eq!(location.snippet, "commands = [[\"cargo\", 1]]");
+    }
+
+    #[test]
+    fn locate_finds_the_layer_a_value_came_from() {
+        let layer = |path: &str, raw: &str| SourceLayer {
+            path: Utf8PathBuf::from(path),
+            raw: raw.to_owned(),
+            prefix: Vec::new(),
+        };
+        let local = layer(
+            "config.toml",
+            "[tasks.build]\ncommands = [[\"cargo\", 1]]\n\n[profiles.ci.tasks.build]\ncommands = [[\"make\", 2]]\n",
+        );
+        let shared = layer("shared.toml", "[tasks.lint]\ncommands = [[\"clippy\", 3]]\n");
+        let argument = |task| {
+            [
+                ConfigKey::Key("tasks"),
+                ConfigKey::Key(task),
+                ConfigKey::Key("commands"),
+                ConfigKey::Index(0),
+                ConfigKey::Index(1),
+            ]
+        };
+
+        let base = ConfigSource::layered(local.clone(), Some(shared.clone()), None);
+        let location = base.locate(&argument("build")).unwrap();
+        assert_eq!((location.path.as_str(), location.line), ("config.toml", 2));
+        let location = base.locate(&argument("lint")).unwrap();
+        assert_eq!((location.path.as_str(), location.line), ("shared.toml", 2));
+
+        let profiled = ConfigSource::layered(local, Some(shared), Some("ci"));
+        let location = profiled.locate(&argument("build")).unwrap();
+        assert_eq!((location.path.as_str(), location.line), ("config.toml", 5));
+        assert_eq!(location.snippet, "commands = [[\"make\", 2]]");
+        // A key no layer has fully points at the deepest ancestor found.
+        let location = profiled
+            .locate(&[ConfigKey::Key("tasks"), ConfigKey::Key("lint"), ConfigKey::Key("env")])
+            .unwrap();
+        assert_eq!(location.path.as_str(), "shared.toml");
+    }
+
+    #[test]
+    fn migrate_is_noop_at_current_version() {
+        let mut doc: DocumentMut = format!("version = {}\n", CONFIG_VERSION).parse().unwrap();
+        assert!(migrate_document(&mut doc).unwrap().is_empty());
+    }
+
+    #[test]
+    fn migrate_rejects_newer_versions() {
+        let mut doc: DocumentMut = format!("version = {}\n", CONFIG_VERSION + 1).parse().unwrap();
+        assert!(migrate_document(&mut doc).is_err());
+    }
+
+    #[test]
+    fn merge_layer_replaces_tasks_but_merges_pipelines() {
+        let mut base: toml::Table = toml::from_str(
+            r#"
+[tasks.build]
+description = "shared"
+commands = [["make"]]
+
+[languages.rust.pipelines]
+fmt = ["fmt"]
+lint = ["clippy"]
+"#,
+        )
+        .unwrap();
+        let local: toml::Table = toml::from_str(
+            r#"
+[tasks.build]
+commands = [["cargo", "build"]]
+
+[languages.rust.pipelines]
+lint = ["build"]
+"#,
+        )
+        .unwrap();
+
+        merge_layer(&mut base, local, "");
+        let config: DevConfig = Value::Table(base).try_into().unwrap();
+        let build = &config.tasks.as_ref().unwrap()["build"];
+        assert!(build.description.is_none());
+        let languages = config.languages.unwrap();
+        let pipelines = languages["rust"].pipelines.as_ref().unwrap();
+        assert_eq!(pipelines.fmt, Some(vec!["fmt".to_owned()]));
+        assert_eq!(pipelines.lint, Some(vec!["build".to_owned()]));
     }
-    if pipelines.lint.is_some() {
-        names.push("lint");
+
+    #[test]
+    fn set_path_creates_tables_and_keeps_comments() {
+        let mut doc: DocumentMut = "# top\n[git]\nmain_branch = \"main\" # trunk\n".parse().unwrap();
+        set_path(&mut doc, "git.main_branch", parse_cli_value("develop")).unwrap();
+        set_path(&mut doc, "languages.python.pipelines.test", parse_cli_value("[\"pytest\"]"))
+            .unwrap();
+
+        let rendered = doc.to_string();
+        assert!(rendered.contains("main_branch = \"develop\" # trunk"), "{rendered}");
+        assert_eq!(
+            get_path(&doc, "languages.python.pipelines.test")
+                .and_then(Item::as_array)
+                .map(|array| array.len()),
+            Some(1)
+        );
+        assert!(set_path(&mut doc, "git.main_branch.nested", parse_cli_value("x")).is_err());
     }
-    if pipelines.type_check.is_some() {
-        names.push("type");
+
+    #[test]
+    fn profile_overrides_tasks_and_git() {
+        let dir = crate::testutil::unique_temp_dir("profile");
+        let path = dir.join("config.toml");
+        fs::write(
+            &path,
+            r#"
+[tasks.deploy]
+commands = [["echo", "dev"]]
+
+[git]
+main_branch = "main"
+
+[profiles.staging.tasks.deploy]
+commands = [["echo", "staging"]]
+
+[profiles.staging.git]
+main_branch = "staging"
+"#,
+        )
+        .unwrap();
+
+        let shared = dir.join("shared.toml");
+        fs::write(&shared, "[tasks.lint]\ncommands = [[\"cargo\", \"clippy\"]]\n").unwrap();
+
+        let base = load_layered(&path, None, None).unwrap();
+        assert_eq!(base.git.unwrap().main_branch.as_deref(), Some("main"));
+        assert!(base.shared.is_none());
+
+        let staging = load_layered(&path, Some(&shared), Some("staging")).unwrap();
+        assert_eq!(staging.git.unwrap().main_branch.as_deref(), Some("staging"));
+        assert_eq!(staging.shared.as_deref(), Some(shared.as_path()));
+        let tasks = staging.tasks.unwrap();
+        assert_eq!(tasks["deploy"].commands[0].as_array().unwrap()[1].as_str(), Some("staging"));
+        assert!(tasks.contains_key("lint"));
+
+        assert!(load_layered(&path, None, Some("prod")).is_err());
+        let _ = fs::remove_dir_all(&dir);
     }
-    if pipelines.test.is_some() {
-        names.push("test");
+
+    #[test]
+    fn git_hooks_parse_under_git() {
+        let config: DevConfig = toml::from_str(
+            r#"
+[git]
+finalize_strategy = "squash"
+require_ci = ["check"]
+
+[git.hooks]
+pre_bump = ["lint"]
+post_write = ["build"]
+post_bump = ["publish", "notify"]
+"#,
+        )
+        .unwrap();
+        let git = config.git.unwrap();
+        assert_eq!(git.finalize_strategy.as_deref(), Some("squash"));
+        assert_eq!(git.require_ci.unwrap(), ["check"]);
+        let hooks = git.hooks.unwrap();
+        assert_eq!(hooks.pre_bump.unwrap(), ["lint"]);
+        assert_eq!(hooks.post_write.unwrap(), ["build"]);
+        assert_eq!(hooks.post_bump.unwrap(), ["publish", "notify"]);
     }
-    if pipelines.fix.is_some() {
-        names.push("fix");
+
+    #[test]
+    fn builtin_pipelines_fill_only_missing_verbs() {
+        let mut merged: toml::Table = toml::from_str(
+            r#"
+default_language = "rust"
+
+[tasks.unit]
+commands = [["cargo", "nextest", "run"]]
+
+[languages.rust.pipelines]
+test = ["unit"]
+"#,
+        )
+        .unwrap();
+        apply_builtin_pipelines(&mut merged, &["rust".to_owned()]).unwrap();
+        let config: DevConfig = Value::Table(merged).try_into().unwrap();
+
+        let languages = config.languages.as_ref().unwrap();
+        let pipelines = languages["rust"].pipelines.as_ref().unwrap();
+        assert_eq!(pipelines.test, Some(vec!["unit".to_owned()]));
+        assert_eq!(pipelines.fmt, Some(vec!["builtin_rust_fmt".to_owned()]));
+        assert!(!languages.contains_key("python"));
+
+        let tasks = config.tasks.as_ref().unwrap();
+        // `check` references the lint/test tasks, which come along with it.
+        assert!(tasks.contains_key("builtin_rust_lint"));
+        assert!(tasks.contains_key("builtin_rust_test"));
     }
-    if pipelines.check.is_some() {
-        names.push("check");
+
+    #[test]
+    fn loaded_config_gets_no_builtin_pipelines() {
+        let dir = crate::testutil::unique_temp_dir("no-builtins");
+        let path = dir.join("config.toml");
+        fs::write(
+            &path,
+            r#"
+default_language = "rust"
+
+[tasks.unit]
+commands = [["cargo", "nextest", "run"]]
+
+[languages.rust.pipelines]
+test = ["unit"]
+"#,
+        )
+        .unwrap();
+
+        let config = load_layered(&path, None, None).unwrap();
+        let pipelines = config.languages.as_ref().unwrap()["rust"].pipelines.as_ref().unwrap();
+        assert_eq!(pipelines.test, Some(vec!["unit".to_owned()]));
+        assert_eq!(pipelines.fmt, None);
+        let tasks = config.tasks.as_ref().unwrap();
+        assert_eq!(tasks.keys().collect::<Vec<_>>(), ["unit"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rename_and_remove_task_update_references() {
+        let mut doc: DocumentMut = r#"
+[tasks.build]
+# compile
+commands = [["cargo", "build"]]
+
+[tasks.ci]
+commands = ["build", ["cargo", "test"]]
+
+[languages.rust.pipelines]
+check = ["build"]
+"#
+        .parse()
+        .unwrap();
+
+        let updated = rename_task(&mut doc, "build", "compile").unwrap();
+        assert_eq!(updated, ["tasks.ci.commands", "languages.rust.pipelines.check"]);
+        let rendered = doc.to_string();
+        assert!(rendered.contains("[tasks.compile]\n# compile"), "{rendered}");
+        assert!(rendered.contains(r#"commands = ["compile", ["cargo", "test"]]"#));
+
+        assert!(remove_task(&mut doc, "compile", false).is_err());
+        let stripped = remove_task(&mut doc, "compile", true).unwrap();
+        assert_eq!(
+            stripped,
+            ["tasks.ci.commands", "languages.rust.pipelines.check (now empty)"]
+        );
+        assert!(get_path(&doc, "tasks.compile").is_none());
+    }
+
+    #[test]
+    fn validate_pipeline_refs_suggests_close_names() {
+        let config: DevConfig = toml::from_str(
+            r#"
+[tasks.unit]
+commands = [["cargo", "test"]]
+
+[languages.rust.pipelines]
+test = ["unti"]
+"#,
+        )
+        .unwrap();
+        let error = validate_pipeline_refs(&config).unwrap_err().to_string();
+        assert!(error.contains("unknown task `unti` (did you mean `unit`?)"), "{error}");
+        assert!(error.contains("available tasks: unit"), "{error}");
+    }
+
+    #[test]
+    fn export_resolved_applies_profile_and_drops_profiles() {
+        let dir = crate::testutil::unique_temp_dir("export");
+        let path = dir.join("config.toml");
+        fs::write(
+            &path,
+            r#"
+[tasks.unit]
+commands = [["cargo", "test"]]
+
+[profiles.ci.tasks.unit]
+commands = [["cargo", "test", "--locked"]]
+"#,
+        )
+        .unwrap();
+
+        let raw = fs::read_to_string(&path).unwrap();
+        let doc = render_resolved(layered_table(&path, &raw, Some("ci"), None).unwrap().0).unwrap();
+        assert!(doc.get("profiles").is_none());
+        assert_eq!(doc["version"].as_integer(), Some(i64::from(CONFIG_VERSION)));
+        let unit = get_path(&doc, "tasks.unit.commands").unwrap().to_string();
+        assert!(unit.contains("--locked"), "{unit}");
+        let _ = fs::remove_dir_all(&dir);
     }
-    if pipelines.ci.is_some() {
-        names.push("ci");
+
+    #[test]
+    fn project_upsert_and_remove() {
+        let mut doc: DocumentMut = "default_project = \"api\"\n".parse().unwrap();
+        upsert_project(&mut doc, "api", Some("services/api"), Some("python")).unwrap();
+        upsert_project(&mut doc, "api", None, Some("rust")).unwrap();
+        assert_eq!(
+            get_path(&doc, "projects.api.chdir").and_then(Item::as_str),
+            Some("services/api")
+        );
+        assert_eq!(get_path(&doc, "projects.api.language").and_then(Item::as_str), Some("rust"));
+
+        assert!(remove_project(&mut doc, "api").unwrap());
+        assert!(doc.get("default_project").is_none());
+        assert!(remove_project(&mut doc, "api").is_err());
+    }
+
+    #[test]
+    fn diff_compares_user_named_entries_by_shape() {
+        let reference: DocumentMut = r#"
+            [tasks.build]
+            description = "Build"
+            commands = [["cargo", "build"]]
+            [profiles.ci.tasks.test]
+            commands = [["cargo", "test"]]
+            [git]
+            main_branch = "main"
+        "#
+        .parse()
+        .unwrap();
+        let active: DocumentMut = r#"
+            [tasks.deploy-site]
+            commands = [["./deploy"]]
+            [tasks.deploy-site.bogus]
+            x = 1
+            [profiles.staging.tasks.smoke]
+            commands = [["./smoke"]]
+            [git]
+            main_branch = "trunk"
+        "#
+        .parse()
+        .unwrap();
+        let diff = diff_documents(&active, &reference);
+        assert!(diff.missing_sections.is_empty(), "{:?}", diff);
+        assert!(diff.missing_keys.is_empty(), "{:?}", diff);
+        assert_eq!(diff.extra_sections, ["tasks.*.bogus"]);
+
+        let legacy: DocumentMut = "[tasks.old]\ncommand = \"make\"\n".parse().unwrap();
+        let diff = diff_documents(&legacy, &reference);
+        assert_eq!(diff.deprecated[0].0, "tasks.old.command");
+        assert!(diff.missing_sections.contains(&"git".to_owned()));
     }
-    names
 }
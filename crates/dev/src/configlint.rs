@@ -0,0 +1,238 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Result, anyhow};
+use toml_edit::{Array, DocumentMut, Item, Value as EditValue};
+
+use crate::config::{ConfigKey, DevConfig};
+
+/// Characters that need a shell to interpret; commands without them can run as argv.
+const SHELL_SYNTAX: &[char] = &[
+    '|', '&', ';', '<', '>', '(', ')', '$', '`', '\\', '"', '\'', '*', '?', '[', ']', '#', '~',
+    '=', '%', '{', '}', '\n',
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    NoDescription,
+    UnnecessaryShell,
+    EmptyPipeline,
+    DuplicateCommand,
+}
+
+impl Rule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Rule::NoDescription => "no-description",
+            Rule::UnnecessaryShell => "unnecessary-shell",
+            Rule::EmptyPipeline => "empty-pipeline",
+            Rule::DuplicateCommand => "duplicate-command",
+        }
+    }
+}
+
+/// How `--fix` resolves a finding, when it can be fixed mechanically.
+#[derive(Debug, Clone)]
+enum Fix {
+    /// Replace `tasks.<task>.commands[<index>]` with a plain argv.
+    UnwrapShell {
+        task: String,
+        index: usize,
+        argv: Vec<String>,
+    },
+    /// Drop `languages.<language>.pipelines.<verb>`.
+    RemovePipeline { language: String, verb: &'static str },
+}
+
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub rule: Rule,
+    /// Dotted config path the finding refers to, e.g. `tasks.build`.
+    pub path: String,
+    pub line: Option<usize>,
+    pub message: String,
+    pub suggestion: String,
+    fix: Option<Fix>,
+}
+
+impl Finding {
+    pub fn fixable(&self) -> bool {
+        self.fix.is_some()
+    }
+}
+
+pub fn lint(config: &DevConfig) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let line_of = |keys: &[ConfigKey<'_>]| {
+        config
+            .source
+            .as_ref()
+            .and_then(|source| source.locate(keys))
+            .map(|location| location.line)
+    };
+
+    let mut seen_commands: BTreeMap<Vec<String>, Vec<String>> = BTreeMap::new();
+    for (name, task) in config.tasks.iter().flatten() {
+        let task_keys = [ConfigKey::Key("tasks"), ConfigKey::Key(name)];
+        if task.description.as_deref().is_none_or(|d| d.trim().is_empty()) {
+            findings.push(Finding {
+                rule: Rule::NoDescription,
+                path: format!("tasks.{}", name),
+                line: line_of(&task_keys),
+                message: "task has no description".to_owned(),
+                suggestion: "add `description = \"...\"` so `dev list` can explain it".to_owned(),
+                fix: None,
+            });
+        }
+
+        for (index, command) in task.commands.iter().enumerate() {
+            let Some(argv) = command.as_array().and_then(|items| {
+                items
+                    .iter()
+                    .map(|item| item.as_str().map(str::to_owned))
+                    .collect::<Option<Vec<_>>>()
+            }) else {
+                continue;
+            };
+
+            if let Some(unwrapped) = unwrap_shell(&argv) {
+                findings.push(Finding {
+                    rule: Rule::UnnecessaryShell,
+                    path: format!("tasks.{}.commands[{}]", name, index),
+                    line: line_of(&[
+                        ConfigKey::Key("tasks"),
+                        ConfigKey::Key(name),
+                        ConfigKey::Key("commands"),
+                        ConfigKey::Index(index),
+                    ]),
+                    message: format!("`{}` shells through bash -lc unnecessarily", argv[2]),
+                    suggestion: format!("use the argv form {:?}", unwrapped),
+                    fix: Some(Fix::UnwrapShell {
+                        task: name.clone(),
+                        index,
+                        argv: unwrapped,
+                    }),
+                });
+            }
+
+            let tasks = seen_commands.entry(argv).or_default();
+            if !tasks.contains(name) {
+                tasks.push(name.clone());
+            }
+        }
+    }
+
+    for (argv, tasks) in &seen_commands {
+        if tasks.len() > 1 {
+            findings.push(Finding {
+                rule: Rule::DuplicateCommand,
+                path: format!("tasks.{}", tasks[0]),
+                line: line_of(&[ConfigKey::Key("tasks"), ConfigKey::Key(&tasks[0])]),
+                message: format!(
+                    "command `{}` is repeated in tasks {}",
+                    argv.join(" "),
+                    tasks.join(", ")
+                ),
+                suggestion: "define it once and reference that task by name".to_owned(),
+                fix: None,
+            });
+        }
+    }
+
+    for (language, spec) in config.languages.iter().flatten() {
+        let Some(pipelines) = &spec.pipelines else {
+            continue;
+        };
+        for (verb, tasks) in pipelines.entries() {
+            if tasks.is_some_and(Vec::is_empty) {
+                findings.push(Finding {
+                    rule: Rule::EmptyPipeline,
+                    path: format!("languages.{}.pipelines.{}", language, verb),
+                    line: line_of(&[
+                        ConfigKey::Key("languages"),
+                        ConfigKey::Key(language),
+                        ConfigKey::Key("pipelines"),
+                        ConfigKey::Key(verb),
+                    ]),
+                    message: format!("`{}` pipeline is empty", verb),
+                    suggestion: "list tasks to run or remove the entry".to_owned(),
+                    fix: Some(Fix::RemovePipeline {
+                        language: language.clone(),
+                        verb,
+                    }),
+                });
+            }
+        }
+    }
+
+    findings
+}
+
+/// Apply every mechanical fix to `doc`, returning the findings that were fixed.
+pub fn apply_fixes<'a>(doc: &mut DocumentMut, findings: &'a [Finding]) -> Result<Vec<&'a Finding>> {
+    let mut fixed = Vec::new();
+    for finding in findings {
+        let Some(fix) = &finding.fix else {
+            continue;
+        };
+        match fix {
+            Fix::UnwrapShell { task, index, argv } => {
+                let commands = doc
+                    .get_mut("tasks")
+                    .and_then(|tasks| tasks.get_mut(task.as_str()))
+                    .and_then(|task| task.get_mut("commands"))
+                    .and_then(Item::as_array_mut)
+                    .ok_or_else(|| anyhow!("tasks.{}.commands is not an array", task))?;
+                let mut replacement = Array::new();
+                for arg in argv {
+                    replacement.push(arg.as_str());
+                }
+                commands.replace(*index, EditValue::Array(replacement));
+            }
+            Fix::RemovePipeline { language, verb } => {
+                let pipelines = doc
+                    .get_mut("languages")
+                    .and_then(|languages| languages.get_mut(language.as_str()))
+                    .and_then(|language| language.get_mut("pipelines"))
+                    .and_then(Item::as_table_like_mut)
+                    .ok_or_else(|| anyhow!("languages.{}.pipelines is not a table", language))?;
+                pipelines.remove(verb);
+            }
+        }
+        fixed.push(finding);
+    }
+    Ok(fixed)
+}
+
+/// `["bash", "-lc", "cargo build"]` -> `["cargo", "build"]` when no shell syntax is used.
+fn unwrap_shell(argv: &[String]) -> Option<Vec<String>> {
+    let [shell, flag, script] = argv else {
+        return None;
+    };
+    if !matches!(shell.as_str(), "bash" | "sh") || !matches!(flag.as_str(), "-lc" | "-c") {
+        return None;
+    }
+    if script.trim().is_empty() || script.contains(SHELL_SYNTAX) {
+        return None;
+    }
+    Some(script.split_whitespace().map(str::to_owned).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn unwrap_shell_only_for_plain_commands() {
+        assert_eq!(
+            unwrap_shell(&argv(&["bash", "-lc", "cargo test --all"])),
+            Some(argv(&["cargo", "test", "--all"]))
+        );
+        assert_eq!(unwrap_shell(&argv(&["bash", "-lc", "cargo test && echo ok"])), None);
+        assert_eq!(unwrap_shell(&argv(&["bash", "-lc", "echo $HOME"])), None);
+        assert_eq!(unwrap_shell(&argv(&["cargo", "test"])), None);
+    }
+}
@@ -0,0 +1,133 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::config::path_glob_matches;
+
+pub const FILE_NAME: &str = ".devignore";
+
+/// A project's `.devignore`: gitignore-syntax patterns that `dev walk` and `dev review`
+/// leave out, on top of walk's built-in list.
+#[derive(Debug, Default)]
+pub struct DevIgnore {
+    rules: Vec<Rule>,
+    /// Where the paths asked about sit relative to the `.devignore`: `/`-terminated, or
+    /// empty when they are relative to its own directory.
+    prefix: String,
+}
+
+#[derive(Debug)]
+struct Rule {
+    pattern: String,
+    /// `!pattern`: re-include what an earlier rule (or the built-in list) ignores.
+    negated: bool,
+}
+
+impl DevIgnore {
+    pub fn parse(text: &str) -> Self {
+        let rules = text
+            .lines()
+            .map(str::trim_end)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let (negated, pattern) = match line.strip_prefix('!') {
+                    Some(pattern) => (true, pattern),
+                    None => (false, line),
+                };
+                // `\#` and `\!` escape a leading `#` or `!`.
+                let pattern = pattern.strip_prefix('\\').unwrap_or(pattern);
+                Rule {
+                    pattern: pattern.to_string(),
+                    negated,
+                }
+            })
+            .collect();
+        DevIgnore {
+            rules,
+            prefix: String::new(),
+        }
+    }
+
+    /// The nearest `.devignore` in `dir` or above it, stopping at the enclosing git
+    /// repository's root; no file means no rules. Paths passed in later are relative
+    /// to `dir`.
+    pub fn find(dir: &Path) -> Result<Self> {
+        let dir = dir
+            .canonicalize()
+            .with_context(|| format!("failed to resolve {}", dir.display()))?;
+        for ancestor in dir.ancestors() {
+            let path = ancestor.join(FILE_NAME);
+            if path.is_file() {
+                let text = fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read {}", path.display()))?;
+                let mut ignore = Self::parse(&text);
+                let below: Vec<String> = dir
+                    .strip_prefix(ancestor)
+                    .unwrap_or(Path::new(""))
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy().into_owned())
+                    .collect();
+                ignore.prefix = below
+                    .iter()
+                    .map(|segment| format!("{}/", segment))
+                    .collect();
+                return Ok(ignore);
+            }
+            if ancestor.join(".git").exists() {
+                break;
+            }
+        }
+        Ok(Self::default())
+    }
+
+    /// What the last rule matching `path` says: `Some(true)` to ignore it, `Some(false)`
+    /// when a `!` rule re-includes it, `None` when no rule matches.
+    pub fn decision(&self, path: &str, is_dir: bool) -> Option<bool> {
+        let path = format!("{}{}", self.prefix, path);
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| path_glob_matches(&rule.pattern, &path, is_dir))
+            .map(|rule| !rule.negated)
+    }
+
+    /// Whether a file is ignored, itself or through one of its directories, for flat
+    /// path lists such as a diff's. As in git, nothing inside an ignored directory
+    /// can be re-included.
+    pub fn is_ignored(&self, path: &str) -> bool {
+        path.match_indices('/')
+            .map(|(index, _)| (&path[..index], true))
+            .chain([(path, false)])
+            .any(|(path, is_dir)| self.decision(path, is_dir) == Some(true))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_matching_rule_wins() {
+        let ignore = DevIgnore::parse(
+            "# generated code\n*.gen.rs\n!keep.gen.rs\nfixtures/\n\\#notes.md\n\n/dist\n",
+        );
+        assert_eq!(ignore.decision("src/api.gen.rs", false), Some(true));
+        assert_eq!(ignore.decision("src/keep.gen.rs", false), Some(false));
+        assert_eq!(ignore.decision("src/main.rs", false), None);
+        assert_eq!(ignore.decision("tests/fixtures", true), Some(true));
+        assert_eq!(ignore.decision("#notes.md", false), Some(true));
+        assert_eq!(ignore.decision("web/dist", true), None);
+
+        assert!(ignore.is_ignored("tests/fixtures/keep.gen.rs"));
+        assert!(ignore.is_ignored("dist/app.js"));
+        assert!(!ignore.is_ignored("src/keep.gen.rs"));
+
+        let nested = DevIgnore {
+            prefix: "web/".to_string(),
+            ..ignore
+        };
+        assert_eq!(nested.decision("dist", true), None);
+        assert_eq!(nested.decision("a.gen.rs", false), Some(true));
+    }
+}
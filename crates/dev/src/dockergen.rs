@@ -4,17 +4,38 @@ use std::path::Path;
 use anyhow::{Context, Result, bail};
 
 use crate::cli::DockerInitArgs;
+use crate::config::EnvConfig;
 use crate::templates;
+use crate::vars::Vars;
 
-pub fn init(args: &DockerInitArgs, dry_run: bool) -> Result<()> {
+const DEFAULT_BASE_IMAGE: &str = "nvcr.io/nvidia/pytorch:25.09-py3";
+const DEFAULT_SERVICE: &str = "core";
+
+/// Scaffold the docker files. `--base-image`/`--service` win over `[vars]`
+/// entries of the same name, which win over the built-in defaults. Keys from
+/// `[env] required`/`optional` are passed through to the service and get
+/// placeholders in the generated `.env`.
+pub fn init(args: &DockerInitArgs, vars: &Vars, env: Option<&EnvConfig>, dry_run: bool) -> Result<()> {
     let docker_dir = Path::new("docker");
     let dockerfile_path = docker_dir.join("Dockerfile.core");
     let compose_path = Path::new("docker-compose.yml");
     let env_path = Path::new(".env");
 
-    let dockerfile = render_dockerfile_core(&args.base_image)?;
-    let compose = render_compose(&args.service)?;
-    let env_file = render_env();
+    let mut vars = vars.clone();
+    let base_image = resolve_arg(&vars, args.base_image.as_deref(), "base_image", DEFAULT_BASE_IMAGE)?;
+    let service = resolve_arg(&vars, args.service.as_deref(), "service", DEFAULT_SERVICE)?;
+    vars.insert("base_image", base_image);
+    vars.insert("service", service);
+
+    let dockerfile = vars.expand(&load_template("docker/Dockerfile.core")?)?;
+    let required = env.and_then(|env| env.required.as_deref()).unwrap_or_default();
+    let optional = env.and_then(|env| env.optional.as_deref()).unwrap_or_default();
+    let compose = wire_env(
+        &vars.expand(&load_template("services/docker-compose.yml")?)?,
+        required,
+        optional,
+    );
+    let env_file = render_env(required, optional);
 
     if dry_run {
         println!("[dry-run] would create {}", dockerfile_path.display());
@@ -46,16 +67,98 @@ fn load_template(path: &str) -> Result<String> {
     templates::get_string(path)
 }
 
-fn render_dockerfile_core(base_image: &str) -> Result<String> {
-    let template = load_template("docker/Dockerfile.core")?;
-    Ok(template.replace("{{base_image}}", base_image))
+fn resolve_arg(vars: &Vars, flag: Option<&str>, name: &str, default: &str) -> Result<String> {
+    match flag {
+        Some(value) => vars.expand(value),
+        None => Ok(vars.get(name).unwrap_or(default).to_owned()),
+    }
 }
 
-fn render_compose(service: &str) -> Result<String> {
-    let template = load_template("services/docker-compose.yml")?;
-    Ok(template.replace("{{service}}", service))
+/// Add `env_file: [.env]` to the service and list the configured keys under its
+/// `environment:`; required keys use `${KEY:?...}` so compose fails fast when unset.
+fn wire_env(compose: &str, required: &[String], optional: &[String]) -> String {
+    if required.is_empty() && optional.is_empty() {
+        return compose.to_owned();
+    }
+
+    let mut entries: Vec<String> = required
+        .iter()
+        .map(|key| format!("      - {0}=${{{0}:?set {0} in .env}}", key))
+        .collect();
+    entries.extend(
+        optional
+            .iter()
+            .filter(|key| !required.contains(key))
+            .map(|key| format!("      - {0}=${{{0}:-}}", key)),
+    );
+
+    let mut out = Vec::new();
+    let mut in_environment = false;
+    for line in compose.lines() {
+        if in_environment && !line.starts_with("      - ") {
+            out.append(&mut entries);
+            in_environment = false;
+        }
+        if line == "    environment:" {
+            out.push("    env_file:".to_owned());
+            out.push("      - .env".to_owned());
+            in_environment = true;
+        }
+        out.push(line.to_owned());
+    }
+    out.append(&mut entries);
+
+    let mut rendered = out.join("\n");
+    rendered.push('\n');
+    rendered
 }
 
-fn render_env() -> String {
-    "UID=1000\nGID=1000\n".to_owned()
+fn render_env(required: &[String], optional: &[String]) -> String {
+    let mut out = "UID=1000\nGID=1000\n".to_owned();
+    let optional: Vec<&String> = optional.iter().filter(|key| !required.contains(key)).collect();
+    if !required.is_empty() {
+        out.push_str("\n# Required ([env] required)\n");
+        for key in required {
+            out.push_str(&format!("{}=\n", key));
+        }
+    }
+    if !optional.is_empty() {
+        out.push_str("\n# Optional ([env] optional)\n");
+        for key in optional {
+            out.push_str(&format!("{}=\n", key));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_keys_are_wired_into_compose_and_env() {
+        let compose = "services:\n  core:\n    environment:\n      - HOME=/home/dev\n    user: dev\n";
+        let required = vec!["DATABASE_URL".to_owned()];
+        let optional = vec!["DEBUG".to_owned()];
+
+        assert_eq!(
+            wire_env(compose, &required, &optional),
+            concat!(
+                "services:\n",
+                "  core:\n",
+                "    env_file:\n",
+                "      - .env\n",
+                "    environment:\n",
+                "      - HOME=/home/dev\n",
+                "      - DATABASE_URL=${DATABASE_URL:?set DATABASE_URL in .env}\n",
+                "      - DEBUG=${DEBUG:-}\n",
+                "    user: dev\n",
+            )
+        );
+        assert_eq!(wire_env(compose, &[], &[]), compose);
+        assert_eq!(
+            render_env(&required, &optional),
+            "UID=1000\nGID=1000\n\n# Required ([env] required)\nDATABASE_URL=\n\n# Optional ([env] optional)\nDEBUG=\n"
+        );
+    }
 }
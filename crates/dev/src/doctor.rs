@@ -0,0 +1,238 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+
+use crate::setup::{Component, SetupConfig, SetupContext};
+use crate::{envfile, scaffold};
+
+pub struct DoctorOptions {
+    pub language: Option<String>,
+    pub fix: bool,
+    pub dry_run: bool,
+}
+
+/// Individual health checks run by `dev doctor`. Every check has a matching fix
+/// that reuses the command a user would otherwise run by hand.
+#[derive(Clone, Copy, Debug)]
+enum Check {
+    Scaffold,
+    GitLfs,
+    EnvFile,
+    GitHooks,
+}
+
+impl Check {
+    const ALL: [Check; 4] = [Check::Scaffold, Check::GitLfs, Check::EnvFile, Check::GitHooks];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Check::Scaffold => "scaffold",
+            Check::GitLfs => "git-lfs",
+            Check::EnvFile => "env",
+            Check::GitHooks => "hooks",
+        }
+    }
+
+    /// Describe the problem found, or `None` when the check passes or does not apply.
+    fn diagnose(&self, opts: &DoctorOptions) -> Result<Option<String>> {
+        match self {
+            Check::Scaffold => {
+                let Some(language) = opts.language.as_deref() else {
+                    return Ok(None);
+                };
+                let Ok(missing) = scaffold::missing_files(language) else {
+                    return Ok(None);
+                };
+                if missing.is_empty() {
+                    Ok(None)
+                } else {
+                    Ok(Some(format!(
+                        "missing {} scaffold files: {}",
+                        language,
+                        missing.join(", ")
+                    )))
+                }
+            }
+            Check::GitLfs => {
+                let Some(root) = repo_root() else {
+                    return Ok(None);
+                };
+                let attributes = fs::read_to_string(root.join(".gitattributes")).unwrap_or_default();
+                if !attributes.contains("filter=lfs") {
+                    return Ok(None);
+                }
+                if git_succeeds(&["config", "--get", "filter.lfs.process"]) {
+                    Ok(None)
+                } else {
+                    Ok(Some(
+                        ".gitattributes uses LFS but git-lfs is not initialized".to_owned(),
+                    ))
+                }
+            }
+            Check::EnvFile => {
+                let cwd = envfile::current_working_dir()?;
+                let env_path = envfile::locate(&cwd)?;
+                if env_path.exists() {
+                    return Ok(None);
+                }
+                let example = envfile::example_path(&env_path)?;
+                if example.exists() {
+                    Ok(Some(format!("{} missing but {} exists", env_path, example)))
+                } else {
+                    Ok(None)
+                }
+            }
+            Check::GitHooks => {
+                let Some(root) = repo_root() else {
+                    return Ok(None);
+                };
+                if !root.join(".pre-commit-config.yaml").exists() {
+                    return Ok(None);
+                }
+                match pre_commit_hook_path() {
+                    Some(hook) if hook.exists() => Ok(None),
+                    _ => Ok(Some(
+                        ".pre-commit-config.yaml present but pre-commit hook is not installed"
+                            .to_owned(),
+                    )),
+                }
+            }
+        }
+    }
+
+    /// Apply the fix for this check, returning a short description of what changed.
+    fn fix(&self, opts: &DoctorOptions) -> Result<String> {
+        match self {
+            Check::Scaffold => {
+                let language = opts
+                    .language
+                    .as_deref()
+                    .context("no language selected for scaffold fix")?;
+                let missing = scaffold::missing_files(language)?;
+                if !opts.dry_run {
+                    scaffold::install(language)?;
+                }
+                Ok(format!("created {}", missing.join(", ")))
+            }
+            Check::GitLfs => {
+                let setup_ctx = SetupContext::new(opts.dry_run, None, SetupConfig::default())?;
+                Component::GitLfs.install(&setup_ctx)?;
+                Ok("ran `git lfs install`".to_owned())
+            }
+            Check::EnvFile => {
+                let cwd = envfile::current_working_dir()?;
+                let env_path = envfile::locate(&cwd)?;
+                let example = if opts.dry_run {
+                    envfile::example_path(&env_path)?
+                } else {
+                    envfile::init_from_example(&env_path, None)?
+                };
+                Ok(format!("created {} from {}", env_path, example))
+            }
+            Check::GitHooks => {
+                let argv = ["pre-commit", "install"];
+                if !opts.dry_run {
+                    let status = Command::new(argv[0])
+                        .args(&argv[1..])
+                        .status()
+                        .with_context(|| format!("running `{}`", argv.join(" ")))?;
+                    if !status.success() {
+                        bail!(
+                            "command `{}` failed with exit code {:?}",
+                            argv.join(" "),
+                            status.code()
+                        );
+                    }
+                }
+                Ok(format!("ran `{}`", argv.join(" ")))
+            }
+        }
+    }
+}
+
+/// Run every check, optionally fixing failures and re-verifying afterwards.
+pub fn run(opts: &DoctorOptions) -> Result<()> {
+    let mut problems = Vec::new();
+    for check in Check::ALL {
+        match check.diagnose(opts)? {
+            Some(problem) => {
+                println!("[warn] {}: {}", check.name(), problem);
+                problems.push(check);
+            }
+            None => println!("[ok] {}", check.name()),
+        }
+    }
+
+    if problems.is_empty() {
+        println!("No issues found.");
+        return Ok(());
+    }
+
+    if !opts.fix {
+        println!("Run `dev doctor --fix` to apply automatic fixes.");
+        bail!("doctor found {} issue(s)", problems.len());
+    }
+
+    println!("Applying fixes:");
+    let mut unresolved = 0;
+    for check in problems {
+        let change = match check.fix(opts) {
+            Ok(change) => change,
+            Err(err) => {
+                println!("[error] {}: fix failed: {:#}", check.name(), err);
+                unresolved += 1;
+                continue;
+            }
+        };
+
+        if opts.dry_run {
+            println!("[dry-run] {}: would have {}", check.name(), change);
+            continue;
+        }
+
+        match check.diagnose(opts)? {
+            None => println!("[fixed] {}: {}", check.name(), change),
+            Some(problem) => {
+                println!("[error] {}: still failing after fix: {}", check.name(), problem);
+                unresolved += 1;
+            }
+        }
+    }
+
+    if unresolved > 0 {
+        bail!("{} issue(s) could not be fixed automatically", unresolved);
+    }
+    Ok(())
+}
+
+fn repo_root() -> Option<PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()))
+}
+
+fn pre_commit_hook_path() -> Option<PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--git-path", "hooks/pre-commit"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()))
+}
+
+fn git_succeeds(args: &[&str]) -> bool {
+    Command::new("git")
+        .args(args)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::process::Command;
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result, bail};
+use camino::{Utf8Path, Utf8PathBuf};
+use regex::Regex;
+
+/// Extensions whose files are scanned for env lookups.
+const SOURCE_EXTENSIONS: &[&str] = &[
+    "js", "jsx", "mjs", "cjs", "ts", "tsx", "mts", "cts", "vue", "svelte", "py", "rs",
+];
+
+/// Variables the OS or toolchain provides; referencing them is never "missing".
+pub const AMBIENT_KEYS: &[&str] = &[
+    "CI", "HOME", "NODE_ENV", "PATH", "PWD", "SHELL", "TERM", "TMPDIR", "USER",
+];
+
+/// Where a key is read in the source tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    pub path: Utf8PathBuf,
+    pub line: usize,
+}
+
+fn lookup() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(concat!(
+            // process.env.X, import.meta.env.X
+            r"(?:process|import\.meta)\.env\.([A-Za-z_][A-Za-z0-9_]*)",
+            // process.env["X"], os.environ["X"]
+            r#"|(?:process\.env|os\.environ)\[\s*['"]([A-Za-z_][A-Za-z0-9_]*)['"]\s*\]"#,
+            // os.environ.get("X"), os.getenv("X")
+            r#"|os\.(?:environ\.get|getenv)\(\s*['"]([A-Za-z_][A-Za-z0-9_]*)['"]"#,
+            // std::env::var("X"), env::var_os("X")
+            r#"|env::var(?:_os)?\(\s*"([A-Za-z_][A-Za-z0-9_]*)""#,
+        ))
+        .expect("valid env reference regex")
+    })
+}
+
+/// `(key, 1-based line)` for every env lookup in `text`.
+pub fn references_in(text: &str) -> Vec<(String, usize)> {
+    let mut found = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        for caps in lookup().captures_iter(line) {
+            if let Some(key) = caps.iter().skip(1).flatten().next() {
+                found.push((key.as_str().to_owned(), idx + 1));
+            }
+        }
+    }
+    found
+}
+
+/// Scan tracked and untracked-but-not-ignored source files under `root`.
+pub fn scan(root: &Utf8Path) -> Result<BTreeMap<String, Vec<Reference>>> {
+    let output = Command::new("git")
+        .current_dir(root)
+        .args(["ls-files", "--cached", "--others", "--exclude-standard", "-z"])
+        .output()
+        .context("running git ls-files")?;
+    if !output.status.success() {
+        bail!(
+            "git ls-files failed in {} (dev env audit scans a git checkout): {}",
+            root,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let mut references: BTreeMap<String, Vec<Reference>> = BTreeMap::new();
+    for file in String::from_utf8_lossy(&output.stdout).split('\0') {
+        let path = Utf8PathBuf::from(file);
+        if !path
+            .extension()
+            .is_some_and(|ext| SOURCE_EXTENSIONS.contains(&ext))
+        {
+            continue;
+        }
+        // Deleted-but-tracked and non-UTF-8 files are skipped.
+        let Ok(text) = fs::read_to_string(root.join(&path)) else {
+            continue;
+        };
+        for (key, line) in references_in(&text) {
+            references.entry(key).or_default().push(Reference {
+                path: path.clone(),
+                line,
+            });
+        }
+    }
+    Ok(references)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_lookups_across_languages() {
+        let source = r#"
+const url = process.env.DATABASE_URL ?? process.env["FALLBACK_URL"];
+const mode = import.meta.env.VITE_MODE;
+token = os.environ["API_TOKEN"]; debug = os.environ.get('DEBUG', '0')
+port = os.getenv("PORT")
+let key = std::env::var("SECRET_KEY").unwrap(); let p = env::var_os("PROFILE");
+let not_env = vars("NOPE");
+"#;
+        let keys: Vec<(String, usize)> = references_in(source);
+        let names: Vec<&str> = keys.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "DATABASE_URL",
+                "FALLBACK_URL",
+                "VITE_MODE",
+                "API_TOKEN",
+                "DEBUG",
+                "PORT",
+                "SECRET_KEY",
+                "PROFILE",
+            ]
+        );
+        assert_eq!(keys[0].1, 2);
+        assert_eq!(keys[7].1, 6);
+    }
+}
@@ -0,0 +1,274 @@
+use anyhow::{Result, anyhow, bail};
+use serde_json::{Map, Value};
+
+use crate::base64;
+use crate::cli::{EnvExportFormat, EnvImportFormat};
+
+/// Render env entries for `dev env export`. Shell formats expect keys already
+/// filtered with `is_valid_key`; JSON and YAML accept any key.
+pub fn render(entries: &[(String, String)], format: EnvExportFormat) -> String {
+    match format {
+        EnvExportFormat::Json => {
+            let object: Map<String, Value> = entries
+                .iter()
+                .map(|(key, value)| (key.clone(), Value::String(value.clone())))
+                .collect();
+            let mut out = serde_json::to_string_pretty(&Value::Object(object))
+                .expect("string map serializes");
+            out.push('\n');
+            out
+        }
+        // JSON string escapes are valid YAML double-quoted scalars.
+        EnvExportFormat::Yaml => entries
+            .iter()
+            .map(|(key, value)| format!("{}: {}\n", yaml_key(key), Value::String(value.clone())))
+            .collect(),
+        _ => entries
+            .iter()
+            .map(|(key, value)| format!("{}\n", export_line(key, value, format)))
+            .collect(),
+    }
+}
+
+/// One line that sets `key` to `value` in the given shell, quoted so `eval` sees the literal value.
+fn export_line(key: &str, value: &str, format: EnvExportFormat) -> String {
+    match format {
+        EnvExportFormat::Sh => format!("export {}='{}'", key, value.replace('\'', r"'\''")),
+        EnvExportFormat::Fish => format!(
+            "set -gx {} '{}'",
+            key,
+            value.replace('\\', r"\\").replace('\'', r"\'")
+        ),
+        EnvExportFormat::Powershell => format!("$env:{} = '{}'", key, value.replace('\'', "''")),
+        EnvExportFormat::Dotenv => format!("{}={}", key, dotenv_value(value)),
+        EnvExportFormat::Json | EnvExportFormat::Yaml => {
+            unreachable!("document formats are rendered whole")
+        }
+    }
+}
+
+/// Value as written to a `.env` file: bare when safe, otherwise double-quoted with escapes.
+pub fn dotenv_value(value: &str) -> String {
+    let bare = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./:@,+%".contains(c));
+    if bare {
+        return value.to_owned();
+    }
+    format!(
+        "\"{}\"",
+        value
+            .replace('\\', r"\\")
+            .replace('"', r#"\""#)
+            .replace('\n', r"\n")
+    )
+}
+
+fn yaml_key(key: &str) -> String {
+    if is_valid_key(key) {
+        key.to_owned()
+    } else {
+        Value::String(key.to_owned()).to_string()
+    }
+}
+
+/// Whether `key` can be exported as a shell variable name.
+pub fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|first| first == '_' || first.is_ascii_alphabetic())
+        && chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+}
+
+/// Format implied by a file name: `.json`, `.yaml`/`.yml`, otherwise dotenv.
+pub fn detect_format(path: &str) -> EnvImportFormat {
+    let lower = path.to_ascii_lowercase();
+    if lower.ends_with(".json") {
+        EnvImportFormat::Json
+    } else if lower.ends_with(".yaml") || lower.ends_with(".yml") {
+        EnvImportFormat::Yaml
+    } else {
+        EnvImportFormat::Dotenv
+    }
+}
+
+/// Parse an env set exported elsewhere. JSON and YAML accept a flat `KEY: value`
+/// mapping or a Kubernetes `Secret` (`data` is base64-decoded, `stringData` is used
+/// as is); JSON also accepts `[{"name": ..., "value": ...}]` lists.
+pub fn parse(raw: &str, format: EnvImportFormat) -> Result<Vec<(String, String)>> {
+    match format {
+        EnvImportFormat::Json => {
+            entries_from_value(serde_json::from_str(raw).map_err(|err| anyhow!("invalid JSON: {}", err))?)
+        }
+        EnvImportFormat::Yaml => entries_from_value(parse_yaml(raw)?),
+        EnvImportFormat::Dotenv => Ok(raw
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let line = line.strip_prefix("export ").unwrap_or(line);
+                let (key, value) = line.split_once('=')?;
+                Some((key.trim().to_owned(), crate::envfile::unquote(value)))
+            })
+            .collect()),
+    }
+}
+
+fn entries_from_value(value: Value) -> Result<Vec<(String, String)>> {
+    match value {
+        Value::Object(object) if object.get("kind").and_then(Value::as_str) == Some("Secret") => {
+            let mut entries = Vec::new();
+            if let Some(Value::Object(data)) = object.get("data") {
+                for (key, value) in data {
+                    let encoded = scalar(key, value)?;
+                    let decoded = base64::decode(&encoded)
+                        .and_then(|bytes| String::from_utf8(bytes).ok())
+                        .ok_or_else(|| anyhow!("data.{} is not base64-encoded UTF-8", key))?;
+                    entries.push((key.clone(), decoded));
+                }
+            }
+            if let Some(Value::Object(data)) = object.get("stringData") {
+                for (key, value) in data {
+                    entries.push((key.clone(), scalar(key, value)?));
+                }
+            }
+            Ok(entries)
+        }
+        Value::Object(object) => object
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), scalar(key, value)?)))
+            .collect(),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| {
+                let field = |names: &[&str]| names.iter().find_map(|name| item.get(*name));
+                let key = field(&["name", "Name", "key", "Key"])
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| anyhow!("list entries need a `name` and a `value`"))?;
+                let value = field(&["value", "Value"]).unwrap_or(&Value::Null);
+                Ok((key.to_owned(), scalar(key, value)?))
+            })
+            .collect(),
+        _ => bail!("expected an object or a list of name/value pairs"),
+    }
+}
+
+fn scalar(key: &str, value: &Value) -> Result<String> {
+    match value {
+        Value::String(value) => Ok(value.clone()),
+        Value::Null => Ok(String::new()),
+        Value::Bool(_) | Value::Number(_) => Ok(value.to_string()),
+        _ => bail!("`{}` has a nested value; only flat key/value sets can be imported", key),
+    }
+}
+
+/// Just enough YAML for env sets: `key: value` lines plus one level of nested
+/// mappings (as in a Kubernetes Secret's `metadata`, `data`, and `stringData`).
+fn parse_yaml(raw: &str) -> Result<Value> {
+    let mut root = Map::new();
+    let mut section: Option<String> = None;
+    for (idx, line) in raw.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed == "---" {
+            continue;
+        }
+        let (key, value) = trimmed
+            .split_once(':')
+            .ok_or_else(|| anyhow!("line {}: expected `key: value`", idx + 1))?;
+        let key = yaml_scalar(key.trim(), idx)?;
+        let value = value.trim();
+
+        let nested = line.starts_with([' ', '\t']);
+        if !nested {
+            if value.is_empty() {
+                root.insert(key.clone(), Value::Object(Map::new()));
+                section = Some(key);
+            } else {
+                root.insert(key, Value::String(yaml_scalar(value, idx)?));
+                section = None;
+            }
+            continue;
+        }
+
+        let Some(Value::Object(parent)) = section.as_ref().and_then(|name| root.get_mut(name)) else {
+            bail!("line {}: unexpected indentation", idx + 1);
+        };
+        if value.is_empty() {
+            bail!("line {}: values nested more than one level are not supported", idx + 1);
+        }
+        parent.insert(key, Value::String(yaml_scalar(value, idx)?));
+    }
+    Ok(Value::Object(root))
+}
+
+fn yaml_scalar(raw: &str, idx: usize) -> Result<String> {
+    if raw.starts_with('"') {
+        return serde_json::from_str(raw)
+            .map_err(|_| anyhow!("line {}: unsupported double-quoted string {}", idx + 1, raw));
+    }
+    if let Some(inner) = raw.strip_prefix('\'').and_then(|rest| rest.strip_suffix('\'')) {
+        return Ok(inner.replace("''", "'"));
+    }
+    if raw.starts_with(['|', '>', '[', '{', '&', '*']) {
+        bail!("line {}: block scalars, flow collections, and anchors are not supported", idx + 1);
+    }
+    let value = match raw.find(" #") {
+        Some(comment) => &raw[..comment],
+        None => raw,
+    };
+    Ok(value.trim_end().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_lines_quote_for_each_shell() {
+        let value = crate::envfile::unquote(r#""it's a \"test\"""#);
+        assert_eq!(value, r#"it's a "test""#);
+        assert_eq!(
+            export_line("MSG", &value, EnvExportFormat::Sh),
+            r#"export MSG='it'\''s a "test"'"#
+        );
+        assert_eq!(
+            export_line("MSG", &value, EnvExportFormat::Fish),
+            r#"set -gx MSG 'it\'s a "test"'"#
+        );
+        assert_eq!(
+            export_line("MSG", &value, EnvExportFormat::Powershell),
+            r#"$env:MSG = 'it''s a "test"'"#
+        );
+        assert_eq!(
+            export_line("MSG", &value, EnvExportFormat::Dotenv),
+            r#"MSG="it's a \"test\"""#
+        );
+        assert!(!is_valid_key("1BAD") && !is_valid_key("A-B") && is_valid_key("_OK1"));
+    }
+
+    #[test]
+    fn import_round_trips_json_yaml_and_k8s_secrets() {
+        let entries = vec![
+            ("A".to_owned(), "x y".to_owned()),
+            ("B".to_owned(), "it's \"q\"".to_owned()),
+        ];
+        for format in [EnvExportFormat::Json, EnvExportFormat::Yaml] {
+            let import = match format {
+                EnvExportFormat::Json => EnvImportFormat::Json,
+                _ => EnvImportFormat::Yaml,
+            };
+            assert_eq!(parse(&render(&entries, format), import).unwrap(), entries);
+        }
+
+        let secret = "apiVersion: v1\nkind: Secret\nmetadata:\n  name: app\ndata:\n  TOKEN: c2VjcmV0\nstringData:\n  PORT: '8080'\n";
+        assert_eq!(
+            parse(secret, EnvImportFormat::Yaml).unwrap(),
+            vec![
+                ("TOKEN".to_owned(), "secret".to_owned()),
+                ("PORT".to_owned(), "8080".to_owned()),
+            ]
+        );
+    }
+}
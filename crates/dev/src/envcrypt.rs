@@ -0,0 +1,179 @@
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Output, Stdio};
+
+use anyhow::{Context, Result, bail};
+use camino::{Utf8Path, Utf8PathBuf};
+
+/// Environment variable naming the age identity used to decrypt `.env.enc`.
+const IDENTITY_ENV: &str = "DEV_AGE_IDENTITY";
+
+/// Encrypted sibling of a `.env` file: `.env` -> `.env.enc`.
+pub fn encrypted_path(env_path: &Utf8Path) -> Utf8PathBuf {
+    Utf8PathBuf::from(format!("{}.enc", env_path))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Age,
+    Sops,
+}
+
+impl Backend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Backend::Age => "age",
+            Backend::Sops => "sops",
+        }
+    }
+
+    /// Recognise the tool that produced `ciphertext` from its header or metadata.
+    pub fn detect(ciphertext: &str) -> Option<Backend> {
+        if ciphertext.starts_with("age-encryption.org/")
+            || ciphertext.starts_with("-----BEGIN AGE ENCRYPTED FILE-----")
+        {
+            Some(Backend::Age)
+        } else if ciphertext.lines().any(|line| line.starts_with("sops_version=")) {
+            Some(Backend::Sops)
+        } else {
+            None
+        }
+    }
+}
+
+/// Decrypt `path` to dotenv text. age prompts for a passphrase itself when no identity applies.
+pub fn decrypt(path: &Utf8Path, identity: Option<&Utf8Path>) -> Result<String> {
+    let ciphertext = fs::read_to_string(path).with_context(|| format!("reading {}", path))?;
+    let Some(backend) = Backend::detect(&ciphertext) else {
+        bail!("{} is not an age or sops encrypted file", path);
+    };
+
+    let mut argv: Vec<String> = match backend {
+        Backend::Age => {
+            let mut argv = vec!["age".to_owned(), "--decrypt".to_owned()];
+            if let Some(identity) = resolve_identity(identity)? {
+                argv.extend(["--identity".to_owned(), identity.to_string()]);
+            }
+            argv
+        }
+        Backend::Sops => dotenv_sops_args("--decrypt"),
+    };
+    argv.push(path.to_string());
+
+    let output = run_capture(&argv, backend)?;
+    if !output.status.success() {
+        bail!("{} could not decrypt {}", backend.as_str(), path);
+    }
+    String::from_utf8(output.stdout).with_context(|| format!("decrypted {} is not UTF-8", path))
+}
+
+/// Encrypt `plain` into `out`. age uses `recipients` (keys or recipient files), or a
+/// passphrase when there are none; sops reads its keys from `.sops.yaml`.
+pub fn encrypt(plain: &Utf8Path, out: &Utf8Path, backend: Backend, recipients: &[String]) -> Result<()> {
+    match backend {
+        Backend::Age => {
+            let mut argv = vec!["age".to_owned(), "--encrypt".to_owned(), "--armor".to_owned()];
+            if recipients.is_empty() {
+                argv.push("--passphrase".to_owned());
+            }
+            for recipient in recipients {
+                let flag = if Utf8Path::new(recipient).is_file() {
+                    "--recipients-file"
+                } else {
+                    "--recipient"
+                };
+                argv.extend([flag.to_owned(), recipient.clone()]);
+            }
+            argv.extend(["--output".to_owned(), out.to_string(), plain.to_string()]);
+            let output = run_capture(&argv, backend)?;
+            if !output.status.success() {
+                bail!("age could not encrypt {}", plain);
+            }
+        }
+        Backend::Sops => {
+            let mut argv = dotenv_sops_args("--encrypt");
+            argv.push(plain.to_string());
+            let output = run_capture(&argv, backend)?;
+            if !output.status.success() {
+                bail!("sops could not encrypt {}; check the creation rules in .sops.yaml", plain);
+            }
+            fs::write(out, output.stdout).with_context(|| format!("writing {}", out))?;
+        }
+    }
+    Ok(())
+}
+
+/// Identity for `age --decrypt`: the explicit path, `$DEV_AGE_IDENTITY`, age's default key
+/// file, or a prompt when interactive. `None` leaves age to ask for a passphrase.
+fn resolve_identity(explicit: Option<&Utf8Path>) -> Result<Option<Utf8PathBuf>> {
+    if let Some(path) = explicit {
+        return Ok(Some(path.to_owned()));
+    }
+    if let Some(path) = std::env::var_os(IDENTITY_ENV).filter(|value| !value.is_empty()) {
+        return Ok(Some(Utf8PathBuf::from(path.to_string_lossy().into_owned())));
+    }
+    if let Some(path) = dirs::home_dir()
+        .map(|home| home.join(".config").join("age").join("keys.txt"))
+        .filter(|path| path.is_file())
+        .and_then(|path| Utf8PathBuf::from_path_buf(path).ok())
+    {
+        return Ok(Some(path));
+    }
+    if !io::stdin().is_terminal() {
+        return Ok(None);
+    }
+
+    eprint!("age identity file (leave empty to use a passphrase): ");
+    io::stderr().flush().context("writing identity prompt")?;
+    let mut answer = String::new();
+    io::stdin()
+        .read_line(&mut answer)
+        .context("reading identity path")?;
+    let answer = answer.trim();
+    Ok((!answer.is_empty()).then(|| Utf8PathBuf::from(answer)))
+}
+
+fn dotenv_sops_args(mode: &str) -> Vec<String> {
+    ["sops", mode, "--input-type", "dotenv", "--output-type", "dotenv"]
+        .into_iter()
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Run with stdout captured; stdin and stderr stay attached so passphrase prompts work.
+fn run_capture(argv: &[String], backend: Backend) -> Result<Output> {
+    Command::new(&argv[0])
+        .args(&argv[1..])
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .with_context(|| {
+            format!(
+                "running `{}`; is {} installed and on PATH?",
+                argv[0],
+                backend.as_str()
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_backend_from_header() {
+        assert_eq!(
+            Backend::detect("age-encryption.org/v1\n-> X25519 abc\n"),
+            Some(Backend::Age)
+        );
+        assert_eq!(
+            Backend::detect("-----BEGIN AGE ENCRYPTED FILE-----\nYWdl\n"),
+            Some(Backend::Age)
+        );
+        assert_eq!(
+            Backend::detect("API_KEY=ENC[AES256_GCM,data:x]\nsops_version=3.9.0\n"),
+            Some(Backend::Sops)
+        );
+        assert_eq!(Backend::detect("API_KEY=plain\n"), None);
+    }
+}
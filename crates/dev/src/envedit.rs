@@ -0,0 +1,548 @@
+use std::io::{self, IsTerminal, Read, Write};
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result, bail};
+use camino::Utf8Path;
+
+use crate::config::EnvConfig;
+use crate::envfile::{self, EnvFile};
+use crate::{envconvert, envhistory};
+
+const HELP: &str = "↑/↓ move · enter edit · a add · d delete · v show/hide · / search · s save · q quit";
+
+/// Whether a key is listed under `[env] required` / `optional`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Requirement {
+    Required,
+    Optional,
+    Unlisted,
+}
+
+impl Requirement {
+    fn label(&self) -> &'static str {
+        match self {
+            Requirement::Required => "required",
+            Requirement::Optional => "optional",
+            Requirement::Unlisted => "",
+        }
+    }
+}
+
+/// One row in the editor: a key from `.env`, or a configured key that is still missing.
+struct Row {
+    key: String,
+    value: Option<String>,
+    requirement: Requirement,
+}
+
+/// A key press decoded from raw terminal input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Key {
+    Up,
+    Down,
+    Enter,
+    Backspace,
+    Esc,
+    Interrupt,
+    Char(char),
+    Other,
+}
+
+/// What the text typed at the bottom line is for.
+enum Input {
+    NewKey,
+    Value(String),
+    Search,
+}
+
+struct Prompt {
+    input: Input,
+    buffer: String,
+}
+
+struct Editor<'a> {
+    env: EnvFile,
+    config: Option<&'a EnvConfig>,
+    required: &'a [String],
+    optional: &'a [String],
+    filter: String,
+    revealed: Vec<String>,
+    selected: usize,
+    prompt: Option<Prompt>,
+    dirty: bool,
+    quit_armed: bool,
+    status: String,
+}
+
+/// `dev env edit`: a full-screen editor listing keys with sensitive values masked,
+/// saving atomically (with a history snapshot) on `s`.
+pub fn run(env_path: &Utf8Path, config: Option<&EnvConfig>) -> Result<()> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        bail!("`dev env edit` needs an interactive terminal; use `dev env add`/`rm` in scripts");
+    }
+    let mut editor = Editor::new(EnvFile::load(env_path)?, config);
+    let terminal = Terminal::enter()?;
+    let mut stdin = io::stdin().lock();
+    loop {
+        let mut out = io::stdout().lock();
+        // Home the cursor and clear so each frame replaces the last.
+        write!(out, "\x1b[H\x1b[2J{}", editor.render(terminal.height))?;
+        out.flush()?;
+        if editor.handle(read_key(&mut stdin)?)? {
+            return Ok(());
+        }
+    }
+}
+
+impl<'a> Editor<'a> {
+    fn new(env: EnvFile, config: Option<&'a EnvConfig>) -> Self {
+        Editor {
+            env,
+            config,
+            required: config.and_then(|env| env.required.as_deref()).unwrap_or_default(),
+            optional: config.and_then(|env| env.optional.as_deref()).unwrap_or_default(),
+            filter: String::new(),
+            revealed: Vec::new(),
+            selected: 0,
+            prompt: None,
+            dirty: false,
+            quit_armed: false,
+            status: String::new(),
+        }
+    }
+
+    fn requirement(&self, key: &str) -> Requirement {
+        if self.required.iter().any(|name| name == key) {
+            Requirement::Required
+        } else if self.optional.iter().any(|name| name == key) {
+            Requirement::Optional
+        } else {
+            Requirement::Unlisted
+        }
+    }
+
+    /// Whether `key`'s value is shown as stars, both in the list and while typing it.
+    fn masked(&self, key: &str) -> bool {
+        !self.revealed.iter().any(|shown| shown == key)
+            && self.config.is_none_or(|env| env.is_sensitive(key))
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        let mut rows: Vec<Row> = self
+            .env
+            .entries()
+            .map(|(key, value)| Row {
+                key: key.to_owned(),
+                value: Some(value.to_owned()),
+                requirement: self.requirement(key),
+            })
+            .collect();
+        for key in self.required.iter().chain(self.optional) {
+            if !rows.iter().any(|row| row.key == *key) {
+                rows.push(Row {
+                    key: key.clone(),
+                    value: None,
+                    requirement: self.requirement(key),
+                });
+            }
+        }
+        let filter = self.filter.to_ascii_lowercase();
+        rows.retain(|row| filter.is_empty() || row.key.to_ascii_lowercase().contains(&filter));
+        rows
+    }
+
+    /// One frame for a terminal `height` lines tall, with raw-mode `\r\n` line endings.
+    fn render(&self, height: usize) -> String {
+        let rows = self.rows();
+        let mut lines = vec![format!(
+            "Editing {}{}{}",
+            self.env.path(),
+            if self.dirty { " (unsaved changes)" } else { "" },
+            if self.filter.is_empty() {
+                String::new()
+            } else {
+                format!("  search: {}", self.filter)
+            }
+        )];
+        lines.push(String::new());
+
+        // Header, footer, and blank lines take six; scroll to keep the selection visible.
+        let visible = height.saturating_sub(6).max(1);
+        let offset = self.selected.saturating_sub(visible - 1);
+        let width = rows.iter().map(|row| row.key.len()).max().unwrap_or(3).max(3);
+        if rows.is_empty() {
+            lines.push("  (no keys)".to_owned());
+        }
+        for (index, row) in rows.iter().enumerate().skip(offset).take(visible) {
+            let value = match &row.value {
+                None => "(missing)".to_owned(),
+                Some(value) if value.is_empty() => "(empty)".to_owned(),
+                Some(_) if self.masked(&row.key) => "*****".to_owned(),
+                Some(value) => envfile::unquote(value),
+            };
+            lines.push(format!(
+                "{} {:<width$}  {:<12} {}",
+                if index == self.selected { ">" } else { " " },
+                row.key,
+                value,
+                row.requirement.label(),
+                width = width
+            ));
+        }
+        lines.push(String::new());
+        lines.push(self.status.clone());
+        lines.push(match &self.prompt {
+            None => HELP.to_owned(),
+            Some(prompt) => match &prompt.input {
+                Input::NewKey => format!("new key: {}", prompt.buffer),
+                Input::Search => format!("search: {}", prompt.buffer),
+                Input::Value(key) if self.masked(key) => format!(
+                    "value for {}: {}",
+                    key,
+                    "*".repeat(prompt.buffer.chars().count())
+                ),
+                Input::Value(key) => format!("value for {}: {}", key, prompt.buffer),
+            },
+        });
+        lines.join("\r\n")
+    }
+
+    /// Apply one key press; returns `true` once the editor should close.
+    fn handle(&mut self, key: Key) -> Result<bool> {
+        if self.prompt.is_some() {
+            self.handle_prompt(key);
+        } else {
+            let armed = std::mem::take(&mut self.quit_armed);
+            let rows = self.rows();
+            let row = rows.get(self.selected);
+            match key {
+                Key::Up | Key::Char('k') => self.selected = self.selected.saturating_sub(1),
+                Key::Down | Key::Char('j') => self.selected += 1,
+                Key::Enter | Key::Char('e') => {
+                    if let Some(row) = row {
+                        self.open(Input::Value(row.key.clone()));
+                    }
+                }
+                Key::Char('a') => self.open(Input::NewKey),
+                Key::Char('d') => match row {
+                    Some(row) if self.env.remove(&row.key) => {
+                        self.dirty = true;
+                        self.status = format!("deleted {}", row.key);
+                    }
+                    Some(row) => self.status = format!("{} is not in the file", row.key),
+                    None => {}
+                },
+                Key::Char('v') => {
+                    if let Some(row) = row {
+                        match self.revealed.iter().position(|shown| *shown == row.key) {
+                            Some(index) => {
+                                self.revealed.remove(index);
+                            }
+                            None => self.revealed.push(row.key.clone()),
+                        }
+                    }
+                }
+                Key::Char('/') => {
+                    self.prompt = Some(Prompt {
+                        input: Input::Search,
+                        buffer: self.filter.clone(),
+                    });
+                }
+                Key::Char('s') => self.save()?,
+                Key::Char('q') | Key::Esc | Key::Interrupt => {
+                    if !self.dirty || armed {
+                        return Ok(true);
+                    }
+                    self.quit_armed = true;
+                    self.status = "unsaved changes: s saves, q again discards them".to_owned();
+                }
+                _ => {}
+            }
+        }
+        self.selected = self.selected.min(self.rows().len().saturating_sub(1));
+        Ok(false)
+    }
+
+    fn open(&mut self, input: Input) {
+        self.status.clear();
+        self.prompt = Some(Prompt {
+            input,
+            buffer: String::new(),
+        });
+    }
+
+    fn handle_prompt(&mut self, key: Key) {
+        let Some(prompt) = self.prompt.as_mut() else {
+            return;
+        };
+        match key {
+            Key::Char(c) => prompt.buffer.push(c),
+            Key::Backspace => {
+                prompt.buffer.pop();
+            }
+            Key::Esc | Key::Interrupt => {
+                self.prompt = None;
+                self.status = "cancelled".to_owned();
+                return;
+            }
+            Key::Enter => {
+                let Some(Prompt { input, buffer }) = self.prompt.take() else {
+                    return;
+                };
+                self.submit(input, buffer);
+                return;
+            }
+            _ => {}
+        }
+        // Searching filters as you type.
+        if let Some(Prompt {
+            input: Input::Search,
+            buffer,
+        }) = &self.prompt
+        {
+            self.filter = buffer.trim().to_owned();
+            self.selected = 0;
+        }
+    }
+
+    fn submit(&mut self, input: Input, buffer: String) {
+        match input {
+            Input::NewKey => {
+                let key = buffer.trim();
+                if envconvert::is_valid_key(key) {
+                    self.open(Input::Value(key.to_owned()));
+                } else {
+                    self.status = format!("`{}` is not a valid key", key);
+                }
+            }
+            Input::Value(key) => {
+                if buffer.is_empty() {
+                    self.status = "unchanged".to_owned();
+                    return;
+                }
+                self.env.upsert(&key, &envconvert::dotenv_value(&buffer));
+                self.dirty = true;
+                self.status = format!("set {}", key);
+                if let Some(index) = self.rows().iter().position(|row| row.key == key) {
+                    self.selected = index;
+                }
+            }
+            Input::Search => self.filter = buffer.trim().to_owned(),
+        }
+    }
+
+    fn save(&mut self) -> Result<()> {
+        if !self.dirty {
+            self.status = "nothing to save".to_owned();
+            return Ok(());
+        }
+        envhistory::snapshot(self.env.path(), "edit")?;
+        self.env.save()?;
+        self.dirty = false;
+        self.status = format!("saved {}", self.env.path());
+        Ok(())
+    }
+}
+
+/// Raw, no-echo terminal on the alternate screen; restored when dropped.
+struct Terminal {
+    saved: String,
+    height: usize,
+}
+
+impl Terminal {
+    fn enter() -> Result<Self> {
+        let saved = stty(&["-g"])?.trim().to_owned();
+        let height = stty(&["size"])?
+            .split_whitespace()
+            .next()
+            .and_then(|rows| rows.parse().ok())
+            .filter(|rows| *rows > 0)
+            .unwrap_or(24);
+        // `min 0 time 1` lets a lone Esc be told apart from an arrow key's escape sequence.
+        stty(&["raw", "-echo", "min", "0", "time", "1"])?;
+        print!("\x1b[?1049h\x1b[?25l");
+        io::stdout().flush().context("writing to terminal")?;
+        Ok(Terminal { saved, height })
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+fn stty(args: &[&str]) -> Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .context("running `stty`")?;
+    if !output.status.success() {
+        bail!("`stty {}` failed; `dev env edit` needs a Unix terminal", args.join(" "));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The next byte, or `None` when the read timed out.
+fn read_byte(input: &mut impl Read) -> Result<Option<u8>> {
+    let mut byte = [0];
+    let read = input.read(&mut byte).context("reading input")?;
+    Ok((read == 1).then_some(byte[0]))
+}
+
+fn read_key(input: &mut impl Read) -> Result<Key> {
+    let first = loop {
+        if let Some(byte) = read_byte(input)? {
+            break byte;
+        }
+    };
+    Ok(match first {
+        b'\r' | b'\n' => Key::Enter,
+        0x7f | 0x08 => Key::Backspace,
+        0x03 => Key::Interrupt,
+        0x1b => match read_byte(input)? {
+            Some(b'[' | b'O') => {
+                // Consume the whole sequence up to its final byte.
+                let mut last = None;
+                while let Some(byte) = read_byte(input)? {
+                    last = Some(byte);
+                    if (0x40..=0x7e).contains(&byte) {
+                        break;
+                    }
+                }
+                match last {
+                    Some(b'A') => Key::Up,
+                    Some(b'B') => Key::Down,
+                    _ => Key::Other,
+                }
+            }
+            _ => Key::Esc,
+        },
+        byte if byte < 0x20 => Key::Other,
+        byte => {
+            let len = match byte {
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => 1,
+            };
+            let mut bytes = vec![byte];
+            while bytes.len() < len {
+                match read_byte(input)? {
+                    Some(next) => bytes.push(next),
+                    None => break,
+                }
+            }
+            std::str::from_utf8(&bytes)
+                .ok()
+                .and_then(|text| text.chars().next())
+                .map_or(Key::Other, Key::Char)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino::Utf8PathBuf;
+    use std::fs;
+
+    fn type_text(editor: &mut Editor, text: &str) {
+        for c in text.chars() {
+            editor.handle(Key::Char(c)).unwrap();
+        }
+    }
+
+    #[test]
+    fn rows_include_missing_configured_keys_and_respect_search() {
+        let root = Utf8PathBuf::from_path_buf(std::env::temp_dir())
+            .unwrap()
+            .join(format!("devkit-envedit-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let env_path = root.join(".env");
+        fs::write(&env_path, "# db\nDATABASE_URL=postgres://x\nDEBUG=1\n").unwrap();
+
+        let required = vec!["DATABASE_URL".to_owned(), "API_KEY".to_owned()];
+        let optional = vec!["DEBUG".to_owned()];
+        let mut editor = Editor::new(EnvFile::load(&env_path).unwrap(), None);
+        editor.required = &required;
+        editor.optional = &optional;
+
+        let rows = editor.rows();
+        let keys: Vec<&str> = rows.iter().map(|row| row.key.as_str()).collect();
+        assert_eq!(keys, ["DATABASE_URL", "DEBUG", "API_KEY"]);
+        assert!(rows[2].value.is_none());
+        assert!(rows[2].requirement == Requirement::Required);
+        assert!(rows[1].requirement == Requirement::Optional);
+
+        editor.handle(Key::Char('/')).unwrap();
+        type_text(&mut editor, "api");
+        assert_eq!(editor.rows().len(), 1);
+        editor.handle(Key::Enter).unwrap();
+        editor.handle(Key::Enter).unwrap();
+        type_text(&mut editor, "secret value");
+        editor.handle(Key::Enter).unwrap();
+        assert!(editor.dirty);
+
+        // Quitting with unsaved changes needs a second `q`; `s` saves instead.
+        assert!(!editor.handle(Key::Char('q')).unwrap());
+        editor.handle(Key::Char('s')).unwrap();
+        assert!(!editor.dirty);
+        assert!(editor.handle(Key::Char('q')).unwrap());
+        let saved = fs::read_to_string(&env_path).unwrap();
+        assert!(saved.starts_with("# db\nDATABASE_URL=postgres://x\n"));
+        assert!(saved.contains("API_KEY="));
+
+        let _ = fs::remove_dir_all(root.as_std_path());
+    }
+
+    #[test]
+    fn typed_values_are_masked_until_revealed() {
+        let root = Utf8PathBuf::from_path_buf(std::env::temp_dir())
+            .unwrap()
+            .join(format!("devkit-envedit-mask-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let env_path = root.join(".env");
+        fs::write(&env_path, "TOKEN=old\n").unwrap();
+        let mut editor = Editor::new(EnvFile::load(&env_path).unwrap(), None);
+
+        editor.handle(Key::Char('a')).unwrap();
+        type_text(&mut editor, "API_KEY");
+        editor.handle(Key::Enter).unwrap();
+        type_text(&mut editor, "hunter2");
+        let frame = editor.render(24);
+        assert!(frame.ends_with("value for API_KEY: *******"), "{frame}");
+        assert!(!frame.contains("hunter2"));
+        editor.handle(Key::Enter).unwrap();
+        assert!(!editor.render(24).contains("hunter2"));
+
+        editor.handle(Key::Char('v')).unwrap();
+        assert!(editor.render(24).contains("> API_KEY  hunter2"));
+        editor.handle(Key::Enter).unwrap();
+        type_text(&mut editor, "visible");
+        assert!(editor.render(24).ends_with("value for API_KEY: visible"));
+
+        let mut input: &[u8] = b"\x1b[A\x1b[B\x1b[3~\r\x7f\x03\xc3\xa9x";
+        let keys: Vec<Key> = (0..8).map(|_| read_key(&mut input).unwrap()).collect();
+        assert_eq!(
+            keys,
+            [
+                Key::Up,
+                Key::Down,
+                Key::Other,
+                Key::Enter,
+                Key::Backspace,
+                Key::Interrupt,
+                Key::Char('é'),
+                Key::Char('x'),
+            ]
+        );
+
+        let _ = fs::remove_dir_all(root.as_std_path());
+    }
+}
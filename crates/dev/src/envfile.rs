@@ -1,8 +1,12 @@
+use std::collections::HashMap;
 use std::fs::{self, File};
 
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result, anyhow, bail};
 use camino::{Utf8Path, Utf8PathBuf};
 
+use crate::{envconvert, envcrypt};
+use crate::vars::Vars;
+
 const ENV_FILENAME: &str = ".env";
 
 /// Lightweight representation of a `.env` file.
@@ -10,10 +14,23 @@ const ENV_FILENAME: &str = ".env";
 pub struct EnvFile {
     path: Utf8PathBuf,
     lines: Vec<Line>,
+    /// Read from `.env.enc` because no plaintext `.env` exists; such files are read-only.
+    encrypted: bool,
 }
 
 impl EnvFile {
+    /// Load `path`, decrypting its `.env.enc` sibling when only the encrypted file exists.
     pub fn load(path: &Utf8Path) -> Result<Self> {
+        let encrypted_path = envcrypt::encrypted_path(path);
+        if !path.exists() && encrypted_path.exists() {
+            let contents = envcrypt::decrypt(&encrypted_path, None)?;
+            return Ok(Self {
+                path: path.to_owned(),
+                lines: parse_lines(&contents),
+                encrypted: true,
+            });
+        }
+
         let contents = if path.exists() {
             fs::read_to_string(path).with_context(|| format!("reading {}", path))?
         } else {
@@ -30,9 +47,19 @@ impl EnvFile {
         Ok(Self {
             path: path.to_owned(),
             lines,
+            encrypted: false,
         })
     }
 
+    /// An empty file at `path`; nothing touches disk until `save`.
+    pub fn empty(path: &Utf8Path) -> Self {
+        Self {
+            path: path.to_owned(),
+            lines: Vec::new(),
+            encrypted: false,
+        }
+    }
+
     pub fn path(&self) -> &Utf8Path {
         &self.path
     }
@@ -44,6 +71,36 @@ impl EnvFile {
         })
     }
 
+    /// Description of `key`: the comment on the line directly above it, without `#`.
+    pub fn description(&self, key: &str) -> Option<String> {
+        let index = self.entry_index(key)?;
+        match index.checked_sub(1).map(|above| &self.lines[above]) {
+            Some(Line::Comment(text)) => {
+                let text = text.trim_start().trim_start_matches('#').trim();
+                (!text.is_empty()).then(|| text.to_owned())
+            }
+            _ => None,
+        }
+    }
+
+    /// Write `description` as the comment directly above `key`, replacing an existing one.
+    pub fn set_description(&mut self, key: &str, description: &str) {
+        let Some(index) = self.entry_index(key) else {
+            return;
+        };
+        let comment = Line::Comment(format!("# {}", description.trim()));
+        match index.checked_sub(1) {
+            Some(above) if matches!(self.lines[above], Line::Comment(_)) => self.lines[above] = comment,
+            _ => self.lines.insert(index, comment),
+        }
+    }
+
+    fn entry_index(&self, key: &str) -> Option<usize> {
+        self.lines
+            .iter()
+            .position(|line| matches!(line, Line::Entry { key: existing, .. } if existing == key))
+    }
+
     pub fn upsert(&mut self, key: &str, value: &str) {
         for line in &mut self.lines {
             if let Line::Entry {
@@ -63,6 +120,24 @@ impl EnvFile {
         });
     }
 
+    /// Rename `old` to `new` in place, keeping its line position (and so any comment
+    /// above it). Fails when `new` already exists; returns `false` when `old` is absent.
+    pub fn rename(&mut self, old: &str, new: &str) -> Result<bool> {
+        if self.entries().any(|(key, _)| key == new) {
+            bail!("key `{}` already exists in {}", new, self.path);
+        }
+        let mut renamed = false;
+        for line in &mut self.lines {
+            if let Line::Entry { key, .. } = line
+                && key == old
+            {
+                *key = new.to_owned();
+                renamed = true;
+            }
+        }
+        Ok(renamed)
+    }
+
     pub fn remove(&mut self, key: &str) -> bool {
         let mut removed = false;
         self.lines.retain(|line| match line {
@@ -75,28 +150,140 @@ impl EnvFile {
         removed
     }
 
-    pub fn save(&self) -> Result<()> {
-        if let Some(parent) = self.path.parent() {
-            fs::create_dir_all(parent).with_context(|| format!("creating directory {}", parent))?;
+    /// Reorganize in place for `dev env fmt`: a comment block that follows a blank
+    /// line heads a group, keys are sorted within their group, duplicates keep the
+    /// last occurrence, quoting is normalized, and groups are separated by one blank
+    /// line. Comments directly above a key with no blank line before them travel with
+    /// that key, and standalone comment blocks stay put. Returns whether anything changed.
+    pub fn format(&mut self) -> bool {
+        struct Group {
+            header: Vec<String>,
+            entries: Vec<(Vec<String>, String, String)>,
+            /// Standalone comment blocks take no entries.
+            open: bool,
         }
 
-        let mut buffer = String::new();
-        for (idx, line) in self.lines.iter().enumerate() {
-            if idx > 0 {
-                buffer.push('\n');
-            }
+        let last_index: HashMap<&str, usize> = self
+            .entries()
+            .enumerate()
+            .map(|(index, (key, _))| (key, index))
+            .collect();
+
+        let mut groups: Vec<Group> = Vec::new();
+        let mut pending: Vec<String> = Vec::new();
+        let mut pending_is_header = true;
+        let mut after_blank = true;
+        let mut entry_index = 0;
+        for line in &self.lines {
             match line {
+                Line::Blank => {
+                    if !pending.is_empty() {
+                        groups.push(Group {
+                            header: std::mem::take(&mut pending),
+                            entries: Vec::new(),
+                            open: false,
+                        });
+                    }
+                    after_blank = true;
+                }
+                Line::Comment(text) => {
+                    if pending.is_empty() {
+                        pending_is_header = after_blank;
+                    }
+                    pending.push(text.clone());
+                    after_blank = false;
+                }
                 Line::Entry { key, value } => {
-                    buffer.push_str(key);
-                    buffer.push('=');
-                    buffer.push_str(value);
+                    let needs_group = match groups.last() {
+                        Some(group) => !group.open || (pending_is_header && !pending.is_empty()),
+                        None => true,
+                    };
+                    if needs_group {
+                        let header = if pending_is_header {
+                            std::mem::take(&mut pending)
+                        } else {
+                            Vec::new()
+                        };
+                        groups.push(Group {
+                            header,
+                            entries: Vec::new(),
+                            open: true,
+                        });
+                    }
+                    let group = groups.last_mut().expect("group pushed above");
+                    if last_index.get(key.as_str()) == Some(&entry_index) {
+                        group.entries.push((
+                            std::mem::take(&mut pending),
+                            key.clone(),
+                            normalize_value(value),
+                        ));
+                    } else if !pending.is_empty() {
+                        // An earlier duplicate is dropped; its comments stay with the group.
+                        group.header.append(&mut pending);
+                    }
+                    entry_index += 1;
+                    after_blank = false;
                 }
-                Line::Comment(text) => buffer.push_str(text),
-                Line::Blank => {}
             }
         }
+        if !pending.is_empty() {
+            groups.push(Group {
+                header: pending,
+                entries: Vec::new(),
+                open: false,
+            });
+        }
+
+        let mut lines = Vec::new();
+        for mut group in groups {
+            if group.header.is_empty() && group.entries.is_empty() {
+                continue;
+            }
+            if !lines.is_empty() {
+                lines.push(Line::Blank);
+            }
+            lines.extend(group.header.into_iter().map(Line::Comment));
+            group.entries.sort_by(|a, b| a.1.cmp(&b.1));
+            for (comments, key, value) in group.entries {
+                lines.extend(comments.into_iter().map(Line::Comment));
+                lines.push(Line::Entry { key, value });
+            }
+        }
+        if !lines.is_empty() {
+            // `save` joins lines with newlines; a trailing blank line ends the file with one.
+            lines.push(Line::Blank);
+        }
+
+        let changed = render_lines(&lines).trim_end_matches('\n')
+            != render_lines(&self.lines).trim_end_matches('\n');
+        self.lines = lines;
+        changed
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if self.encrypted {
+            bail!(
+                "{} is encrypted; run `dev env decrypt`, make the change, then `dev env encrypt`",
+                envcrypt::encrypted_path(&self.path)
+            );
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("creating directory {}", parent))?;
+        }
+
+        let buffer = render_lines(&self.lines);
 
-        fs::write(&self.path, buffer).with_context(|| format!("writing {}", self.path))
+        // Write beside the target and rename so an interrupted save never leaves a truncated file.
+        let file_name = self.path.file_name().unwrap_or(ENV_FILENAME);
+        let temp = self.path.with_file_name(format!(".{}.tmp-{}", file_name, std::process::id()));
+        fs::write(&temp, buffer).with_context(|| format!("writing {}", temp))?;
+        if let Ok(metadata) = fs::metadata(&self.path) {
+            // Keep e.g. 0600 on an existing .env rather than the temp file's defaults.
+            fs::set_permissions(&temp, metadata.permissions())
+                .with_context(|| format!("setting permissions on {}", temp))?;
+        }
+        fs::rename(&temp, &self.path)
+            .with_context(|| format!("replacing {} with {}", self.path, temp))
     }
 }
 
@@ -104,7 +291,7 @@ pub fn locate(start: &Utf8Path) -> Result<Utf8PathBuf> {
     let mut current: Option<&Utf8Path> = Some(start);
     while let Some(dir) = current {
         let candidate = dir.join(ENV_FILENAME);
-        if candidate.exists() {
+        if candidate.exists() || envcrypt::encrypted_path(&candidate).exists() {
             return Ok(candidate);
         }
         current = dir.parent();
@@ -121,6 +308,77 @@ pub fn locate(start: &Utf8Path) -> Result<Utf8PathBuf> {
     Ok(start.join(ENV_FILENAME))
 }
 
+/// Path of the `.env.example` sibling for the given `.env` path.
+pub fn example_path(env_path: &Utf8Path) -> Result<Utf8PathBuf> {
+    let dir = env_path
+        .parent()
+        .ok_or_else(|| anyhow!("cannot determine parent directory of {}", env_path))?;
+    Ok(dir.join(".env.example"))
+}
+
+/// Copy `.env.example` into place at `env_path`, returning the example path used.
+/// Create `.env` from `.env.example`, expanding `{{name}}` placeholders when `vars` is given.
+pub fn init_from_example(env_path: &Utf8Path, vars: Option<&Vars>) -> Result<Utf8PathBuf> {
+    let example = example_path(env_path)?;
+    if !example.exists() {
+        bail!(
+            "no .env.example found at {}. Create one first or use `dev env template` to generate it.",
+            example
+        );
+    }
+
+    match vars {
+        Some(vars) => {
+            let raw = fs::read_to_string(&example).with_context(|| format!("reading {}", example))?;
+            let expanded = vars
+                .expand(&raw)
+                .with_context(|| format!("expanding [vars] in {}", example))?;
+            fs::write(env_path, expanded).with_context(|| format!("writing {}", env_path))?;
+        }
+        None => {
+            fs::copy(example.as_std_path(), env_path.as_std_path())
+                .with_context(|| format!("copying {} to {}", example, env_path))?;
+        }
+    }
+    Ok(example)
+}
+
+fn render_lines(lines: &[Line]) -> String {
+    let mut buffer = String::new();
+    for (idx, line) in lines.iter().enumerate() {
+        if idx > 0 {
+            buffer.push('\n');
+        }
+        match line {
+            Line::Entry { key, value } => {
+                buffer.push_str(key);
+                buffer.push('=');
+                buffer.push_str(value);
+            }
+            Line::Comment(text) => buffer.push_str(text),
+            Line::Blank => {}
+        }
+    }
+    buffer
+}
+
+/// Canonical quoting for a raw value: bare when safe, double-quoted otherwise.
+/// Single-quoted values containing `$` and bare values with an inline `#` comment
+/// are left alone, since requoting would change how loaders read them.
+fn normalize_value(raw: &str) -> String {
+    let trimmed = raw.trim();
+    let single_quoted = trimmed.len() >= 2 && trimmed.starts_with('\'') && trimmed.ends_with('\'');
+    let double_quoted = trimmed.len() >= 2 && trimmed.starts_with('"') && trimmed.ends_with('"');
+    if (single_quoted && trimmed.contains('$')) || (!single_quoted && !double_quoted && trimmed.contains(" #")) {
+        return trimmed.to_owned();
+    }
+    let value = unquote(trimmed);
+    if value.is_empty() {
+        return String::new();
+    }
+    envconvert::dotenv_value(&value)
+}
+
 fn parse_lines(contents: &str) -> Vec<Line> {
     contents
         .lines()
@@ -156,9 +414,253 @@ pub fn current_working_dir() -> Result<Utf8PathBuf> {
     Utf8PathBuf::from_path_buf(cwd).map_err(|_| anyhow!("current directory is not valid UTF-8"))
 }
 
+/// Entries of `.env` (or `.env.enc`) with `.env.<profile>` layered on top, values unquoted.
+/// Missing files contribute nothing; unlike `EnvFile::load`, nothing is created.
+pub fn resolved_entries(env_path: &Utf8Path, profile: Option<&str>) -> Result<Vec<(String, String)>> {
+    let layers = layer_paths(env_path, false, profile);
+    Ok(resolve_layers(&layers)?
+        .into_iter()
+        .map(|entry| (entry.key, entry.value))
+        .collect())
+}
+
+/// An effective value and the layer file it was read from.
+#[derive(Debug)]
+pub struct ResolvedEntry {
+    pub key: String,
+    pub value: String,
+    pub source: Utf8PathBuf,
+    /// Comment directly above the key in `source`.
+    pub description: Option<String>,
+}
+
+/// Files read for `env_path`, lowest precedence first: `.env`, then `.env.local`
+/// when `local` is set, then `.env.<profile>`.
+pub fn layer_paths(env_path: &Utf8Path, local: bool, profile: Option<&str>) -> Vec<Utf8PathBuf> {
+    let mut layers = vec![env_path.to_owned()];
+    if local {
+        layers.push(Utf8PathBuf::from(format!("{}.local", env_path)));
+    }
+    if let Some(profile) = profile {
+        layers.push(Utf8PathBuf::from(format!("{}.{}", env_path, profile)));
+    }
+    layers
+}
+
+/// Merge `layers` in order so later files override earlier ones, keeping the
+/// position where each key first appeared. Values are unquoted.
+pub fn resolve_layers(layers: &[Utf8PathBuf]) -> Result<Vec<ResolvedEntry>> {
+    let mut entries: Vec<ResolvedEntry> = Vec::new();
+    for layer in layers {
+        if !layer.exists() && !envcrypt::encrypted_path(layer).exists() {
+            continue;
+        }
+        let env = EnvFile::load(layer)?;
+        for (key, value) in env.entries() {
+            let value = unquote(value);
+            let description = env.description(key);
+            match entries.iter_mut().find(|entry| entry.key == key) {
+                Some(entry) => {
+                    entry.value = value;
+                    entry.source = layer.clone();
+                    entry.description = description.or(entry.description.take());
+                }
+                None => entries.push(ResolvedEntry {
+                    key: key.to_owned(),
+                    value,
+                    source: layer.clone(),
+                    description,
+                }),
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// `.dev/env-profile` next to `.env`: the profile `dev env switch` selected in layered mode.
+pub fn active_profile_path(env_path: &Utf8Path) -> Result<Utf8PathBuf> {
+    let dir = env_path
+        .parent()
+        .ok_or_else(|| anyhow!("cannot determine parent directory of {}", env_path))?;
+    Ok(dir.join(".dev").join("env-profile"))
+}
+
+/// Profile recorded by `dev env switch` in layered mode, if any.
+pub fn active_profile(env_path: &Utf8Path) -> Result<Option<String>> {
+    let path = active_profile_path(env_path)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let raw = fs::read_to_string(&path).with_context(|| format!("reading {}", path))?;
+    let profile = raw.trim();
+    Ok((!profile.is_empty()).then(|| profile.to_owned()))
+}
+
+/// Record `profile` as the layer applied on top of `.env` and `.env.local`.
+pub fn set_active_profile(env_path: &Utf8Path, profile: &str) -> Result<Utf8PathBuf> {
+    let path = active_profile_path(env_path)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("creating directory {}", parent))?;
+    }
+    fs::write(&path, format!("{}\n", profile)).with_context(|| format!("writing {}", path))?;
+    Ok(path)
+}
+
+/// Value with one layer of surrounding quotes removed; double-quoted values also
+/// have `\n`, `\"`, and `\\` escapes decoded.
+pub fn unquote(value: &str) -> String {
+    let trimmed = value.trim();
+    let quoted = |quote: char| {
+        trimmed.len() >= 2 && trimmed.starts_with(quote) && trimmed.ends_with(quote)
+    };
+    if quoted('\'') {
+        return trimmed[1..trimmed.len() - 1].to_owned();
+    }
+    if !quoted('"') {
+        return trimmed.to_owned();
+    }
+
+    let mut out = String::new();
+    let mut chars = trimmed[1..trimmed.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                out.push('\n');
+                chars.next();
+            }
+            ('\\', Some(escaped @ ('"' | '\\'))) => {
+                out.push(escaped);
+                chars.next();
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
 #[derive(Debug)]
 enum Line {
     Entry { key: String, value: String },
     Comment(String),
     Blank,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn later_layers_override_and_record_their_source() {
+        let root = Utf8PathBuf::from_path_buf(std::env::temp_dir())
+            .unwrap()
+            .join(format!("devkit-envfile-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let env_path = root.join(".env");
+        fs::write(&env_path, "A=base\nB=\"quoted\"\nC=base\n").unwrap();
+        fs::write(root.join(".env.local"), "B=local\n").unwrap();
+        fs::write(root.join(".env.staging"), "C=staging\nD=staging\n").unwrap();
+
+        assert_eq!(active_profile(&env_path).unwrap(), None);
+        set_active_profile(&env_path, "staging").unwrap();
+        let profile = active_profile(&env_path).unwrap();
+        assert_eq!(profile.as_deref(), Some("staging"));
+
+        let layers = layer_paths(&env_path, true, profile.as_deref());
+        let resolved = resolve_layers(&layers).unwrap();
+        let summary: Vec<(&str, &str, &str)> = resolved
+            .iter()
+            .map(|entry| {
+                (entry.key.as_str(), entry.value.as_str(), entry.source.file_name().unwrap())
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("A", "base", ".env"),
+                ("B", "local", ".env.local"),
+                ("C", "staging", ".env.staging"),
+                ("D", "staging", ".env.staging"),
+            ]
+        );
+
+        let plain = resolved_entries(&env_path, None).unwrap();
+        assert_eq!(plain[1], ("B".to_owned(), "quoted".to_owned()));
+
+        let _ = fs::remove_dir_all(root.as_std_path());
+    }
+
+    #[test]
+    fn format_sorts_groups_dedupes_and_normalizes_quotes() {
+        let mut env = EnvFile {
+            path: Utf8PathBuf::from(".env.example"),
+            lines: parse_lines(concat!(
+                "# Team env\n",
+                "\n",
+                "# Database\n",
+                "DB_USER='app'\n",
+                "DB_HOST=localhost\n",
+                "\n",
+                "\n",
+                "# Web\n",
+                "PORT=8080\n",
+                "# why debug\n",
+                "DEBUG=\"true\"\n",
+                "DB_HOST=db\n",
+                "MSG=hello world\n",
+                "PASS='a$b'\n",
+            )),
+            encrypted: false,
+        };
+
+        assert!(env.format());
+        assert_eq!(
+            render_lines(&env.lines),
+            concat!(
+                "# Team env\n",
+                "\n",
+                "# Database\n",
+                "DB_USER=app\n",
+                "\n",
+                "# Web\n",
+                "DB_HOST=db\n",
+                "# why debug\n",
+                "DEBUG=true\n",
+                "MSG=\"hello world\"\n",
+                "PASS='a$b'\n",
+                "PORT=8080\n",
+            )
+        );
+        assert!(!env.format());
+    }
+
+    #[test]
+    fn rename_keeps_position_and_comment() {
+        let mut env = EnvFile {
+            path: Utf8PathBuf::from(".env"),
+            lines: parse_lines("A=1\n# the db\nDB=x\nC=3\n"),
+            encrypted: false,
+        };
+        assert!(env.rename("DB", "DATABASE_URL").unwrap());
+        assert_eq!(render_lines(&env.lines), "A=1\n# the db\nDATABASE_URL=x\nC=3");
+        assert!(!env.rename("MISSING", "OTHER").unwrap());
+        assert!(env.rename("A", "C").is_err());
+    }
+
+    #[test]
+    fn descriptions_come_from_the_comment_above() {
+        let mut env = EnvFile {
+            path: Utf8PathBuf::from(".env"),
+            lines: parse_lines("# Postgres connection string\nDATABASE_URL=x\n\nDEBUG=1\n"),
+            encrypted: false,
+        };
+        assert_eq!(env.description("DATABASE_URL").as_deref(), Some("Postgres connection string"));
+        assert_eq!(env.description("DEBUG"), None);
+
+        env.set_description("DEBUG", "Verbose logging");
+        env.set_description("DATABASE_URL", "Primary database");
+        assert_eq!(
+            render_lines(&env.lines),
+            "# Primary database\nDATABASE_URL=x\n\n# Verbose logging\nDEBUG=1"
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use std::fs;
+
+use anyhow::{Context, Result, anyhow, bail};
+use camino::{Utf8Path, Utf8PathBuf};
+use chrono::{Local, NaiveDateTime};
+
+/// Snapshots beyond this many are pruned, oldest first.
+const MAX_SNAPSHOTS: usize = 50;
+
+/// A copy of `.env` taken before a mutating `dev env` command.
+#[derive(Debug)]
+pub struct Snapshot {
+    pub path: Utf8PathBuf,
+    /// `YYYYMMDDTHHMMSS.ffffff` local time the snapshot was taken.
+    pub taken: String,
+    /// Command that was about to change `.env`, e.g. `switch`.
+    pub operation: String,
+}
+
+impl Snapshot {
+    /// `taken` as `YYYY-MM-DD HH:MM:SS` for display.
+    pub fn taken_display(&self) -> String {
+        NaiveDateTime::parse_from_str(&self.taken, "%Y%m%dT%H%M%S%.f")
+            .map(|taken| taken.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|_| self.taken.clone())
+    }
+}
+
+/// `.dev/env-history/` next to the `.env` file.
+pub fn history_dir(env_path: &Utf8Path) -> Result<Utf8PathBuf> {
+    let dir = env_path
+        .parent()
+        .ok_or_else(|| anyhow!("cannot determine parent directory of {}", env_path))?;
+    Ok(dir.join(".dev").join("env-history"))
+}
+
+/// Copy the current `.env` into the history before `operation` changes it.
+/// Returns `None` when there is no `.env` yet, so nothing can be lost.
+pub fn snapshot(env_path: &Utf8Path, operation: &str) -> Result<Option<Utf8PathBuf>> {
+    if !env_path.exists() {
+        return Ok(None);
+    }
+    let dir = history_dir(env_path)?;
+    if !dir.exists() {
+        fs::create_dir_all(&dir).with_context(|| format!("creating directory {}", dir))?;
+        // Snapshots hold secrets; keep them out of git even if `.dev/` is committed.
+        fs::write(dir.join(".gitignore"), "*\n")
+            .with_context(|| format!("writing {}", dir.join(".gitignore")))?;
+    }
+
+    let taken = Local::now().format("%Y%m%dT%H%M%S%.6f");
+    let path = dir.join(format!("{}-{}.env", taken, operation));
+    fs::copy(env_path, &path).with_context(|| format!("copying {} to {}", env_path, path))?;
+
+    let snapshots = list(env_path)?;
+    for stale in snapshots.iter().skip(MAX_SNAPSHOTS) {
+        fs::remove_file(&stale.path).with_context(|| format!("removing {}", stale.path))?;
+    }
+    Ok(Some(path))
+}
+
+/// Snapshots for `env_path`, newest first.
+pub fn list(env_path: &Utf8Path) -> Result<Vec<Snapshot>> {
+    let dir = history_dir(env_path)?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut snapshots = Vec::new();
+    for entry in fs::read_dir(&dir).with_context(|| format!("reading {}", dir))? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        let Some((taken, operation)) = name.strip_suffix(".env").and_then(|stem| stem.split_once('-'))
+        else {
+            continue;
+        };
+        snapshots.push(Snapshot {
+            path: dir.join(&name),
+            taken: taken.to_owned(),
+            operation: operation.to_owned(),
+        });
+    }
+    snapshots.sort_by(|a, b| b.taken.cmp(&a.taken));
+    Ok(snapshots)
+}
+
+/// Restore snapshot `n` (1 = newest) over `.env`, snapshotting the current file first
+/// so the rollback itself can be undone.
+pub fn rollback(env_path: &Utf8Path, n: usize) -> Result<Snapshot> {
+    let mut snapshots = list(env_path)?;
+    if n == 0 || n > snapshots.len() {
+        bail!(
+            "no snapshot #{} for {} ({} available; see `dev env history`)",
+            n,
+            env_path,
+            snapshots.len()
+        );
+    }
+    let chosen = snapshots.remove(n - 1);
+    // Read first: the snapshot below may prune the chosen file if it is the oldest.
+    let contents = fs::read(&chosen.path).with_context(|| format!("reading {}", chosen.path))?;
+    snapshot(env_path, "rollback")?;
+    fs::write(env_path, contents).with_context(|| format!("writing {}", env_path))?;
+    Ok(chosen)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rollback_restores_and_can_be_undone() {
+        let root = Utf8PathBuf::from_path_buf(std::env::temp_dir())
+            .unwrap()
+            .join(format!("devkit-envhistory-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let env_path = root.join(".env");
+
+        fs::write(&env_path, "A=1\n").unwrap();
+        snapshot(&env_path, "switch").unwrap();
+        fs::write(&env_path, "A=2\n").unwrap();
+
+        let restored = rollback(&env_path, 1).unwrap();
+        assert_eq!(restored.operation, "switch");
+        assert_eq!(fs::read_to_string(&env_path).unwrap(), "A=1\n");
+
+        let snapshots = list(&env_path).unwrap();
+        assert_eq!(snapshots[0].operation, "rollback");
+        rollback(&env_path, 1).unwrap();
+        assert_eq!(fs::read_to_string(&env_path).unwrap(), "A=2\n");
+        assert!(rollback(&env_path, 99).is_err());
+
+        let _ = fs::remove_dir_all(root.as_std_path());
+    }
+}
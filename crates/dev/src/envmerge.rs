@@ -0,0 +1,130 @@
+use anyhow::Result;
+use camino::Utf8Path;
+
+use crate::cli::EnvMergeStrategy;
+use crate::envfile::{self, EnvFile};
+
+/// One key of the merged output, with its raw (still quoted) value.
+#[derive(Debug)]
+pub struct MergedEntry {
+    pub key: String,
+    pub value: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Merge {
+    /// Keys in the order they first appear across the inputs.
+    pub entries: Vec<MergedEntry>,
+    /// Keys whose values differed between inputs.
+    pub conflicts: Vec<String>,
+}
+
+/// Merge `inputs` for `dev env merge`. Keys with differing values are settled by
+/// `strategy`; under `interactive`, `choose` gets the key and each `(file, value)`
+/// candidate and returns the index of the one to keep.
+pub fn merge(
+    inputs: &[EnvFile],
+    strategy: EnvMergeStrategy,
+    mut choose: impl FnMut(&str, &[(&Utf8Path, &str)]) -> Result<usize>,
+) -> Result<Merge> {
+    let mut keys: Vec<&str> = Vec::new();
+    for input in inputs {
+        for (key, _) in input.entries() {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+    }
+
+    let mut merge = Merge {
+        entries: Vec::new(),
+        conflicts: Vec::new(),
+    };
+    for key in keys {
+        let candidates: Vec<(&EnvFile, &str)> = inputs
+            .iter()
+            .filter_map(|input| {
+                input
+                    .entries()
+                    .filter(|(existing, _)| *existing == key)
+                    .last()
+                    .map(|(_, value)| (input, value))
+            })
+            .collect();
+
+        let first = envfile::unquote(candidates[0].1);
+        let conflicting = candidates.iter().any(|(_, value)| envfile::unquote(value) != first);
+        let index = if !conflicting {
+            candidates.len() - 1
+        } else {
+            merge.conflicts.push(key.to_owned());
+            match strategy {
+                EnvMergeStrategy::PreferFirst => 0,
+                EnvMergeStrategy::PreferLast => candidates.len() - 1,
+                EnvMergeStrategy::Interactive => {
+                    let options: Vec<(&Utf8Path, &str)> = candidates
+                        .iter()
+                        .map(|(input, value)| (input.path(), *value))
+                        .collect();
+                    choose(key, &options)?.min(candidates.len() - 1)
+                }
+            }
+        };
+
+        let (chosen, value) = candidates[index];
+        let description = chosen.description(key).or_else(|| {
+            candidates
+                .iter()
+                .find_map(|(input, _)| input.description(key))
+        });
+        merge.entries.push(MergedEntry {
+            key: key.to_owned(),
+            value: value.to_owned(),
+            description,
+        });
+    }
+    Ok(merge)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino::Utf8PathBuf;
+    use std::fs;
+
+    #[test]
+    fn strategies_settle_conflicting_values() {
+        let root = Utf8PathBuf::from_path_buf(std::env::temp_dir())
+            .unwrap()
+            .join(format!("devkit-envmerge-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("api.env"), "# shared db\nDB=one\nPORT=80\n").unwrap();
+        fs::write(root.join("web.env"), "DB=two\nPORT=\"80\"\nCDN=x\n").unwrap();
+        let inputs = vec![
+            EnvFile::load(&root.join("api.env")).unwrap(),
+            EnvFile::load(&root.join("web.env")).unwrap(),
+        ];
+        let never = |_: &str, _: &[(&Utf8Path, &str)]| -> Result<usize> { unreachable!() };
+
+        let last = merge(&inputs, EnvMergeStrategy::PreferLast, never).unwrap();
+        assert_eq!(last.conflicts, ["DB"]);
+        let keys: Vec<&str> = last.entries.iter().map(|entry| entry.key.as_str()).collect();
+        assert_eq!(keys, ["DB", "PORT", "CDN"]);
+        assert_eq!(last.entries[0].value, "two");
+        assert_eq!(last.entries[0].description.as_deref(), Some("shared db"));
+
+        let first = merge(&inputs, EnvMergeStrategy::PreferFirst, never).unwrap();
+        assert_eq!(first.entries[0].value, "one");
+
+        let picked = merge(&inputs, EnvMergeStrategy::Interactive, |key, options| {
+            assert_eq!(key, "DB");
+            assert_eq!(options.len(), 2);
+            Ok(0)
+        })
+        .unwrap();
+        assert_eq!(picked.entries[0].value, "one");
+
+        let _ = fs::remove_dir_all(root.as_std_path());
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use anyhow::{Context, Result, anyhow, bail};
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::Deserialize;
+use toml::Value;
+use toml_edit::DocumentMut;
+
+use crate::envconvert;
+
+pub const SCHEMA_FILENAME: &str = ".env.schema";
+
+/// Declared value type of a schema key.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyType {
+    #[default]
+    String,
+    Int,
+    Float,
+    Bool,
+    Url,
+}
+
+impl KeyType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            KeyType::String => "string",
+            KeyType::Int => "int",
+            KeyType::Float => "float",
+            KeyType::Bool => "bool",
+            KeyType::Url => "url",
+        }
+    }
+
+    fn accepts(&self, value: &str) -> bool {
+        match self {
+            KeyType::String => true,
+            KeyType::Int => value.parse::<i64>().is_ok(),
+            KeyType::Float => value.parse::<f64>().is_ok(),
+            KeyType::Bool => matches!(
+                value.to_ascii_lowercase().as_str(),
+                "true" | "false" | "1" | "0" | "yes" | "no"
+            ),
+            KeyType::Url => value
+                .split_once("://")
+                .is_some_and(|(scheme, rest)| !scheme.is_empty() && !rest.is_empty()),
+        }
+    }
+}
+
+/// One `[KEY]` table in `.env.schema`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeySpec {
+    #[serde(rename = "type", default)]
+    pub kind: KeyType,
+    #[serde(default)]
+    pub required: bool,
+    pub default: Option<Value>,
+    pub description: Option<String>,
+}
+
+impl KeySpec {
+    /// `default` as it would be written to `.env`, before quoting.
+    pub fn default_value(&self) -> Option<String> {
+        self.default.as_ref().map(|value| match value {
+            Value::String(value) => value.clone(),
+            other => other.to_string(),
+        })
+    }
+}
+
+/// `.env.schema`: keys in declaration order with their specs.
+#[derive(Debug)]
+pub struct EnvSchema {
+    pub path: Utf8PathBuf,
+    pub keys: Vec<(String, KeySpec)>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Problem {
+    Missing(String),
+    Empty(String),
+    WrongType { key: String, expected: &'static str, value: String },
+    Undeclared(String),
+}
+
+impl Problem {
+    /// Undeclared keys are worth a warning; everything else fails the check.
+    pub fn is_error(&self) -> bool {
+        !matches!(self, Problem::Undeclared(_))
+    }
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::Missing(key) => write!(f, "{} is required but missing", key),
+            Problem::Empty(key) => write!(f, "{} is required but empty", key),
+            Problem::WrongType { key, expected, value } => {
+                write!(f, "{} should be {} (got `{}`)", key, expected, value)
+            }
+            Problem::Undeclared(key) => write!(f, "{} is not declared in the schema", key),
+        }
+    }
+}
+
+/// `.env.schema` next to the `.env` file.
+pub fn schema_path(env_path: &Utf8Path) -> Result<Utf8PathBuf> {
+    let dir = env_path
+        .parent()
+        .ok_or_else(|| anyhow!("cannot determine parent directory of {}", env_path))?;
+    Ok(dir.join(SCHEMA_FILENAME))
+}
+
+/// Load the schema beside `env_path`, or `None` when there is none.
+pub fn load(env_path: &Utf8Path) -> Result<Option<EnvSchema>> {
+    let path = schema_path(env_path)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let raw = fs::read_to_string(&path).with_context(|| format!("reading {}", path))?;
+    parse(&path, &raw).map(Some)
+}
+
+fn parse(path: &Utf8Path, raw: &str) -> Result<EnvSchema> {
+    let mut specs: BTreeMap<String, KeySpec> =
+        toml::from_str(raw).with_context(|| format!("parsing {}", path))?;
+    // `toml` sorts keys; take the order from the document so `init` follows the file.
+    let doc: DocumentMut = raw.parse().with_context(|| format!("parsing {}", path))?;
+    let mut keys = Vec::new();
+    for (key, _) in doc.iter() {
+        let spec = specs.remove(key).expect("both parsers see the same keys");
+        if spec.required && spec.default.is_some() {
+            bail!("{}: `{}` is required and has a default; pick one", path, key);
+        }
+        if let Some(default) = spec.default_value()
+            && !spec.kind.accepts(&default)
+        {
+            bail!("{}: default for `{}` is not a valid {}", path, key, spec.kind.as_str());
+        }
+        keys.push((key.to_owned(), spec));
+    }
+    Ok(EnvSchema {
+        path: path.to_owned(),
+        keys,
+    })
+}
+
+impl EnvSchema {
+    /// `.env` contents for `dev env init`: descriptions as comments, defaults filled in.
+    pub fn render_env(&self) -> String {
+        let mut out = format!("# Generated from {}\n", SCHEMA_FILENAME);
+        for (key, spec) in &self.keys {
+            out.push('\n');
+            if let Some(description) = &spec.description {
+                out.push_str(&format!("# {}\n", description.trim()));
+            }
+            let value = spec
+                .default_value()
+                .map(|value| envconvert::dotenv_value(&value))
+                .unwrap_or_default();
+            out.push_str(&format!("{}={}\n", key, value));
+        }
+        out
+    }
+
+    /// Check unquoted `.env` entries against the schema.
+    pub fn validate(&self, entries: &[(String, String)]) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        for (key, spec) in &self.keys {
+            match entries.iter().find(|(existing, _)| existing == key) {
+                None if spec.required => problems.push(Problem::Missing(key.clone())),
+                Some((_, value)) if value.is_empty() && spec.required => {
+                    problems.push(Problem::Empty(key.clone()))
+                }
+                Some((_, value)) if !value.is_empty() && !spec.kind.accepts(value) => {
+                    problems.push(Problem::WrongType {
+                        key: key.clone(),
+                        expected: spec.kind.as_str(),
+                        value: value.clone(),
+                    })
+                }
+                _ => {}
+            }
+        }
+        for (key, _) in entries {
+            if !self.keys.iter().any(|(declared, _)| declared == key) {
+                problems.push(Problem::Undeclared(key.clone()));
+            }
+        }
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = r#"
+[DATABASE_URL]
+type = "url"
+required = true
+description = "Primary database"
+
+[PORT]
+type = "int"
+default = 8080
+
+[DEBUG]
+type = "bool"
+default = false
+"#;
+
+    #[test]
+    fn init_fills_defaults_in_declaration_order() {
+        let schema = parse(Utf8Path::new(".env.schema"), SCHEMA).unwrap();
+        assert_eq!(
+            schema.render_env(),
+            "# Generated from .env.schema\n\n# Primary database\nDATABASE_URL=\n\nPORT=8080\n\nDEBUG=false\n"
+        );
+    }
+
+    #[test]
+    fn validate_reports_missing_types_and_undeclared_keys() {
+        let schema = parse(Utf8Path::new(".env.schema"), SCHEMA).unwrap();
+        let entries = vec![
+            ("PORT".to_owned(), "eighty".to_owned()),
+            ("DEBUG".to_owned(), "yes".to_owned()),
+            ("EXTRA".to_owned(), "1".to_owned()),
+        ];
+        let problems = schema.validate(&entries);
+        assert_eq!(
+            problems,
+            [
+                Problem::Missing("DATABASE_URL".to_owned()),
+                Problem::WrongType {
+                    key: "PORT".to_owned(),
+                    expected: "int",
+                    value: "eighty".to_owned()
+                },
+                Problem::Undeclared("EXTRA".to_owned()),
+            ]
+        );
+        assert!(!problems[2].is_error());
+
+        assert!(parse(Utf8Path::new("s"), "[A]\ntype = \"int\"\ndefault = \"x\"\n").is_err());
+        assert!(parse(Utf8Path::new("s"), "[A]\ncolour = \"red\"\n").is_err());
+    }
+}
@@ -0,0 +1,277 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde_json::{Map, Value};
+
+use crate::cli::{EnvImportFormat, SecretBackend};
+use crate::envconvert;
+
+impl SecretBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SecretBackend::Aws => "aws",
+            SecretBackend::Vault => "vault",
+            SecretBackend::OnePassword => "1password",
+        }
+    }
+
+    fn program(&self) -> &'static str {
+        match self {
+            SecretBackend::Aws => "aws",
+            SecretBackend::Vault => "vault",
+            SecretBackend::OnePassword => "op",
+        }
+    }
+}
+
+/// Fetch every field stored at `path`: an AWS secret id, a Vault KV path, or a
+/// 1Password item (`vault/item`, or just `item`).
+pub fn pull(backend: SecretBackend, path: &str) -> Result<Vec<(String, String)>> {
+    match backend {
+        SecretBackend::Aws => {
+            let raw = run(
+                backend,
+                &[
+                    "secretsmanager",
+                    "get-secret-value",
+                    "--secret-id",
+                    path,
+                    "--query",
+                    "SecretString",
+                    "--output",
+                    "text",
+                ],
+                None,
+            )?;
+            envconvert::parse(&raw, EnvImportFormat::Json)
+                .with_context(|| format!("secret `{}` is not a JSON object of key/value pairs", path))
+        }
+        SecretBackend::Vault => {
+            let raw = run(backend, &["kv", "get", "-format=json", path], None)?;
+            let response: Value = serde_json::from_str(&raw).context("parsing vault output")?;
+            // KV v2 nests the fields one level deeper than KV v1.
+            let data = response
+                .pointer("/data/data")
+                .filter(|data| data.is_object())
+                .or_else(|| response.get("data"))
+                .cloned()
+                .ok_or_else(|| anyhow!("vault returned no data for `{}`", path))?;
+            envconvert::parse(&data.to_string(), EnvImportFormat::Json)
+        }
+        SecretBackend::OnePassword => {
+            let item = op_item(path)?;
+            Ok(item
+                .get("fields")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|field| {
+                    let label = field.get("label")?.as_str()?;
+                    let value = field.get("value")?.as_str()?;
+                    Some((label.to_owned(), value.to_owned()))
+                })
+                .collect())
+        }
+    }
+}
+
+/// Write `entries` to `path`, keeping remote fields that are not being pushed.
+pub fn push(backend: SecretBackend, path: &str, entries: &[(String, String)]) -> Result<()> {
+    match backend {
+        SecretBackend::Aws | SecretBackend::Vault => {
+            let mut merged: Map<String, Value> = pull(backend, path)?
+                .into_iter()
+                .map(|(key, value)| (key, Value::String(value)))
+                .collect();
+            for (key, value) in entries {
+                merged.insert(key.clone(), Value::String(value.clone()));
+            }
+            // Values go over stdin so they never show up in the process list.
+            let payload = Value::Object(merged).to_string();
+            let args: &[&str] = match backend {
+                SecretBackend::Aws => &[
+                    "secretsmanager",
+                    "put-secret-value",
+                    "--secret-id",
+                    path,
+                    "--secret-string",
+                    "file:///dev/stdin",
+                ],
+                _ => &["kv", "put", path, "-"],
+            };
+            run(backend, args, Some(&payload))?;
+        }
+        SecretBackend::OnePassword => {
+            // Field assignments would put the values in argv; send the whole
+            // item back as a JSON template over stdin instead.
+            let payload = op_template(op_item(path)?, entries).to_string();
+            run(backend, &op_edit_args(path), Some(&payload))?;
+        }
+    }
+    Ok(())
+}
+
+/// Translate remote field names to `.env` keys using `[env.sync.keys]` (`ENV_KEY = "remote"`).
+/// With a mapping, only mapped keys are kept.
+pub fn map_pulled(
+    remote: Vec<(String, String)>,
+    keys: Option<&BTreeMap<String, String>>,
+) -> Vec<(String, String)> {
+    let Some(keys) = keys else {
+        return remote;
+    };
+    keys.iter()
+        .filter_map(|(env_key, remote_key)| {
+            remote
+                .iter()
+                .find(|(name, _)| name == remote_key)
+                .map(|(_, value)| (env_key.clone(), value.clone()))
+        })
+        .collect()
+}
+
+/// Inverse of [`map_pulled`]: `.env` entries renamed to their remote field names.
+pub fn map_pushed(
+    local: Vec<(String, String)>,
+    keys: Option<&BTreeMap<String, String>>,
+) -> Vec<(String, String)> {
+    let Some(keys) = keys else {
+        return local;
+    };
+    local
+        .into_iter()
+        .filter_map(|(key, value)| keys.get(&key).map(|remote| (remote.clone(), value)))
+        .collect()
+}
+
+fn op_item_args(path: &str) -> Vec<&str> {
+    match path.split_once('/') {
+        Some((vault, item)) => vec![item, "--vault", vault],
+        None => vec![path],
+    }
+}
+
+fn op_item(path: &str) -> Result<Value> {
+    let mut args = vec!["item", "get"];
+    args.extend(op_item_args(path));
+    args.extend(["--format", "json"]);
+    let raw = run(SecretBackend::OnePassword, &args, None)?;
+    serde_json::from_str(&raw).context("parsing 1Password output")
+}
+
+fn op_edit_args(path: &str) -> Vec<&str> {
+    let mut args = vec!["item", "edit"];
+    args.extend(op_item_args(path));
+    args.extend(["--template", "/dev/stdin"]);
+    args
+}
+
+/// `item` with each entry's field updated, or added as a concealed field.
+fn op_template(mut item: Value, entries: &[(String, String)]) -> Value {
+    let Some(object) = item.as_object_mut() else {
+        return item;
+    };
+    let fields = object
+        .entry("fields")
+        .or_insert_with(|| Value::Array(Vec::new()));
+    if !fields.is_array() {
+        *fields = Value::Array(Vec::new());
+    }
+    let fields = fields.as_array_mut().expect("fields was just made an array");
+    for (key, value) in entries {
+        let existing = fields
+            .iter_mut()
+            .find(|field| field.get("label").and_then(Value::as_str) == Some(key.as_str()));
+        match existing {
+            Some(field) => field["value"] = Value::String(value.clone()),
+            None => fields.push(serde_json::json!({
+                "label": key,
+                "type": "CONCEALED",
+                "value": value,
+            })),
+        }
+    }
+    item
+}
+
+fn run(backend: SecretBackend, args: &[&str], stdin: Option<&str>) -> Result<String> {
+    let program = backend.program();
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::inherit() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .with_context(|| format!("running `{}`; is the {} CLI installed?", program, backend.as_str()))?;
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(input.as_bytes())
+            .with_context(|| format!("writing to `{}`", program))?;
+    }
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("waiting for `{}`", program))?;
+    if !output.status.success() {
+        bail!(
+            "`{} {}` failed with exit code {:?}",
+            program,
+            args.first().copied().unwrap_or_default(),
+            output.status.code()
+        );
+    }
+    String::from_utf8(output.stdout).with_context(|| format!("`{}` printed non-UTF-8 output", program))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_mapping_renames_and_filters() {
+        let keys = BTreeMap::from([("DATABASE_URL".to_owned(), "db_url".to_owned())]);
+        let remote = vec![
+            ("db_url".to_owned(), "postgres://".to_owned()),
+            ("unrelated".to_owned(), "x".to_owned()),
+        ];
+        assert_eq!(
+            map_pulled(remote, Some(&keys)),
+            vec![("DATABASE_URL".to_owned(), "postgres://".to_owned())]
+        );
+        let local = vec![
+            ("DATABASE_URL".to_owned(), "postgres://".to_owned()),
+            ("LOCAL_ONLY".to_owned(), "1".to_owned()),
+        ];
+        assert_eq!(
+            map_pushed(local, Some(&keys)),
+            vec![("db_url".to_owned(), "postgres://".to_owned())]
+        );
+    }
+
+    #[test]
+    fn onepassword_push_keeps_values_out_of_argv() {
+        assert_eq!(
+            op_edit_args("Team/app"),
+            ["item", "edit", "app", "--vault", "Team", "--template", "/dev/stdin"]
+        );
+        let item = serde_json::json!({
+            "title": "app",
+            "fields": [
+                { "label": "API_KEY", "type": "CONCEALED", "value": "old" },
+                { "label": "notes", "type": "STRING", "value": "keep" },
+            ],
+        });
+        let entries = [
+            ("API_KEY".to_owned(), "new".to_owned()),
+            ("DB_URL".to_owned(), "postgres://".to_owned()),
+        ];
+        let template = op_template(item, &entries);
+        let fields = template["fields"].as_array().unwrap();
+        assert_eq!(fields.len(), 3);
+        assert_eq!(fields[0]["value"], "new");
+        assert_eq!(fields[1]["value"], "keep");
+        assert_eq!(fields[2]["label"], "DB_URL");
+        assert_eq!(fields[2]["type"], "CONCEALED");
+        assert_eq!(template["title"], "app");
+    }
+}
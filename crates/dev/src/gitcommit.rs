@@ -0,0 +1,338 @@
+use std::io::{self, IsTerminal, Write};
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+
+use crate::cli::CommitArgs;
+use crate::gitops;
+
+/// Commit types accepted by the wizard, with the hint shown when prompting.
+pub const TYPES: &[(&str, &str)] = &[
+    ("feat", "a new feature"),
+    ("fix", "a bug fix"),
+    ("docs", "documentation only"),
+    ("style", "formatting, no code change"),
+    (
+        "refactor",
+        "code change that neither fixes a bug nor adds a feature",
+    ),
+    ("perf", "performance improvement"),
+    ("test", "adding or fixing tests"),
+    ("build", "build system or dependencies"),
+    ("ci", "CI configuration"),
+    ("chore", "maintenance"),
+    ("revert", "reverts a previous commit"),
+];
+
+/// Longest header (`type(scope)!: subject`) accepted.
+pub const MAX_HEADER: usize = 72;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub kind: String,
+    pub scope: Option<String>,
+    pub subject: String,
+    pub body: Option<String>,
+    /// Description for the `BREAKING CHANGE:` footer; `Some` marks the header with `!`.
+    pub breaking: Option<String>,
+}
+
+impl Message {
+    pub fn header(&self) -> String {
+        let scope = self
+            .scope
+            .as_deref()
+            .map(|scope| format!("({})", scope))
+            .unwrap_or_default();
+        let bang = if self.breaking.is_some() { "!" } else { "" };
+        format!("{}{}{}: {}", self.kind, scope, bang, self.subject)
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = self.header();
+        if let Some(body) = &self.body {
+            out.push_str("\n\n");
+            out.push_str(body.trim_end());
+        }
+        if let Some(breaking) = &self.breaking {
+            out.push_str("\n\nBREAKING CHANGE: ");
+            out.push_str(breaking);
+        }
+        out
+    }
+
+    /// Every conventional-commit rule the message breaks; empty when it is valid.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = kind_problems(&self.kind);
+        if let Some(scope) = &self.scope {
+            problems.extend(scope_problems(scope));
+        }
+        problems.extend(subject_problems(&self.subject));
+        let header = self.header();
+        if header.chars().count() > MAX_HEADER {
+            problems.push(format!(
+                "header is {} characters; keep it within {}",
+                header.chars().count(),
+                MAX_HEADER
+            ));
+        }
+        if self
+            .breaking
+            .as_deref()
+            .is_some_and(|text| text.trim().is_empty())
+        {
+            problems.push("breaking change needs a description".to_string());
+        }
+        problems
+    }
+}
+
+fn kind_problems(kind: &str) -> Vec<String> {
+    if TYPES.iter().any(|(known, _)| *known == kind) {
+        return Vec::new();
+    }
+    let known: Vec<&str> = TYPES.iter().map(|(kind, _)| *kind).collect();
+    vec![format!(
+        "unknown type `{}` (expected one of: {})",
+        kind,
+        known.join(", ")
+    )]
+}
+
+fn scope_problems(scope: &str) -> Vec<String> {
+    let valid = !scope.is_empty()
+        && scope
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "-_/.".contains(c));
+    if valid {
+        return Vec::new();
+    }
+    vec![format!(
+        "scope `{}` must be lowercase letters, digits, `-`, `_`, `/` or `.`",
+        scope
+    )]
+}
+
+fn subject_problems(subject: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+    let trimmed = subject.trim();
+    if trimmed.is_empty() {
+        problems.push("subject must not be empty".to_string());
+        return problems;
+    }
+    if trimmed != subject {
+        problems.push("subject must not start or end with whitespace".to_string());
+    }
+    if trimmed.ends_with('.') {
+        problems.push("subject must not end with a period".to_string());
+    }
+    if trimmed.chars().next().is_some_and(|c| c.is_uppercase()) {
+        problems.push("subject must start with a lowercase letter".to_string());
+    }
+    problems
+}
+
+/// Build the message from flags, prompting for whatever is missing when attached to a terminal.
+/// Flags are checked before the first prompt, and prompted values are asked again until valid.
+pub fn compose(args: &CommitArgs) -> Result<Message> {
+    let problems: Vec<String> = [
+        args.kind.as_deref().map(kind_problems),
+        args.scope.as_deref().map(scope_problems),
+        args.subject.as_deref().map(subject_problems),
+    ]
+    .into_iter()
+    .flatten()
+    .flatten()
+    .collect();
+    if !problems.is_empty() {
+        bail!("invalid commit message:\n  - {}", problems.join("\n  - "));
+    }
+
+    let interactive = io::stdin().is_terminal();
+    let kind = match &args.kind {
+        Some(kind) => kind.clone(),
+        None if interactive => {
+            println!("Commit types:");
+            for (kind, hint) in TYPES {
+                println!("  {:<9} {}", kind, hint);
+            }
+            ask_valid("type: ", kind_problems)?
+        }
+        None => bail!("--type is required when stdin is not a terminal"),
+    };
+    let scope = match &args.scope {
+        Some(scope) => Some(scope.clone()),
+        None if interactive && args.subject.is_none() => {
+            Some(ask_valid("scope (optional): ", |scope| {
+                if scope.is_empty() {
+                    Vec::new()
+                } else {
+                    scope_problems(scope)
+                }
+            })?)
+            .filter(|scope| !scope.is_empty())
+        }
+        None => None,
+    };
+    let subject = match &args.subject {
+        Some(subject) => subject.clone(),
+        None if interactive => ask_valid("subject: ", subject_problems)?,
+        None => bail!("--subject is required when stdin is not a terminal"),
+    };
+    let body = match &args.body {
+        Some(body) => Some(body.clone()),
+        None if interactive && args.subject.is_none() => read_body()?,
+        None => None,
+    };
+    let breaking = match &args.breaking {
+        Some(text) => Some(text.clone()),
+        None if interactive && args.subject.is_none() => {
+            let answer = ask("breaking change? [y/N] ")?;
+            if matches!(answer.to_ascii_lowercase().as_str(), "y" | "yes") {
+                Some(ask("describe the breaking change: ")?)
+            } else {
+                None
+            }
+        }
+        None => None,
+    };
+    Ok(Message {
+        kind,
+        scope,
+        subject,
+        body,
+        breaking,
+    })
+}
+
+/// Fail unless something is staged, so the wizard never produces an empty commit.
+pub fn ensure_staged() -> Result<()> {
+    let status = Command::new("git")
+        .args(["diff", "--cached", "--quiet"])
+        .status()
+        .context("checking staged changes")?;
+    match status.code() {
+        Some(1) => Ok(()),
+        Some(0) => bail!("nothing staged; `git add` changes before committing"),
+        _ => bail!("git diff --cached exited with {}", status),
+    }
+}
+
+pub fn commit(message: &Message, sign: bool, dry_run: bool) -> Result<()> {
+    let text = message.render();
+    let flag = if sign { " -S" } else { "" };
+    if dry_run {
+        println!("[dry-run] git commit{} with message:\n{}", flag, text);
+        return Ok(());
+    }
+    let mut cmd = Command::new("git");
+    cmd.arg("commit");
+    if sign {
+        cmd.arg("-S");
+    }
+    let status = cmd
+        .args(["-m", &text])
+        .status()
+        .context("running git commit")?;
+    if !status.success() {
+        let code = status.code().unwrap_or(-1);
+        if sign {
+            bail!("git commit failed with status {}; {}", code, gitops::SIGNING_HINT);
+        }
+        bail!("git commit failed with status {}", code);
+    }
+    Ok(())
+}
+
+fn ask(label: &str) -> Result<String> {
+    print!("{}", label);
+    io::stdout().flush().context("writing prompt")?;
+    let mut buf = String::new();
+    if io::stdin().read_line(&mut buf).context("reading input")? == 0 {
+        bail!("input ended; nothing committed");
+    }
+    Ok(buf.trim().to_owned())
+}
+
+/// [`ask`] until `problems` finds nothing wrong with the answer.
+fn ask_valid(label: &str, problems: impl Fn(&str) -> Vec<String>) -> Result<String> {
+    loop {
+        let answer = ask(label)?;
+        let found = problems(&answer);
+        if found.is_empty() {
+            return Ok(answer);
+        }
+        for problem in found {
+            println!("  - {}", problem);
+        }
+    }
+}
+
+/// Multi-line body terminated by an empty line; `None` when the first line is empty.
+fn read_body() -> Result<Option<String>> {
+    println!("body (optional, finish with an empty line):");
+    let mut lines = Vec::new();
+    loop {
+        let mut buf = String::new();
+        let read = io::stdin().read_line(&mut buf).context("reading input")?;
+        let line = buf.trim_end_matches(['\n', '\r']);
+        if read == 0 || line.is_empty() {
+            break;
+        }
+        lines.push(line.to_owned());
+    }
+    Ok((!lines.is_empty()).then(|| lines.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_and_validates_messages() {
+        let message = Message {
+            kind: "feat".into(),
+            scope: Some("env".into()),
+            subject: "add gen command".into(),
+            body: Some("Generates secrets.\n".into()),
+            breaking: Some("`env new` was removed".into()),
+        };
+        assert!(message.validate().is_empty());
+        assert_eq!(
+            message.render(),
+            "feat(env)!: add gen command\n\nGenerates secrets.\n\nBREAKING CHANGE: `env new` was removed"
+        );
+
+        let bad = Message {
+            kind: "feature".into(),
+            scope: Some("Env".into()),
+            subject: "Added things.".into(),
+            ..Message::default()
+        };
+        let problems = bad.validate();
+        assert_eq!(problems.len(), 4, "{:?}", problems);
+
+        let long = Message {
+            kind: "fix".into(),
+            subject: "x".repeat(MAX_HEADER),
+            ..Message::default()
+        };
+        assert!(long.validate()[0].starts_with("header is"));
+    }
+
+    #[test]
+    fn compose_rejects_invalid_flags_before_prompting() {
+        let args = CommitArgs {
+            kind: Some("feature".into()),
+            scope: Some("Env".into()),
+            subject: None,
+            body: None,
+            breaking: None,
+            lint: false,
+            sign: false,
+        };
+        let err = compose(&args).unwrap_err().to_string();
+        assert!(err.contains("unknown type `feature`"), "{err}");
+        assert!(err.contains("scope `Env`"), "{err}");
+    }
+}
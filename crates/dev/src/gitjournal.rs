@@ -0,0 +1,288 @@
+use std::fs;
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use camino::Utf8PathBuf;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+/// Entries beyond this many are dropped, oldest first.
+const MAX_ENTRIES: usize = 50;
+
+const JOURNAL_FILE: &str = "gitops-journal.json";
+
+/// One completed `dev git` flow and the ref changes it made.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    /// Flow that ran, e.g. `branch-finalize`.
+    pub command: String,
+    /// Local time the flow finished, RFC 3339.
+    pub recorded: String,
+    pub actions: Vec<Action>,
+}
+
+/// A single ref change, with what `dev git undo` needs to reverse it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+pub enum Action {
+    /// `branch` was created (or reset, when `previous` is set) at `tip` from `base`.
+    BranchCreated {
+        branch: String,
+        base: String,
+        tip: String,
+        previous: Option<String>,
+        pushed: bool,
+    },
+    /// `branch` moved from `before` to `after` (merge, rebase, squash, release commit).
+    BranchMoved {
+        branch: String,
+        before: String,
+        after: String,
+        pushed: bool,
+    },
+    /// `branch` was deleted locally (and on origin when `remote`) while at `tip`.
+    BranchDeleted {
+        branch: String,
+        tip: String,
+        remote: bool,
+    },
+    /// Local tag `tag` was created at `target`.
+    Tagged { tag: String, target: String },
+    /// `dev version bump` moved `manifests` from `previous` to `version`, added the
+    /// `tag` section to `changelog`, and rewrote `provenance`; `dev version rollback`
+    /// reverses it.
+    Released {
+        version: String,
+        previous: String,
+        tag: String,
+        previous_tag: Option<String>,
+        manifests: Vec<String>,
+        changelog: Option<String>,
+        /// Per-package changelogs (`[git] package_changelogs`) that also got the section.
+        #[serde(default)]
+        package_changelogs: Vec<String>,
+        provenance: Option<String>,
+    },
+}
+
+impl Action {
+    pub fn describe(&self) -> String {
+        let origin = |pushed: bool| if pushed { " (pushed)" } else { "" };
+        match self {
+            Action::BranchCreated {
+                branch,
+                base,
+                pushed,
+                ..
+            } => format!("created `{}` from `{}`{}", branch, base, origin(*pushed)),
+            Action::BranchMoved {
+                branch,
+                before,
+                after,
+                pushed,
+            } => format!(
+                "moved `{}` {}..{}{}",
+                branch,
+                short(before),
+                short(after),
+                origin(*pushed)
+            ),
+            Action::BranchDeleted {
+                branch,
+                tip,
+                remote,
+            } => {
+                format!("deleted `{}` at {}{}", branch, short(tip), origin(*remote))
+            }
+            Action::Tagged { tag, target } => format!("tagged {} at {}", tag, short(target)),
+            Action::Released {
+                version, previous, ..
+            } => format!("released {} (was {})", version, previous),
+        }
+    }
+}
+
+/// `.dev/gitops-journal.json` at the repository root.
+pub fn journal_path() -> Result<Utf8PathBuf> {
+    Ok(git_path(&["rev-parse", "--show-toplevel"])?
+        .join(".dev")
+        .join(JOURNAL_FILE))
+}
+
+/// Entries, oldest first; empty when nothing has been recorded.
+pub fn load() -> Result<Vec<Entry>> {
+    let path = journal_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let raw = fs::read_to_string(&path).with_context(|| format!("reading {}", path))?;
+    serde_json::from_str(&raw).with_context(|| format!("parsing {}", path))
+}
+
+/// Append a finished flow. Flows that changed nothing are not recorded.
+pub fn record(command: &str, actions: Vec<Action>) -> Result<()> {
+    if actions.is_empty() {
+        return Ok(());
+    }
+    let mut entries = load()?;
+    entries.push(Entry {
+        command: command.to_owned(),
+        recorded: Local::now().to_rfc3339(),
+        actions,
+    });
+    let excess = entries.len().saturating_sub(MAX_ENTRIES);
+    entries.drain(..excess);
+    save(&entries)
+}
+
+/// Remove the newest entry once it has been undone.
+pub fn pop() -> Result<Option<Entry>> {
+    let mut entries = load()?;
+    let last = entries.pop();
+    if last.is_some() {
+        save(&entries)?;
+    }
+    Ok(last)
+}
+
+/// Edit the newest entry's actions, e.g. once a push it recorded ahead of time lands.
+pub fn amend_last(edit: impl FnOnce(&mut Vec<Action>)) -> Result<()> {
+    let mut entries = load()?;
+    let Some(entry) = entries.last_mut() else {
+        bail!("the gitops journal is empty");
+    };
+    edit(&mut entry.actions);
+    save(&entries)
+}
+
+/// Keep only the actions of entry `index` that `keep` accepts, dropping the entry when
+/// none are left.
+pub fn retain(index: usize, keep: impl Fn(&Action) -> bool) -> Result<()> {
+    let mut entries = load()?;
+    let Some(entry) = entries.get_mut(index) else {
+        bail!("no gitops journal entry #{}", index);
+    };
+    entry.actions.retain(|action| keep(action));
+    if entry.actions.is_empty() {
+        entries.remove(index);
+    }
+    save(&entries)
+}
+
+fn save(entries: &[Entry]) -> Result<()> {
+    let path = journal_path()?;
+    if let Some(dir) = path.parent()
+        && !dir.exists()
+    {
+        fs::create_dir_all(dir).with_context(|| format!("creating directory {}", dir))?;
+    }
+    if !path.exists() {
+        exclude_journal()?;
+    }
+    let json = serde_json::to_string_pretty(entries).context("serializing gitops journal")?;
+    fs::write(&path, json + "\n").with_context(|| format!("writing {}", path))
+}
+
+/// The journal is local state: keep it out of `git status` (and so out of the clean-worktree
+/// checks) via `.git/info/exclude`, without touching tracked ignore files.
+fn exclude_journal() -> Result<()> {
+    let exclude = git_path(&[
+        "rev-parse",
+        "--path-format=absolute",
+        "--git-path",
+        "info/exclude",
+    ])?;
+    let pattern = format!("/.dev/{}", JOURNAL_FILE);
+    let current = fs::read_to_string(&exclude).unwrap_or_default();
+    if current.lines().any(|line| line.trim() == pattern) {
+        return Ok(());
+    }
+    if let Some(dir) = exclude.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("creating directory {}", dir))?;
+    }
+    let mut updated = current;
+    if !updated.is_empty() && !updated.ends_with('\n') {
+        updated.push('\n');
+    }
+    updated.push_str(&pattern);
+    updated.push('\n');
+    fs::write(&exclude, updated).with_context(|| format!("writing {}", exclude))
+}
+
+fn git_path(args: &[&str]) -> Result<Utf8PathBuf> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .context("running git rev-parse")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(Utf8PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim(),
+    ))
+}
+
+fn short(sha: &str) -> &str {
+    &sha[..sha.len().min(8)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{InDir, repo_with_origin};
+
+    #[test]
+    fn actions_round_trip_through_json() {
+        let actions = vec![
+            Action::BranchMoved {
+                branch: "main".into(),
+                before: "1111111111".into(),
+                after: "2222222222".into(),
+                pushed: true,
+            },
+            Action::Tagged {
+                tag: "v1.0.0".into(),
+                target: "2222222222".into(),
+            },
+        ];
+        let json = serde_json::to_string(&actions).unwrap();
+        assert!(json.contains(r#""action":"branch-moved""#), "{}", json);
+        let parsed: Vec<Action> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, actions);
+        assert_eq!(
+            parsed[0].describe(),
+            "moved `main` 11111111..22222222 (pushed)"
+        );
+    }
+
+    #[test]
+    fn retain_drops_only_rejected_actions() {
+        let work = repo_with_origin();
+        let _dir = InDir::new(&work);
+        let moved = Action::BranchMoved {
+            branch: "main".into(),
+            before: "1111111111".into(),
+            after: "2222222222".into(),
+            pushed: false,
+        };
+        let tagged = Action::Tagged {
+            tag: "v1.0.0".into(),
+            target: "2222222222".into(),
+        };
+        record("version bump", vec![moved.clone(), tagged.clone()]).unwrap();
+        record("cleanup", vec![tagged.clone()]).unwrap();
+
+        retain(0, |action| *action != tagged).unwrap();
+        let entries = load().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].actions, [moved]);
+
+        retain(1, |_| false).unwrap();
+        assert_eq!(load().unwrap().len(), 1);
+        assert!(retain(1, |_| true).is_err());
+    }
+}
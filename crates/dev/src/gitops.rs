@@ -1,18 +1,36 @@
 use anyhow::{Context, Result, anyhow, bail};
+use std::io::{self, IsTerminal, Write};
 use std::process::Command;
 
 const DEFAULT_BASE_BRANCH: &str = "release-candidate";
 const DEFAULT_MAIN_BRANCH: &str = "main";
 
-use crate::cli::{BranchCreate, BranchFinalize, ReleasePr};
-use crate::config::DevConfig;
+use clap::ValueEnum;
+
+use crate::changelog;
+use crate::cli::{
+    BranchCreate, BranchFinalize, CleanupArgs, FinalizeStrategy, PrCreate, ReleasePr, SyncArgs,
+    UndoArgs,
+};
+use crate::config::{self, DevConfig};
+use crate::gitjournal::{self, Action};
+use crate::gitpr;
+use crate::gitprovider::{self, Provider, PullRequest};
+use crate::vars::Vars;
 
 pub fn branch_create(args: &BranchCreate, dry_run: bool) -> Result<()> {
+    if args.autostash {
+        return with_autostash("branch-create", dry_run, || create(args, dry_run));
+    }
     if !args.allow_dirty && !dry_run {
         ensure_clean_worktree()?;
     }
+    create(args, dry_run)
+}
 
+fn create(args: &BranchCreate, dry_run: bool) -> Result<()> {
     let base = args.base.as_deref().unwrap_or(DEFAULT_BASE_BRANCH);
+    let previous = rev_parse(&format!("refs/heads/{}", args.name))?;
     let mut steps: Vec<Vec<String>> = vec![
         vec![
             "git".into(),
@@ -36,6 +54,13 @@ pub fn branch_create(args: &BranchCreate, dry_run: bool) -> Result<()> {
             args.name.clone(),
             base.into(),
         ],
+        // Remembered so `dev git sync` knows what to rebase onto.
+        vec![
+            "git".into(),
+            "config".into(),
+            format!("branch.{}.dev-base", args.name),
+            base.into(),
+        ],
     ];
 
     if args.push {
@@ -49,6 +74,18 @@ pub fn branch_create(args: &BranchCreate, dry_run: bool) -> Result<()> {
     }
 
     run_steps(&steps, dry_run)?;
+    if !dry_run && let Some(tip) = rev_parse(&args.name)? {
+        gitjournal::record(
+            "branch-create",
+            vec![Action::BranchCreated {
+                branch: args.name.clone(),
+                base: base.to_string(),
+                tip,
+                previous,
+                pushed: args.push,
+            }],
+        )?;
+    }
     let pushed = if args.push {
         " and pushed to origin"
     } else {
@@ -58,17 +95,192 @@ pub fn branch_create(args: &BranchCreate, dry_run: bool) -> Result<()> {
     Ok(())
 }
 
-pub fn branch_finalize(args: &BranchFinalize, dry_run: bool) -> Result<()> {
+pub fn branch_finalize(args: &BranchFinalize, dry_run: bool, config: &DevConfig) -> Result<()> {
+    let strategy = match args
+        .strategy
+        .or(args.squash.then_some(FinalizeStrategy::Squash))
+    {
+        Some(strategy) => strategy,
+        None => match config
+            .git
+            .as_ref()
+            .and_then(|git| git.finalize_strategy.as_deref())
+        {
+            Some(name) => FinalizeStrategy::from_str(name, true).map_err(|_| {
+                anyhow!(
+                    "unknown [git] finalize_strategy `{}` (expected pr, merge, rebase, or squash)",
+                    name
+                )
+            })?,
+            None => FinalizeStrategy::Pr,
+        },
+    };
+    let provider = gitprovider::from_config(config)?;
+    if args.autostash {
+        return with_autostash("branch-finalize", dry_run, || {
+            finalize(args, strategy, provider.as_ref(), config, dry_run)
+        });
+    }
     if !args.allow_dirty && !dry_run {
         ensure_clean_worktree()?;
     }
+    finalize(args, strategy, provider.as_ref(), config, dry_run)
+}
 
+fn finalize(
+    args: &BranchFinalize,
+    strategy: FinalizeStrategy,
+    provider: &dyn Provider,
+    config: &DevConfig,
+    dry_run: bool,
+) -> Result<()> {
     let branch = match &args.name {
         Some(name) => name.clone(),
         None => current_branch()?.ok_or_else(|| anyhow!("unable to determine current branch"))?,
     };
     let base = args.base.as_deref().unwrap_or(DEFAULT_BASE_BRANCH);
+    if branch == base {
+        bail!("cannot finalize `{}` into itself", branch);
+    }
+
+    if strategy == FinalizeStrategy::Pr {
+        return finalize_pr(args, &branch, base, provider, dry_run);
+    }
+
+    let git_config = config.git.as_ref();
+    let protected = git_config
+        .and_then(|git| git.protected_branches.as_deref())
+        .unwrap_or_default();
+    if !args.force
+        && protected
+            .iter()
+            .any(|pattern| config::glob_matches(pattern, base))
+    {
+        bail!(
+            "`{}` is a protected branch; use `--strategy pr` to go through review, or pass --force",
+            base
+        );
+    }
+    let require_checks = args.require_checks
+        || git_config
+            .and_then(|git| git.require_checks)
+            .unwrap_or(false);
+    if require_checks && !args.force {
+        ensure_checks_green(&branch, provider, dry_run)?;
+    }
+
+    let git = |args: &[&str]| -> Vec<String> {
+        std::iter::once("git")
+            .chain(args.iter().copied())
+            .map(String::from)
+            .collect()
+    };
+    let branch_before = rev_parse(&branch)?;
+    let mut steps = vec![git(&["fetch", "--all", "--prune"])];
+    if strategy == FinalizeStrategy::Rebase {
+        steps.push(git(&["checkout", &branch]));
+        steps.push(git(&["rebase", &format!("origin/{}", base)]));
+    }
+    steps.push(git(&["checkout", base]));
+    steps.push(git(&["pull", "--ff-only", "origin", base]));
+    // Run up to the merge so the journal sees both branches as they stand before it.
+    run_steps(&steps, dry_run)?;
+    steps.clear();
+    let base_before = rev_parse(base)?;
+    let branch_tip = rev_parse(&branch)?;
+    match strategy {
+        FinalizeStrategy::Merge => steps.push(git(&["merge", "--no-ff", "--no-edit", &branch])),
+        FinalizeStrategy::Rebase => steps.push(git(&["merge", "--ff-only", &branch])),
+        FinalizeStrategy::Squash => {
+            // Collect subjects before the squash so the range still resolves. After the
+            // pull `base` matches origin; a dry run skipped it, so use what it would pull.
+            let upstream = format!("origin/{}", base);
+            let pulled = if dry_run && rev_parse(&upstream)?.is_some() {
+                upstream.as_str()
+            } else {
+                base
+            };
+            let subjects = branch_subjects(pulled, &branch)?;
+            steps.push(git(&["merge", "--squash", &branch]));
+            run_steps(&steps, dry_run)?;
+            steps.clear();
+            squash_commit(&squash_message(&branch, &subjects), args.no_edit, dry_run)?;
+        }
+        FinalizeStrategy::Pr => unreachable!("handled above"),
+    }
+    steps.push(git(&["push", "origin", base]));
+
+    let republish =
+        strategy == FinalizeStrategy::Rebase && !args.delete && remote_branch_exists(&branch)?;
+    if republish {
+        // Keep the published branch in step with its rebased history.
+        steps.push(git(&["push", "--force-with-lease", "origin", &branch]));
+    }
+    // The squashed commits live on only as the new commit, so the local branch goes unless
+    // `--keep`; origin is only touched with `--delete`, as for the other strategies.
+    let delete = args.delete || (strategy == FinalizeStrategy::Squash && !args.keep);
+    let delete_remote = args.delete && (dry_run || remote_branch_exists(&branch)?);
+    if delete {
+        // A squash leaves the branch unmerged by ancestry, so `-d` would refuse it.
+        let flag = if strategy == FinalizeStrategy::Squash {
+            "-D"
+        } else {
+            "-d"
+        };
+        steps.push(git(&["branch", flag, &branch]));
+        if delete_remote {
+            steps.push(git(&["push", "origin", "--delete", &branch]));
+        }
+    }
+
+    run_steps(&steps, dry_run)?;
+    if !dry_run {
+        let mut actions = Vec::new();
+        if let (Some(before), Some(after)) = (base_before, rev_parse(base)?)
+            && before != after
+        {
+            actions.push(Action::BranchMoved {
+                branch: base.to_string(),
+                before,
+                after,
+                pushed: true,
+            });
+        }
+        match (branch_before, branch_tip) {
+            (_, Some(tip)) if delete => actions.push(Action::BranchDeleted {
+                branch: branch.clone(),
+                tip,
+                remote: delete_remote,
+            }),
+            (Some(before), Some(after)) if before != after => actions.push(Action::BranchMoved {
+                branch: branch.clone(),
+                before,
+                after,
+                pushed: republish,
+            }),
+            _ => {}
+        }
+        gitjournal::record("branch-finalize", actions)?;
+    }
+    println!(
+        "Finalized `{}` into `{}` ({}).",
+        branch,
+        base,
+        strategy
+            .to_possible_value()
+            .expect("every strategy is a CLI value")
+            .get_name()
+    );
+    Ok(())
+}
 
+fn finalize_pr(
+    args: &BranchFinalize,
+    branch: &str,
+    base: &str,
+    provider: &dyn Provider,
+    dry_run: bool,
+) -> Result<()> {
     // Push the branch first to ensure it's up to date on remote
     let steps: Vec<Vec<String>> = vec![
         vec![
@@ -82,38 +294,297 @@ pub fn branch_finalize(args: &BranchFinalize, dry_run: bool) -> Result<()> {
             "push".into(),
             "-u".into(),
             "origin".into(),
-            branch.clone(),
-        ],
-        vec![
-            "gh".into(),
-            "pr".into(),
-            "create".into(),
-            "--base".into(),
-            base.into(),
-            "--head".into(),
-            branch.clone(),
-            "--fill".into(),
+            branch.into(),
         ],
+        provider.create_pr(&PullRequest {
+            base,
+            head: branch,
+            title: None,
+            body: None,
+            reviewers: &[],
+            labels: &[],
+            draft: false,
+        })?,
     ];
 
     // Warn if --delete was passed (deprecated, deletion now happens via GitHub)
     if args.delete {
-        println!("Note: --delete is deprecated. Branch deletion now happens via GitHub after PR merge.");
+        println!(
+            "Note: --delete is deprecated. Branch deletion now happens on {} after the PR is merged.",
+            provider.name()
+        );
     }
 
     run_steps(&steps, dry_run)?;
+    println!("Created PR for `{}` into `{}`.", branch, base);
     println!(
-        "Created PR for `{}` into `{}`.",
-        branch, base
+        "Review and merge on {}, then delete the branch if desired.",
+        provider.name()
     );
-    println!("Review and merge via GitHub, then delete the branch if desired.");
     Ok(())
 }
 
-pub fn release_pr(args: &ReleasePr, dry_run: bool, config: &DevConfig) -> Result<()> {
+/// Refuse to merge unless every CI check reported for the tip of `branch` has passed.
+fn ensure_checks_green(branch: &str, provider: &dyn Provider, dry_run: bool) -> Result<()> {
+    if provider.name() != "github" {
+        bail!(
+            "CI check verification is only supported on GitHub (provider is {}); pass --force to skip it",
+            provider.name()
+        );
+    }
+    let output = Command::new("git")
+        .args(["rev-parse", "--verify", branch])
+        .output()
+        .context("resolving branch tip")?;
+    if !output.status.success() {
+        bail!("branch `{}` does not exist", branch);
+    }
+    let sha = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+    if dry_run {
+        println!(
+            "[dry-run] verify CI checks for {} ({})",
+            branch,
+            &sha[..sha.len().min(12)]
+        );
+        return Ok(());
+    }
+
+    let checks = gitpr::commit_checks(&sha)
+        .with_context(|| format!("verifying CI checks for `{}`", branch))?;
+    match gitpr::summarize(&checks) {
+        "passing" => {
+            println!(
+                "CI checks for `{}` are green ({} checks).",
+                branch,
+                checks.len()
+            );
+            Ok(())
+        }
+        "none" => bail!(
+            "no CI checks reported for `{}` at {}; push it and wait for CI, or pass --force",
+            branch,
+            sha
+        ),
+        summary => {
+            let pending: Vec<String> = checks
+                .iter()
+                .filter(|check| check.result == "failing" || check.result == "pending")
+                .map(|check| format!("  {:<8} {}", check.result, check.name))
+                .collect();
+            bail!(
+                "CI checks for `{}` are {}:\n{}\npass --force to merge anyway",
+                branch,
+                summary,
+                pending.join("\n")
+            )
+        }
+    }
+}
+
+/// Subjects of the commits on `branch` but not `base`, oldest first.
+fn branch_subjects(base: &str, branch: &str) -> Result<Vec<String>> {
+    let range = format!("{}..{}", base, branch);
+    let output = Command::new("git")
+        .args(["log", "--reverse", "--pretty=format:%s", &range])
+        .output()
+        .with_context(|| format!("collecting commits for {}", range))?;
+    if !output.status.success() {
+        let code = output.status.code().unwrap_or(-1);
+        bail!("git log failed with status {}", code);
+    }
+    let subjects: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.trim().to_owned())
+        .filter(|line| !line.is_empty())
+        .collect();
+    if subjects.is_empty() {
+        bail!(
+            "`{}` has no commits that are not already on `{}`",
+            branch,
+            base
+        );
+    }
+    Ok(subjects)
+}
+
+/// Squash commit message: a single commit keeps its subject, several get a title derived
+/// from the branch name (`feat/login-page` -> `feat: login page`) and a bullet per subject.
+fn squash_message(branch: &str, subjects: &[String]) -> String {
+    if let [subject] = subjects {
+        return subject.clone();
+    }
+    let mut message = format!(
+        "{}\n\nSquashed commits from `{}`:\n",
+        title_from_branch(branch),
+        branch
+    );
+    for subject in subjects {
+        message.push_str(&format!("\n- {}", subject));
+    }
+    message
+}
+
+/// `feat/login-page` -> `feat: login page`; names without a `type/` prefix just lose
+/// their dashes and underscores.
+fn title_from_branch(branch: &str) -> String {
+    let words = |text: &str| text.replace(['-', '_'], " ");
+    match branch.split_once('/') {
+        Some((kind, rest)) if !kind.is_empty() && !rest.is_empty() => {
+            format!("{}: {}", kind, words(rest))
+        }
+        _ => words(branch),
+    }
+}
+
+/// Commit the staged squash, opening the editor on the synthesized message when interactive.
+fn squash_commit(message: &str, no_edit: bool, dry_run: bool) -> Result<()> {
+    use std::io::IsTerminal;
+
+    let edit = !no_edit && std::io::stdin().is_terminal();
+    if dry_run {
+        let flag = if edit { " --edit" } else { "" };
+        println!("[dry-run] git commit{} with message:\n{}", flag, message);
+        return Ok(());
+    }
+    // A file rather than stdin, so the editor keeps the terminal.
+    let path = std::env::temp_dir().join(format!("dev-squash-msg-{}", std::process::id()));
+    std::fs::write(&path, message)
+        .with_context(|| format!("writing commit message to {}", path.display()))?;
+    let mut cmd = Command::new("git");
+    cmd.args(["commit", "--cleanup=strip", "-F"]).arg(&path);
+    if edit {
+        cmd.arg("--edit");
+    }
+    let status = cmd.status().context("running git commit");
+    let _ = std::fs::remove_file(&path);
+    let status = status?;
+    if !status.success() {
+        let code = status.code().unwrap_or(-1);
+        bail!("git commit failed with status {}", code);
+    }
+    Ok(())
+}
+
+/// Hint appended to signing failures; the usual culprit is a locked key or missing agent.
+pub const SIGNING_HINT: &str = "signing failed; check that your signing key is available and unlocked \
+     (for GPG try `echo test | gpg --clearsign`, for SSH check `ssh-add -l`)";
+
+/// Fail early with an actionable message when git is not set up to sign, rather than
+/// after files have been edited.
+pub fn ensure_signing_configured() -> Result<()> {
+    check_signing(git_config)
+}
+
+/// [`ensure_signing_configured`] with git config values read through `config`.
+fn check_signing(config: impl Fn(&str) -> Result<Option<String>>) -> Result<()> {
+    let format = config("gpg.format")?.unwrap_or_else(|| "openpgp".to_string());
+    let key = config("user.signingkey")?;
+    match format.as_str() {
+        "ssh" => {
+            let Some(key) = key else {
+                bail!(
+                    "gpg.format is `ssh` but user.signingkey is not set; run \
+                     `git config user.signingkey ~/.ssh/id_ed25519.pub`"
+                );
+            };
+            let program = config("gpg.ssh.program")?.unwrap_or_else(|| "ssh-keygen".to_string());
+            ensure_program(&program, "gpg.ssh.program")?;
+            // The key may be given literally (`key::...` or `ssh-...`) instead of as a path.
+            let literal = key.starts_with("key::") || key.starts_with("ssh-");
+            let path = std::path::PathBuf::from(shellexpand_home(&key));
+            if !literal && !path.exists() {
+                bail!(
+                    "user.signingkey points at {}, which does not exist",
+                    path.display()
+                );
+            }
+        }
+        "x509" => {
+            let program = config("gpg.x509.program")?.unwrap_or_else(|| "gpgsm".to_string());
+            ensure_program(&program, "gpg.x509.program")?;
+        }
+        _ => {
+            let program = config("gpg.program")?.unwrap_or_else(|| "gpg".to_string());
+            ensure_program(&program, "gpg.program")?;
+            let mut cmd = Command::new(&program);
+            cmd.args(["--list-secret-keys", "--with-colons"]);
+            if let Some(key) = &key {
+                cmd.arg(key);
+            }
+            let output = cmd
+                .output()
+                .with_context(|| format!("running {}", program))?;
+            let has_secret = String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .any(|line| line.starts_with("sec:"));
+            if !output.status.success() || !has_secret {
+                match key {
+                    Some(key) => bail!(
+                        "no GPG secret key matches user.signingkey `{}`; check `gpg --list-secret-keys`",
+                        key
+                    ),
+                    None => bail!(
+                        "no GPG secret key found; create one with `gpg --full-generate-key` and set \
+                         `git config user.signingkey <KEYID>` (or use SSH signing with `git config gpg.format ssh`)"
+                    ),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn git_config(key: &str) -> Result<Option<String>> {
+    let output = Command::new("git")
+        .args(["config", "--get", key])
+        .output()
+        .with_context(|| format!("reading git config {}", key))?;
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+    Ok((output.status.success() && !value.is_empty()).then_some(value))
+}
+
+fn ensure_program(program: &str, setting: &str) -> Result<()> {
+    match Command::new(program)
+        .arg("--version")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+    {
+        Ok(_) => Ok(()),
+        Err(_) => bail!(
+            "signing program `{}` was not found; install it or point git config {} at it",
+            program,
+            setting
+        ),
+    }
+}
+
+fn shellexpand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest).display().to_string(),
+        _ => path.to_string(),
+    }
+}
+
+fn remote_branch_exists(branch: &str) -> Result<bool> {
+    let status = Command::new("git")
+        .args([
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("refs/remotes/origin/{}", branch),
+        ])
+        .stdout(std::process::Stdio::null())
+        .status()
+        .context("checking remote branch")?;
+    Ok(status.success())
+}
+
+pub fn release_pr(args: &ReleasePr, dry_run: bool, config: &DevConfig, vars: &Vars) -> Result<()> {
     if !dry_run {
         ensure_clean_worktree()?;
     }
+    let provider = gitprovider::from_config(config)?;
 
     let base = args
         .from
@@ -136,7 +607,7 @@ pub fn release_pr(args: &ReleasePr, dry_run: bool, config: &DevConfig) -> Result
         })
         .unwrap_or(DEFAULT_BASE_BRANCH);
 
-    let commits = collect_commits(base, head)?;
+    let commits = changelog::collect(&format!("{}..{}", base, head), &[])?;
     if commits.is_empty() {
         println!(
             "No commits between {} and {}; skipping PR creation.",
@@ -165,17 +636,38 @@ pub fn release_pr(args: &ReleasePr, dry_run: bool, config: &DevConfig) -> Result
         "origin".into(),
         head.into(),
     ]);
-    steps.push(vec![
-        "gh".into(),
-        "pr".into(),
-        "create".into(),
-        "--base".into(),
-        base.into(),
-        "--head".into(),
-        head.into(),
-        "--fill".into(),
-    ]);
-    if args.no_open {
+    let body = pr_body(config, vars, base, head)?;
+    let title = format!("Release {} into {}", head, base);
+    let git_config = config.git.as_ref();
+    let reviewers = if args.reviewers.is_empty() {
+        git_config
+            .and_then(|git| git.release_reviewers.clone())
+            .unwrap_or_default()
+    } else {
+        args.reviewers.clone()
+    };
+    let labels = if args.labels.is_empty() {
+        git_config
+            .and_then(|git| git.release_labels.clone())
+            .unwrap_or_default()
+    } else {
+        args.labels.clone()
+    };
+    steps.push(
+        provider.create_pr(&PullRequest {
+            base,
+            head,
+            title: body.as_ref().map(|_| title.as_str()),
+            body: body.as_deref(),
+            reviewers: &reviewers,
+            labels: &labels,
+            draft: args.draft
+                || git_config
+                    .and_then(|git| git.release_draft)
+                    .unwrap_or(false),
+        })?,
+    );
+    if args.no_open && provider.name() == "github" {
         if let Some(step) = steps.last_mut() {
             step.push("--no-open".into());
         }
@@ -187,7 +679,695 @@ pub fn release_pr(args: &ReleasePr, dry_run: bool, config: &DevConfig) -> Result
     Ok(())
 }
 
-fn run_steps(steps: &[Vec<String>], dry_run: bool) -> Result<()> {
+/// Push the current branch and open a PR for it, with a body from `[git] pr_template`.
+pub fn pr_create(args: &PrCreate, dry_run: bool, config: &DevConfig, vars: &Vars) -> Result<()> {
+    let head = current_branch()?.ok_or_else(|| anyhow!("unable to determine current branch"))?;
+    let base = args.base.as_deref().unwrap_or(DEFAULT_BASE_BRANCH);
+    if head == base {
+        bail!("cannot open a PR from `{}` into itself", head);
+    }
+    let provider = gitprovider::from_config(config)?;
+
+    let body = pr_body(config, vars, base, &head)?;
+    let title = match &args.title {
+        Some(title) => Some(title.clone()),
+        // A body replaces the provider's fill, so it needs a title of its own.
+        None if body.is_some() => {
+            let messages = changelog::messages(&format!("{}..{}", base, head), &[])?;
+            Some(match messages.as_slice() {
+                [(subject, _)] => subject.clone(),
+                _ => title_from_branch(&head),
+            })
+        }
+        None => None,
+    };
+
+    let steps = vec![
+        vec![
+            "git".into(),
+            "push".into(),
+            "-u".into(),
+            "origin".into(),
+            head.clone(),
+        ],
+        provider.create_pr(&PullRequest {
+            base,
+            head: &head,
+            title: title.as_deref(),
+            body: body.as_deref(),
+            reviewers: &[],
+            labels: &[],
+            draft: false,
+        })?,
+    ];
+    run_steps(&steps, dry_run)
+}
+
+/// `[git] pr_template` filled in for `base..head`, or `None` when no template is configured.
+/// Besides `[vars]`, the template can use `{{base}}`, `{{head}}`, `{{commits}}`, `{{issues}}`,
+/// and `{{changelog}}`.
+fn pr_body(config: &DevConfig, vars: &Vars, base: &str, head: &str) -> Result<Option<String>> {
+    let Some(template) = config
+        .git
+        .as_ref()
+        .and_then(|git| git.pr_template.as_deref())
+    else {
+        return Ok(None);
+    };
+    // Relative paths name a file in the repository, wherever `dev` runs from.
+    let mut path = std::path::PathBuf::from(template);
+    if path.is_relative() {
+        let output = Command::new("git")
+            .args(["rev-parse", "--show-toplevel"])
+            .output()
+            .context("locating the repository root")?;
+        if !output.status.success() {
+            bail!(
+                "PR templates need a git repository (pr_template = {})",
+                template
+            );
+        }
+        path = std::path::PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()).join(path);
+    }
+    let raw = std::fs::read_to_string(&path)
+        .with_context(|| format!("reading PR template {}", path.display()))?;
+
+    let range = format!("{}..{}", base, head);
+    let messages = changelog::messages(&range, &[])?;
+    let commits: Vec<String> = messages
+        .iter()
+        .rev()
+        .map(|(subject, _)| format!("- {}", subject))
+        .collect();
+    let entries: Vec<changelog::Commit> = messages
+        .iter()
+        .map(|(subject, body)| changelog::Commit::parse(subject, body))
+        .collect();
+
+    let mut vars = vars.clone();
+    vars.insert("base", base.to_string());
+    vars.insert("head", head.to_string());
+    vars.insert("commits", commits.join("\n"));
+    vars.insert("issues", linked_issues(&messages).join("\n"));
+    vars.insert(
+        "changelog",
+        changelog::render(&entries).trim_end().to_string(),
+    );
+    vars.expand(&raw)
+        .with_context(|| format!("filling PR template {}", template))
+        .map(Some)
+}
+
+/// Issue references in commit messages as bullets, oldest first: closing keywords
+/// (`Fixes #12`, `closes org/repo#3`) keep their verb so the host links and closes them.
+fn linked_issues(messages: &[(String, String)]) -> Vec<String> {
+    static PATTERN: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let pattern = PATTERN.get_or_init(|| {
+        regex::Regex::new(
+            r"(?i)(?:\b(close[sd]?|fix(?:e[sd])?|resolve[sd]?)\s+)?((?:[\w.-]+/[\w.-]+)?#\d+)\b",
+        )
+        .expect("valid issue regex")
+    });
+    let mut closing: Vec<String> = Vec::new();
+    let mut mentioned: Vec<String> = Vec::new();
+    for (subject, body) in messages.iter().rev() {
+        for text in [subject, body] {
+            for caps in pattern.captures_iter(text) {
+                let issue = caps[2].to_string();
+                if caps.get(1).is_some() {
+                    mentioned.retain(|seen| *seen != issue);
+                    if !closing.contains(&issue) {
+                        closing.push(issue);
+                    }
+                } else if !closing.contains(&issue) && !mentioned.contains(&issue) {
+                    mentioned.push(issue);
+                }
+            }
+        }
+    }
+    closing
+        .into_iter()
+        .map(|issue| format!("- Closes {}", issue))
+        .chain(mentioned.into_iter().map(|issue| format!("- {}", issue)))
+        .collect()
+}
+
+/// Rebase the current branch onto the latest `origin/<base>` and force-push it with lease.
+pub fn sync(args: &SyncArgs, dry_run: bool, config: &DevConfig) -> Result<()> {
+    let branch = current_branch()?.ok_or_else(|| anyhow!("unable to determine current branch"))?;
+    let base = match &args.base {
+        Some(base) => base.clone(),
+        None => git_config(&format!("branch.{}.dev-base", branch))?
+            .unwrap_or_else(|| DEFAULT_BASE_BRANCH.to_string()),
+    };
+    if branch == base {
+        bail!(
+            "`{}` is the base branch; check out a feature branch to sync",
+            branch
+        );
+    }
+    let protected = config
+        .git
+        .as_ref()
+        .and_then(|git| git.protected_branches.as_deref())
+        .unwrap_or_default();
+    if protected
+        .iter()
+        .any(|pattern| config::glob_matches(pattern, &branch))
+    {
+        bail!("`{}` is a protected branch; refusing to rewrite it", branch);
+    }
+    if !args.autostash && !dry_run && worktree_dirty()? {
+        bail!("working tree has uncommitted changes; commit them or pass --autostash");
+    }
+
+    let git = |args: &[&str]| -> Vec<String> {
+        std::iter::once("git")
+            .chain(args.iter().copied())
+            .map(String::from)
+            .collect()
+    };
+    let before = rev_parse(&branch)?;
+    // Fetch only the base: refreshing `origin/<branch>` would let the lease below
+    // overwrite pushes we have never seen.
+    run_steps(&[git(&["fetch", "origin", &base])], dry_run)?;
+    let upstream = format!("origin/{}", base);
+    let mut rebase = git(&["rebase"]);
+    if args.autostash {
+        rebase.push("--autostash".into());
+    }
+    rebase.push(upstream.clone());
+    run_steps(&[rebase], dry_run).with_context(|| {
+        format!(
+            "rebasing `{}` onto {} stopped; resolve the conflicts and run `git rebase --continue`, or `git rebase --abort`",
+            branch, upstream
+        )
+    })?;
+
+    let push = !args.no_push && (dry_run || remote_branch_exists(&branch)?);
+    if push {
+        run_steps(
+            &[git(&["push", "--force-with-lease", "origin", &branch])],
+            dry_run,
+        )?;
+    }
+    if !dry_run
+        && let (Some(before), Some(after)) = (before, rev_parse(&branch)?)
+        && before != after
+    {
+        gitjournal::record(
+            "sync",
+            vec![Action::BranchMoved {
+                branch: branch.clone(),
+                before,
+                after,
+                pushed: push,
+            }],
+        )?;
+    }
+
+    if args.no_push {
+        println!("Rebased `{}` onto {}.", branch, upstream);
+    } else if push {
+        println!("Rebased `{}` onto {} and pushed.", branch, upstream);
+    } else {
+        println!(
+            "Rebased `{}` onto {}; it is not on origin yet, so nothing was pushed.",
+            branch, upstream
+        );
+    }
+    Ok(())
+}
+
+/// A local branch whose commits are all reachable from one of the cleanup bases.
+struct MergedBranch {
+    name: String,
+    merged_into: String,
+    /// Committer time of the branch tip, for sorting.
+    timestamp: i64,
+    /// Human-readable age of the tip, e.g. `3 weeks ago`.
+    age: String,
+    origin: OriginBranch,
+}
+
+/// How a merged local branch's namesake on origin relates to the base.
+#[derive(Debug, PartialEq)]
+enum OriginBranch {
+    Missing,
+    /// Merged into the base as well, so it is safe to delete.
+    Merged,
+    /// Has commits the base lacks; cleanup leaves it alone.
+    Ahead,
+}
+
+/// List local branches fully merged into the main/release branches and delete the chosen
+/// ones locally and on origin.
+pub fn cleanup(args: &CleanupArgs, dry_run: bool, config: &DevConfig) -> Result<()> {
+    let git_config = config.git.as_ref();
+    let bases: Vec<String> = if args.bases.is_empty() {
+        let main = git_config
+            .and_then(|git| git.main_branch.as_deref())
+            .unwrap_or(DEFAULT_MAIN_BRANCH);
+        let release = git_config
+            .and_then(|git| git.release_branch.as_deref())
+            .unwrap_or(DEFAULT_BASE_BRANCH);
+        [main, release]
+            .into_iter()
+            .filter(|base| local_branch_exists(base))
+            .map(String::from)
+            .collect()
+    } else {
+        args.bases.clone()
+    };
+    if bases.is_empty() {
+        bail!("neither the main nor the release branch exists locally; pass --into <branch>");
+    }
+
+    let protected = git_config
+        .and_then(|git| git.protected_branches.as_deref())
+        .unwrap_or_default();
+    let current = current_branch()?;
+    let mut branches: Vec<MergedBranch> = Vec::new();
+    for base in &bases {
+        for branch in merged_branches(base)? {
+            let keep = bases.contains(&branch.name)
+                || current.as_deref() == Some(branch.name.as_str())
+                || protected
+                    .iter()
+                    .any(|pattern| config::glob_matches(pattern, &branch.name))
+                || branches.iter().any(|seen| seen.name == branch.name);
+            if !keep {
+                branches.push(branch);
+            }
+        }
+    }
+    if branches.is_empty() {
+        println!(
+            "No branches merged into {} to clean up.",
+            bases.join(" or ")
+        );
+        return Ok(());
+    }
+    branches.sort_by_key(|branch| branch.timestamp);
+
+    let width = branches
+        .iter()
+        .map(|branch| branch.name.len())
+        .max()
+        .unwrap_or(0);
+    let base_width = bases.iter().map(|base| base.len()).max().unwrap_or(0);
+    println!("Branches merged into {}:", bases.join(" or "));
+    for (index, branch) in branches.iter().enumerate() {
+        let origin = match branch.origin {
+            OriginBranch::Missing => "",
+            OriginBranch::Merged => "  (on origin)",
+            OriginBranch::Ahead => "  (origin is ahead; kept there)",
+        };
+        println!(
+            "  {:>2}  {:<width$}  into {:<base_width$}  {}{}",
+            index + 1,
+            branch.name,
+            branch.merged_into,
+            branch.age,
+            origin,
+            width = width,
+            base_width = base_width
+        );
+    }
+
+    let selected: Vec<&MergedBranch> = if args.yes || dry_run {
+        branches.iter().collect()
+    } else {
+        if !io::stdin().is_terminal() {
+            bail!("pass --yes to delete these branches without prompting");
+        }
+        let answer = ask("Delete which branches? [a]ll, [n]one, or numbers like 1,3-5: ")?;
+        let picked = parse_selection(&answer, branches.len())?;
+        if picked.is_empty() {
+            println!("Nothing deleted.");
+            return Ok(());
+        }
+        let selected: Vec<&MergedBranch> =
+            picked.into_iter().map(|index| &branches[index]).collect();
+        let names: Vec<&str> = selected.iter().map(|branch| branch.name.as_str()).collect();
+        let remote = if args.local_only {
+            ""
+        } else {
+            " (and on origin where published)"
+        };
+        let confirm = ask(&format!("Delete {}{}? [y/N] ", names.join(", "), remote))?;
+        if !matches!(confirm.to_ascii_lowercase().as_str(), "y" | "yes") {
+            println!("Nothing deleted.");
+            return Ok(());
+        }
+        selected
+    };
+
+    let mut steps: Vec<Vec<String>> = selected
+        .iter()
+        // Merged into a base, but not necessarily into HEAD, which `-d` would demand.
+        .map(|branch| {
+            vec![
+                "git".into(),
+                "branch".into(),
+                "-D".into(),
+                branch.name.clone(),
+            ]
+        })
+        .collect();
+    let remote: Vec<String> = selected
+        .iter()
+        .filter(|branch| branch.origin == OriginBranch::Merged && !args.local_only)
+        .map(|branch| branch.name.clone())
+        .collect();
+    if !remote.is_empty() {
+        let mut push: Vec<String> = vec![
+            "git".into(),
+            "push".into(),
+            "origin".into(),
+            "--delete".into(),
+        ];
+        push.extend(remote);
+        steps.push(push);
+    }
+    let mut actions = Vec::new();
+    for branch in &selected {
+        if let Some(tip) = rev_parse(&format!("refs/heads/{}", branch.name))? {
+            actions.push(Action::BranchDeleted {
+                branch: branch.name.clone(),
+                tip,
+                remote: branch.origin == OriginBranch::Merged && !args.local_only,
+            });
+        }
+    }
+    run_steps(&steps, dry_run)?;
+    if !dry_run {
+        gitjournal::record("cleanup", actions)?;
+        println!("Deleted {} merged branch(es).", selected.len());
+    }
+    Ok(())
+}
+
+fn merged_branches(base: &str) -> Result<Vec<MergedBranch>> {
+    let output = Command::new("git")
+        .args([
+            "for-each-ref",
+            "--merged",
+            base,
+            "--format=%(refname:short)%09%(committerdate:unix)%09%(committerdate:relative)",
+            "refs/heads",
+        ])
+        .output()
+        .context("listing merged branches")?;
+    if !output.status.success() {
+        bail!(
+            "git for-each-ref --merged {} failed: {}",
+            base,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let mut branches = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let mut fields = line.splitn(3, '\t');
+        let (Some(name), Some(timestamp), Some(age)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        branches.push(MergedBranch {
+            name: name.to_string(),
+            merged_into: base.to_string(),
+            timestamp: timestamp.parse().unwrap_or_default(),
+            age: age.to_string(),
+            origin: origin_branch(name, base)?,
+        });
+    }
+    Ok(branches)
+}
+
+fn origin_branch(name: &str, base: &str) -> Result<OriginBranch> {
+    if !remote_branch_exists(name)? {
+        return Ok(OriginBranch::Missing);
+    }
+    let merged = Command::new("git")
+        .args([
+            "merge-base",
+            "--is-ancestor",
+            &format!("refs/remotes/origin/{}", name),
+            base,
+        ])
+        .status()
+        .context("checking whether the origin branch is merged")?
+        .success();
+    Ok(if merged {
+        OriginBranch::Merged
+    } else {
+        OriginBranch::Ahead
+    })
+}
+
+fn local_branch_exists(branch: &str) -> bool {
+    Command::new("git")
+        .args([
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("refs/heads/{}", branch),
+        ])
+        .stdout(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Zero-based indices picked by `all`, `none`/empty, or 1-based numbers and ranges (`1,3-5`).
+fn parse_selection(input: &str, len: usize) -> Result<Vec<usize>> {
+    let input = input.trim().to_ascii_lowercase();
+    match input.as_str() {
+        "a" | "all" => return Ok((0..len).collect()),
+        "" | "n" | "none" => return Ok(Vec::new()),
+        _ => {}
+    }
+    let number = |text: &str| -> Result<usize> {
+        match text.trim().parse::<usize>() {
+            Ok(n) if (1..=len).contains(&n) => Ok(n - 1),
+            _ => bail!(
+                "`{}` is not a branch number between 1 and {}",
+                text.trim(),
+                len
+            ),
+        }
+    };
+    let mut picked = Vec::new();
+    for part in input
+        .split([',', ' '])
+        .filter(|part| !part.trim().is_empty())
+    {
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (number(start)?, number(end)?),
+            None => (number(part)?, number(part)?),
+        };
+        if start > end {
+            bail!("range `{}` runs backwards", part);
+        }
+        for index in start..=end {
+            if !picked.contains(&index) {
+                picked.push(index);
+            }
+        }
+    }
+    Ok(picked)
+}
+
+fn ask(label: &str) -> Result<String> {
+    print!("{}", label);
+    io::stdout().flush().context("writing prompt")?;
+    let mut buf = String::new();
+    io::stdin().read_line(&mut buf).context("reading input")?;
+    Ok(buf.trim().to_owned())
+}
+
+/// Reverse the newest journaled flow, refusing whenever a ref has moved since it ran.
+pub fn undo(args: &UndoArgs, dry_run: bool) -> Result<()> {
+    let entries = gitjournal::load()?;
+    if args.list {
+        if entries.is_empty() {
+            println!("The gitops journal is empty.");
+        }
+        for (index, entry) in entries.iter().rev().enumerate() {
+            println!("{:>2}  {}  {}", index + 1, entry.recorded, entry.command);
+            for action in &entry.actions {
+                println!("      {}", action.describe());
+            }
+        }
+        return Ok(());
+    }
+    let Some(entry) = entries.last() else {
+        println!("Nothing to undo.");
+        return Ok(());
+    };
+    if !dry_run && worktree_dirty()? {
+        bail!("working tree has uncommitted changes; commit or stash them before undoing");
+    }
+
+    // Resetting the release commit reverts a bump's file changes; without one they stay.
+    let committed = entry
+        .actions
+        .iter()
+        .any(|action| matches!(action, Action::BranchMoved { .. }));
+    if let Some(Action::Released { version, .. }) = entry
+        .actions
+        .iter()
+        .find(|action| matches!(action, Action::Released { .. }))
+        && !committed
+    {
+        bail!(
+            "`{}` left the version files at {} without a release commit; run `dev version rollback` to restore them",
+            entry.command,
+            version
+        );
+    }
+
+    let mut current = current_branch()?;
+    let mut steps = Vec::new();
+    for action in entry.actions.iter().rev() {
+        steps.extend(undo_steps(action, args.force, &mut current)?);
+    }
+    println!("Undoing `{}` from {}:", entry.command, entry.recorded);
+    for action in &entry.actions {
+        println!("  - {}", action.describe());
+    }
+    run_steps(&steps, dry_run)?;
+    if !dry_run {
+        gitjournal::pop()?;
+        println!("Undid `{}`.", entry.command);
+    }
+    Ok(())
+}
+
+fn undo_steps(
+    action: &Action,
+    force: bool,
+    current: &mut Option<String>,
+) -> Result<Vec<Vec<String>>> {
+    let git = |args: &[&str]| -> Vec<String> {
+        std::iter::once("git")
+            .chain(args.iter().copied())
+            .map(String::from)
+            .collect()
+    };
+    let mut steps = Vec::new();
+    match action {
+        Action::BranchCreated {
+            branch,
+            base,
+            tip,
+            previous,
+            pushed,
+        } => {
+            if rev_parse(&format!("refs/heads/{}", branch))?.as_deref() != Some(tip.as_str()) {
+                bail!(
+                    "`{}` has moved since it was created; delete it by hand",
+                    branch
+                );
+            }
+            let remote = rev_parse(&format!("refs/remotes/origin/{}", branch))?;
+            if *pushed && remote.as_deref().is_some_and(|remote| remote != tip) {
+                bail!("`{}` on origin has new commits; delete it by hand", branch);
+            }
+            if current.as_deref() == Some(branch.as_str()) {
+                steps.push(git(&["checkout", base]));
+                *current = Some(base.clone());
+            }
+            match previous {
+                Some(previous) => steps.push(git(&["branch", "-f", branch, previous])),
+                None => steps.push(git(&["branch", "-D", branch])),
+            }
+            if *pushed && remote.is_some() {
+                steps.push(git(&["push", "origin", "--delete", branch]));
+            }
+        }
+        Action::BranchMoved {
+            branch,
+            before,
+            after,
+            pushed,
+        } => {
+            let now = rev_parse(&format!("refs/heads/{}", branch))?;
+            if now.as_deref() != Some(after.as_str()) {
+                bail!(
+                    "`{}` has moved since (expected {}, found {}); not undoing",
+                    branch,
+                    after,
+                    now.as_deref().unwrap_or("nothing")
+                );
+            }
+            if *pushed && !force {
+                bail!(
+                    "`{}` was pushed; pass --force to reset it on origin as well (with lease)",
+                    branch
+                );
+            }
+            if current.as_deref() == Some(branch.as_str()) {
+                steps.push(git(&["reset", "--keep", before]));
+            } else {
+                steps.push(git(&["branch", "-f", branch, before]));
+            }
+            if *pushed {
+                let lease = format!("--force-with-lease={}:{}", branch, after);
+                steps.push(git(&["push", &lease, "origin", branch]));
+            }
+        }
+        Action::BranchDeleted {
+            branch,
+            tip,
+            remote,
+        } => {
+            if rev_parse(&format!("refs/heads/{}", branch))?.is_some() {
+                bail!("`{}` exists again; not restoring it", branch);
+            }
+            steps.push(git(&["branch", branch, tip]));
+            if *remote {
+                steps.push(git(&["push", "origin", branch]));
+            }
+        }
+        Action::Tagged { tag, target } => {
+            let now = rev_parse(&format!("refs/tags/{}^{{commit}}", tag))?;
+            if now.as_deref() != Some(target.as_str()) {
+                bail!(
+                    "tag {} no longer points at {}; not deleting it",
+                    tag,
+                    target
+                );
+            }
+            steps.push(git(&["tag", "-d", tag]));
+        }
+        // The release commit carries the file changes; resetting the branch reverts them
+        // (`undo` refuses bumps that were not committed).
+        Action::Released { .. } => {}
+    }
+    Ok(steps)
+}
+
+/// Commit SHA `rev` resolves to, or `None` when it does not exist.
+pub fn rev_parse(rev: &str) -> Result<Option<String>> {
+    let output = Command::new("git")
+        .args([
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", rev),
+        ])
+        .output()
+        .context("resolving git revision")?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(Some(
+        String::from_utf8_lossy(&output.stdout).trim().to_owned(),
+    ))
+}
+
+pub fn run_steps(steps: &[Vec<String>], dry_run: bool) -> Result<()> {
     for step in steps {
         let display = step.join(" ");
         if dry_run {
@@ -210,6 +1390,15 @@ fn run_steps(steps: &[Vec<String>], dry_run: bool) -> Result<()> {
 }
 
 fn ensure_clean_worktree() -> Result<()> {
+    if worktree_dirty()? {
+        return Err(anyhow!(
+            "working tree has uncommitted changes; pass --autostash or --allow-dirty to override"
+        ));
+    }
+    Ok(())
+}
+
+pub fn worktree_dirty() -> Result<bool> {
     let output = Command::new("git")
         .args(["status", "--porcelain"])
         .output()
@@ -217,15 +1406,73 @@ fn ensure_clean_worktree() -> Result<()> {
     if !output.status.success() {
         bail!("git status --porcelain exited with {}", output.status);
     }
-    if !output.stdout.is_empty() {
-        return Err(anyhow!(
-            "working tree has uncommitted changes; pass --allow-dirty to override"
+    Ok(!output.stdout.is_empty())
+}
+
+/// Run `flow` with local changes stashed, re-applying them afterwards even when the flow
+/// fails. A conflicting re-apply keeps the stash and lists the conflicted files.
+fn with_autostash(label: &str, dry_run: bool, flow: impl FnOnce() -> Result<()>) -> Result<()> {
+    if !worktree_dirty()? {
+        return flow();
+    }
+
+    let message = format!("dev autostash: {}", label);
+    run_steps(
+        &[vec![
+            "git".into(),
+            "stash".into(),
+            "push".into(),
+            "--include-untracked".into(),
+            "-m".into(),
+            message,
+        ]],
+        dry_run,
+    )?;
+    if !dry_run {
+        println!("Stashed local changes.");
+    }
+
+    let result = flow();
+
+    if dry_run {
+        println!("[dry-run] git stash pop");
+        return result;
+    }
+    let output = Command::new("git")
+        .args(["stash", "pop"])
+        .output()
+        .context("re-applying stashed changes")?;
+    if output.status.success() {
+        println!("Re-applied stashed changes.");
+        return result;
+    }
+
+    let conflicts = Command::new("git")
+        .args(["diff", "--name-only", "--diff-filter=U"])
+        .output()
+        .map(|out| String::from_utf8_lossy(&out.stdout).trim().to_owned())
+        .unwrap_or_default();
+    let mut report =
+        String::from("re-applying stashed changes failed; the stash was kept as stash@{0}");
+    if conflicts.is_empty() {
+        report.push_str(&format!(
+            ":\n{}",
+            String::from_utf8_lossy(&output.stderr).trim()
         ));
+    } else {
+        report.push_str(". Conflicts in:");
+        for path in conflicts.lines() {
+            report.push_str(&format!("\n  {}", path));
+        }
+        report.push_str("\nResolve them, then run `git stash drop`.");
+    }
+    match result {
+        Ok(()) => bail!(report),
+        Err(err) => Err(err.context(report)),
     }
-    Ok(())
 }
 
-fn current_branch() -> Result<Option<String>> {
+pub fn current_branch() -> Result<Option<String>> {
     let output = Command::new("git")
         .args(["rev-parse", "--abbrev-ref", "HEAD"])
         .output()
@@ -241,25 +1488,12 @@ fn current_branch() -> Result<Option<String>> {
     Ok(Some(branch))
 }
 
-fn collect_commits(base: &str, head: &str) -> Result<Vec<String>> {
-    let range = format!("{}..{}", base, head);
-    let output = Command::new("git")
-        .args(["log", &range, "--pretty=format:%s"])
-        .output()
-        .with_context(|| format!("collecting commits for {}", range))?;
-    if !output.status.success() {
-        let code = output.status.code().unwrap_or(-1);
-        bail!("git log failed with status {}", code);
-    }
-    let commits = String::from_utf8_lossy(&output.stdout)
-        .lines()
-        .map(|s| s.trim().to_string())
-        .filter(|s| !s.is_empty())
-        .collect();
-    Ok(commits)
-}
-
-fn update_changelog(base: &str, head: &str, commits: &[String], dry_run: bool) -> Result<()> {
+fn update_changelog(
+    base: &str,
+    head: &str,
+    commits: &[changelog::Commit],
+    dry_run: bool,
+) -> Result<()> {
     if commits.is_empty() {
         println!("No commits between {} and {}.", base, head);
         return Ok(());
@@ -276,12 +1510,7 @@ fn update_changelog(base: &str, head: &str, commits: &[String], dry_run: bool) -
     let mut section = String::new();
     let date = Utc::now().format("%Y-%m-%d");
     section.push_str(&format!("## {} ({base} → {head})\n\n", date));
-    for commit in commits {
-        section.push_str("- ");
-        section.push_str(commit);
-        section.push('\n');
-    }
-    section.push('\n');
+    section.push_str(&changelog::render(commits));
 
     if dry_run {
         println!(
@@ -306,3 +1535,242 @@ fn update_changelog(base: &str, head: &str, commits: &[String], dry_run: bool) -
         .with_context(|| format!("writing {}", changelog_path.display()))?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{InDir, commit_file, git, repo_with_origin};
+    use camino::Utf8Path;
+
+    #[test]
+    fn squash_message_from_subjects() {
+        let one = vec!["fix: handle empty input".to_string()];
+        assert_eq!(squash_message("fix/empty", &one), "fix: handle empty input");
+
+        let many = vec!["add form".to_string(), "wire submit".to_string()];
+        assert_eq!(
+            squash_message("feat/login-page", &many),
+            "feat: login page\n\nSquashed commits from `feat/login-page`:\n\n- add form\n- wire submit"
+        );
+        assert!(squash_message("cleanup_tmp", &many).starts_with("cleanup tmp\n"));
+    }
+
+    #[test]
+    fn signing_checks_explain_what_is_missing() {
+        let config = |pairs: &'static [(&'static str, &'static str)]| {
+            move |key: &str| -> Result<Option<String>> {
+                Ok(pairs
+                    .iter()
+                    .find(|(name, _)| *name == key)
+                    .map(|(_, value)| value.to_string()))
+            }
+        };
+        let err = check_signing(config(&[("gpg.format", "ssh")])).unwrap_err();
+        assert!(
+            err.to_string().contains("user.signingkey is not set"),
+            "{err}"
+        );
+
+        let err = check_signing(config(&[
+            ("gpg.format", "ssh"),
+            ("gpg.ssh.program", "true"),
+            ("user.signingkey", "/nonexistent/id_ed25519.pub"),
+        ]))
+        .unwrap_err();
+        assert!(err.to_string().contains("which does not exist"), "{err}");
+        check_signing(config(&[
+            ("gpg.format", "ssh"),
+            ("gpg.ssh.program", "true"),
+            ("user.signingkey", "ssh-ed25519 AAAA"),
+        ]))
+        .unwrap();
+
+        let err = check_signing(config(&[
+            ("gpg.format", "x509"),
+            ("gpg.x509.program", "dev-missing-signer"),
+        ]))
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("`dev-missing-signer` was not found"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn parses_branch_selection() {
+        assert_eq!(parse_selection("all", 3).unwrap(), [0, 1, 2]);
+        assert!(parse_selection("", 3).unwrap().is_empty());
+        assert_eq!(parse_selection("3, 1-2,2", 4).unwrap(), [2, 0, 1]);
+        assert!(parse_selection("0", 3).is_err());
+        assert!(parse_selection("2-1", 3).is_err());
+        assert!(parse_selection("x", 3).is_err());
+    }
+
+    #[test]
+    fn linked_issues_prefer_closing_keywords() {
+        let messages = vec![
+            (
+                "fix: crash on start (#14)".to_string(),
+                "Fixes #12".to_string(),
+            ),
+            (
+                "feat: add flag".to_string(),
+                "See #12 and org/repo#3.\nCloses #9".to_string(),
+            ),
+        ];
+        assert_eq!(
+            linked_issues(&messages),
+            ["- Closes #9", "- Closes #12", "- org/repo#3", "- #14"]
+        );
+        assert!(linked_issues(&[("chore: tidy".to_string(), String::new())]).is_empty());
+    }
+
+    #[test]
+    fn cleanup_keeps_origin_branches_that_are_ahead() {
+        let work = repo_with_origin();
+        for name in ["feat/done", "feat/moved-on"] {
+            git(&work, &["checkout", "--quiet", "-b", name, "main"]);
+            git(&work, &["push", "--quiet", "-u", "origin", name]);
+        }
+        // Someone pushed more work to feat/moved-on after the local copy was merged.
+        git(&work, &["checkout", "--quiet", "feat/moved-on"]);
+        commit_file(&work, "later.txt", "more\n");
+        git(&work, &["push", "--quiet", "origin", "feat/moved-on"]);
+        git(&work, &["reset", "--quiet", "--hard", "main"]);
+        git(&work, &["checkout", "--quiet", "main"]);
+
+        let _dir = InDir::new(&work);
+        let branches = merged_branches("main").unwrap();
+        let origin = |name: &str| {
+            &branches
+                .iter()
+                .find(|branch| branch.name == name)
+                .unwrap()
+                .origin
+        };
+        assert_eq!(origin("feat/done"), &OriginBranch::Merged);
+        assert_eq!(origin("feat/moved-on"), &OriginBranch::Ahead);
+        assert_eq!(origin("main"), &OriginBranch::Merged);
+    }
+
+    fn moved_main(work: &Utf8Path) -> (String, String) {
+        let before = git(work, &["rev-parse", "HEAD"]);
+        let after = commit_file(work, "next.txt", "next\n");
+        git(work, &["push", "--quiet", "origin", "main"]);
+        gitjournal::record(
+            "branch-finalize",
+            vec![Action::BranchMoved {
+                branch: "main".into(),
+                before: before.clone(),
+                after: after.clone(),
+                pushed: true,
+            }],
+        )
+        .unwrap();
+        (before, after)
+    }
+
+    #[test]
+    fn undo_resets_pushed_branches_only_with_force() {
+        let work = repo_with_origin();
+        let _dir = InDir::new(&work);
+        let (before, after) = moved_main(&work);
+
+        let plain = UndoArgs {
+            list: false,
+            force: false,
+        };
+        let err = undo(&plain, false).unwrap_err().to_string();
+        assert!(err.contains("--force"), "{err}");
+        assert_eq!(git(&work, &["rev-parse", "main"]), after);
+
+        let force = UndoArgs {
+            list: false,
+            force: true,
+        };
+        undo(&force, false).unwrap();
+        assert_eq!(git(&work, &["rev-parse", "main"]), before);
+        assert_eq!(git(&work, &["ls-remote", "origin", "main"])[..40], before);
+        assert!(gitjournal::load().unwrap().is_empty());
+    }
+
+    #[test]
+    fn undo_lease_keeps_commits_pushed_since() {
+        let work = repo_with_origin();
+        let _dir = InDir::new(&work);
+        let (_, after) = moved_main(&work);
+        // Someone else builds on the pushed commit.
+        git(&work, &["checkout", "--quiet", "--detach"]);
+        let theirs = commit_file(&work, "theirs.txt", "theirs\n");
+        git(&work, &["push", "--quiet", "origin", "HEAD:main"]);
+        git(&work, &["update-ref", "refs/remotes/origin/main", &after]);
+        git(&work, &["checkout", "--quiet", "main"]);
+
+        let force = UndoArgs {
+            list: false,
+            force: true,
+        };
+        assert!(undo(&force, false).is_err());
+        assert_eq!(git(&work, &["ls-remote", "origin", "main"])[..40], theirs);
+        assert_eq!(gitjournal::load().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn undo_refuses_bumps_without_a_release_commit() {
+        let work = repo_with_origin();
+        let _dir = InDir::new(&work);
+        let head = git(&work, &["rev-parse", "HEAD"]);
+        gitjournal::record(
+            "version bump",
+            vec![
+                Action::Released {
+                    version: "1.1.0".into(),
+                    previous: "1.0.0".into(),
+                    tag: "v1.1.0".into(),
+                    previous_tag: None,
+                    manifests: vec!["Cargo.toml".into()],
+                    changelog: None,
+                    package_changelogs: Vec::new(),
+                    provenance: None,
+                },
+                Action::Tagged {
+                    tag: "v1.1.0".into(),
+                    target: head,
+                },
+            ],
+        )
+        .unwrap();
+        let args = UndoArgs {
+            list: false,
+            force: false,
+        };
+        let err = undo(&args, false).unwrap_err().to_string();
+        assert!(err.contains("dev version rollback"), "{err}");
+        assert_eq!(gitjournal::load().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn undo_refuses_a_dirty_worktree_without_naming_other_flags() {
+        let work = repo_with_origin();
+        let _dir = InDir::new(&work);
+        let head = git(&work, &["rev-parse", "HEAD"]);
+        gitjournal::record(
+            "tag",
+            vec![Action::Tagged {
+                tag: "v1.0.0".into(),
+                target: head,
+            }],
+        )
+        .unwrap();
+        std::fs::write(work.join("README.md"), "edited\n").unwrap();
+        let args = UndoArgs {
+            list: false,
+            force: false,
+        };
+        let err = undo(&args, false).unwrap_err().to_string();
+        assert!(err.contains("commit or stash them before undoing"), "{err}");
+        assert!(!err.contains("--autostash"), "{err}");
+        assert_eq!(gitjournal::load().unwrap().len(), 1);
+    }
+}
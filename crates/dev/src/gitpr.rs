@@ -0,0 +1,500 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::cli::PrFormat;
+
+const GH_FIELDS: &str =
+    "number,title,headRefName,baseRefName,author,isDraft,reviewDecision,statusCheckRollup,url";
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct PullRequest {
+    pub number: u64,
+    pub title: String,
+    pub branch: String,
+    pub base: String,
+    pub author: String,
+    pub draft: bool,
+    /// `approved`, `changes requested`, `review required`, or `unknown`.
+    pub review: String,
+    /// `passing`, `failing`, `pending`, `none`, or `unknown`.
+    pub checks: String,
+    pub url: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub check_runs: Vec<Check>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct Check {
+    pub name: String,
+    /// `passing`, `failing`, `pending`, or `skipped`.
+    pub result: String,
+}
+
+pub fn list(limit: usize, format: PrFormat) -> Result<()> {
+    let prs = match gh(&[
+        "pr",
+        "list",
+        "--state",
+        "open",
+        "--limit",
+        &limit.to_string(),
+        "--json",
+        GH_FIELDS,
+    ])? {
+        Some(raw) => parse_gh(&raw)?,
+        None => Api::from_env()?.list(limit)?,
+    };
+    match format {
+        PrFormat::Json => println!("{}", serde_json::to_string_pretty(&prs)?),
+        PrFormat::Table if prs.is_empty() => println!("No open pull requests."),
+        PrFormat::Table => print!("{}", render_table(&prs)),
+    }
+    Ok(())
+}
+
+pub fn status(format: PrFormat) -> Result<()> {
+    let branch = current_branch()?;
+    let pr = match gh(&[
+        "pr", "list", "--state", "open", "--head", &branch, "--json", GH_FIELDS,
+    ])? {
+        Some(raw) => parse_gh(&raw)?.into_iter().next(),
+        None => Api::from_env()?.for_branch(&branch)?,
+    };
+    let Some(pr) = pr else {
+        if format == PrFormat::Json {
+            println!("null");
+        } else {
+            println!("No open pull request for branch `{}`.", branch);
+        }
+        return Ok(());
+    };
+    match format {
+        PrFormat::Json => println!("{}", serde_json::to_string_pretty(&pr)?),
+        PrFormat::Table => print!("{}", render_status(&pr)),
+    }
+    Ok(())
+}
+
+/// Output of a `gh` invocation, or `None` when `gh` is missing or not authenticated so
+/// the caller can fall back to the REST API.
+fn gh(args: &[&str]) -> Result<Option<String>> {
+    let output = match Command::new("gh").args(args).output() {
+        Ok(output) => output,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).context("running gh"),
+    };
+    if output.status.success() {
+        return Ok(Some(String::from_utf8_lossy(&output.stdout).into_owned()));
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("gh auth login") || stderr.contains("GH_TOKEN") {
+        return Ok(None);
+    }
+    bail!("gh {} failed: {}", args[..2].join(" "), stderr.trim())
+}
+
+fn parse_gh(raw: &str) -> Result<Vec<PullRequest>> {
+    let value: Value = serde_json::from_str(raw).context("parsing gh output")?;
+    let items = value
+        .as_array()
+        .ok_or_else(|| anyhow!("unexpected gh output: expected a JSON array"))?;
+    Ok(items.iter().map(from_gh).collect())
+}
+
+fn from_gh(item: &Value) -> PullRequest {
+    let check_runs: Vec<Check> = item["statusCheckRollup"]
+        .as_array()
+        .map(|rollup| {
+            rollup
+                .iter()
+                .map(|check| {
+                    // CheckRun entries carry status/conclusion, StatusContext entries carry state.
+                    let name = check["name"].as_str().or(check["context"].as_str());
+                    let result = match check["state"].as_str() {
+                        Some(state) => check_result("COMPLETED", state),
+                        None => check_result(
+                            check["status"].as_str().unwrap_or(""),
+                            check["conclusion"].as_str().unwrap_or(""),
+                        ),
+                    };
+                    Check {
+                        name: name.unwrap_or("unnamed").to_string(),
+                        result: result.to_string(),
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let review = match item["reviewDecision"].as_str().unwrap_or("") {
+        "APPROVED" => "approved",
+        "CHANGES_REQUESTED" => "changes requested",
+        "REVIEW_REQUIRED" => "review required",
+        _ => "none",
+    };
+    PullRequest {
+        number: item["number"].as_u64().unwrap_or_default(),
+        title: str_field(item, "title"),
+        branch: str_field(item, "headRefName"),
+        base: str_field(item, "baseRefName"),
+        author: item["author"]["login"].as_str().unwrap_or("").to_string(),
+        draft: item["isDraft"].as_bool().unwrap_or(false),
+        review: review.to_string(),
+        checks: summarize(&check_runs).to_string(),
+        url: str_field(item, "url"),
+        check_runs,
+    }
+}
+
+fn str_field(item: &Value, key: &str) -> String {
+    item[key].as_str().unwrap_or("").to_string()
+}
+
+/// Map a check's status/conclusion (or a commit status `state`) onto a single result.
+fn check_result(status: &str, conclusion: &str) -> &'static str {
+    let status = status.to_ascii_uppercase();
+    if status != "COMPLETED" && !status.is_empty() {
+        return "pending";
+    }
+    match conclusion.to_ascii_uppercase().as_str() {
+        "SUCCESS" | "NEUTRAL" => "passing",
+        "SKIPPED" => "skipped",
+        "PENDING" | "EXPECTED" | "" => "pending",
+        _ => "failing",
+    }
+}
+
+/// CI results reported for commit `sha` (check runs plus commit statuses), read through
+/// `gh api` or, without `gh`, the REST API with `GITHUB_TOKEN`.
+pub fn commit_checks(sha: &str) -> Result<Vec<Check>> {
+    let runs = format!("commits/{}/check-runs", sha);
+    let statuses = format!("commits/{}/status", sha);
+    let gh_path = |path: &str| format!("repos/{{owner}}/{{repo}}/{}", path);
+    if let Some(raw_runs) = gh(&["api", &gh_path(&runs)])? {
+        let raw_statuses = gh(&["api", &gh_path(&statuses)])?.unwrap_or_default();
+        let runs: Value = serde_json::from_str(&raw_runs).context("parsing gh api output")?;
+        let statuses: Value = serde_json::from_str(&raw_statuses).unwrap_or(Value::Null);
+        return Ok(checks_from_rest(&runs, &statuses));
+    }
+    let api = Api::from_env()?;
+    Ok(checks_from_rest(&api.get(&runs)?, &api.get(&statuses)?))
+}
+
+fn checks_from_rest(runs: &Value, statuses: &Value) -> Vec<Check> {
+    let mut checks = Vec::new();
+    for run in runs["check_runs"].as_array().into_iter().flatten() {
+        checks.push(Check {
+            name: str_field(run, "name"),
+            result: check_result(
+                run["status"].as_str().unwrap_or(""),
+                run["conclusion"].as_str().unwrap_or(""),
+            )
+            .to_string(),
+        });
+    }
+    for status in statuses["statuses"].as_array().into_iter().flatten() {
+        checks.push(Check {
+            name: str_field(status, "context"),
+            result: check_result("COMPLETED", status["state"].as_str().unwrap_or(""))
+                .to_string(),
+        });
+    }
+    checks
+}
+
+/// Overall result: any failure wins, then anything still running.
+pub fn summarize(checks: &[Check]) -> &'static str {
+    if checks.is_empty() {
+        "none"
+    } else if checks.iter().any(|check| check.result == "failing") {
+        "failing"
+    } else if checks.iter().any(|check| check.result == "pending") {
+        "pending"
+    } else {
+        "passing"
+    }
+}
+
+fn render_table(prs: &[PullRequest]) -> String {
+    let rows: Vec<[String; 6]> = prs
+        .iter()
+        .map(|pr| {
+            let title = if pr.draft {
+                format!("[draft] {}", pr.title)
+            } else {
+                pr.title.clone()
+            };
+            [
+                format!("#{}", pr.number),
+                truncate(&title, 50),
+                pr.branch.clone(),
+                pr.author.clone(),
+                pr.review.clone(),
+                pr.checks.clone(),
+            ]
+        })
+        .collect();
+    let header = ["PR", "TITLE", "BRANCH", "AUTHOR", "REVIEW", "CHECKS"].map(String::from);
+    let mut widths = header.clone().map(|cell| cell.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let mut out = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        out.push_str(cells.join("  ").trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+fn render_status(pr: &PullRequest) -> String {
+    let draft = if pr.draft { " [draft]" } else { "" };
+    let mut out = format!(
+        "#{} {}{} ({} → {})\n  {}\n  review: {}\n  checks: {}\n",
+        pr.number, pr.title, draft, pr.branch, pr.base, pr.url, pr.review, pr.checks
+    );
+    for check in &pr.check_runs {
+        out.push_str(&format!("    {:<8} {}\n", check.result, check.name));
+    }
+    out
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut out: String = text.chars().take(max - 1).collect();
+    out.push('…');
+    out
+}
+
+fn current_branch() -> Result<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .output()
+        .context("determining current branch")?;
+    let branch = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+    if !output.status.success() || branch == "HEAD" {
+        bail!("unable to determine current branch");
+    }
+    Ok(branch)
+}
+
+/// GitHub REST fallback used when `gh` is unavailable, authenticated with `GITHUB_TOKEN`.
+struct Api {
+    token: String,
+    owner: String,
+    repo: String,
+}
+
+impl Api {
+    fn from_env() -> Result<Self> {
+        let token = std::env::var("GITHUB_TOKEN")
+            .ok()
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| {
+                anyhow!("`gh` is unavailable or unauthenticated and GITHUB_TOKEN is not set")
+            })?;
+        let output = Command::new("git")
+            .args(["remote", "get-url", "origin"])
+            .output()
+            .context("reading origin remote")?;
+        let url = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+        let (owner, repo) =
+            parse_remote(&url).ok_or_else(|| anyhow!("origin `{}` is not a GitHub remote", url))?;
+        Ok(Api { token, owner, repo })
+    }
+
+    fn list(&self, limit: usize) -> Result<Vec<PullRequest>> {
+        let path = format!("pulls?state=open&per_page={}", limit.min(100));
+        let items = self.get(&path)?;
+        Ok(items
+            .as_array()
+            .map(|items| items.iter().map(from_api).collect())
+            .unwrap_or_default())
+    }
+
+    fn for_branch(&self, branch: &str) -> Result<Option<PullRequest>> {
+        let path = format!("pulls?state=open&head={}:{}", self.owner, branch);
+        let items = self.get(&path)?;
+        let Some(item) = items.as_array().and_then(|items| items.first()) else {
+            return Ok(None);
+        };
+        let mut pr = from_api(item);
+        let sha = item["head"]["sha"].as_str().unwrap_or("");
+
+        let checks = checks_from_rest(
+            &self.get(&format!("commits/{}/check-runs", sha))?,
+            &self.get(&format!("commits/{}/status", sha))?,
+        );
+        pr.checks = summarize(&checks).to_string();
+        pr.check_runs = checks;
+
+        let reviews = self.get(&format!("pulls/{}/reviews", pr.number))?;
+        pr.review =
+            review_state(reviews.as_array().map(Vec::as_slice).unwrap_or_default()).to_string();
+        Ok(Some(pr))
+    }
+
+    fn get(&self, path: &str) -> Result<Value> {
+        let url = format!(
+            "https://api.github.com/repos/{}/{}/{}",
+            self.owner, self.repo, path
+        );
+        // The token goes over stdin so it never shows up in the process list.
+        let mut child = Command::new("curl")
+            .args([
+                "-fsSL",
+                "-H",
+                "@-",
+                "-H",
+                "Accept: application/vnd.github+json",
+                &url,
+            ])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("running curl")?;
+        if let Some(mut stdin) = child.stdin.take() {
+            writeln!(stdin, "Authorization: Bearer {}", self.token)
+                .context("writing curl headers")?;
+        }
+        let output = child.wait_with_output().context("waiting for curl")?;
+        if !output.status.success() {
+            bail!(
+                "GitHub API request to {} failed: {}",
+                url,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        serde_json::from_slice(&output.stdout).context("parsing GitHub API response")
+    }
+}
+
+fn from_api(item: &Value) -> PullRequest {
+    PullRequest {
+        number: item["number"].as_u64().unwrap_or_default(),
+        title: str_field(item, "title"),
+        branch: item["head"]["ref"].as_str().unwrap_or("").to_string(),
+        base: item["base"]["ref"].as_str().unwrap_or("").to_string(),
+        author: item["user"]["login"].as_str().unwrap_or("").to_string(),
+        draft: item["draft"].as_bool().unwrap_or(false),
+        review: "unknown".to_string(),
+        checks: "unknown".to_string(),
+        url: str_field(item, "html_url"),
+        check_runs: Vec::new(),
+    }
+}
+
+/// Decision from the latest review of each reviewer, mirroring GitHub's `reviewDecision`.
+fn review_state(reviews: &[Value]) -> &'static str {
+    let mut latest = BTreeMap::new();
+    for review in reviews {
+        let state = review["state"].as_str().unwrap_or("");
+        if state == "COMMENTED" || state == "PENDING" {
+            continue;
+        }
+        latest.insert(review["user"]["login"].as_str().unwrap_or(""), state);
+    }
+    if latest.values().any(|state| *state == "CHANGES_REQUESTED") {
+        "changes requested"
+    } else if latest.values().any(|state| *state == "APPROVED") {
+        "approved"
+    } else {
+        "review required"
+    }
+}
+
+/// `(owner, repo)` from an SSH or HTTPS GitHub remote URL.
+fn parse_remote(url: &str) -> Option<(String, String)> {
+    let rest = url
+        .strip_prefix("git@github.com:")
+        .or_else(|| url.strip_prefix("ssh://git@github.com/"))
+        .or_else(|| url.strip_prefix("https://github.com/"))
+        .or_else(|| url.strip_prefix("http://github.com/"))?;
+    let rest = rest.trim_end_matches('/');
+    let rest = rest.strip_suffix(".git").unwrap_or(rest);
+    let (owner, repo) = rest.split_once('/')?;
+    if owner.is_empty() || repo.is_empty() || repo.contains('/') {
+        return None;
+    }
+    Some((owner.to_string(), repo.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_gh_output_and_summarizes_checks() {
+        let raw = r#"[{
+            "number": 7, "title": "feat: add pr status", "headRefName": "feature/pr",
+            "baseRefName": "main", "author": {"login": "octo"}, "isDraft": false,
+            "reviewDecision": "CHANGES_REQUESTED", "url": "https://github.com/o/r/pull/7",
+            "statusCheckRollup": [
+                {"__typename": "CheckRun", "name": "build", "status": "COMPLETED", "conclusion": "SUCCESS"},
+                {"__typename": "CheckRun", "name": "test", "status": "IN_PROGRESS", "conclusion": ""},
+                {"__typename": "StatusContext", "context": "ci/lint", "state": "FAILURE"}
+            ]
+        }]"#;
+        let prs = parse_gh(raw).unwrap();
+        assert_eq!(prs.len(), 1);
+        let pr = &prs[0];
+        assert_eq!(pr.author, "octo");
+        assert_eq!(pr.review, "changes requested");
+        assert_eq!(pr.checks, "failing");
+        let results: Vec<&str> = pr.check_runs.iter().map(|c| c.result.as_str()).collect();
+        assert_eq!(results, ["passing", "pending", "failing"]);
+
+        let table = render_table(&prs);
+        assert!(table.starts_with("PR  TITLE"));
+        assert!(table.contains("#7  feat: add pr status"));
+    }
+
+    #[test]
+    fn parses_github_remotes_and_reviews() {
+        let expected = Some(("o".to_string(), "r".to_string()));
+        assert_eq!(parse_remote("git@github.com:o/r.git"), expected);
+        assert_eq!(parse_remote("https://github.com/o/r"), expected);
+        assert_eq!(parse_remote("ssh://git@github.com/o/r.git"), expected);
+        assert_eq!(parse_remote("https://gitlab.com/o/r"), None);
+
+        let reviews: Vec<Value> = serde_json::from_str(
+            r#"[{"user":{"login":"a"},"state":"CHANGES_REQUESTED"},
+                {"user":{"login":"a"},"state":"APPROVED"},
+                {"user":{"login":"b"},"state":"COMMENTED"}]"#,
+        )
+        .unwrap();
+        assert_eq!(review_state(&reviews), "approved");
+        assert_eq!(review_state(&[]), "review required");
+    }
+
+    #[test]
+    fn rest_checks_combine_runs_and_statuses() {
+        let runs: Value = serde_json::from_str(
+            r#"{"check_runs": [{"name": "build", "status": "completed", "conclusion": "success"},
+                               {"name": "docs", "status": "completed", "conclusion": "skipped"}]}"#,
+        )
+        .unwrap();
+        let statuses: Value =
+            serde_json::from_str(r#"{"statuses": [{"context": "deploy", "state": "pending"}]}"#)
+                .unwrap();
+        let checks = checks_from_rest(&runs, &statuses);
+        let results: Vec<&str> = checks.iter().map(|c| c.result.as_str()).collect();
+        assert_eq!(results, ["passing", "skipped", "pending"]);
+        assert_eq!(summarize(&checks), "pending");
+        assert_eq!(summarize(&checks[..2]), "passing");
+    }
+}
@@ -0,0 +1,459 @@
+use std::process::Command;
+
+use anyhow::{Result, anyhow, bail};
+use camino::Utf8PathBuf;
+
+use crate::config::DevConfig;
+
+/// A pull/merge request to open from `head` into `base`.
+pub struct PullRequest<'a> {
+    pub base: &'a str,
+    pub head: &'a str,
+    /// Title and body; when unset the provider fills them from the commits.
+    pub title: Option<&'a str>,
+    pub body: Option<&'a str>,
+    pub reviewers: &'a [String],
+    pub labels: &'a [String],
+    pub draft: bool,
+}
+
+/// A release to publish for an existing, pushed tag.
+pub struct Release<'a> {
+    pub tag: &'a str,
+    pub notes: Option<&'a str>,
+    pub draft: bool,
+    pub prerelease: bool,
+    pub assets: &'a [Utf8PathBuf],
+}
+
+/// A command to run, with optional text piped to its stdin.
+pub struct Invocation {
+    pub argv: Vec<String>,
+    pub stdin: Option<String>,
+}
+
+/// Hosting-specific commands for the PR and release flows, so `dev git` works beyond
+/// GitHub. Each provider shells out to its own CLI.
+pub trait Provider {
+    fn name(&self) -> &'static str;
+    fn create_pr(&self, pr: &PullRequest) -> Result<Vec<String>>;
+    fn create_release(&self, release: &Release) -> Result<Invocation>;
+    /// Web page comparing `from` with `to`, given the repository's web URL.
+    fn compare_url(&self, web: &str, from: &str, to: &str) -> String {
+        format!("{}/compare/{}...{}", web, from, to)
+    }
+    /// Web page showing lines `first..=last` of `path` as of commit `sha`.
+    fn blob_url(&self, web: &str, sha: &str, path: &str, first: usize, last: usize) -> String {
+        format!(
+            "{}/blob/{}/{}{}",
+            web,
+            sha,
+            path,
+            line_anchor(first, last, "L")
+        )
+    }
+}
+
+pub struct GitHub;
+pub struct GitLab;
+pub struct Gitea;
+
+impl Provider for GitHub {
+    fn name(&self) -> &'static str {
+        "github"
+    }
+
+    fn create_pr(&self, pr: &PullRequest) -> Result<Vec<String>> {
+        let mut argv = strings(&["gh", "pr", "create", "--base", pr.base, "--head", pr.head]);
+        push_title_body(&mut argv, pr, "--title", "--body", "--fill");
+        push_routing(&mut argv, pr, "--reviewer", "--label", "--draft");
+        Ok(argv)
+    }
+
+    fn create_release(&self, release: &Release) -> Result<Invocation> {
+        let mut argv = strings(&[
+            "gh",
+            "release",
+            "create",
+            release.tag,
+            "--verify-tag",
+            "--title",
+            release.tag,
+        ]);
+        match release.notes {
+            Some(_) => argv.extend(strings(&["--notes-file", "-"])),
+            // Without a changelog section, let GitHub summarize the merged PRs.
+            None => argv.push("--generate-notes".into()),
+        }
+        if release.draft {
+            argv.push("--draft".into());
+        }
+        if release.prerelease {
+            argv.push("--prerelease".into());
+        }
+        argv.extend(release.assets.iter().map(|asset| asset.to_string()));
+        Ok(Invocation {
+            argv,
+            stdin: release.notes.map(str::to_owned),
+        })
+    }
+}
+
+impl Provider for GitLab {
+    fn name(&self) -> &'static str {
+        "gitlab"
+    }
+
+    fn create_pr(&self, pr: &PullRequest) -> Result<Vec<String>> {
+        let mut argv = strings(&[
+            "glab",
+            "mr",
+            "create",
+            "--target-branch",
+            pr.base,
+            "--source-branch",
+            pr.head,
+            "--yes",
+        ]);
+        push_title_body(&mut argv, pr, "--title", "--description", "--fill");
+        push_routing(&mut argv, pr, "--reviewer", "--label", "--draft");
+        Ok(argv)
+    }
+
+    fn create_release(&self, release: &Release) -> Result<Invocation> {
+        if release.draft {
+            bail!("GitLab releases have no draft state; drop --draft");
+        }
+        let mut argv = strings(&[
+            "glab",
+            "release",
+            "create",
+            release.tag,
+            "--name",
+            release.tag,
+        ]);
+        if let Some(notes) = release.notes {
+            argv.extend(strings(&["--notes", notes]));
+        }
+        argv.extend(release.assets.iter().map(|asset| asset.to_string()));
+        Ok(Invocation { argv, stdin: None })
+    }
+
+    fn compare_url(&self, web: &str, from: &str, to: &str) -> String {
+        format!("{}/-/compare/{}...{}", web, from, to)
+    }
+
+    fn blob_url(&self, web: &str, sha: &str, path: &str, first: usize, last: usize) -> String {
+        format!(
+            "{}/-/blob/{}/{}{}",
+            web,
+            sha,
+            path,
+            line_anchor(first, last, "")
+        )
+    }
+}
+
+impl Provider for Gitea {
+    fn name(&self) -> &'static str {
+        "gitea"
+    }
+
+    fn create_pr(&self, pr: &PullRequest) -> Result<Vec<String>> {
+        if !pr.reviewers.is_empty() {
+            bail!("`tea` cannot request reviewers; drop --reviewer or assign them in Gitea");
+        }
+        // `tea` cannot fill from commits, so default the title to the branch being merged.
+        let mut title = pr.title.unwrap_or(pr.head).to_string();
+        // Gitea has no draft flag; a `WIP:` title prefix marks the PR as a work in progress.
+        if pr.draft {
+            title = format!("WIP: {}", title);
+        }
+        let mut argv = strings(&[
+            "tea", "pulls", "create", "--base", pr.base, "--head", pr.head, "--title", &title,
+        ]);
+        if let Some(body) = pr.body {
+            argv.extend(strings(&["--description", body]));
+        }
+        if !pr.labels.is_empty() {
+            argv.extend(["--labels".to_string(), pr.labels.join(",")]);
+        }
+        Ok(argv)
+    }
+
+    fn create_release(&self, release: &Release) -> Result<Invocation> {
+        let mut argv = strings(&[
+            "tea",
+            "releases",
+            "create",
+            "--tag",
+            release.tag,
+            "--title",
+            release.tag,
+        ]);
+        if let Some(notes) = release.notes {
+            argv.extend(strings(&["--note", notes]));
+        }
+        if release.draft {
+            argv.push("--draft".into());
+        }
+        if release.prerelease {
+            argv.push("--prerelease".into());
+        }
+        for asset in release.assets {
+            argv.extend(["--asset".to_string(), asset.to_string()]);
+        }
+        Ok(Invocation { argv, stdin: None })
+    }
+
+    fn blob_url(&self, web: &str, sha: &str, path: &str, first: usize, last: usize) -> String {
+        format!(
+            "{}/src/commit/{}/{}{}",
+            web,
+            sha,
+            path,
+            line_anchor(first, last, "L")
+        )
+    }
+}
+
+/// `#L3` or `#L3-L7`; GitLab writes the end of a range without the `L` (`#L3-7`).
+fn line_anchor(first: usize, last: usize, end_prefix: &str) -> String {
+    if last > first {
+        format!("#L{}-{}{}", first, end_prefix, last)
+    } else {
+        format!("#L{}", first)
+    }
+}
+
+/// `[git] provider` when set, otherwise guessed from the `origin` remote (GitHub by default).
+pub fn from_config(config: &DevConfig) -> Result<Box<dyn Provider>> {
+    let configured = config.git.as_ref().and_then(|git| git.provider.clone());
+    let name = match configured {
+        Some(name) => name,
+        None => detect(&origin_url()).to_string(),
+    };
+    by_name(&name)
+}
+
+fn by_name(name: &str) -> Result<Box<dyn Provider>> {
+    match name.to_ascii_lowercase().as_str() {
+        "github" => Ok(Box::new(GitHub)),
+        "gitlab" => Ok(Box::new(GitLab)),
+        "gitea" | "forgejo" => Ok(Box::new(Gitea)),
+        other => Err(anyhow!(
+            "unknown [git] provider `{}` (expected github, gitlab, or gitea)",
+            other
+        )),
+    }
+}
+
+fn detect(remote: &str) -> &'static str {
+    let remote = remote.to_ascii_lowercase();
+    if remote.contains("gitlab") {
+        "gitlab"
+    } else if remote.contains("gitea")
+        || remote.contains("codeberg.org")
+        || remote.contains("forgejo")
+    {
+        "gitea"
+    } else {
+        "github"
+    }
+}
+
+/// Web URL of the `origin` repository (`https://host/owner/repo`), if it has one.
+pub fn repository_url() -> Option<String> {
+    web_url(&origin_url())
+}
+
+/// Web URL and provider of `origin` when the host identifies the provider, for links into
+/// its web UI; unlike `from_config`, an unrecognised host is not assumed to be GitHub.
+pub fn recognized_origin() -> Option<(String, Box<dyn Provider>)> {
+    let remote = origin_url();
+    let name = detect(&remote);
+    if name == "github" && !remote.to_ascii_lowercase().contains("github") {
+        return None;
+    }
+    Some((web_url(&remote)?, by_name(name).ok()?))
+}
+
+/// `git@host:o/r.git`, `ssh://git@host:22/o/r`, or `https://user@host/o/r.git` as
+/// `https://host/o/r`; `None` for local paths.
+fn web_url(remote: &str) -> Option<String> {
+    let remote = remote.trim().trim_end_matches('/');
+    let remote = remote.strip_suffix(".git").unwrap_or(remote);
+    let (authority, path) = match remote.split_once("://") {
+        Some((scheme, rest)) => {
+            let (authority, path) = rest.split_once('/')?;
+            // An ssh port is not the web port; an explicit http(s) port is kept.
+            let authority = if scheme == "ssh" {
+                authority.split(':').next().unwrap_or(authority)
+            } else {
+                authority
+            };
+            (authority, path)
+        }
+        None => remote
+            .split_once(':')
+            .filter(|(authority, _)| !authority.contains('/'))?,
+    };
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    if host.is_empty() || path.is_empty() {
+        return None;
+    }
+    Some(format!("https://{}/{}", host, path))
+}
+
+fn origin_url() -> String {
+    Command::new("git")
+        .args(["remote", "get-url", "origin"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+        .unwrap_or_default()
+}
+
+fn push_title_body(argv: &mut Vec<String>, pr: &PullRequest, title: &str, body: &str, fill: &str) {
+    match pr.title {
+        Some(value) => argv.extend([title.to_string(), value.to_string()]),
+        None => argv.push(fill.into()),
+    }
+    if let Some(value) = pr.body {
+        argv.extend([body.to_string(), value.to_string()]);
+    }
+}
+
+fn push_routing(
+    argv: &mut Vec<String>,
+    pr: &PullRequest,
+    reviewer: &str,
+    label: &str,
+    draft: &str,
+) {
+    for name in pr.reviewers {
+        argv.extend([reviewer.to_string(), name.clone()]);
+    }
+    for name in pr.labels {
+        argv.extend([label.to_string(), name.clone()]);
+    }
+    if pr.draft {
+        argv.push(draft.into());
+    }
+}
+
+fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|item| item.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn providers_build_pr_and_release_commands() {
+        let pr = PullRequest {
+            base: "main",
+            head: "release-candidate",
+            title: None,
+            body: None,
+            reviewers: &[],
+            labels: &[],
+            draft: false,
+        };
+        assert_eq!(
+            GitHub.create_pr(&pr).unwrap().join(" "),
+            "gh pr create --base main --head release-candidate --fill"
+        );
+        assert_eq!(
+            GitLab.create_pr(&pr).unwrap().join(" "),
+            "glab mr create --target-branch main --source-branch release-candidate --yes --fill"
+        );
+        assert_eq!(
+            Gitea.create_pr(&pr).unwrap().join(" "),
+            "tea pulls create --base main --head release-candidate --title release-candidate"
+        );
+
+        let reviewers = ["alice".to_string()];
+        let labels = ["release".to_string(), "skip-ci".to_string()];
+        let routed = PullRequest {
+            reviewers: &reviewers,
+            labels: &labels,
+            draft: true,
+            ..pr
+        };
+        assert_eq!(
+            GitHub.create_pr(&routed).unwrap().join(" "),
+            "gh pr create --base main --head release-candidate --fill --reviewer alice --label release --label skip-ci --draft"
+        );
+        assert!(Gitea.create_pr(&routed).is_err());
+        assert_eq!(
+            Gitea
+                .create_pr(&PullRequest {
+                    reviewers: &[],
+                    ..routed
+                })
+                .unwrap()
+                .join(" "),
+            "tea pulls create --base main --head release-candidate --title WIP: release-candidate --labels release,skip-ci"
+        );
+
+        let assets = [Utf8PathBuf::from("dist/app.tar.gz")];
+        let release = Release {
+            tag: "v1.2.0",
+            notes: Some("- fix"),
+            draft: true,
+            prerelease: false,
+            assets: &assets,
+        };
+        let github = GitHub.create_release(&release).unwrap();
+        assert_eq!(
+            github.argv.join(" "),
+            "gh release create v1.2.0 --verify-tag --title v1.2.0 --notes-file - --draft dist/app.tar.gz"
+        );
+        assert_eq!(github.stdin.as_deref(), Some("- fix"));
+        assert!(GitLab.create_release(&release).is_err());
+        assert_eq!(
+            Gitea.create_release(&release).unwrap().argv.join(" "),
+            "tea releases create --tag v1.2.0 --title v1.2.0 --note - fix --draft --asset dist/app.tar.gz"
+        );
+    }
+
+    #[test]
+    fn detects_provider_from_remote() {
+        assert_eq!(detect("git@gitlab.com:o/r.git"), "gitlab");
+        assert_eq!(detect("https://codeberg.org/o/r"), "gitea");
+        assert_eq!(detect("git@github.com:o/r.git"), "github");
+        assert_eq!(detect(""), "github");
+        assert!(by_name("bitbucket").is_err());
+    }
+
+    #[test]
+    fn web_urls_from_remotes() {
+        let expected = Some("https://github.com/o/r".to_string());
+        assert_eq!(web_url("git@github.com:o/r.git"), expected);
+        assert_eq!(web_url("https://token@github.com/o/r.git"), expected);
+        assert_eq!(web_url("ssh://git@github.com:22/o/r"), expected);
+        assert_eq!(web_url("/srv/git/r.git"), None);
+        assert_eq!(web_url(""), None);
+        assert_eq!(
+            GitLab.compare_url("https://gitlab.com/g/p", "v1.0.0", "HEAD"),
+            "https://gitlab.com/g/p/-/compare/v1.0.0...HEAD"
+        );
+        assert_eq!(
+            GitHub.blob_url("https://github.com/o/r", "abc", "src/a.rs", 3, 7),
+            "https://github.com/o/r/blob/abc/src/a.rs#L3-L7"
+        );
+        assert_eq!(
+            GitLab.blob_url("https://gitlab.com/g/p", "abc", "src/a.rs", 3, 7),
+            "https://gitlab.com/g/p/-/blob/abc/src/a.rs#L3-7"
+        );
+        assert_eq!(
+            Gitea.blob_url("https://codeberg.org/o/r", "abc", "a.rs", 5, 5),
+            "https://codeberg.org/o/r/src/commit/abc/a.rs#L5"
+        );
+    }
+}
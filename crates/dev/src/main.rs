@@ -1,20 +1,51 @@
+mod base64;
+mod calver;
+mod changelog;
+mod clipboard;
 mod cli;
+mod completions;
 mod config;
+mod configlint;
+mod devignore;
+mod dockergen;
+mod doctor;
+mod envaudit;
+mod envconvert;
+mod envcrypt;
+mod envedit;
 mod envfile;
+mod envgen;
+mod envhistory;
+mod envmerge;
+mod envschema;
+mod envsync;
+mod gitcommit;
+mod gitjournal;
 mod gitops;
+mod gitpr;
+mod gitprovider;
 mod logging;
-mod templates;
+mod provenance;
 mod review;
+mod reviewhtml;
 mod runner;
 mod scaffold;
+mod secrets;
 mod setup;
-mod dockergen;
 mod tasks;
+mod templates;
+#[cfg(test)]
+mod testutil;
+mod textdiff;
+mod toollock;
+mod vars;
+mod versioncheck;
 mod versioning;
 mod walk;
 
 fn main() -> anyhow::Result<()> {
-    logging::init();
+    // Parse first so `--version`/`--help` exit before any setup work.
     let app = cli::parse();
+    logging::init();
     runner::run(app)
 }
@@ -0,0 +1,116 @@
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use camino::Utf8Path;
+
+/// What `dev version bump` records about a release in the `[git] provenance` file.
+pub struct Provenance<'a> {
+    pub tag: &'a str,
+    pub version: &'a str,
+    /// Commit the release was cut from; the release commit is its child, so it cannot
+    /// name itself.
+    pub source: Option<&'a str>,
+    /// Release date, `YYYY-MM-DD`.
+    pub date: &'a str,
+    /// `(tool, version)` pairs from `.dev/lock.toml`.
+    pub tools: &'a [(String, String)],
+    /// `(sha256, path)` pairs for the release artifacts.
+    pub checksums: &'a [(String, String)],
+}
+
+/// Markdown provenance record. Checksums are in `sha256sum` format so the block can be
+/// fed to `sha256sum -c` as is.
+pub fn render(release: &Provenance) -> String {
+    let mut out = format!("# Release {}\n\n", release.tag);
+    out.push_str(&format!("- Version: {}\n", release.version));
+    out.push_str(&format!("- Tag: `{}`\n", release.tag));
+    match release.source {
+        Some(sha) => out.push_str(&format!("- Source commit: `{}`\n", sha)),
+        None => out.push_str("- Source commit: none (first commit)\n"),
+    }
+    out.push_str(&format!("- Date: {}\n", release.date));
+
+    out.push_str("\n## Toolchain\n\n");
+    if release.tools.is_empty() {
+        out.push_str("No tool versions recorded; run `dev check-tools --update`.\n");
+    } else {
+        out.push_str("Versions pinned in `.dev/lock.toml`:\n\n");
+        for (tool, version) in release.tools {
+            out.push_str(&format!("- {} {}\n", tool, version));
+        }
+    }
+
+    out.push_str("\n## Checksums\n\n");
+    if release.checksums.is_empty() {
+        out.push_str("No artifacts configured in `[git] release_assets`.\n");
+    } else {
+        out.push_str("SHA-256, verify with `sha256sum -c`:\n\n```\n");
+        for (sum, path) in release.checksums {
+            out.push_str(&format!("{}  {}\n", sum, path));
+        }
+        out.push_str("```\n");
+    }
+    out
+}
+
+/// Hex SHA-256 of `path`, from `sha256sum` (Linux) or `shasum -a 256` (macOS).
+pub fn sha256(path: &Utf8Path) -> Result<String> {
+    let candidates: [&[&str]; 2] = [&["sha256sum"], &["shasum", "-a", "256"]];
+    for argv in candidates {
+        let Ok(output) = Command::new(argv[0]).args(&argv[1..]).arg(path).output() else {
+            continue;
+        };
+        if !output.status.success() {
+            bail!(
+                "{} {} failed: {}",
+                argv.join(" "),
+                path,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        return stdout
+            .split_whitespace()
+            .next()
+            .map(str::to_owned)
+            .with_context(|| format!("no checksum in `{}` output", argv[0]));
+    }
+    bail!(
+        "neither `sha256sum` nor `shasum` is installed; cannot checksum {}",
+        path
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_release_record() {
+        let tools = vec![("rustc".to_string(), "1.89.0".to_string())];
+        let checksums = vec![("ab12".to_string(), "dist/dev.tar.gz".to_string())];
+        let rendered = render(&Provenance {
+            tag: "v1.2.0",
+            version: "1.2.0",
+            source: Some("0123abcd"),
+            date: "2026-10-16",
+            tools: &tools,
+            checksums: &checksums,
+        });
+        assert!(rendered.starts_with("# Release v1.2.0\n\n- Version: 1.2.0\n"));
+        assert!(rendered.contains("- Source commit: `0123abcd`\n"));
+        assert!(rendered.contains("- rustc 1.89.0\n"));
+        assert!(rendered.contains("```\nab12  dist/dev.tar.gz\n```\n"));
+
+        let bare = render(&Provenance {
+            tag: "v0.1.0",
+            version: "0.1.0",
+            source: None,
+            date: "2026-10-16",
+            tools: &[],
+            checksums: &[],
+        });
+        assert!(bare.contains("run `dev check-tools --update`"));
+        assert!(bare.contains("No artifacts configured"));
+    }
+}
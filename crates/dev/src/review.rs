@@ -1,12 +1,32 @@
 use anyhow::{Context, Result};
+use serde::Serialize;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
+use crate::devignore::DevIgnore;
+use crate::gitprovider::Provider;
+
 pub struct ReviewOptions {
     pub include_working: bool,
-    pub compare_main: bool,
+    /// Add new files git does not track yet, as all-added overlays.
+    pub include_untracked: bool,
+    /// Annotate each hunk with `git blame` data for the lines around it.
+    pub blame: bool,
+    /// Unchanged lines of the file kept around each hunk; `None` embeds the whole file.
+    pub context: Option<usize>,
+    /// Repository paths (`*` wildcards) left out of the report.
+    pub exclude: Vec<String>,
+    /// Files larger than this many bytes get a size summary instead of an overlay.
+    pub max_file_bytes: Option<u64>,
+    /// Review `base...HEAD` (a branch, tag or SHA) instead of the staged changes.
+    pub base: Option<String>,
+    /// Review each commit in this range (`main..HEAD`) as its own section.
+    pub commits: Option<String>,
 }
 
+/// `max_file_bytes` when `[review]` does not set it.
+pub const DEFAULT_MAX_FILE_BYTES: u64 = 256 * 1024;
+
 fn run_git(args: &[&str]) -> Result<String> {
     let output = Command::new("git")
         .args(args)
@@ -49,11 +69,81 @@ fn collect_file_diffs(diff_args: &[&str]) -> Result<Vec<(String, String)>> {
     Ok(diffs)
 }
 
+/// Per-file line counts for a diff, from `git diff --numstat`.
+fn collect_numstat(diff_args: &[&str]) -> Result<Vec<FileStat>> {
+    let mut args = vec!["diff", "--numstat"];
+    args.extend_from_slice(diff_args);
+    Ok(parse_numstat(&run_git(&args)?))
+}
+
+/// `<added>\t<removed>\t<path>` lines; binary files have `-` for both counts.
+fn parse_numstat(numstat: &str) -> Vec<FileStat> {
+    numstat
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let (added, removed, path) = (fields.next()?, fields.next()?, fields.next()?);
+            Some(FileStat {
+                path: path.to_string(),
+                added: added.parse().ok(),
+                removed: removed.parse().ok(),
+            })
+        })
+        .collect()
+}
+
+/// Untracked, non-ignored files, each with a diff adding its whole content.
+fn collect_untracked(repo_root: &Path) -> Result<Vec<(String, String)>> {
+    let names = run_git(&[
+        "ls-files",
+        "--others",
+        "--exclude-standard",
+        "--full-name",
+        "--",
+        ":/",
+    ])?;
+    let mut diffs = Vec::new();
+    for path in names.lines().filter(|line| !line.trim().is_empty()) {
+        let diff = match std::fs::read_to_string(repo_root.join(path)) {
+            Ok(content) if content.is_empty() => continue,
+            // git's own test: a NUL byte means binary.
+            Ok(content) if !content.contains('\0') => crate::textdiff::unified(path, "", &content),
+            _ => format!("Binary files /dev/null and b/{} differ", path),
+        };
+        diffs.push((path.to_string(), diff));
+    }
+    Ok(diffs)
+}
+
+/// Stats for `collect_untracked` entries, which git has no numstat for: every line is
+/// added, and binary files have no counts.
+fn untracked_stats(entries: &[(String, String)]) -> Vec<FileStat> {
+    entries
+        .iter()
+        .map(|(path, diff)| {
+            let binary = diff.starts_with("Binary files ");
+            let added = diff
+                .lines()
+                .filter(|line| line.starts_with('+') && !line.starts_with("+++ "))
+                .count();
+            FileStat {
+                path: path.clone(),
+                added: (!binary).then_some(added),
+                removed: (!binary).then_some(0),
+            }
+        })
+        .collect()
+}
+
 #[derive(Debug)]
 struct DiffHunk {
     header: String,
     content: Vec<String>,
     new_start: usize,
+    /// Lines on the new side, context included.
+    new_count: usize,
+    old_start: usize,
+    old_count: usize,
 }
 
 fn parse_hunks(diff_text: &str) -> Vec<DiffHunk> {
@@ -61,6 +151,8 @@ fn parse_hunks(diff_text: &str) -> Vec<DiffHunk> {
     let mut current_header: Option<String> = None;
     let mut current_content: Vec<String> = Vec::new();
     let mut current_start = 1;
+    let mut current_count = 1;
+    let mut current_old = (1, 1);
 
     for line in diff_text.lines() {
         if line.starts_with("@@") {
@@ -69,21 +161,28 @@ fn parse_hunks(diff_text: &str) -> Vec<DiffHunk> {
                     header,
                     content: current_content.clone(),
                     new_start: current_start,
+                    new_count: current_count,
+                    old_start: current_old.0,
+                    old_count: current_old.1,
                 });
             }
             current_header = Some(line.to_string());
             current_content.clear();
 
-            // Parse new file start line: @@ -a,b +c,d @@
-            if let Some(new_seg) = line.split_whitespace().find(|s| s.starts_with('+')) {
-                let new_start_raw = new_seg.trim_start_matches('+');
-                if let Some(comma_pos) = new_start_raw.find(',') {
-                    if let Ok(start) = new_start_raw[..comma_pos].parse() {
-                        current_start = start;
-                    }
-                } else if let Ok(start) = new_start_raw.parse() {
-                    current_start = start;
-                }
+            // Parse the file ranges: @@ -a,b +c,d @@
+            let range = |sign: char| {
+                line.split_whitespace()
+                    .skip(1)
+                    .take(2)
+                    .find_map(|segment| segment.strip_prefix(sign))
+                    .and_then(parse_range)
+            };
+            if let Some((start, count)) = range('+') {
+                current_start = start;
+                current_count = count;
+            }
+            if let Some(old) = range('-') {
+                current_old = old;
             }
         } else if current_header.is_some() {
             current_content.push(line.to_string());
@@ -95,134 +194,1807 @@ fn parse_hunks(diff_text: &str) -> Vec<DiffHunk> {
             header,
             content: current_content,
             new_start: current_start,
+            new_count: current_count,
+            old_start: current_old.0,
+            old_count: current_old.1,
         });
     }
 
     hunks
 }
 
-fn render_overlay(file_path: &str, diff: &str, repo_root: &Path) -> Vec<String> {
-    let mut overlay = Vec::new();
-    let file_lang = Path::new(file_path)
+/// `a,b` or `a` (one line) from a hunk header.
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+/// One line of a file overlay: the current file with each hunk's changes spliced in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OverlayLine {
+    Context(String),
+    Added(String),
+    Removed(String),
+    /// Start of a hunk, with its `@@ -a,b +c,d @@` header.
+    HunkStart(String),
+    HunkEnd,
+    /// Why the raw diff is shown instead of an overlay (deleted or unreadable file), or a
+    /// binary or oversized file's size summary, which stands alone.
+    Note(String),
+    /// A line of that raw diff.
+    Raw(String),
+    /// `git blame` summary for a hunk (`--blame`), right after its `HunkStart`.
+    Blame(String),
+    /// This many unchanged lines left out (`--context`, `--diff-only`).
+    Skipped(usize),
+    /// Permalink to the hunk's lines on `origin`, right after its `HunkStart`.
+    Link(String),
+}
+
+/// How each file overlay is built, shared by every section of a report.
+#[derive(Clone, Copy)]
+struct OverlaySettings<'a> {
+    /// Repository root, when hunks get `git blame` annotations.
+    blame: Option<&'a Path>,
+    /// Unchanged lines kept around each hunk; `None` for the whole file.
+    context: Option<usize>,
+    /// Where each hunk links to in `origin`'s web UI.
+    link: Option<Permalink<'a>>,
+    /// Path globs of files left out of the report altogether.
+    exclude: &'a [String],
+    /// The project's `.devignore`, also applied to every section.
+    ignore: &'a DevIgnore,
+    /// Size above which a file is summarised rather than overlaid.
+    max_bytes: Option<u64>,
+}
+
+#[derive(Clone, Copy)]
+struct Permalink<'a> {
+    web: &'a str,
+    provider: &'a dyn Provider,
+    /// Commit the files are shown at.
+    rev: &'a str,
+    /// Link the lines a hunk replaces rather than its new lines: uncommitted changes are
+    /// not on the remote, but the lines they touch are, at HEAD.
+    old_side: bool,
+    /// Files whose old side is not the file at `rev`: unstaged edits diff against the
+    /// index, which differs from HEAD where the file also has staged changes.
+    unlinked: &'a [String],
+}
+
+impl Permalink<'_> {
+    /// `None` when the linked side of the hunk is not in the file at `rev` (a new file).
+    fn url(&self, file_path: &str, hunk: &DiffHunk) -> Option<String> {
+        if self.unlinked.iter().any(|path| path == file_path) {
+            return None;
+        }
+        let (start, count) = if self.old_side {
+            (hunk.old_start, hunk.old_count)
+        } else {
+            (hunk.new_start, hunk.new_count)
+        };
+        if start == 0 {
+            return None;
+        }
+        let last = (start + count).saturating_sub(1).max(start);
+        Some(
+            self.provider
+                .blob_url(self.web, self.rev, file_path, start, last),
+        )
+    }
+}
+
+fn permalink<'a>(
+    origin: &'a Option<(String, Box<dyn Provider>)>,
+    rev: Option<&'a str>,
+    old_side: bool,
+) -> Option<Permalink<'a>> {
+    let (web, provider) = origin.as_ref()?;
+    Some(Permalink {
+        web,
+        provider: provider.as_ref(),
+        rev: rev?,
+        old_side,
+        unlinked: &[],
+    })
+}
+
+/// Whether `rev` is on a remote-tracking branch, so the web UI can show it.
+fn on_remote(rev: &str) -> bool {
+    run_git(&["branch", "--remotes", "--contains", rev])
+        .is_ok_and(|branches| !branches.trim().is_empty())
+}
+
+pub struct FileOverlay {
+    pub path: String,
+    /// File extension, which the HTML report picks its highlighting by.
+    pub lang: String,
+    pub lines: Vec<OverlayLine>,
+}
+
+pub struct Section {
+    pub title: String,
+    /// Set for the per-commit sections of `--commits`.
+    pub commit: Option<CommitInfo>,
+    pub files: Vec<FileOverlay>,
+    pub stats: Vec<FileStat>,
+}
+
+/// Lines added and removed in one file; `None` for binary files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileStat {
+    pub path: String,
+    pub added: Option<usize>,
+    pub removed: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommitInfo {
+    pub sha: String,
+    /// `Name <email>`.
+    pub author: String,
+    /// Author date, `YYYY-MM-DD`.
+    pub date: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Where the changed version of each file is read from.
+#[derive(Clone, Copy)]
+enum Source<'a> {
+    WorkTree(&'a Path),
+    /// The file as of a commit, for `--commits`.
+    Commit(&'a str),
+}
+
+impl Source<'_> {
+    /// The file's contents, or `None` when it does not exist there (deleted).
+    fn read(&self, file_path: &str) -> Result<Option<String>> {
+        match self {
+            Source::WorkTree(repo_root) => {
+                let target_path = repo_root.join(file_path);
+                if !target_path.exists() {
+                    return Ok(None);
+                }
+                Ok(Some(std::fs::read_to_string(&target_path)?))
+            }
+            Source::Commit(sha) => {
+                let spec = format!("{}:{}", sha, file_path);
+                if run_git(&["cat-file", "-e", &spec]).is_err() {
+                    return Ok(None);
+                }
+                run_git(&["show", &spec]).map(Some)
+            }
+        }
+    }
+
+    /// The file's size in bytes, or `None` when it does not exist there.
+    fn size(&self, file_path: &str) -> Option<u64> {
+        match self {
+            Source::WorkTree(repo_root) => std::fs::metadata(repo_root.join(file_path))
+                .ok()
+                .map(|meta| meta.len()),
+            Source::Commit(sha) => blob_size(&format!("{}:{}", sha, file_path)),
+        }
+    }
+}
+
+fn blob_size(object: &str) -> Option<u64> {
+    run_git(&["cat-file", "-s", object])
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// The pre-image blob from a diff's `index <old>..<new>` line; `None` for new files.
+fn old_blob(diff: &str) -> Option<&str> {
+    diff.lines()
+        .find_map(|line| line.strip_prefix("index "))?
+        .split("..")
+        .next()
+        .filter(|blob| !blob.chars().all(|c| c == '0'))
+}
+
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// `10.0 KiB -> 12.5 KiB (+2.5 KiB)`, or how the file came or went.
+fn size_change(old: Option<u64>, new: Option<u64>) -> String {
+    match (old, new) {
+        (Some(old), Some(new)) => format!(
+            "{} -> {} ({}{})",
+            human_size(old),
+            human_size(new),
+            if new >= old { '+' } else { '-' },
+            human_size(new.abs_diff(old))
+        ),
+        (None, Some(new)) => format!("new, {}", human_size(new)),
+        (Some(old), None) => format!("deleted, was {}", human_size(old)),
+        (None, None) => "size unknown".to_string(),
+    }
+}
+
+/// Everything a review report shows, independent of the output format.
+pub struct Report {
+    pub timestamp: String,
+    pub branch: String,
+    pub status: String,
+    pub sections: Vec<Section>,
+}
+
+fn build_overlay(
+    file_path: &str,
+    diff: &str,
+    source: Source,
+    settings: OverlaySettings,
+) -> FileOverlay {
+    let lang = Path::new(file_path)
         .extension()
         .and_then(|e| e.to_str())
-        .unwrap_or("");
-    
-    overlay.push(format!("```{}", file_lang));
-
-    let target_path = repo_root.join(file_path);
-    if !target_path.exists() {
-        overlay.push("_File deleted; showing diff below._".to_string());
-        overlay.extend(diff.lines().map(|s| s.to_string()));
-        overlay.push("```".to_string());
-        return overlay;
-    }
-
-    let file_lines = match std::fs::read_to_string(&target_path) {
-        Ok(content) => content.lines().map(|s| s.to_string()).collect::<Vec<_>>(),
-        Err(_) => {
-            overlay.push("_Could not read file; showing diff below._".to_string());
-            overlay.extend(diff.lines().map(|s| s.to_string()));
-            overlay.push("```".to_string());
-            return overlay;
-        }
+        .unwrap_or("")
+        .to_string();
+    let raw = |note: &str| {
+        let mut lines = vec![OverlayLine::Note(note.to_string())];
+        lines.extend(diff.lines().map(|s| OverlayLine::Raw(s.to_string())));
+        lines
+    };
+    let overlay = |lines| FileOverlay {
+        path: file_path.to_string(),
+        lang: lang.clone(),
+        lines,
+    };
+
+    // Binary and oversized files are summarised by size: their contents are noise
+    // in a review and can swamp an LLM's context.
+    let new_size = source.size(file_path);
+    let old_size = || old_blob(diff).and_then(blob_size);
+    if diff.lines().any(|line| line.starts_with("Binary files ")) {
+        return overlay(vec![OverlayLine::Note(format!(
+            "_Binary file, {}._",
+            size_change(old_size(), new_size)
+        ))]);
+    }
+    if let Some(limit) = settings.max_bytes
+        && new_size.or_else(old_size).is_some_and(|size| size > limit)
+    {
+        return overlay(vec![OverlayLine::Note(format!(
+            "_Large file, {}, over the {} limit; overlay skipped._",
+            size_change(old_size(), new_size),
+            human_size(limit)
+        ))]);
+    }
+
+    let file_lines = match source.read(file_path) {
+        Ok(Some(content)) => content.lines().map(|s| s.to_string()).collect::<Vec<_>>(),
+        Ok(None) => return overlay(raw("_File deleted; showing diff below._")),
+        Err(_) => return overlay(raw("_Could not read file; showing diff below._")),
     };
 
-    let hunks = parse_hunks(diff);
+    let mut lines = Vec::new();
     let mut line_idx = 1;
+    let mut after_hunk = false;
 
-    for hunk in hunks {
+    for hunk in parse_hunks(diff) {
         // Add unchanged lines before this hunk
-        while line_idx < hunk.new_start && line_idx <= file_lines.len() {
-            overlay.push(file_lines[line_idx - 1].clone());
-            line_idx += 1;
-        }
+        let gap_end = hunk.new_start.min(file_lines.len() + 1).max(line_idx);
+        push_gap(
+            &mut lines,
+            &file_lines[line_idx - 1..gap_end - 1],
+            settings.context,
+            after_hunk,
+            true,
+        );
+        line_idx = gap_end;
+        after_hunk = true;
 
-        // Add a visual separator for the diff section
-        overlay.push(String::new());
-        overlay.push(format!(">>> CHANGES START {} <<<", hunk.header));
-        
-        // Process the diff hunk content
+        lines.push(OverlayLine::HunkStart(hunk.header.clone()));
+        if let Some(url) = settings.link.and_then(|link| link.url(file_path, &hunk)) {
+            lines.push(OverlayLine::Link(url));
+        }
+        if let Some(summary) = settings
+            .blame
+            .and_then(|repo_root| blame_hunk(repo_root, source, file_path, &hunk))
+        {
+            lines.push(OverlayLine::Blame(summary));
+        }
         for diff_line in &hunk.content {
             if diff_line.starts_with('+') && !diff_line.starts_with("+++") {
-                // Added line - show with + prefix
-                overlay.push(format!("+ {}", &diff_line[1..]));
+                lines.push(OverlayLine::Added(diff_line[1..].to_string()));
                 line_idx += 1;
             } else if diff_line.starts_with('-') && !diff_line.starts_with("---") {
-                // Removed line - show with - prefix (don't increment line_idx)
-                overlay.push(format!("- {}", &diff_line[1..]));
-            } else if diff_line.starts_with(' ') {
-                // Context line - show as-is
-                overlay.push(diff_line[1..].to_string());
+                // Removed lines are not in the current file, so line_idx stays put.
+                lines.push(OverlayLine::Removed(diff_line[1..].to_string()));
+            } else if let Some(context) = diff_line.strip_prefix(' ') {
+                lines.push(OverlayLine::Context(context.to_string()));
                 line_idx += 1;
             }
         }
-        
-        overlay.push(">>> CHANGES END <<<".to_string());
-        overlay.push(String::new());
+        lines.push(OverlayLine::HunkEnd);
     }
 
     // Add remaining unchanged lines
-    while line_idx <= file_lines.len() {
-        overlay.push(file_lines[line_idx - 1].clone());
-        line_idx += 1;
+    if line_idx <= file_lines.len() {
+        push_gap(
+            &mut lines,
+            &file_lines[line_idx - 1..],
+            settings.context,
+            after_hunk,
+            false,
+        );
+    }
+
+    overlay(lines)
+}
+
+/// Unchanged file lines between hunks: all of them, or with `context` only that many next
+/// to the neighbouring hunks and a `Skipped` marker for the rest.
+fn push_gap(
+    lines: &mut Vec<OverlayLine>,
+    gap: &[String],
+    context: Option<usize>,
+    after_hunk: bool,
+    before_hunk: bool,
+) {
+    let context_line = |text: &String| OverlayLine::Context(text.clone());
+    let Some(context) = context else {
+        lines.extend(gap.iter().map(context_line));
+        return;
+    };
+    let head = if after_hunk { context } else { 0 };
+    let tail = if before_hunk { context } else { 0 };
+    // Eliding a single line saves nothing.
+    if head.saturating_add(tail).saturating_add(1) >= gap.len() {
+        lines.extend(gap.iter().map(context_line));
+        return;
+    }
+    lines.extend(gap[..head].iter().map(context_line));
+    lines.push(OverlayLine::Skipped(gap.len() - head - tail));
+    lines.extend(gap[gap.len() - tail..].iter().map(context_line));
+}
+
+/// The commit a blamed line was last changed in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BlameLine {
+    /// Line number in the blamed file.
+    line: usize,
+    sha: String,
+    author: String,
+    /// Author time, seconds since the epoch.
+    time: i64,
+}
+
+/// Summarise `git blame` for a hunk's unchanged context lines, the code the change
+/// lands in; `None` when there is no committed context or git cannot blame the file.
+fn blame_hunk(
+    repo_root: &Path,
+    source: Source,
+    file_path: &str,
+    hunk: &DiffHunk,
+) -> Option<String> {
+    let mut context = Vec::new();
+    let mut number = hunk.new_start;
+    for diff_line in &hunk.content {
+        if diff_line.starts_with(' ') {
+            context.push(number);
+            number += 1;
+        } else if diff_line.starts_with('+') {
+            number += 1;
+        }
+    }
+    if context.is_empty() || hunk.new_count == 0 {
+        return None;
+    }
+
+    let root = repo_root.to_string_lossy();
+    let range = format!("{},{}", hunk.new_start, hunk.new_start + hunk.new_count - 1);
+    let mut args = vec!["-C", &root, "blame", "--porcelain", "-L", &range];
+    if let Source::Commit(sha) = source {
+        args.push(sha);
+    }
+    args.extend(["--", file_path]);
+    let porcelain = run_git(&args).ok()?;
+    let lines: Vec<BlameLine> = parse_blame(&porcelain)
+        .into_iter()
+        .filter(|line| context.contains(&line.line))
+        .collect();
+    blame_summary(&lines, chrono::Utc::now().timestamp())
+}
+
+fn parse_blame(porcelain: &str) -> Vec<BlameLine> {
+    // Commit details are only given the first time each commit appears.
+    let mut commits: std::collections::HashMap<String, (String, i64)> = Default::default();
+    let mut order = Vec::new();
+    let mut current: Option<(String, usize)> = None;
+    for line in porcelain.lines() {
+        if line.starts_with('\t') {
+            order.extend(current.take());
+            continue;
+        }
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        if key.len() >= 40 && key.chars().all(|c| c.is_ascii_hexdigit()) {
+            // `<sha> <original line> <final line> [<group size>]`
+            let line = value
+                .split(' ')
+                .nth(1)
+                .and_then(|n| n.parse().ok())
+                .unwrap_or_default();
+            commits.entry(key.to_string()).or_default();
+            current = Some((key.to_string(), line));
+        } else if let Some((sha, _)) = &current
+            && let Some(commit) = commits.get_mut(sha)
+        {
+            match key {
+                "author" => commit.0 = value.to_string(),
+                "author-time" => commit.1 = value.parse().unwrap_or_default(),
+                _ => {}
+            }
+        }
+    }
+    order
+        .into_iter()
+        .map(|(sha, line)| {
+            let (author, time) = commits[&sha].clone();
+            BlameLine {
+                line,
+                sha,
+                author,
+                time,
+            }
+        })
+        .collect()
+}
+
+/// `last changed by <author>, <age> ago (<sha>)`, or the newest and oldest commits when
+/// the lines come from several. Uncommitted lines are ignored.
+fn blame_summary(lines: &[BlameLine], now: i64) -> Option<String> {
+    let committed: Vec<&BlameLine> = lines
+        .iter()
+        .filter(|line| !line.sha.bytes().all(|b| b == b'0'))
+        .collect();
+    let newest = committed.iter().max_by_key(|line| line.time)?;
+    let oldest = committed.iter().min_by_key(|line| line.time)?;
+    let describe = |line: &BlameLine| {
+        format!(
+            "{}, {} ago ({})",
+            line.author,
+            age(now - line.time),
+            &line.sha[..7]
+        )
+    };
+    if newest.sha == oldest.sha {
+        Some(format!("last changed by {}", describe(newest)))
+    } else {
+        Some(format!(
+            "newest {}; oldest {}",
+            describe(newest),
+            describe(oldest)
+        ))
+    }
+}
+
+fn age(seconds: i64) -> String {
+    let seconds = seconds.max(0) as usize;
+    let days = seconds / 86_400;
+    match seconds {
+        0..3_600 => "under an hour".to_string(),
+        3_600..86_400 => plural(seconds / 3_600, "hour"),
+        _ if days < 60 => plural(days, "day"),
+        _ if days < 730 => plural(days / 30, "month"),
+        _ => plural(days / 365, "year"),
     }
+}
 
-    overlay.push("```".to_string());
+fn render_overlay(file: &FileOverlay) -> Vec<String> {
+    let name = file.path.rsplit('/').next().unwrap_or(&file.path);
+    let mut out = vec![format!(
+        "```{}",
+        crate::walk::fence_language(name).unwrap_or_default()
+    )];
+    for line in &file.lines {
+        match line {
+            OverlayLine::Context(text) | OverlayLine::Note(text) | OverlayLine::Raw(text) => {
+                out.push(text.clone())
+            }
+            OverlayLine::Added(text) => out.push(format!("+ {}", text)),
+            OverlayLine::Removed(text) => out.push(format!("- {}", text)),
+            OverlayLine::HunkStart(header) => {
+                out.push(String::new());
+                out.push(format!(">>> CHANGES START {} <<<", header));
+            }
+            OverlayLine::Link(url) => out.push(format!(">>> LINK {} <<<", url)),
+            OverlayLine::Blame(summary) => out.push(format!(">>> BLAME {} <<<", summary)),
+            OverlayLine::Skipped(count) => {
+                out.push(format!("... {} unchanged ...", plural(*count, "line")))
+            }
+            OverlayLine::HunkEnd => {
+                out.push(">>> CHANGES END <<<".to_string());
+                out.push(String::new());
+            }
+        }
+    }
+    out.push("```".to_string());
+    out
+}
 
-    overlay
+fn section_heading(section: &Section) -> Vec<String> {
+    let mut lines = vec![format!("## {}", section.title)];
+    if let Some(commit) = &section.commit {
+        lines.push(format!("_{}, {}_", commit.author, commit.date));
+        lines.push(String::new());
+        if !commit.body.is_empty() {
+            lines.push(commit.body.clone());
+            lines.push(String::new());
+        }
+    }
+    lines
 }
 
-fn render_section(title: &str, entries: &[(String, String)], repo_root: &Path) -> String {
-    let mut lines = vec![format!("## {}", title)];
-    
-    if entries.is_empty() {
+fn render_section(section: &Section) -> String {
+    let mut lines = section_heading(section);
+
+    if section.files.is_empty() {
         lines.push("_No changes detected in this scope._".to_string());
         lines.push(String::new());
     } else {
-        for (file_path, diff) in entries {
-            lines.push(format!("### `{}`", file_path));
-            lines.extend(render_overlay(file_path, diff, repo_root));
+        for file in &section.files {
+            lines.push(format!("### `{}`", file.path));
+            lines.extend(render_overlay(file));
         }
     }
 
     lines.join("\n")
 }
 
-pub fn generate_review(opts: ReviewOptions, repo_root: &Path) -> Result<String> {
-    let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M:%SZ");
-    let current_branch = run_git(&["rev-parse", "--abbrev-ref", "HEAD"])?.trim().to_string();
+/// Whether an exclude glob matches `path`, itself or one of its directories, as
+/// `DevIgnore::is_ignored` checks them: `vendor/` drops `vendor/lib.rs`.
+fn excluded(patterns: &[String], path: &str) -> bool {
+    path.match_indices('/')
+        .map(|(index, _)| (&path[..index], true))
+        .chain([(path, false)])
+        .any(|(path, is_dir)| {
+            patterns
+                .iter()
+                .any(|pattern| crate::config::path_glob_matches(pattern, path, is_dir))
+        })
+}
+
+fn section(
+    title: &str,
+    entries: &[(String, String)],
+    stats: Vec<FileStat>,
+    source: Source,
+    settings: OverlaySettings,
+) -> Section {
+    let included =
+        |path: &str| !excluded(settings.exclude, path) && !settings.ignore.is_ignored(path);
+    Section {
+        title: title.to_string(),
+        commit: None,
+        files: entries
+            .iter()
+            .filter(|(path, _)| included(path))
+            .map(|(path, diff)| build_overlay(path, diff, source, settings))
+            .collect(),
+        stats: stats
+            .into_iter()
+            .filter(|stat| included(&stat.path))
+            .collect(),
+    }
+}
+
+/// Commits in `range`, oldest first so the sections read as the branch's story.
+fn commits_in(range: &str) -> Result<Vec<CommitInfo>> {
+    let log = run_git(&[
+        "log",
+        "--reverse",
+        "--date=short",
+        "--format=%H%x1f%an <%ae>%x1f%ad%x1f%s%x1f%b%x1e",
+        range,
+    ])?;
+    Ok(parse_commits(&log))
+}
+
+fn parse_commits(log: &str) -> Vec<CommitInfo> {
+    log.split('\x1e')
+        .filter_map(|record| {
+            let fields: Vec<&str> = record.trim_start().splitn(5, '\x1f').collect();
+            match fields.as_slice() {
+                [sha, author, date, subject, body] if !sha.is_empty() => Some(CommitInfo {
+                    sha: sha.to_string(),
+                    author: author.to_string(),
+                    date: date.to_string(),
+                    subject: subject.to_string(),
+                    body: body.trim().to_string(),
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// One section per commit, diffed against its first parent (the empty tree for a root
+/// commit) and overlaid on the files as of that commit.
+fn commit_sections(range: &str, settings: OverlaySettings) -> Result<Vec<Section>> {
+    let commits = commits_in(range)?;
+    if commits.is_empty() {
+        return Ok(vec![Section {
+            title: format!("Commits in {}", range),
+            commit: None,
+            files: Vec::new(),
+            stats: Vec::new(),
+        }]);
+    }
+    let empty_tree = run_git(&["hash-object", "-t", "tree", "--stdin"])?
+        .trim()
+        .to_string();
+    let mut sections = Vec::new();
+    for commit in commits {
+        let parent = run_git(&[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^", commit.sha),
+        ])
+        .map(|parent| parent.trim().to_string())
+        .unwrap_or_else(|_| empty_tree.clone());
+        let entries = collect_file_diffs(&[&parent, &commit.sha])?;
+        let mut section = section(
+            &format!("{} {}", &commit.sha[..7], commit.subject),
+            &entries,
+            collect_numstat(&[&parent, &commit.sha])?,
+            Source::Commit(&commit.sha),
+            OverlaySettings {
+                link: settings
+                    .link
+                    .filter(|_| on_remote(&commit.sha))
+                    .map(|link| Permalink {
+                        rev: &commit.sha,
+                        ..link
+                    }),
+                ..settings
+            },
+        );
+        section.commit = Some(commit);
+        sections.push(section);
+    }
+    Ok(sections)
+}
+
+/// The repository's default branch, from `origin/HEAD` when the remote has one (as the
+/// local branch when it exists, else the remote-tracking ref), falling back to a local
+/// `main` or `master`.
+pub fn default_branch() -> String {
+    let exists = |branch: &str| {
+        run_git(&[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("refs/heads/{}", branch),
+        ])
+        .is_ok()
+    };
+    if let Ok(remote_head) = run_git(&[
+        "symbolic-ref",
+        "--quiet",
+        "--short",
+        "refs/remotes/origin/HEAD",
+    ]) {
+        let remote_head = remote_head.trim();
+        let local = remote_head.strip_prefix("origin/").unwrap_or(remote_head);
+        return if exists(local) { local } else { remote_head }.to_string();
+    }
+    if !exists("main") && exists("master") {
+        return "master".to_string();
+    }
+    "main".to_string()
+}
+
+/// Collect the diffs `opts` asks for into a report.
+pub fn build_report(opts: &ReviewOptions, repo_root: &Path) -> Result<Report> {
+    let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M:%SZ").to_string();
+    let branch = run_git(&["rev-parse", "--abbrev-ref", "HEAD"])?
+        .trim()
+        .to_string();
     let status = run_git(&["status", "-sb"])?;
 
     let mut sections = Vec::new();
 
-    if opts.compare_main {
-        let main_entries = collect_file_diffs(&["main...HEAD"])?;
-        sections.push(render_section("Changes vs main", &main_entries, repo_root));
+    let worktree = Source::WorkTree(repo_root);
+    let origin = crate::gitprovider::recognized_origin();
+    let head = run_git(&["rev-parse", "HEAD"])
+        .ok()
+        .map(|sha| sha.trim().to_string());
+    // Links to a commit the remote has never seen would 404.
+    let pushed_head = head.as_deref().filter(|sha| on_remote(sha));
+    let devignore = DevIgnore::find(repo_root)?;
+    let settings = OverlaySettings {
+        blame: opts.blame.then_some(repo_root),
+        context: opts.context,
+        link: permalink(&origin, pushed_head, false),
+        exclude: &opts.exclude,
+        ignore: &devignore,
+        max_bytes: opts.max_file_bytes,
+    };
+    let uncommitted = OverlaySettings {
+        link: permalink(&origin, pushed_head, true),
+        ..settings
+    };
+    if let Some(range) = &opts.commits {
+        // Each commit section links to its own commit, when that one is pushed.
+        let any_commit = OverlaySettings {
+            link: permalink(&origin, head.as_deref(), false),
+            ..settings
+        };
+        sections = commit_sections(range, any_commit)?;
+    } else if let Some(base) = &opts.base {
+        run_git(&[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", base),
+        ])
+        .with_context(|| format!("unknown base ref `{}`", base))?;
+        let range = format!("{}...HEAD", base);
+        let base_entries = collect_file_diffs(&[&range])?;
+        sections.push(section(
+            &format!("Changes vs {}", base),
+            &base_entries,
+            collect_numstat(&[&range])?,
+            worktree,
+            settings,
+        ));
     } else {
         let staged_entries = collect_file_diffs(&["--cached"])?;
-        sections.push(render_section("Staged Changes", &staged_entries, repo_root));
+        sections.push(section(
+            "Staged Changes",
+            &staged_entries,
+            collect_numstat(&["--cached"])?,
+            worktree,
+            uncommitted,
+        ));
 
         if opts.include_working {
             let worktree_entries = collect_file_diffs(&[])?;
-            sections.push(render_section("Unstaged Changes", &worktree_entries, repo_root));
+            let staged: Vec<String> = staged_entries
+                .iter()
+                .map(|(path, _)| path.clone())
+                .collect();
+            sections.push(section(
+                "Unstaged Changes",
+                &worktree_entries,
+                collect_numstat(&[])?,
+                worktree,
+                OverlaySettings {
+                    link: uncommitted.link.map(|link| Permalink {
+                        unlinked: &staged,
+                        ..link
+                    }),
+                    ..uncommitted
+                },
+            ));
         }
     }
 
+    if opts.include_untracked {
+        let untracked_entries = collect_untracked(repo_root)?;
+        sections.push(section(
+            "Untracked Files",
+            &untracked_entries,
+            untracked_stats(&untracked_entries),
+            worktree,
+            OverlaySettings {
+                blame: None,
+                link: None,
+                ..settings
+            },
+        ));
+    }
+
+    Ok(Report {
+        timestamp,
+        branch,
+        status,
+        sections,
+    })
+}
+
+/// The Markdown report: one fenced overlay per changed file.
+pub fn render_markdown(report: &Report) -> String {
     let header = format!(
-        "# Code Review Overlay\n\n\
-         _Generated at {} on branch `{}`_\n\n\
-         ## Git Status\n\
-         ```\n\
-         {}\
-         ```\n\n",
-        timestamp, current_branch, status
+        "# Code Review Overlay\n\n_Generated at {} on branch `{}`_\n\n{}{}",
+        report.timestamp,
+        report.branch,
+        summary_block(report),
+        status_block(report)
     );
+    let sections: Vec<String> = report.sections.iter().map(render_section).collect();
+    header + &sections.join("\n")
+}
+
+/// Files changed with insertions and deletions per file, one table per section (headed
+/// by the section title and followed by the overall totals when there are several).
+fn summary_block(report: &Report) -> String {
+    let count = |n: Option<usize>| n.map_or_else(|| "binary".to_string(), |n| n.to_string());
+    let mut out = String::from("## Summary\n\n");
+    let mut total = DiffTotals::default();
+    for section in &report.sections {
+        if report.sections.len() > 1 {
+            out.push_str(&format!("### {}\n\n", section.title));
+        }
+        if section.stats.is_empty() {
+            out.push_str("_No changes._\n\n");
+            continue;
+        }
+        out.push_str("| File | + | - |\n| --- | ---: | ---: |\n");
+        for stat in &section.stats {
+            out.push_str(&format!(
+                "| `{}` | {} | {} |\n",
+                stat.path,
+                count(stat.added),
+                count(stat.removed)
+            ));
+        }
+        let totals = DiffTotals::of(&section.stats);
+        out.push_str(&format!(
+            "| **{}** | **{}** | **{}** |\n\n",
+            plural(totals.files, "file"),
+            totals.added,
+            totals.removed
+        ));
+        total.add(&totals);
+    }
+    if report.sections.len() > 1 {
+        out.push_str(&format!("**Total:** {}\n\n", total.describe()));
+    }
+    out
+}
+
+/// Sums of a set of `FileStat`s; binary files count as changed with no lines.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DiffTotals {
+    pub files: usize,
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl DiffTotals {
+    pub fn of(stats: &[FileStat]) -> Self {
+        DiffTotals {
+            files: stats.len(),
+            added: stats.iter().filter_map(|stat| stat.added).sum(),
+            removed: stats.iter().filter_map(|stat| stat.removed).sum(),
+        }
+    }
+
+    pub fn add(&mut self, other: &DiffTotals) {
+        self.files += other.files;
+        self.added += other.added;
+        self.removed += other.removed;
+    }
+
+    /// `git diff --shortstat` wording: `3 files changed, 10 insertions(+), 2 deletions(-)`.
+    pub fn describe(&self) -> String {
+        format!(
+            "{} changed, {} insertion{}(+), {} deletion{}(-)",
+            plural(self.files, "file"),
+            self.added,
+            if self.added == 1 { "" } else { "s" },
+            self.removed,
+            if self.removed == 1 { "" } else { "s" }
+        )
+    }
+}
+
+fn plural(count: usize, unit: &str) -> String {
+    format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" })
+}
+
+/// `dev review --format json` output.
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    timestamp: &'a str,
+    branch: &'a str,
+    sections: Vec<JsonSection<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonSection<'a> {
+    title: &'a str,
+    commit: Option<&'a CommitInfo>,
+    stats: &'a [FileStat],
+    files: Vec<JsonFile<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonFile<'a> {
+    path: &'a str,
+    lang: &'a str,
+    /// Why there is no overlay (deleted, binary or oversized file); a deleted file's hunks
+    /// still come from its diff.
+    note: Option<&'a str>,
+    hunks: Vec<JsonHunk>,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+struct JsonHunk {
+    header: String,
+    new_start: usize,
+    new_count: usize,
+    added: Vec<String>,
+    removed: Vec<String>,
+    blame: Option<String>,
+    link: Option<String>,
+}
+
+/// The report as JSON for editor plugins and bots: per section its commit and stats, per
+/// file its hunks with their added and removed lines.
+pub fn render_json(report: &Report) -> Result<String> {
+    let sections = report
+        .sections
+        .iter()
+        .map(|section| JsonSection {
+            title: &section.title,
+            commit: section.commit.as_ref(),
+            stats: &section.stats,
+            files: section.files.iter().map(json_file).collect(),
+        })
+        .collect();
+    serde_json::to_string_pretty(&JsonReport {
+        timestamp: &report.timestamp,
+        branch: &report.branch,
+        sections,
+    })
+    .context("serializing review report")
+}
+
+fn json_file(file: &FileOverlay) -> JsonFile<'_> {
+    let mut note = None;
+    let mut raw = Vec::new();
+    let mut hunks: Vec<JsonHunk> = Vec::new();
+    for line in &file.lines {
+        match line {
+            OverlayLine::HunkStart(header) => {
+                let (new_start, new_count) = parse_hunks(header)
+                    .first()
+                    .map_or((1, 1), |hunk| (hunk.new_start, hunk.new_count));
+                hunks.push(JsonHunk {
+                    header: header.clone(),
+                    new_start,
+                    new_count,
+                    added: Vec::new(),
+                    removed: Vec::new(),
+                    blame: None,
+                    link: None,
+                });
+            }
+            OverlayLine::Added(text) => {
+                if let Some(hunk) = hunks.last_mut() {
+                    hunk.added.push(text.clone());
+                }
+            }
+            OverlayLine::Removed(text) => {
+                if let Some(hunk) = hunks.last_mut() {
+                    hunk.removed.push(text.clone());
+                }
+            }
+            OverlayLine::Blame(summary) => {
+                if let Some(hunk) = hunks.last_mut() {
+                    hunk.blame = Some(summary.clone());
+                }
+            }
+            OverlayLine::Link(url) => {
+                if let Some(hunk) = hunks.last_mut() {
+                    hunk.link = Some(url.clone());
+                }
+            }
+            OverlayLine::Note(text) => note = Some(text.trim_matches('_')),
+            OverlayLine::Raw(text) => raw.push(text.as_str()),
+            OverlayLine::Context(_) | OverlayLine::HunkEnd | OverlayLine::Skipped(_) => {}
+        }
+    }
+    if !raw.is_empty() {
+        hunks = parse_hunks(&raw.join("\n"))
+            .into_iter()
+            .map(|hunk| {
+                let changed = |sign: char, header: &str| {
+                    hunk.content
+                        .iter()
+                        .filter(|line| !line.starts_with(header))
+                        .filter_map(|line| line.strip_prefix(sign))
+                        .map(str::to_owned)
+                        .collect()
+                };
+                JsonHunk {
+                    added: changed('+', "+++"),
+                    removed: changed('-', "---"),
+                    header: hunk.header,
+                    new_start: hunk.new_start,
+                    new_count: hunk.new_count,
+                    blame: None,
+                    link: None,
+                }
+            })
+            .collect();
+    }
+    JsonFile {
+        path: &file.path,
+        lang: &file.lang,
+        note,
+        hunks,
+    }
+}
+
+fn status_block(report: &Report) -> String {
+    format!("## Git Status\n```\n{}```\n\n", report.status)
+}
+
+/// Rough LLM token count: about four characters per token.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
 
-    Ok(header + &sections.join("\n"))
+/// A user-written LLM prompt wrapped around the report: review instructions,
+/// a rubric, the expected answer format.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Prompt {
+    pub preamble: String,
+    pub epilogue: String,
+}
+
+impl Prompt {
+    /// Marks where the report goes in a prompt template.
+    pub const PLACEHOLDER: &'static str = "{{review}}";
+
+    /// Splits a template at `{{review}}`; a template without one is all preamble.
+    pub fn parse(template: &str) -> Self {
+        let (preamble, epilogue) = template
+            .split_once(Self::PLACEHOLDER)
+            .unwrap_or((template, ""));
+        Prompt {
+            preamble: preamble.trim().to_string(),
+            epilogue: epilogue.trim().to_string(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.preamble.is_empty() && self.epilogue.is_empty()
+    }
+
+    /// Tokens the preamble and epilogue add to the report.
+    pub fn tokens(&self) -> usize {
+        estimate_tokens(&self.preamble) + estimate_tokens(&self.epilogue) + 1
+    }
+
+    /// Puts the preamble before the first part and the epilogue after the last.
+    pub fn wrap(&self, mut parts: Vec<String>) -> Vec<String> {
+        if let Some(first) = parts.first_mut().filter(|_| !self.preamble.is_empty()) {
+            *first = format!("{}\n\n{}", self.preamble, first);
+        }
+        if let Some(last) = parts.last_mut().filter(|_| !self.epilogue.is_empty()) {
+            *last = format!("{}\n\n{}\n", last.trim_end(), self.epilogue);
+        }
+        parts
+    }
+}
+
+/// Parts of a budgeted report being filled, measured in characters.
+struct Parts {
+    budget: usize,
+    done: Vec<String>,
+    current: String,
+    /// Section whose heading the current part already shows.
+    section: Option<usize>,
+}
+
+impl Parts {
+    fn fits(&self, text: &str) -> bool {
+        self.current.chars().count() + text.chars().count() <= self.budget
+    }
+
+    fn flush(&mut self) {
+        if !self.current.is_empty() {
+            self.done.push(std::mem::take(&mut self.current));
+        }
+        self.section = None;
+    }
+}
+
+/// `render_markdown` split into parts that each fit `max_tokens` by `estimate_tokens`,
+/// headed `part i of n`. Parts break between files; a file too large for a part of its
+/// own is split between lines, each piece re-opening the code fence under a
+/// `(continued)` heading. A report that already fits comes back as a single part.
+pub fn render_markdown_parts(report: &Report, max_tokens: usize) -> Vec<String> {
+    let whole = render_markdown(report);
+    if estimate_tokens(&whole) <= max_tokens {
+        return vec![whole];
+    }
+
+    // Reserve room for the widest part header.
+    let reserved = part_header(report, 9999, 9999).chars().count();
+    let mut parts = Parts {
+        budget: (max_tokens * 4).saturating_sub(reserved),
+        done: Vec::new(),
+        current: summary_block(report) + &status_block(report),
+        section: None,
+    };
+
+    for (index, section) in report.sections.iter().enumerate() {
+        let heading = section_heading(section).join("\n") + "\n";
+        let continued = format!("## {} (continued)\n", section.title);
+        let mut started = false;
+
+        let files: Vec<(String, Vec<String>)> = if section.files.is_empty() {
+            vec![(
+                String::new(),
+                vec!["_No changes detected in this scope._".to_string()],
+            )]
+        } else {
+            section
+                .files
+                .iter()
+                .map(|file| (format!("### `{}`", file.path), render_overlay(file)))
+                .collect()
+        };
+
+        for (file_heading, lines) in files {
+            let block = if file_heading.is_empty() {
+                lines.join("\n") + "\n\n"
+            } else {
+                format!("{}\n{}\n\n", file_heading, lines.join("\n"))
+            };
+            let lead = |parts: &Parts, started: bool| match parts.section {
+                Some(shown) if shown == index => "",
+                _ if started => continued.as_str(),
+                _ => heading.as_str(),
+            };
+
+            // A file that cannot fit any part on its own starts in this one and is split.
+            let fits_alone = heading.chars().count().max(continued.chars().count())
+                + block.chars().count()
+                <= parts.budget;
+            if !parts.fits(&format!("{}{}", lead(&parts, started), block)) && fits_alone {
+                parts.flush();
+            }
+            let mut fresh = parts.current.is_empty();
+            parts.current.push_str(lead(&parts, started));
+            parts.section = Some(index);
+            started = true;
+            if parts.fits(&block) || file_heading.is_empty() {
+                parts.current.push_str(&block);
+                continue;
+            }
+
+            let (fence, body) = (&lines[0], &lines[1..lines.len() - 1]);
+            let mut piece_heading = file_heading.clone();
+            let mut rest = body;
+            while !rest.is_empty() {
+                let mut piece = format!("{}\n{}\n", piece_heading, fence);
+                let mut taken = 0;
+                for line in rest {
+                    let next = format!("{}{}\n", piece, line);
+                    // A fresh part takes at least one line, however long, so this ends.
+                    if !parts.fits(&format!("{}```\n\n", next)) && (!fresh || taken > 0) {
+                        break;
+                    }
+                    piece = next;
+                    taken += 1;
+                }
+                if taken > 0 {
+                    piece.push_str("```\n\n");
+                    parts.current.push_str(&piece);
+                    rest = &rest[taken..];
+                    piece_heading = format!("{} (continued)", file_heading);
+                }
+                if !rest.is_empty() {
+                    parts.flush();
+                    parts.current.push_str(&continued);
+                    parts.section = Some(index);
+                    fresh = true;
+                }
+            }
+        }
+    }
+    parts.flush();
+
+    let total = parts.done.len();
+    parts
+        .done
+        .iter()
+        .enumerate()
+        .map(|(i, body)| part_header(report, i + 1, total) + body)
+        .collect()
+}
+
+fn part_header(report: &Report, part: usize, total: usize) -> String {
+    format!(
+        "# Code Review Overlay (part {} of {})\n\n_Generated at {} on branch `{}`_\n\n",
+        part, total, report.timestamp, report.branch
+    )
+}
+
+/// What the output file of a report split by `--max-tokens` becomes: a list of `parts`,
+/// each a file name with its rendered text.
+pub fn render_parts_index(
+    report: &Report,
+    max_tokens: usize,
+    parts: &[(String, String)],
+) -> String {
+    let mut out = format!(
+        "# Code Review Overlay (index)\n\n_Generated at {} on branch `{}`_\n\n\
+         Split into {} parts of at most {} tokens each:\n\n",
+        report.timestamp,
+        report.branch,
+        parts.len(),
+        max_tokens
+    );
+    for (index, (file, text)) in parts.iter().enumerate() {
+        out.push_str(&format!(
+            "{}. [{}]({}) (~{} tokens)\n",
+            index + 1,
+            file,
+            file,
+            estimate_tokens(text)
+        ));
+    }
+    out
+}
+
+/// The part files listed by an index `render_parts_index` wrote; `None` when `text` is
+/// not such an index.
+pub fn index_parts(text: &str) -> Option<Vec<String>> {
+    let list = text.strip_prefix("# Code Review Overlay (index)\n")?;
+    Some(
+        list.lines()
+            .filter_map(|line| {
+                let (_, rest) = line.split_once(". [")?;
+                rest.split_once("](").map(|(file, _)| file.to_string())
+            })
+            .collect(),
+    )
 }
 
 pub fn get_repo_root() -> Result<PathBuf> {
     let output = run_git(&["rev-parse", "--show-toplevel"])?;
     Ok(PathBuf::from(output.trim()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{InDir, git, repo_with_origin};
+
+    #[test]
+    fn parses_commit_log_records() {
+        let log = "aaaaaaaaaa\x1fAda <ada@x>\x1f2026-10-01\x1ffeat: add overlay\x1f\x1e\n\
+                   bbbbbbbbbb\x1fBob <bob@x>\x1f2026-10-02\x1ffix: typo\x1fLonger story.\n\x1e\n";
+        let commits = parse_commits(log);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].author, "Ada <ada@x>");
+        assert_eq!(commits[0].body, "");
+        assert_eq!(commits[1].sha, "bbbbbbbbbb");
+        assert_eq!(commits[1].subject, "fix: typo");
+        assert_eq!(commits[1].body, "Longer story.");
+
+        let section = Section {
+            title: "bbbbbbb fix: typo".into(),
+            commit: Some(commits[1].clone()),
+            files: Vec::new(),
+            stats: Vec::new(),
+        };
+        assert_eq!(
+            render_section(&section),
+            "## bbbbbbb fix: typo\n_Bob <bob@x>, 2026-10-02_\n\nLonger story.\n\n\
+             _No changes detected in this scope._\n"
+        );
+    }
+
+    #[test]
+    fn wraps_reports_in_prompt_templates() {
+        let prompt = Prompt::parse("Review this change.\n\n{{review}}\n\nAnswer as a list.\n");
+        assert_eq!(prompt.preamble, "Review this change.");
+        assert_eq!(prompt.epilogue, "Answer as a list.");
+        assert_eq!(
+            prompt.wrap(vec!["# Report\n".into()]),
+            vec!["Review this change.\n\n# Report\n\nAnswer as a list.\n".to_string()]
+        );
+        assert_eq!(
+            prompt.wrap(vec!["one\n".into(), "two\n".into()]),
+            vec![
+                "Review this change.\n\none\n".to_string(),
+                "two\n\nAnswer as a list.\n".to_string()
+            ]
+        );
+
+        let preamble_only = Prompt::parse("Be terse.");
+        assert_eq!(preamble_only.epilogue, "");
+        assert_eq!(
+            preamble_only.wrap(vec!["x\n".into()]),
+            vec!["Be terse.\n\nx\n"]
+        );
+        assert!(Prompt::parse(" {{review}}\n").is_empty());
+    }
+
+    #[test]
+    fn splits_reports_into_token_budgeted_parts() {
+        let file = |path: &str, count: usize| FileOverlay {
+            path: path.into(),
+            lang: "rs".into(),
+            lines: (0..count)
+                .map(|n| OverlayLine::Added(format!("let line_{} = {};", n, n)))
+                .collect(),
+        };
+        let report = Report {
+            timestamp: "2026-10-16 12:00:00Z".into(),
+            branch: "feat/x".into(),
+            status: "## feat/x\n".into(),
+            sections: vec![Section {
+                title: "Staged Changes".into(),
+                commit: None,
+                files: vec![file("small.rs", 3), file("big.rs", 400), file("tail.rs", 3)],
+                stats: Vec::new(),
+            }],
+        };
+
+        let whole = render_markdown(&report);
+        assert_eq!(
+            render_markdown_parts(&report, estimate_tokens(&whole)),
+            vec![whole]
+        );
+
+        let parts = render_markdown_parts(&report, 500);
+        let total = parts.len();
+        assert!(total > 3, "{}", total);
+        for (i, part) in parts.iter().enumerate() {
+            assert!(estimate_tokens(part) <= 500, "part {}: {}", i + 1, part);
+            assert!(part.starts_with(&format!(
+                "# Code Review Overlay (part {} of {})",
+                i + 1,
+                total
+            )));
+            assert_eq!(part.matches("```").count() % 2, 0, "{}", part);
+        }
+        assert!(parts[0].contains("## Git Status"));
+        assert!(parts[0].contains("### `small.rs`\n```rust\n+ let line_0 = 0;"));
+        assert!(parts[1].contains("## Staged Changes (continued)\n### `big.rs` (continued)\n"));
+        let joined = parts.concat();
+        for n in 0..400 {
+            assert!(joined.contains(&format!("+ let line_{} = {};\n", n, n)));
+        }
+        assert!(parts[total - 1].contains("### `tail.rs`"));
+
+        let named: Vec<(String, String)> = parts
+            .iter()
+            .enumerate()
+            .map(|(i, part)| (format!("review-report.{}.md", i + 1), part.clone()))
+            .collect();
+        let index = render_parts_index(&report, 500, &named);
+        assert_eq!(
+            index_parts(&index).unwrap(),
+            named
+                .iter()
+                .map(|(file, _)| file.clone())
+                .collect::<Vec<_>>()
+        );
+        assert!(index_parts(&parts[0]).is_none());
+    }
+
+    #[test]
+    fn overlays_new_files_as_fully_added() {
+        let root = std::env::temp_dir().join(format!("dev-review-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("new.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+
+        let whole = OverlaySettings {
+            blame: None,
+            context: None,
+            link: None,
+            exclude: &[],
+            ignore: &DevIgnore::default(),
+            max_bytes: None,
+        };
+        let diff = crate::textdiff::unified("new.rs", "", "fn a() {}\nfn b() {}\n");
+        let overlay = build_overlay("new.rs", &diff, Source::WorkTree(&root), whole);
+        assert_eq!(
+            overlay.lines,
+            vec![
+                OverlayLine::HunkStart("@@ -0,0 +1,2 @@".into()),
+                OverlayLine::Added("fn a() {}".into()),
+                OverlayLine::Added("fn b() {}".into()),
+                OverlayLine::HunkEnd,
+            ]
+        );
+
+        let binary = build_overlay(
+            "new.rs",
+            "Binary files /dev/null and b/new.rs differ",
+            Source::WorkTree(&root),
+            whole,
+        );
+        assert_eq!(
+            binary.lines,
+            vec![OverlayLine::Note("_Binary file, new, 20 B._".into())]
+        );
+        let large = build_overlay(
+            "new.rs",
+            &diff,
+            Source::WorkTree(&root),
+            OverlaySettings {
+                max_bytes: Some(10),
+                ..whole
+            },
+        );
+        assert_eq!(
+            large.lines,
+            vec![OverlayLine::Note(
+                "_Large file, new, 20 B, over the 10 B limit; overlay skipped._".into()
+            )]
+        );
+
+        // Twenty lines with the tenth changed: the hunk spans lines 7-13.
+        let old: Vec<String> = (1..=20).map(|n| format!("line {}", n)).collect();
+        let mut new = old.clone();
+        new[9] = "line ten".into();
+        std::fs::write(root.join("long.rs"), new.join("\n")).unwrap();
+        let diff = crate::textdiff::unified("long.rs", &old.join("\n"), &new.join("\n"));
+        let build = |context| {
+            build_overlay(
+                "long.rs",
+                &diff,
+                Source::WorkTree(&root),
+                OverlaySettings { context, ..whole },
+            )
+            .lines
+        };
+        let context = |n: usize| OverlayLine::Context(format!("line {}", n));
+        assert_eq!(build(None).len(), 20 + 3);
+        let trimmed = build(Some(2));
+        assert_eq!(
+            trimmed[..4],
+            [
+                OverlayLine::Skipped(4),
+                context(5),
+                context(6),
+                OverlayLine::HunkStart("@@ -7,7 +7,7 @@".into()),
+            ]
+        );
+        assert_eq!(
+            trimmed[trimmed.len() - 3..],
+            [context(14), context(15), OverlayLine::Skipped(5)]
+        );
+        let diff_only = build(Some(0));
+        assert_eq!(diff_only[0], OverlayLine::Skipped(6));
+        assert_eq!(diff_only[diff_only.len() - 1], OverlayLine::Skipped(7));
+
+        let link = |old_side| Permalink {
+            web: "https://github.com/o/r",
+            provider: &crate::gitprovider::GitHub,
+            rev: "abc",
+            old_side,
+            unlinked: &[],
+        };
+        let linked = build_overlay(
+            "long.rs",
+            &diff,
+            Source::WorkTree(&root),
+            OverlaySettings {
+                link: Some(link(false)),
+                ..whole
+            },
+        );
+        assert_eq!(
+            linked.lines[7],
+            OverlayLine::Link("https://github.com/o/r/blob/abc/long.rs#L7-L13".into())
+        );
+        // A file that is new at HEAD has no old side to link to.
+        let new_file = crate::textdiff::unified("new.rs", "", "fn a() {}\nfn b() {}\n");
+        let unlinked = build_overlay(
+            "new.rs",
+            &new_file,
+            Source::WorkTree(&root),
+            OverlaySettings {
+                link: Some(link(true)),
+                ..whole
+            },
+        );
+        assert!(
+            !unlinked
+                .lines
+                .iter()
+                .any(|line| matches!(line, OverlayLine::Link(_)))
+        );
+        // Unstaged edits of a file with staged changes diff against the index, not HEAD.
+        let staged = ["long.rs".to_string()];
+        let against_index = build_overlay(
+            "long.rs",
+            &diff,
+            Source::WorkTree(&root),
+            OverlaySettings {
+                link: Some(Permalink {
+                    unlinked: &staged,
+                    ..link(true)
+                }),
+                ..whole
+            },
+        );
+        assert!(
+            !against_index
+                .lines
+                .iter()
+                .any(|line| matches!(line, OverlayLine::Link(_)))
+        );
+
+        let stat = |path: &str| FileStat {
+            path: path.into(),
+            added: Some(2),
+            removed: Some(0),
+        };
+        let excluded = section(
+            "Staged Changes",
+            &[
+                ("new.rs".into(), new_file.clone()),
+                ("long.rs".into(), diff.clone()),
+            ],
+            vec![stat("new.rs"), stat("long.rs")],
+            Source::WorkTree(&root),
+            OverlaySettings {
+                exclude: &["lo*.rs".to_string()],
+                ..whole
+            },
+        );
+        assert_eq!(excluded.files.len(), 1);
+        assert_eq!(excluded.files[0].path, "new.rs");
+        assert_eq!(excluded.stats, vec![stat("new.rs")]);
+        // Gitignore-style: `*` stays within a directory, `**` spans any number of them.
+        let generated = |pattern: &str| {
+            section(
+                "Staged Changes",
+                &[
+                    ("src/gen/deep/a.rs".into(), diff.clone()),
+                    ("src/gen/b.rs".into(), diff.clone()),
+                ],
+                vec![stat("src/gen/deep/a.rs"), stat("src/gen/b.rs")],
+                Source::WorkTree(&root),
+                OverlaySettings {
+                    exclude: &[pattern.to_string()],
+                    ..whole
+                },
+            )
+            .stats
+        };
+        assert_eq!(generated("src/gen/*.rs"), vec![stat("src/gen/deep/a.rs")]);
+        assert_eq!(generated("src/gen/**"), vec![]);
+        assert_eq!(generated("b.rs"), vec![stat("src/gen/deep/a.rs")]);
+        // Directory patterns drop everything under a matching directory.
+        assert_eq!(generated("deep/"), vec![stat("src/gen/b.rs")]);
+        assert_eq!(generated("src/gen/"), vec![]);
+        assert_eq!(generated("gen"), vec![]);
+        assert_eq!(
+            generated("b.rs/"),
+            vec![stat("src/gen/deep/a.rs"), stat("src/gen/b.rs")]
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn summarises_blame_for_hunk_context() {
+        let a = "a".repeat(40);
+        let b = "b".repeat(40);
+        let uncommitted = "0".repeat(40);
+        let porcelain = format!(
+            "{a} 1 10 2\nauthor Ada\nauthor-time 1000\nsummary x\n\tline\n\
+             {a} 2 11\n\tline\n\
+             {b} 5 12 1\nauthor Bob\nauthor-time 90000\n\tline\n\
+             {uncommitted} 13 13 1\nauthor Not Committed Yet\nauthor-time 99999\n\tline\n"
+        );
+        let lines = parse_blame(&porcelain);
+        assert_eq!(
+            lines.iter().map(|line| line.line).collect::<Vec<_>>(),
+            vec![10, 11, 12, 13]
+        );
+        assert_eq!(lines[1].author, "Ada");
+
+        let now = 90000 + 3 * 86_400;
+        assert_eq!(
+            blame_summary(&lines, now).unwrap(),
+            "newest Bob, 3 days ago (bbbbbbb); oldest Ada, 4 days ago (aaaaaaa)"
+        );
+        assert_eq!(
+            blame_summary(&lines[..2], now).unwrap(),
+            "last changed by Ada, 4 days ago (aaaaaaa)"
+        );
+        assert_eq!(blame_summary(&lines[3..], now), None);
+
+        assert_eq!(age(120), "under an hour");
+        assert_eq!(age(3_600), "1 hour");
+        assert_eq!(age(200 * 86_400), "6 months");
+        assert_eq!(age(800 * 86_400), "2 years");
+
+        let hunks = parse_hunks("@@ -1,3 +1,4 @@\n a\n+b\n c\n@@ -9 +10 @@\n-x\n+y\n");
+        assert_eq!((hunks[0].new_start, hunks[0].new_count), (1, 4));
+        assert_eq!((hunks[1].new_start, hunks[1].new_count), (10, 1));
+    }
+
+    #[test]
+    fn describes_size_changes() {
+        assert_eq!(human_size(512), "512 B");
+        assert_eq!(human_size(1536), "1.5 KiB");
+        assert_eq!(human_size(3 * 1024 * 1024), "3.0 MiB");
+        assert_eq!(
+            size_change(Some(2048), Some(1024)),
+            "2.0 KiB -> 1.0 KiB (-1.0 KiB)"
+        );
+        assert_eq!(size_change(Some(2048), None), "deleted, was 2.0 KiB");
+        assert_eq!(
+            old_blob("diff --git a/x b/x\nindex 3b18e51..0000000\n"),
+            Some("3b18e51")
+        );
+        assert_eq!(
+            old_blob("new file mode 100644\nindex 0000000..3b18e51\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn summarises_diff_stats_per_section() {
+        let stats = parse_numstat("3\t1\tsrc/lib.rs\n-\t-\tlogo.png\n");
+        assert_eq!(
+            stats,
+            vec![
+                FileStat {
+                    path: "src/lib.rs".into(),
+                    added: Some(3),
+                    removed: Some(1),
+                },
+                FileStat {
+                    path: "logo.png".into(),
+                    added: None,
+                    removed: None,
+                },
+            ]
+        );
+        let untracked = untracked_stats(&[(
+            "new.rs".into(),
+            crate::textdiff::unified("new.rs", "", "a\nb\n"),
+        )]);
+        assert_eq!(
+            (untracked[0].added, untracked[0].removed),
+            (Some(2), Some(0))
+        );
+
+        let section = |title: &str, stats: Vec<FileStat>| Section {
+            title: title.into(),
+            commit: None,
+            files: Vec::new(),
+            stats,
+        };
+        let report = Report {
+            timestamp: "2026-10-16 12:00:00Z".into(),
+            branch: "feat/x".into(),
+            status: "## feat/x\n".into(),
+            sections: vec![
+                section("Staged Changes", stats),
+                section("Untracked Files", untracked),
+                section("Unstaged Changes", Vec::new()),
+            ],
+        };
+        assert_eq!(
+            summary_block(&report),
+            "## Summary\n\n### Staged Changes\n\n| File | + | - |\n| --- | ---: | ---: |\n\
+             | `src/lib.rs` | 3 | 1 |\n| `logo.png` | binary | binary |\n| **2 files** | **3** | **1** |\n\n\
+             ### Untracked Files\n\n| File | + | - |\n| --- | ---: | ---: |\n\
+             | `new.rs` | 2 | 0 |\n| **1 file** | **2** | **0** |\n\n\
+             ### Unstaged Changes\n\n_No changes._\n\n\
+             **Total:** 3 files changed, 5 insertions(+), 1 deletion(-)\n\n"
+        );
+    }
+
+    #[test]
+    fn renders_hunks_as_json() {
+        let overlay = FileOverlay {
+            path: "src/lib.rs".into(),
+            lang: "rs".into(),
+            lines: vec![
+                OverlayLine::Context("use std::fs;".into()),
+                OverlayLine::HunkStart("@@ -2,2 +2,2 @@ fn main".into()),
+                OverlayLine::Blame("last changed by Ada, 3 days ago (aaaaaaa)".into()),
+                OverlayLine::Context("fn keep() {}".into()),
+                OverlayLine::Removed("fn old() {}".into()),
+                OverlayLine::Added("fn new() {}".into()),
+                OverlayLine::HunkEnd,
+            ],
+        };
+        let file = json_file(&overlay);
+        assert_eq!(file.note, None);
+        assert_eq!(
+            file.hunks,
+            vec![JsonHunk {
+                header: "@@ -2,2 +2,2 @@ fn main".into(),
+                new_start: 2,
+                new_count: 2,
+                added: vec!["fn new() {}".into()],
+                removed: vec!["fn old() {}".into()],
+                blame: Some("last changed by Ada, 3 days ago (aaaaaaa)".into()),
+                link: None,
+            }]
+        );
+
+        let deleted = FileOverlay {
+            path: "gone.rs".into(),
+            lang: "rs".into(),
+            lines: vec![
+                OverlayLine::Note("_File deleted; showing diff below._".into()),
+                OverlayLine::Raw("--- a/gone.rs".into()),
+                OverlayLine::Raw("+++ /dev/null".into()),
+                OverlayLine::Raw("@@ -1 +0,0 @@".into()),
+                OverlayLine::Raw("-fn gone() {}".into()),
+            ],
+        };
+        let file = json_file(&deleted);
+        assert_eq!(file.note, Some("File deleted; showing diff below."));
+        assert_eq!(file.hunks[0].removed, vec!["fn gone() {}".to_string()]);
+        assert_eq!((file.hunks[0].new_start, file.hunks[0].new_count), (0, 0));
+
+        let json = render_json(&Report {
+            timestamp: "2026-10-16 12:00:00Z".into(),
+            branch: "feat/x".into(),
+            status: String::new(),
+            sections: vec![Section {
+                title: "Staged Changes".into(),
+                commit: None,
+                files: vec![overlay],
+                stats: Vec::new(),
+            }],
+        })
+        .unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["sections"][0]["files"][0]["path"], "src/lib.rs");
+        assert_eq!(
+            parsed["sections"][0]["files"][0]["hunks"][0]["new_start"],
+            2
+        );
+    }
+
+    #[test]
+    fn default_branch_follows_origin_head_then_falls_back() {
+        let work = repo_with_origin();
+        let _dir = InDir::new(&work);
+        git(&work, &["remote", "set-head", "origin", "main"]);
+        assert_eq!(default_branch(), "main");
+
+        // origin/HEAD names a branch with no local copy: use the remote ref.
+        git(&work, &["push", "--quiet", "origin", "main:trunk"]);
+        git(&work, &["remote", "set-head", "origin", "trunk"]);
+        assert_eq!(default_branch(), "origin/trunk");
+
+        git(&work, &["remote", "set-head", "origin", "--delete"]);
+        git(&work, &["branch", "-m", "main", "master"]);
+        assert_eq!(default_branch(), "master");
+        git(&work, &["checkout", "--quiet", "-b", "feature"]);
+        git(&work, &["branch", "-D", "master"]);
+        assert_eq!(default_branch(), "main");
+    }
+
+    #[test]
+    fn only_pushed_commits_are_on_the_remote() {
+        let work = repo_with_origin();
+        let _dir = InDir::new(&work);
+        let pushed = git(&work, &["rev-parse", "HEAD"]);
+        let local = crate::testutil::commit_file(&work, "local.txt", "not pushed\n");
+        assert!(on_remote(&pushed));
+        assert!(!on_remote(&local));
+    }
+}
@@ -0,0 +1,603 @@
+use crate::review::{DiffTotals, FileOverlay, OverlayLine, Report};
+
+const STYLE: &str = "\
+body { font: 14px/1.45 -apple-system, BlinkMacSystemFont, 'Segoe UI', sans-serif; margin: 0 auto; max-width: 1200px; padding: 1.5em; color: #1f2328; background: #fff; }
+h1 { font-size: 1.6em; margin-bottom: 0.2em; }
+h2 { font-size: 1.25em; border-bottom: 1px solid #d0d7de; padding-bottom: 0.3em; margin-top: 2em; }
+.meta, .empty { color: #59636e; }
+code, pre, table.code { font: 12px/1.5 ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; }
+details { border: 1px solid #d0d7de; border-radius: 6px; margin: 0.75em 0; }
+summary { cursor: pointer; padding: 0.5em 0.75em; background: #f6f8fa; border-radius: 6px; }
+details[open] > summary { border-bottom: 1px solid #d0d7de; border-radius: 6px 6px 0 0; }
+pre { margin: 0; padding: 0.75em; overflow-x: auto; }
+pre.message { font-family: inherit; white-space: pre-wrap; padding: 0 0 0.5em; }
+.stat { float: right; }
+.stat .add { color: #1a7f37; }
+.stat .del { color: #cf222e; }
+table.summary { border-collapse: collapse; margin: 0.5em 0 1em; }
+table.summary td, table.summary th { border: 1px solid #d0d7de; padding: 0.2em 0.75em; }
+table.summary td.n, table.summary th.n { text-align: right; }
+table.summary tr.total td { font-weight: 600; }
+table.summary .add { color: #1a7f37; }
+table.summary .del { color: #cf222e; }
+.scroll { overflow-x: auto; }
+table.code { border-collapse: collapse; width: 100%; }
+table.code td { padding: 0 0.6em; white-space: pre; vertical-align: top; }
+td.num { color: #8c959f; text-align: right; user-select: none; width: 1%; }
+td.sign { user-select: none; width: 1%; }
+tr.added { background: #dafbe1; }
+tr.removed { background: #ffebe9; }
+tr.added td.sign { color: #1a7f37; }
+tr.removed td.sign { color: #cf222e; }
+tr.hunk td { background: #ddf4ff; color: #59636e; }
+tr.note td { color: #59636e; font-style: italic; }
+tr.blame td { background: #ddf4ff; color: #59636e; font-size: 11px; }
+tr.skipped td { background: #f6f8fa; color: #59636e; }
+.kw { color: #cf222e; }
+.str { color: #0a3069; }
+.com { color: #6e7781; font-style: italic; }
+.num-lit { color: #0550ae; }
+@media (prefers-color-scheme: dark) {
+  body { color: #e6edf3; background: #0d1117; }
+  h2, details, details[open] > summary, table.summary td, table.summary th { border-color: #30363d; }
+  summary { background: #161b22; }
+  .meta, .empty, tr.hunk td, tr.note td, tr.blame td, tr.skipped td { color: #8d96a0; }
+  tr.skipped td { background: #161b22; }
+  tr.added { background: rgba(46, 160, 67, 0.15); }
+  tr.removed { background: rgba(248, 81, 73, 0.15); }
+  tr.hunk td, tr.blame td { background: rgba(56, 139, 253, 0.1); }
+  .kw { color: #ff7b72; }
+  .str { color: #a5d6ff; }
+  .com { color: #8b949e; }
+  .num-lit { color: #79c0ff; }
+}
+";
+
+/// A standalone HTML report: every file is a collapsible block with line numbers,
+/// added/removed lines tinted, and lightweight syntax highlighting. No external assets.
+pub fn render(report: &Report) -> String {
+    let mut out =
+        String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!(
+        "<title>Code Review: {}</title>\n<style>\n{}</style>\n</head>\n<body>\n",
+        escape(&report.branch),
+        STYLE
+    ));
+    out.push_str("<h1>Code Review Overlay</h1>\n");
+    out.push_str(&format!(
+        "<p class=\"meta\">Generated at {} on branch <code>{}</code></p>\n",
+        escape(&report.timestamp),
+        escape(&report.branch)
+    ));
+    out.push_str(&render_summary(report));
+    out.push_str(&format!(
+        "<details>\n<summary>Git Status</summary>\n<pre>{}</pre>\n</details>\n",
+        escape(report.status.trim_end())
+    ));
+
+    for section in &report.sections {
+        match &section.commit {
+            Some(commit) => {
+                out.push_str(&format!(
+                    "<h2><code>{}</code> {}</h2>\n",
+                    escape(&commit.sha[..7]),
+                    escape(&commit.subject)
+                ));
+                out.push_str(&format!(
+                    "<p class=\"meta\">{}, {}</p>\n",
+                    escape(&commit.author),
+                    escape(&commit.date)
+                ));
+                if !commit.body.is_empty() {
+                    out.push_str(&format!(
+                        "<pre class=\"message\">{}</pre>\n",
+                        escape(&commit.body)
+                    ));
+                }
+            }
+            None => out.push_str(&format!("<h2>{}</h2>\n", escape(&section.title))),
+        }
+        if section.files.is_empty() {
+            out.push_str("<p class=\"empty\">No changes detected in this scope.</p>\n");
+        }
+        for file in &section.files {
+            out.push_str(&render_file(file));
+        }
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Files changed with their insertions and deletions, one table per section.
+fn render_summary(report: &Report) -> String {
+    let count = |n: Option<usize>| n.map_or_else(|| "binary".to_string(), |n| n.to_string());
+    let mut out = String::from("<h2>Summary</h2>\n");
+    let mut total = DiffTotals::default();
+    for section in &report.sections {
+        if report.sections.len() > 1 {
+            out.push_str(&format!("<h3>{}</h3>\n", escape(&section.title)));
+        }
+        if section.stats.is_empty() {
+            out.push_str("<p class=\"empty\">No changes.</p>\n");
+            continue;
+        }
+        out.push_str(
+            "<table class=\"summary\">\n<tr><th>File</th><th class=\"n\">+</th>\
+             <th class=\"n\">&minus;</th></tr>\n",
+        );
+        for stat in &section.stats {
+            out.push_str(&format!(
+                "<tr><td><code>{}</code></td><td class=\"n add\">{}</td><td class=\"n del\">{}</td></tr>\n",
+                escape(&stat.path),
+                count(stat.added),
+                count(stat.removed)
+            ));
+        }
+        let totals = DiffTotals::of(&section.stats);
+        out.push_str(&format!(
+            "<tr class=\"total\"><td>{} file{}</td><td class=\"n add\">{}</td><td class=\"n del\">{}</td></tr>\n</table>\n",
+            totals.files,
+            if totals.files == 1 { "" } else { "s" },
+            totals.added,
+            totals.removed
+        ));
+        total.add(&totals);
+    }
+    if report.sections.len() > 1 {
+        out.push_str(&format!(
+            "<p class=\"meta\">Total: {}</p>\n",
+            total.describe()
+        ));
+    }
+    out
+}
+
+fn render_file(file: &FileOverlay) -> String {
+    let added = file
+        .lines
+        .iter()
+        .filter(|line| matches!(line, OverlayLine::Added(_)))
+        .count();
+    let removed = file
+        .lines
+        .iter()
+        .filter(|line| matches!(line, OverlayLine::Removed(_)))
+        .count();
+    let syntax = syntax(&file.lang);
+
+    let mut out = format!(
+        "<details class=\"file\" open>\n<summary><code>{}</code>\
+         <span class=\"stat\"><span class=\"add\">+{}</span> <span class=\"del\">&minus;{}</span></span></summary>\n\
+         <div class=\"scroll\"><table class=\"code\">\n",
+        escape(&file.path),
+        added,
+        removed
+    );
+    // Line numbers follow the current file; removed lines have none.
+    let mut number = 0;
+    for line in &file.lines {
+        let row = match line {
+            OverlayLine::Context(text) => {
+                number += 1;
+                row(
+                    "",
+                    &number.to_string(),
+                    " ",
+                    &highlight(text, syntax.as_ref()),
+                )
+            }
+            OverlayLine::Added(text) => {
+                number += 1;
+                row(
+                    "added",
+                    &number.to_string(),
+                    "+",
+                    &highlight(text, syntax.as_ref()),
+                )
+            }
+            OverlayLine::Removed(text) => {
+                row("removed", "", "-", &highlight(text, syntax.as_ref()))
+            }
+            OverlayLine::HunkStart(header) => row("hunk", "", "", &escape(header)),
+            OverlayLine::Link(url) => row(
+                "blame",
+                "",
+                "",
+                &format!("<a href=\"{0}\">{0}</a>", escape(url)),
+            ),
+            OverlayLine::Blame(summary) => row("blame", "", "", &escape(summary)),
+            OverlayLine::Skipped(count) => {
+                number += count;
+                let text = format!(
+                    "{} line{} unchanged",
+                    count,
+                    if *count == 1 { "" } else { "s" }
+                );
+                row("skipped", "", "&#8943;", &text)
+            }
+            OverlayLine::HunkEnd => continue,
+            OverlayLine::Note(text) => row("note", "", "", &escape(text.trim_matches('_'))),
+            OverlayLine::Raw(text) => {
+                let class = match text.chars().next() {
+                    Some('+') if !text.starts_with("+++") => "added",
+                    Some('-') if !text.starts_with("---") => "removed",
+                    _ => "",
+                };
+                row(class, "", "", &escape(text))
+            }
+        };
+        out.push_str(&row);
+    }
+    out.push_str("</table></div>\n</details>\n");
+    out
+}
+
+fn row(class: &str, number: &str, sign: &str, code: &str) -> String {
+    let class = if class.is_empty() {
+        String::new()
+    } else {
+        format!(" class=\"{}\"", class)
+    };
+    format!(
+        "<tr{}><td class=\"num\">{}</td><td class=\"sign\">{}</td><td>{}</td></tr>\n",
+        class, number, sign, code
+    )
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Just enough of a language to colour keywords, strings, comments, and numbers line by
+/// line; block comments and multi-line strings are not tracked.
+struct Syntax {
+    comments: &'static [&'static str],
+    quotes: &'static [char],
+    keywords: &'static [&'static str],
+}
+
+fn syntax(lang: &str) -> Option<Syntax> {
+    let (comments, quotes, keywords): (&[&str], &[char], &[&str]) = match lang {
+        "rs" => (
+            &["//"],
+            &['"'],
+            &[
+                "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else",
+                "enum", "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match",
+                "mod", "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct",
+                "super", "trait", "true", "type", "unsafe", "use", "where", "while",
+            ],
+        ),
+        "py" | "pyi" => (
+            &["#"],
+            &['"', '\''],
+            &[
+                "and", "as", "assert", "async", "await", "break", "class", "continue", "def",
+                "del", "elif", "else", "except", "False", "finally", "for", "from", "global", "if",
+                "import", "in", "is", "lambda", "None", "nonlocal", "not", "or", "pass", "raise",
+                "return", "self", "True", "try", "while", "with", "yield",
+            ],
+        ),
+        "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" => (
+            &["//"],
+            &['"', '\'', '`'],
+            &[
+                "as",
+                "async",
+                "await",
+                "break",
+                "case",
+                "catch",
+                "class",
+                "const",
+                "continue",
+                "default",
+                "delete",
+                "else",
+                "enum",
+                "export",
+                "extends",
+                "false",
+                "finally",
+                "for",
+                "from",
+                "function",
+                "if",
+                "implements",
+                "import",
+                "in",
+                "instanceof",
+                "interface",
+                "let",
+                "new",
+                "null",
+                "of",
+                "return",
+                "static",
+                "super",
+                "switch",
+                "this",
+                "throw",
+                "true",
+                "try",
+                "type",
+                "typeof",
+                "undefined",
+                "var",
+                "void",
+                "while",
+                "yield",
+            ],
+        ),
+        "go" => (
+            &["//"],
+            &['"', '`'],
+            &[
+                "break",
+                "case",
+                "chan",
+                "const",
+                "continue",
+                "default",
+                "defer",
+                "else",
+                "false",
+                "for",
+                "func",
+                "go",
+                "goto",
+                "if",
+                "import",
+                "interface",
+                "map",
+                "nil",
+                "package",
+                "range",
+                "return",
+                "select",
+                "struct",
+                "switch",
+                "true",
+                "type",
+                "var",
+            ],
+        ),
+        "c" | "h" | "cc" | "cpp" | "hpp" | "java" => (
+            &["//"],
+            &['"'],
+            &[
+                "break",
+                "case",
+                "catch",
+                "char",
+                "class",
+                "const",
+                "continue",
+                "default",
+                "do",
+                "double",
+                "else",
+                "enum",
+                "extends",
+                "false",
+                "final",
+                "float",
+                "for",
+                "if",
+                "import",
+                "include",
+                "int",
+                "long",
+                "namespace",
+                "new",
+                "null",
+                "nullptr",
+                "package",
+                "private",
+                "protected",
+                "public",
+                "return",
+                "short",
+                "static",
+                "struct",
+                "switch",
+                "this",
+                "throw",
+                "true",
+                "try",
+                "typedef",
+                "unsigned",
+                "void",
+                "while",
+            ],
+        ),
+        "sh" | "bash" | "zsh" => (
+            &["#"],
+            &['"', '\''],
+            &[
+                "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function",
+                "if", "in", "local", "return", "then", "while",
+            ],
+        ),
+        "toml" | "yml" | "yaml" => (&["#"], &['"', '\''], &["true", "false", "null"]),
+        "json" => (&[], &['"'], &["true", "false", "null"]),
+        _ => return None,
+    };
+    Some(Syntax {
+        comments,
+        quotes,
+        keywords,
+    })
+}
+
+/// `text` as escaped HTML with `kw`, `str`, `com`, and `num-lit` spans.
+fn highlight(text: &str, syntax: Option<&Syntax>) -> String {
+    let Some(syntax) = syntax else {
+        return escape(text);
+    };
+    let span =
+        |class: &str, token: &str| format!("<span class=\"{}\">{}</span>", class, escape(token));
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let len = if syntax
+            .comments
+            .iter()
+            .any(|marker| rest.starts_with(marker))
+        {
+            out.push_str(&span("com", rest));
+            rest.len()
+        } else if syntax.quotes.contains(&c) {
+            let len = string_len(rest, c);
+            out.push_str(&span("str", &rest[..len]));
+            len
+        } else if c.is_ascii_digit() {
+            let len = rest
+                .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '.' || ch == '_'))
+                .unwrap_or(rest.len());
+            out.push_str(&span("num-lit", &rest[..len]));
+            len
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest
+                .find(|ch: char| !(ch.is_alphanumeric() || ch == '_'))
+                .unwrap_or(rest.len());
+            let word = &rest[..len];
+            if syntax.keywords.contains(&word) {
+                out.push_str(&span("kw", word));
+            } else {
+                out.push_str(&escape(word));
+            }
+            len
+        } else {
+            out.push_str(&escape(&rest[..c.len_utf8()]));
+            c.len_utf8()
+        };
+        rest = &rest[len..];
+    }
+    out
+}
+
+/// Byte length of the string literal opening `text`, through its closing `quote` (or
+/// the end of the line when it does not close).
+fn string_len(text: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (index, c) in text.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return index + c.len_utf8();
+        }
+    }
+    text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::{CommitInfo, FileStat, Section};
+
+    #[test]
+    fn highlights_tokens_and_escapes() {
+        let rust = syntax("rs");
+        assert_eq!(
+            highlight("let s = \"a<b\"; // 1 < 2", rust.as_ref()),
+            "<span class=\"kw\">let</span> s = <span class=\"str\">&quot;a&lt;b&quot;</span>; \
+             <span class=\"com\">// 1 &lt; 2</span>"
+        );
+        assert_eq!(
+            highlight("x2 = 42", rust.as_ref()),
+            "x2 = <span class=\"num-lit\">42</span>"
+        );
+        let python = syntax("py");
+        assert_eq!(
+            highlight("s = 'it\\'s # not a comment'", python.as_ref()),
+            "s = <span class=\"str\">&#39;it\\&#39;s # not a comment&#39;</span>"
+        );
+        assert_eq!(highlight("<raw>", None), "&lt;raw&gt;");
+    }
+
+    #[test]
+    fn renders_collapsible_files_with_styled_lines() {
+        let report = Report {
+            timestamp: "2026-10-16 12:00:00Z".into(),
+            branch: "feat/<x>".into(),
+            status: "## feat/x\n".into(),
+            sections: vec![
+                Section {
+                    title: "Staged Changes".into(),
+                    commit: None,
+                    stats: vec![FileStat {
+                        path: "src/lib.rs".into(),
+                        added: Some(1),
+                        removed: Some(1),
+                    }],
+                    files: vec![FileOverlay {
+                        path: "src/lib.rs".into(),
+                        lang: "rs".into(),
+                        lines: vec![
+                            OverlayLine::Context("use std::fs;".into()),
+                            OverlayLine::HunkStart("@@ -2 +2 @@".into()),
+                            OverlayLine::Removed("fn old() {}".into()),
+                            OverlayLine::Added("fn new() {}".into()),
+                            OverlayLine::HunkEnd,
+                        ],
+                    }],
+                },
+                Section {
+                    title: "Unstaged Changes".into(),
+                    commit: None,
+                    stats: Vec::new(),
+                    files: Vec::new(),
+                },
+                Section {
+                    title: "abcdef1 feat: <b>".into(),
+                    commit: Some(CommitInfo {
+                        sha: "abcdef1234".into(),
+                        author: "Ada <ada@x>".into(),
+                        date: "2026-10-16".into(),
+                        subject: "feat: <b>".into(),
+                        body: "Why it changed.".into(),
+                    }),
+                    stats: Vec::new(),
+                    files: Vec::new(),
+                },
+            ],
+        };
+        let html = render(&report);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Code Review: feat/&lt;x&gt;</title>"));
+        assert!(html.contains(
+            "<summary><code>src/lib.rs</code><span class=\"stat\"><span class=\"add\">+1</span>"
+        ));
+        assert!(
+            html.contains("<tr class=\"removed\"><td class=\"num\"></td><td class=\"sign\">-</td>")
+        );
+        assert!(html.contains(
+            "<tr class=\"added\"><td class=\"num\">2</td><td class=\"sign\">+</td><td>\
+             <span class=\"kw\">fn</span> new() {}</td></tr>"
+        ));
+        assert!(html.contains("<tr class=\"hunk\">"));
+        assert!(html.contains(
+            "<tr class=\"total\"><td>1 file</td><td class=\"n add\">1</td><td class=\"n del\">1</td></tr>"
+        ));
+        assert!(html.contains("Total: 1 file changed, 1 insertion(+), 1 deletion(-)"));
+        assert!(html.contains("No changes detected in this scope."));
+        assert!(html.contains(
+            "<h2><code>abcdef1</code> feat: &lt;b&gt;</h2>\n<p class=\"meta\">Ada &lt;ada@x&gt;, \
+             2026-10-16</p>\n<pre class=\"message\">Why it changed.</pre>\n"
+        ));
+    }
+}
@@ -1,29 +1,37 @@
-use std::io::{BufRead, BufReader};
+use std::cell::OnceCell;
+use std::io::{BufRead, BufReader, IsTerminal};
 use std::path::{Path, PathBuf};
 use std::process::{Command as ProcessCommand, Stdio};
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime};
 use std::{fs, io};
 
 use anyhow::{Context, Result, anyhow, bail};
-use camino::Utf8PathBuf;
-use clap::Parser;
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::{Parser, ValueEnum};
 
 use crate::cli::{
-    Cli, Command, ConfigCommand, DockerBuildArgs, DockerCommand, DockerComposeCommand,
-    DockerComposeUpCommand, DockerComposeUpBuildArgs, DockerInitArgs, EnvArgs, EnvCommand,
-    GitCommand, InstallArgs, LanguageCommand, SetupCommand, StartArgs, Verb, VersionCommand,
+    Cli, Command, ConfigCommand, DebugCommand, ExportFormat, DockerBuildArgs, DockerCommand, DockerComposeCommand,
+    DockerComposeUpCommand, DockerComposeUpBuildArgs, DockerInitArgs, DoctorArgs, EnvArgs,
+    CommitArgs, EnvCommand, EnvExportFormat, EnvGenFormat, EnvImportFormat, EnvMergeStrategy, GitCommand, PrCommand, SecretBackend, InstallArgs, LanguageCommand, SetupCommand, StartArgs, Verb,
+    ProjectCommand, ReviewFormat, VersionCommand, WalkFormat, WorkspaceCommand,
 };
-use crate::config::{DevConfig, TaskUpdateMode};
-use crate::envfile;
-use crate::tasks::{CommandSpec, TaskIndex};
-use crate::{config, dockergen, gitops, scaffold, versioning};
+use crate::config::{ConfigValue, DevConfig, TaskUpdateMode};
+use crate::{envaudit, envconvert, envcrypt, envedit, envfile, envgen, envhistory, envmerge, envschema, envsync};
+use crate::secrets::SecretResolver;
+use crate::tasks::{CommandArg, CommandSpec, TaskDiff, TaskIndex};
+use crate::vars::Vars;
+use crate::{config, configlint, dockergen, gitcommit, gitops, gitpr, scaffold, toollock, versioning};
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum ConfigPathSource {
     Explicit,
+    /// Named by the `DEV_CONFIG` environment variable.
+    Env,
     Discovered,
     HomeDefault,
+    /// No config file was found; built-in pipelines are in effect.
+    BuiltIn,
 }
 
 fn config_root_dir(config_path: &Utf8PathBuf) -> PathBuf {
@@ -61,8 +69,10 @@ impl ConfigPathSource {
     fn as_str(&self) -> &'static str {
         match self {
             ConfigPathSource::Explicit => "explicit",
+            ConfigPathSource::Env => "DEV_CONFIG",
             ConfigPathSource::Discovered => "discovered",
             ConfigPathSource::HomeDefault => "home-default",
+            ConfigPathSource::BuiltIn => "built-in defaults",
         }
     }
 }
@@ -81,27 +91,73 @@ pub fn run(cli: Cli) -> Result<()> {
     let _ = ctx.no_color;
     let _ = ctx.verbose;
 
+    if cli.all_projects {
+        let target = workspace_target(&cli.command)?;
+        return handle_workspace(&ctx, &target);
+    }
+
     match cli.command {
         Command::Config { command } => handle_config_only(&ctx, command),
+        Command::Workspace {
+            command: WorkspaceCommand::Run { target },
+        } => handle_workspace(&ctx, &target),
+        Command::Project { command } => handle_project(&ctx, command),
         Command::Language {
             command: LanguageCommand::Set { name },
         } => handle_language_set(&ctx, name),
         Command::Setup { command, skip_installed, no_deps } => {
             handle_setup(&ctx, command, skip_installed, no_deps)
         }
-        Command::Review { output, include_working, main } => {
-            handle_review(&ctx, output, include_working, main)
-        }
-        Command::Walk {
-            directory,
+        Command::Doctor(args) => handle_doctor(&ctx, args),
+        Command::CheckTools { update } => handle_check_tools(&ctx, update),
+        Command::Debug { command } => handle_debug(&ctx, command),
+        Command::Completions { shell } => write_stdout(&crate::completions::script(shell)),
+        Command::Review {
             output,
             format,
-            max_depth,
-            no_content,
-            extensions,
-            include_hidden,
-        } => handle_walk(
-            &ctx,
+            include_working,
+            no_include_working,
+            include_untracked,
+            blame,
+            context,
+            diff_only,
+            main,
+            base,
+            commits,
+            max_tokens,
+            prompt,
+            clipboard,
+        } => {
+            let defaults = review_config(&ctx)?;
+            let opts = crate::review::ReviewOptions {
+                include_working: !no_include_working
+                    && (include_working || defaults.include_working.unwrap_or(false)),
+                include_untracked,
+                blame,
+                context: if diff_only { Some(0) } else { context },
+                base: base.or_else(|| {
+                    main.then(|| defaults.base.clone().unwrap_or_else(crate::review::default_branch))
+                }),
+                commits,
+                exclude: defaults.exclude.clone().unwrap_or_default(),
+                max_file_bytes: match defaults.max_file_bytes {
+                    Some(0) => None,
+                    limit => Some(limit.unwrap_or(crate::review::DEFAULT_MAX_FILE_BYTES)),
+                },
+            };
+            let format = match (format, defaults.format.as_deref()) {
+                (Some(format), _) => format,
+                (None, Some(name)) => ReviewFormat::from_str(name, true)
+                    .map_err(|_| {
+                        anyhow!("unknown [review] format `{}` (expected md, html, or json)", name)
+                    })?,
+                (None, None) => ReviewFormat::Md,
+            };
+            let prompt = review_prompt(prompt, &defaults, format)?;
+            let output = output.or_else(|| defaults.output.map(PathBuf::from));
+            handle_review(&ctx, output, format, opts, max_tokens, prompt, clipboard)
+        }
+        Command::Walk {
             directory,
             output,
             format,
@@ -109,9 +165,44 @@ pub fn run(cli: Cli) -> Result<()> {
             no_content,
             extensions,
             include_hidden,
-        ),
+            line_numbers,
+            include,
+            exclude,
+            since,
+            record_hashes,
+            clipboard,
+            max_tokens,
+            split_size,
+        } => {
+            let since = since
+                .as_deref()
+                .map(|value| crate::walk::Since::resolve(&directory, value))
+                .transpose()?;
+            let opts = crate::walk::WalkOptions {
+                max_depth: max_depth as usize,
+                include_content: !no_content,
+                extensions,
+                ignore_hidden: !include_hidden,
+                include,
+                exclude,
+                since,
+                record_hashes,
+                line_numbers,
+            };
+            let output = WalkOutput {
+                path: output,
+                format,
+                max_tokens,
+                split_size,
+                clipboard,
+            };
+            handle_walk(&ctx, directory, opts, output)
+        }
         other => {
-            let state = AppState::new(ctx)?;
+            let mut state = AppState::new(ctx)?;
+            if let Command::Env(args) = &other {
+                state.env_profile = args.env_profile.clone();
+            }
             handle_with_state(&state, other)
         }
     }
@@ -130,6 +221,8 @@ fn handle_with_state(state: &AppState, command: Command) -> Result<()> {
         Command::Check => handle_verb(state, Verb::Check),
         Command::Ci => handle_verb(state, Verb::Ci),
         Command::All { verb } => handle_all(state, verb),
+        Command::Workspace { .. } => unreachable!("workspace commands handled earlier"),
+        Command::Project { .. } => unreachable!("project commands handled earlier"),
         Command::Install(args) => handle_install(state, args),
         Command::Language { command } => handle_language(state, command),
         Command::Git { command } => handle_git(state, command),
@@ -138,6 +231,10 @@ fn handle_with_state(state: &AppState, command: Command) -> Result<()> {
         Command::Docker { command } => handle_docker(state, command),
         Command::Config { .. } => unreachable!("config commands handled earlier"),
         Command::Setup { .. } => unreachable!("setup commands handled earlier"),
+        Command::Doctor(_) => unreachable!("doctor handled earlier"),
+        Command::CheckTools { .. } => unreachable!("check-tools handled earlier"),
+        Command::Debug { .. } => unreachable!("debug commands handled earlier"),
+        Command::Completions { .. } => unreachable!("completions handled earlier"),
         Command::Review { .. } => unreachable!("review commands handled earlier"),
         Command::Walk { .. } => unreachable!("walk commands handled earlier"),
         Command::External(extra) => {
@@ -205,7 +302,7 @@ fn docker_develop(state: &AppState, args: crate::cli::DockerDevelopArgs) -> Resu
 }
 
 fn docker_init(state: &AppState, args: DockerInitArgs) -> Result<()> {
-    dockergen::init(&args, state.ctx.dry_run)
+    dockergen::init(&args, &state.vars, state.config.env.as_ref(), state.ctx.dry_run)
 }
 
 fn docker_build(state: &AppState, args: DockerBuildArgs) -> Result<()> {
@@ -349,7 +446,8 @@ fn normalize_external(cli: Cli) -> Result<Cli> {
 }
 
 fn handle_list(state: &AppState) -> Result<()> {
-    if state.tasks.is_empty() {
+    let tasks = state.tasks()?;
+    if tasks.is_empty() {
         println!(
             "No tasks defined in {} ({}).",
             state.config_path,
@@ -363,15 +461,18 @@ fn handle_list(state: &AppState) -> Result<()> {
         state.config_path,
         state.config_source.as_str()
     );
-    for name in state.tasks.task_names() {
-        println!("  - {}", name);
+    for name in tasks.task_names() {
+        match tasks.description(name) {
+            Some(description) => println!("  - {} — {}", name, description),
+            None => println!("  - {}", name),
+        }
     }
     Ok(())
 }
 
 fn handle_run(state: &AppState, task: &str) -> Result<()> {
     println!("Running task `{}`", task);
-    let commands = state.tasks.flatten(task)?;
+    let commands = state.tasks()?.flatten(task)?;
     execute_commands(state, task, &commands)
 }
 
@@ -457,6 +558,201 @@ fn handle_all(state: &AppState, verb: Verb) -> Result<()> {
     Ok(())
 }
 
+fn handle_project(ctx: &CliContext, command: ProjectCommand) -> Result<()> {
+    let resolved = ctx.resolve_config_path()?;
+    let config_path = resolved.path;
+    match command {
+        ProjectCommand::List => {
+            if !config_path.exists() {
+                println!("No config found at {}; no projects configured.", config_path);
+                return Ok(());
+            }
+            let config = ctx.load_config(&config_path)?;
+            let Some(projects) = config.projects.as_ref().filter(|p| !p.is_empty()) else {
+                println!("No projects configured in {}.", config_path);
+                println!("Add one with `dev project add <name> --chdir <dir> --language <lang>`.");
+                return Ok(());
+            };
+            let current = detect_project(&config, &config_root_dir(&config_path));
+            println!("Projects in {} ({}):", config_path, resolved.source.as_str());
+            for (name, spec) in projects {
+                let mut notes = Vec::new();
+                if config.default_project.as_deref() == Some(name.as_str()) {
+                    notes.push("default");
+                }
+                if current.as_deref() == Some(name.as_str()) {
+                    notes.push("current");
+                }
+                let notes = if notes.is_empty() {
+                    String::new()
+                } else {
+                    format!(" [{}]", notes.join(", "))
+                };
+                println!(
+                    "  - {} (chdir: {}, language: {}){}",
+                    name,
+                    spec.chdir.as_deref().unwrap_or("."),
+                    spec.language
+                        .as_deref()
+                        .or(config.default_language.as_deref())
+                        .unwrap_or("<none>"),
+                    notes
+                );
+            }
+            Ok(())
+        }
+        ProjectCommand::Add {
+            name,
+            chdir,
+            language,
+        } => {
+            let raw = if config_path.exists() {
+                fs::read_to_string(&config_path)
+                    .with_context(|| format!("reading config {}", config_path))?
+            } else {
+                String::new()
+            };
+            let mut doc: toml_edit::DocumentMut = raw
+                .parse()
+                .with_context(|| format!("parsing config {}", config_path))?;
+            config::upsert_project(&mut doc, &name, chdir.as_deref(), language.as_deref())?;
+
+            if let Some(chdir) = &chdir {
+                let dir = config_root_dir(&config_path).join(chdir);
+                if !dir.is_dir() {
+                    println!("[warn] {} does not exist yet", dir.display());
+                }
+            }
+            if ctx.dry_run {
+                println!("[dry-run] would write project `{}` to {}", name, config_path);
+                return Ok(());
+            }
+            if let Some(parent) = config_path.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("creating directory {}", parent))?;
+            }
+            fs::write(&config_path, doc.to_string())
+                .with_context(|| format!("writing config {}", config_path))?;
+            println!("Wrote project `{}` to {}", name, config_path);
+            Ok(())
+        }
+        ProjectCommand::Rm { name } => {
+            let mut doc = read_config_document(&config_path)?;
+            let cleared_default = config::remove_project(&mut doc, &name)?;
+            if cleared_default {
+                println!("[warn] `{}` was the default_project; default_project is now unset", name);
+            }
+            if ctx.dry_run {
+                println!("[dry-run] would remove project `{}` from {}", name, config_path);
+                return Ok(());
+            }
+            fs::write(&config_path, doc.to_string())
+                .with_context(|| format!("writing config {}", config_path))?;
+            println!("Removed project `{}` from {}", name, config_path);
+            Ok(())
+        }
+    }
+}
+
+/// Map a command given with `--all-projects` onto a `dev workspace run` target.
+fn workspace_target(command: &Command) -> Result<String> {
+    let verb = match command {
+        Command::Fmt => Verb::Fmt,
+        Command::Lint => Verb::Lint,
+        Command::TypeCheck => Verb::TypeCheck,
+        Command::Test => Verb::Test,
+        Command::Fix => Verb::Fix,
+        Command::Check => Verb::Check,
+        Command::Ci => Verb::Ci,
+        Command::Run { task } => return Ok(task.clone()),
+        Command::Workspace {
+            command: WorkspaceCommand::Run { target },
+        } => return Ok(target.clone()),
+        _ => bail!("--all-projects only applies to pipeline verbs and `dev run <task>`"),
+    };
+    Ok(verb.as_str().to_owned())
+}
+
+struct ProjectOutcome {
+    name: String,
+    language: String,
+    elapsed: Duration,
+    error: Option<String>,
+}
+
+fn handle_workspace(ctx: &CliContext, target: &str) -> Result<()> {
+    let resolved = ctx.resolve_config_path()?;
+    let config = ctx.load_config(&resolved.path)?;
+    let projects: Vec<(String, String)> = config
+        .projects
+        .iter()
+        .flatten()
+        .map(|(name, spec)| {
+            let language = ctx
+                .language
+                .clone()
+                .or_else(|| spec.language.clone())
+                .or_else(|| config.default_language.clone())
+                .unwrap_or_else(|| "<none>".to_owned());
+            (name.clone(), language)
+        })
+        .collect();
+    if projects.is_empty() {
+        bail!(
+            "no projects configured in {}; add `[projects.<name>]` entries with chdir/language",
+            resolved.path
+        );
+    }
+
+    let verb = Verb::from_str(target, true).ok();
+    let origin = std::env::current_dir().context("reading current directory")?;
+    let mut outcomes = Vec::new();
+    for (name, language) in projects {
+        println!("==> Project `{}` ({})", name, language);
+        // Pin the config file so every project resolves against the same workspace config.
+        let project_ctx = CliContext {
+            project: Some(name.clone()),
+            file: Some(resolved.path.clone().into_std_path_buf()),
+            ..ctx.clone()
+        };
+        let start = Instant::now();
+        let result = AppState::new(project_ctx).and_then(|state| match verb {
+            Some(verb) => handle_verb(&state, verb),
+            None => handle_run(&state, target),
+        });
+        std::env::set_current_dir(&origin)
+            .with_context(|| format!("returning to {}", origin.display()))?;
+        let error = result.err().map(|err| format!("{:#}", err));
+        if let Some(error) = &error {
+            println!("[error] {}", error);
+        }
+        outcomes.push(ProjectOutcome {
+            name,
+            language,
+            elapsed: start.elapsed(),
+            error,
+        });
+    }
+
+    let name_width = outcomes.iter().map(|o| o.name.len()).max().unwrap_or(0);
+    let language_width = outcomes.iter().map(|o| o.language.len()).max().unwrap_or(0);
+    println!();
+    println!("Workspace `{}` results:", target);
+    for outcome in &outcomes {
+        let marker = if outcome.error.is_some() { "[fail]" } else { "[ok]" };
+        println!(
+            "  {:<6} {:<name_width$}  {:<language_width$}  {:.2?}",
+            marker, outcome.name, outcome.language, outcome.elapsed
+        );
+    }
+
+    let failed = outcomes.iter().filter(|o| o.error.is_some()).count();
+    if failed > 0 {
+        bail!("{} of {} projects failed `{}`", failed, outcomes.len(), target);
+    }
+    Ok(())
+}
+
 fn handle_install(state: &AppState, args: InstallArgs) -> Result<()> {
     let language = state.effective_language(args.language).ok_or_else(|| {
         anyhow!("no language selected; pass `dev install <language>` or configure default_language")
@@ -483,13 +779,12 @@ fn handle_install(state: &AppState, args: InstallArgs) -> Result<()> {
             for command in commands {
                 run_external_command(&command)?;
             }
-            Ok(())
         }
         _ => {
             println!("No provisioning commands configured for `{}`.", language);
-            Ok(())
         }
     }
+    record_tool_lock(&state.ctx)
 }
 
 fn handle_language(state: &AppState, command: LanguageCommand) -> Result<()> {
@@ -501,50 +796,174 @@ fn handle_language(state: &AppState, command: LanguageCommand) -> Result<()> {
 fn handle_git(state: &AppState, command: GitCommand) -> Result<()> {
     match command {
         GitCommand::BranchCreate(args) => gitops::branch_create(&args, state.ctx.dry_run),
-        GitCommand::BranchFinalize(args) => gitops::branch_finalize(&args, state.ctx.dry_run),
-        GitCommand::ReleasePr(args) => gitops::release_pr(&args, state.ctx.dry_run, &state.config),
+        GitCommand::BranchFinalize(args) => gitops::branch_finalize(&args, state.ctx.dry_run, &state.config),
+        GitCommand::ReleasePr(args) => {
+            gitops::release_pr(&args, state.ctx.dry_run, &state.config, &state.vars)
+        }
+        GitCommand::Commit(args) => git_commit(state, args),
+        GitCommand::Release(args) => {
+            let scope = versioning::Scope::resolve(&state.config, state.project.as_deref())?;
+            versioning::publish_release(&state.config, &scope, &args, state.ctx.dry_run)
+        }
+        GitCommand::Sync(args) => gitops::sync(&args, state.ctx.dry_run, &state.config),
+        GitCommand::Cleanup(args) => gitops::cleanup(&args, state.ctx.dry_run, &state.config),
+        GitCommand::Undo(args) => gitops::undo(&args, state.ctx.dry_run),
+        GitCommand::Pr { command } => match command {
+            PrCommand::Create(args) => {
+                gitops::pr_create(&args, state.ctx.dry_run, &state.config, &state.vars)
+            }
+            PrCommand::Status { format } => gitpr::status(format),
+            PrCommand::List { format, limit } => gitpr::list(limit, format),
+        },
+    }
+}
+
+fn git_commit(state: &AppState, args: CommitArgs) -> Result<()> {
+    // Check the repository before prompting so a message is never typed in vain.
+    let sign = args.sign || state.config.git.as_ref().and_then(|git| git.sign).unwrap_or(false);
+    if !state.ctx.dry_run {
+        gitcommit::ensure_staged()?;
+        if sign {
+            gitops::ensure_signing_configured()?;
+        }
+    }
+    let message = gitcommit::compose(&args)?;
+    let problems = message.validate();
+    if !problems.is_empty() {
+        for problem in &problems {
+            eprintln!("  - {}", problem);
+        }
+        bail!("commit message `{}` is not a valid conventional commit", message.header());
     }
+    if args.lint {
+        handle_verb(state, Verb::Lint).context("lint pre-check failed; nothing committed")?;
+    }
+    gitcommit::commit(&message, sign, state.ctx.dry_run)?;
+    if !state.ctx.dry_run {
+        println!("Committed `{}`.", message.header());
+    }
+    Ok(())
 }
 
 fn handle_version(state: &AppState, command: VersionCommand) -> Result<()> {
-    versioning::handle(&state.config, state.ctx.dry_run, command)
+    let tasks = |tasks: &[String]| run_task_sequence(state, tasks);
+    let pipeline = |name: &str| {
+        let verb = Verb::from_str(name, true).map_err(|_| {
+            anyhow!(
+                "unknown pipeline `{}` in [git] require_ci (expected fmt, lint, type, test, fix, check, or ci)",
+                name
+            )
+        })?;
+        handle_verb(state, verb)
+    };
+    let runner = versioning::Runner {
+        tasks: &tasks,
+        pipeline: &pipeline,
+    };
+    versioning::handle(
+        &state.config,
+        state.project.as_deref(),
+        state.ctx.dry_run,
+        command,
+        &runner,
+    )
 }
 
 fn handle_env(state: &AppState, args: EnvArgs) -> Result<()> {
     match args.command {
-        Some(EnvCommand::List) | None => env_list(state, args.raw),
+        Some(EnvCommand::List { explain, describe }) => env_list(state, args.raw, explain, describe),
+        None => env_list(state, args.raw, false, false),
         Some(EnvCommand::Get { key }) => env_get(state, &key),
-        Some(EnvCommand::Add { key, value }) => env_add(state, &key, &value),
+        Some(EnvCommand::Add { key, value, desc }) => env_add(state, &key, &value, desc.as_deref()),
         Some(EnvCommand::Rm { key }) => env_remove(state, &key),
+        Some(EnvCommand::Rename { old, new, find_refs }) => env_rename(state, &old, &new, find_refs),
         Some(EnvCommand::Profiles) => env_profiles(state),
-        Some(EnvCommand::Switch { profile }) => env_switch(state, &profile),
+        Some(EnvCommand::Switch { name }) => env_switch(state, &name),
         Some(EnvCommand::Save { name }) => env_save(state, &name),
         Some(EnvCommand::Check) => env_check(state),
         Some(EnvCommand::Init) => env_init(state),
         Some(EnvCommand::Template) => env_template(state),
-        Some(EnvCommand::Diff { reference }) => env_diff(state, &reference),
+        Some(EnvCommand::Diff {
+            reference,
+            values: true,
+            raw,
+        }) => env_diff_values(state, &reference, raw || args.raw),
+        Some(EnvCommand::Diff { reference, .. }) => env_diff(state, &reference),
         Some(EnvCommand::Sync { reference }) => env_sync(state, &reference),
+        Some(EnvCommand::Encrypt {
+            recipients,
+            sops,
+            remove_plain,
+        }) => env_encrypt(state, recipients, sops, remove_plain),
+        Some(EnvCommand::Gen {
+            key,
+            length,
+            format,
+        }) => env_gen(state, &key, length, format),
+        Some(EnvCommand::Fmt { file, check }) => env_fmt(state, file, check),
+        Some(EnvCommand::Merge {
+            files,
+            out,
+            strategy,
+        }) => env_merge(state, files, out, strategy),
+        Some(EnvCommand::Watch {
+            restart,
+            task,
+            interval,
+        }) => env_watch(state, restart, task, Duration::from_secs(interval.max(1))),
+        Some(EnvCommand::Audit) => env_audit(state),
+        Some(EnvCommand::Edit) => env_edit(state),
+        Some(EnvCommand::History) => env_history(state),
+        Some(EnvCommand::Rollback { n }) => env_rollback(state, n),
+        Some(EnvCommand::Exec { command }) => env_exec(state, &command),
+        Some(EnvCommand::Export { format }) => env_export(state, format),
+        Some(EnvCommand::Import {
+            file,
+            format,
+            overwrite,
+        }) => env_import(state, file, format, overwrite),
+        Some(EnvCommand::Pull { backend, path }) => env_pull(state, backend, path),
+        Some(EnvCommand::Push { backend, path }) => env_push(state, backend, path),
+        Some(EnvCommand::Decrypt { identity, force }) => env_decrypt(state, identity, force),
     }
 }
 
-fn env_list(state: &AppState, raw: bool) -> Result<()> {
+fn env_list(state: &AppState, raw: bool, explain: bool, describe: bool) -> Result<()> {
     let env_path = state.env_path()?;
-    let env = envfile::EnvFile::load(&env_path)?;
-    let mut entries: Vec<_> = env.entries().collect();
-    entries.sort_by(|a, b| a.0.cmp(b.0));
+    let layers = state.env_layers(&env_path)?;
+    let mut entries = envfile::resolve_layers(&layers)?;
+    entries.sort_by(|a, b| a.key.cmp(&b.key));
 
     if entries.is_empty() {
-        println!("No environment variables defined in {}.", env.path());
+        println!("No environment variables defined in {}.", env_path);
         return Ok(());
     }
 
-    println!("Environment variables in {}:", env.path());
-    for (key, value) in entries {
-        if raw {
-            println!("  {}={}", key, value);
+    let sources: Vec<String> = layers
+        .iter()
+        .filter(|layer| layer.exists() || envcrypt::encrypted_path(layer).exists())
+        .map(|layer| {
+            if layer.exists() {
+                layer.to_string()
+            } else {
+                format!("{} (decrypted)", envcrypt::encrypted_path(layer))
+            }
+        })
+        .collect();
+    println!("Environment variables in {}:", sources.join(" + "));
+    for entry in entries {
+        let shown = if raw || !state.env_masked(&entry.key) || entry.value.is_empty() {
+            entry.value.as_str()
         } else {
-            let mask = if value.is_empty() { "" } else { "*****" };
-            println!("  {}={}", key, mask);
+            "*****"
+        };
+        if explain {
+            println!("  {}={}  ({})", entry.key, shown, entry.source.file_name().unwrap_or_default());
+        } else {
+            println!("  {}={}", entry.key, shown);
+        }
+        if describe && let Some(description) = &entry.description {
+            println!("      {}", description);
         }
     }
     Ok(())
@@ -552,23 +971,25 @@ fn env_list(state: &AppState, raw: bool) -> Result<()> {
 
 fn env_get(state: &AppState, key: &str) -> Result<()> {
     let env_path = state.env_path()?;
-    let env = envfile::EnvFile::load(&env_path)?;
-
-    for (k, v) in env.entries() {
-        if k == key {
-            println!("{}", v);
-            return Ok(());
+    let layers = state.env_layers(&env_path)?;
+    match envfile::resolve_layers(&layers)?.into_iter().find(|entry| entry.key == key) {
+        Some(entry) => {
+            println!("{}", entry.value);
+            Ok(())
         }
+        None => bail!("key `{}` not found in {}", key, env_path),
     }
-
-    bail!("key `{}` not found in {}", key, env.path())
 }
 
-fn env_add(state: &AppState, key: &str, value: &str) -> Result<()> {
+fn env_add(state: &AppState, key: &str, value: &str, desc: Option<&str>) -> Result<()> {
     let env_path = state.env_path()?;
     let mut env = envfile::EnvFile::load(&env_path)?;
     let existed = env.entries().any(|(existing, _)| existing == key);
     env.upsert(key, value);
+    if let Some(desc) = desc {
+        env.set_description(key, desc);
+    }
+    envhistory::snapshot(&env_path, "add")?;
     env.save()?;
 
     let target = env.path();
@@ -584,6 +1005,7 @@ fn env_remove(state: &AppState, key: &str) -> Result<()> {
     let env_path = state.env_path()?;
     let mut env = envfile::EnvFile::load(&env_path)?;
     if env.remove(key) {
+        envhistory::snapshot(&env_path, "rm")?;
         env.save()?;
         println!("Removed {} from {}", key, env.path());
     } else {
@@ -592,6 +1014,53 @@ fn env_remove(state: &AppState, key: &str) -> Result<()> {
     Ok(())
 }
 
+fn env_rename(state: &AppState, old: &str, new: &str, find_refs: bool) -> Result<()> {
+    let env_path = state.env_path()?;
+    let mut env = envfile::EnvFile::load(&env_path)?;
+    if !env.rename(old, new)? {
+        bail!("key `{}` not found in {}", old, env.path());
+    }
+    envhistory::snapshot(&env_path, "rename")?;
+    env.save()?;
+    println!("Renamed {} to {} in {}", old, new, env.path());
+
+    if find_refs {
+        let dir = env_path
+            .parent()
+            .ok_or_else(|| anyhow!("cannot determine parent directory of {}", env_path))?;
+        let refs = env_key_references(dir, old)?;
+        if refs.is_empty() {
+            println!("[ok] No references to {} in tracked files.", old);
+        } else {
+            println!("[warn] {} still referenced; update these to {}:", old, new);
+            for line in refs {
+                println!("  {}", line);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `path:line:text` for whole-word matches of `key` in git-tracked files under
+/// `dir`, skipping `.env*` files themselves.
+fn env_key_references(dir: &Utf8Path, key: &str) -> Result<Vec<String>> {
+    let output = ProcessCommand::new("git")
+        .current_dir(dir)
+        .args(["grep", "-n", "-I", "-w", "-F", "-e", key, "--", ".", ":(exclude,glob)**/.env*"])
+        .output()
+        .context("running git grep")?;
+    // git grep exits 1 when nothing matches.
+    match output.status.code() {
+        Some(0) => Ok(String::from_utf8_lossy(&output.stdout).lines().map(str::to_owned).collect()),
+        Some(1) => Ok(Vec::new()),
+        _ => bail!(
+            "git grep failed in {}: {}",
+            dir,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+    }
+}
+
 fn env_profiles(state: &AppState) -> Result<()> {
     let env_path = state.env_path()?;
     let dir = env_path
@@ -603,7 +1072,13 @@ fn env_profiles(state: &AppState) -> Result<()> {
         let entry = entry?;
         let name = entry.file_name();
         let name = name.to_string_lossy();
-        if name.starts_with(".env.") && !name.ends_with(".example") {
+        let layered_local = state.env_layered() && name == ".env.local";
+        if name.starts_with(".env.")
+            && !name.ends_with(".example")
+            && !name.ends_with(".enc")
+            && name != envschema::SCHEMA_FILENAME
+            && !layered_local
+        {
             let profile = name.strip_prefix(".env.").unwrap_or(&name);
             profiles.push(profile.to_owned());
         }
@@ -616,9 +1091,18 @@ fn env_profiles(state: &AppState) -> Result<()> {
     }
 
     profiles.sort();
+    let active = if state.env_layered() {
+        envfile::active_profile(&env_path)?
+    } else {
+        None
+    };
     println!("Available profiles in {}:", dir);
     for profile in profiles {
-        println!("  - {}", profile);
+        if active.as_deref() == Some(profile.as_str()) {
+            println!("  - {} (active)", profile);
+        } else {
+            println!("  - {}", profile);
+        }
     }
     Ok(())
 }
@@ -638,6 +1122,16 @@ fn env_switch(state: &AppState, profile: &str) -> Result<()> {
         );
     }
 
+    if state.env_layered() {
+        let marker = envfile::set_active_profile(&env_path, profile)?;
+        println!(
+            "Switched to profile `{}`: {} now layers over {} (recorded in {})",
+            profile, profile_path, env_path, marker
+        );
+        return Ok(());
+    }
+
+    envhistory::snapshot(&env_path, "switch")?;
     fs::copy(profile_path.as_std_path(), env_path.as_std_path())
         .with_context(|| format!("copying {} to {}", profile_path, env_path))?;
 
@@ -665,8 +1159,8 @@ fn env_save(state: &AppState, name: &str) -> Result<()> {
 
 fn env_check(state: &AppState) -> Result<()> {
     let env_path = state.env_path()?;
-    let env = envfile::EnvFile::load(&env_path)?;
-    let entries: std::collections::HashSet<_> = env.entries().map(|(k, _)| k.to_owned()).collect();
+    let resolved = state.env_entries(&env_path)?;
+    let entries: std::collections::HashMap<String, String> = resolved.iter().cloned().collect();
 
     let required = state.config.env.as_ref().and_then(|e| e.required.as_ref());
     let optional = state.config.env.as_ref().and_then(|e| e.optional.as_ref());
@@ -677,29 +1171,32 @@ fn env_check(state: &AppState) -> Result<()> {
 
     if let Some(required) = required {
         for key in required {
-            if !entries.contains(key.as_str()) {
-                missing_required.push(key);
-            } else {
-                let value = env.entries().find(|(k, _)| k == key).map(|(_, v)| v).unwrap_or("");
-                if value.is_empty() {
-                    empty_required.push(key);
-                }
+            match entries.get(key) {
+                None => missing_required.push(key),
+                Some(value) if value.is_empty() => empty_required.push(key),
+                Some(_) => {}
             }
         }
     }
 
     if let Some(optional) = optional {
         for key in optional {
-            if !entries.contains(key.as_str()) {
+            if !entries.contains_key(key) {
                 missing_optional.push(key);
             }
         }
     }
 
-    println!("Checking {} against config requirements...", env_path);
+    let schema = envschema::load(&env_path)?;
+    match &schema {
+        Some(schema) => println!("Checking {} against config requirements and {}...", env_path, schema.path),
+        None => println!("Checking {} against config requirements...", env_path),
+    }
 
     if missing_required.is_empty() && empty_required.is_empty() {
-        println!("[ok] All required keys present and non-empty.");
+        if required.is_some() || schema.is_none() {
+            println!("[ok] All required keys present and non-empty.");
+        }
     } else {
         if !missing_required.is_empty() {
             println!("[error] Missing required keys:");
@@ -722,7 +1219,23 @@ fn env_check(state: &AppState) -> Result<()> {
         }
     }
 
-    if !missing_required.is_empty() || !empty_required.is_empty() {
+    let mut schema_errors = 0;
+    if let Some(schema) = &schema {
+        let problems = schema.validate(&resolved);
+        if problems.is_empty() {
+            println!("[ok] Matches {}.", schema.path);
+        }
+        for problem in &problems {
+            if problem.is_error() {
+                schema_errors += 1;
+                println!("[error] {}", problem);
+            } else {
+                println!("[warn] {}", problem);
+            }
+        }
+    }
+
+    if !missing_required.is_empty() || !empty_required.is_empty() || schema_errors > 0 {
         bail!("environment validation failed");
     }
 
@@ -736,21 +1249,13 @@ fn env_init(state: &AppState) -> Result<()> {
         return Ok(());
     }
 
-    let dir = env_path
-        .parent()
-        .ok_or_else(|| anyhow!("cannot determine parent directory of {}", env_path))?;
-
-    let example_path = dir.join(".env.example");
-    if !example_path.exists() {
-        bail!(
-            "no .env.example found at {}. Create one first or use `dev env template` to generate it.",
-            example_path
-        );
+    if let Some(schema) = envschema::load(&env_path)? {
+        fs::write(&env_path, schema.render_env()).with_context(|| format!("writing {}", env_path))?;
+        println!("Initialized .env from {} at {}", schema.path, env_path);
+        return Ok(());
     }
 
-    fs::copy(example_path.as_std_path(), env_path.as_std_path())
-        .with_context(|| format!("copying {} to {}", example_path, env_path))?;
-
+    let example_path = envfile::init_from_example(&env_path, Some(&state.vars))?;
     println!("Initialized .env from {} at {}", example_path, env_path);
     Ok(())
 }
@@ -770,6 +1275,9 @@ fn env_template(state: &AppState) -> Result<()> {
     output.push_str("# Fill in the values for your environment\n\n");
 
     for (key, _) in env.entries() {
+        if let Some(description) = env.description(key) {
+            output.push_str(&format!("# {}\n", description));
+        }
         output.push_str(&format!("{}=\n", key));
     }
 
@@ -789,11 +1297,7 @@ fn env_diff(state: &AppState, reference: &str) -> Result<()> {
         .parent()
         .ok_or_else(|| anyhow!("cannot determine parent directory of {}", env_path))?;
 
-    let ref_path = dir.join(reference);
-    if !ref_path.exists() {
-        bail!("reference file not found at {}", ref_path);
-    }
-
+    let ref_path = env_reference_path(dir, reference)?;
     let ref_env = envfile::EnvFile::load(&ref_path)?;
     let ref_keys: std::collections::HashSet<_> = ref_env.entries().map(|(k, _)| k.to_owned()).collect();
 
@@ -824,6 +1328,81 @@ fn env_diff(state: &AppState, reference: &str) -> Result<()> {
     Ok(())
 }
 
+/// A reference file next to `.env`, or the `.env.<name>` profile when `reference` names one.
+fn env_reference_path(dir: &Utf8Path, reference: &str) -> Result<Utf8PathBuf> {
+    let path = dir.join(reference);
+    if path.exists() {
+        return Ok(path);
+    }
+    let profile = dir.join(format!(".env.{}", reference));
+    if profile.exists() {
+        return Ok(profile);
+    }
+    bail!("reference file not found at {} (or profile {})", path, profile);
+}
+
+fn env_diff_values(state: &AppState, reference: &str, raw: bool) -> Result<()> {
+    let env_path = state.env_path()?;
+    let dir = env_path
+        .parent()
+        .ok_or_else(|| anyhow!("cannot determine parent directory of {}", env_path))?;
+    let ref_path = env_reference_path(dir, reference)?;
+
+    let load = |path: &Utf8Path| -> Result<std::collections::BTreeMap<String, String>> {
+        Ok(envfile::EnvFile::load(path)?
+            .entries()
+            .map(|(key, value)| (key.to_owned(), envfile::unquote(value)))
+            .collect())
+    };
+    let current = load(&env_path)?;
+    let other = load(&ref_path)?;
+    let show = |key: &str, value: &str| {
+        if value.is_empty() {
+            "(empty)".to_owned()
+        } else if raw || !state.env_masked(key) {
+            value.to_owned()
+        } else {
+            "*****".to_owned()
+        }
+    };
+
+    println!("Comparing values in {} against {}:", env_path, ref_path);
+    let (mut changed, mut added, mut removed) = (0, 0, 0);
+    for (key, value) in &current {
+        match other.get(key) {
+            Some(reference_value) if reference_value != value => {
+                changed += 1;
+                println!("  ~ {}: {} -> {}", key, show(key, reference_value), show(key, value));
+            }
+            Some(_) => {}
+            None => {
+                added += 1;
+                println!("  + {}={}", key, show(key, value));
+            }
+        }
+    }
+    for (key, value) in &other {
+        if !current.contains_key(key) {
+            removed += 1;
+            println!("  - {}={}", key, show(key, value));
+        }
+    }
+
+    if changed + added + removed == 0 {
+        println!("[ok] No differences found.");
+    } else {
+        println!(
+            "{} changed, {} only in .env, {} only in {}{}",
+            changed,
+            added,
+            removed,
+            reference,
+            if raw { "" } else { " (sensitive values masked; --raw to show)" }
+        );
+    }
+    Ok(())
+}
+
 fn env_sync(state: &AppState, reference: &str) -> Result<()> {
     let env_path = state.env_path()?;
     let mut env = envfile::EnvFile::load(&env_path)?;
@@ -855,69 +1434,1275 @@ fn env_sync(state: &AppState, reference: &str) -> Result<()> {
         println!("  + {}={}", key, if value.is_empty() { "(empty)" } else { "*****" });
     }
 
+    envhistory::snapshot(&env_path, "sync")?;
     env.save()?;
     println!("Synced {} keys to {}", missing.len(), env_path);
     Ok(())
 }
 
-fn handle_config_only(ctx: &CliContext, command: Option<ConfigCommand>) -> Result<()> {
-    let resolved = ctx.resolve_config_path()?;
-    let config_path = resolved.path;
-    match command {
-        Some(ConfigCommand::Path) => {
-            println!("Config path: {} ({})", config_path, resolved.source.as_str());
-            Ok(())
-        }
-        None | Some(ConfigCommand::Show) => {
-            if !config_path.exists() {
-                println!("No config found at {}.", config_path);
-                println!("Use `dev config generate` to scaffold a default configuration.");
-                return Ok(());
-            }
+fn env_gen(state: &AppState, key: &str, length: usize, format: EnvGenFormat) -> Result<()> {
+    let value = envgen::generate(length, format)?;
+    let env_path = state.env_path()?;
+    let mut env = envfile::EnvFile::load(&env_path)?;
+    let existed = env.entries().any(|(existing, _)| existing == key);
+    env.upsert(key, &envconvert::dotenv_value(&value));
+    envhistory::snapshot(&env_path, "gen")?;
+    env.save()?;
 
-            let config = config::load_from_path(&config_path)?;
-            println!("Config path: {} ({})", config_path, resolved.source.as_str());
-            println!("{}", config::format_summary(&config));
-            Ok(())
+    let verb = if existed { "Regenerated" } else { "Generated" };
+    let detail = match format {
+        EnvGenFormat::Hex => format!("{} random bytes, hex", length),
+        EnvGenFormat::Base64 => format!("{} random bytes, base64", length),
+        EnvGenFormat::Uuid => "uuid v4".to_owned(),
+    };
+    println!("{} {} ({}) in {}", verb, key, detail, env.path());
+    Ok(())
+}
+
+fn env_fmt(state: &AppState, file: Option<PathBuf>, check: bool) -> Result<()> {
+    let env_path = state.env_path()?;
+    let path = match file {
+        Some(file) => {
+            Utf8PathBuf::from_path_buf(file).map_err(|_| anyhow!("file path must be valid UTF-8"))?
         }
-        Some(ConfigCommand::Check) => {
-            let config = config::load_from_path(&config_path)?;
-            let _ = TaskIndex::from_config(&config)?;
-            println!("Config OK: {} ({})", config_path, resolved.source.as_str());
-            println!("{}", config::format_summary(&config));
-            Ok(())
+        None => env_path.clone(),
+    };
+    if !path.exists() {
+        bail!("no env file found at {}", path);
+    }
+
+    let mut env = envfile::EnvFile::load(&path)?;
+    if !env.format() {
+        println!("[ok] {} is already formatted.", path);
+        return Ok(());
+    }
+    if check {
+        bail!("{} is not formatted; run `dev env fmt` to fix it", path);
+    }
+    if path == env_path {
+        envhistory::snapshot(&env_path, "fmt")?;
+    }
+    env.save()?;
+    println!("Formatted {}", path);
+    Ok(())
+}
+
+fn env_merge(
+    state: &AppState,
+    files: Vec<PathBuf>,
+    out: Option<PathBuf>,
+    strategy: EnvMergeStrategy,
+) -> Result<()> {
+    let mut inputs = Vec::new();
+    for file in files {
+        let file = Utf8PathBuf::from_path_buf(file).map_err(|_| anyhow!("merge paths must be valid UTF-8"))?;
+        if !file.exists() && !envcrypt::encrypted_path(&file).exists() {
+            bail!("no env file found at {}", file);
         }
-        Some(ConfigCommand::Generate { path, force }) => {
-            let target = match path {
-                Some(path) => Utf8PathBuf::from_path_buf(path)
-                    .map_err(|_| anyhow!("config generate path must be valid UTF-8"))?,
-                None => config_path.clone(),
-            };
-            config::write_example_config(&target, force)?;
-            if force {
-                println!("Overwrote config at {}", target);
-            } else {
-                println!("Wrote example config to {}", target);
-            }
-            Ok(())
+        inputs.push(envfile::EnvFile::load(&file)?);
+    }
+    let env_path = state.env_path()?;
+    let out = match out {
+        Some(out) => Utf8PathBuf::from_path_buf(out).map_err(|_| anyhow!("--out must be valid UTF-8"))?,
+        None => env_path.clone(),
+    };
+    if strategy == EnvMergeStrategy::Interactive && !io::stdin().is_terminal() {
+        bail!("--strategy interactive needs a terminal; use prefer-first or prefer-last");
+    }
+
+    let merged = envmerge::merge(&inputs, strategy, |key, options| {
+        println!("{} differs:", key);
+        for (idx, (file, value)) in options.iter().enumerate() {
+            println!("  {}) {} = {}", idx + 1, file, value);
         }
-        Some(ConfigCommand::Reload) => {
-            if !config_path.exists() {
-                println!("No config found at {}. Nothing to reload.", config_path);
-                return Ok(());
+        loop {
+            let answer = prompt(&format!("Keep which value for {}? [1-{}]: ", key, options.len()))?;
+            match answer.trim().parse::<usize>() {
+                Ok(choice) if (1..=options.len()).contains(&choice) => return Ok(choice - 1),
+                _ => println!("Enter a number between 1 and {}.", options.len()),
             }
-            let config = config::load_from_path(&config_path)?;
-            println!("Reloaded config from {} ({})", config_path, resolved.source.as_str());
-            println!("{}", config::format_summary(&config));
-            Ok(())
         }
-        Some(ConfigCommand::Add {
-            name,
-            command,
-            force,
-            append,
-        }) => config_add(&config_path, name, command, force, append),
+    })?;
+
+    if state.ctx.dry_run {
+        println!(
+            "[dry-run] would write {} keys to {} ({} conflicts)",
+            merged.entries.len(),
+            out,
+            merged.conflicts.len()
+        );
+        return Ok(());
+    }
+
+    let mut env = envfile::EnvFile::empty(&out);
+    for entry in &merged.entries {
+        env.upsert(&entry.key, &entry.value);
+        if let Some(description) = &entry.description {
+            env.set_description(&entry.key, description);
+        }
+    }
+    if out == env_path {
+        envhistory::snapshot(&env_path, "merge")?;
+    }
+    env.save()?;
+
+    println!("Merged {} keys from {} files into {}", merged.entries.len(), inputs.len(), out);
+    if !merged.conflicts.is_empty() {
+        println!("Resolved {} conflicts: {}", merged.conflicts.len(), merged.conflicts.join(", "));
+    }
+    Ok(())
+}
+
+/// `dev env watch`: poll the env layers and, after each change, recreate the compose
+/// `restart` service or re-run `task`. Failures are reported and watching continues.
+fn env_watch(
+    state: &AppState,
+    restart: Option<String>,
+    task: Option<String>,
+    interval: Duration,
+) -> Result<()> {
+    let env_path = state.env_path()?;
+    let watched = |state: &AppState| -> Result<Vec<(Utf8PathBuf, Option<SystemTime>)>> {
+        let mut paths = Vec::new();
+        for layer in state.env_layers(&env_path)? {
+            paths.push(envcrypt::encrypted_path(&layer));
+            paths.push(layer);
+        }
+        paths.push(envfile::active_profile_path(&env_path)?);
+        Ok(paths
+            .into_iter()
+            .map(|path| {
+                let modified = modified_time(&path);
+                (path, modified)
+            })
+            .collect())
+    };
+
+    let mut seen = watched(state)?;
+    let mut entries: std::collections::BTreeMap<String, String> =
+        state.env_entries(&env_path)?.into_iter().collect();
+    let action = match (&restart, &task) {
+        (Some(service), _) => format!("recreate compose service `{}`", service),
+        (None, Some(task)) => format!("re-run task `{}`", task),
+        (None, None) => bail!("pass --restart <SERVICE> or --task <TASK>"),
+    };
+    println!("Watching {} ({} keys); will {} on change. Press Ctrl-C to stop.", env_path, entries.len(), action);
+
+    loop {
+        thread::sleep(interval);
+        let current = watched(state)?;
+        if current == seen {
+            continue;
+        }
+        seen = current;
+
+        let next: std::collections::BTreeMap<String, String> = match state.env_entries(&env_path) {
+            Ok(next) => next.into_iter().collect(),
+            Err(err) => {
+                println!("[error] reading env failed, waiting for the next change: {:#}", err);
+                continue;
+            }
+        };
+        let changed: Vec<String> = entries
+            .iter()
+            .filter(|(key, value)| next.get(*key) != Some(value))
+            .map(|(key, _)| {
+                let marker = if next.contains_key(key) { '~' } else { '-' };
+                format!("{}{}", marker, key)
+            })
+            .chain(
+                next.keys()
+                    .filter(|key| !entries.contains_key(*key))
+                    .map(|key| format!("+{}", key)),
+            )
+            .collect();
+        entries = next;
+        if changed.is_empty() {
+            println!("[ok] env files touched but no values changed");
+            continue;
+        }
+        println!("[ok] env changed: {}", changed.join(" "));
+
+        let result = match (&restart, &task) {
+            (Some(service), _) => {
+                // `restart` keeps the old environment; recreating re-reads env_file.
+                let argv: Vec<String> =
+                    ["docker", "compose", "up", "-d", "--force-recreate", "--no-deps", service]
+                        .iter()
+                        .map(|arg| arg.to_string())
+                        .collect();
+                println!("Recreating service: {}", format_command(&argv));
+                if state.ctx.dry_run {
+                    println!("    (dry-run) skipped");
+                    Ok(())
+                } else {
+                    run_process(&argv).and_then(|status| {
+                        if status.success() {
+                            Ok(())
+                        } else {
+                            Err(anyhow!(
+                                "command `{}` failed with exit code {:?}",
+                                format_command(&argv),
+                                status.code()
+                            ))
+                        }
+                    })
+                }
+            }
+            (None, Some(task)) => handle_run(state, task),
+            (None, None) => unreachable!("checked before watching"),
+        };
+        if let Err(err) = result {
+            println!("[error] {:#}; still watching", err);
+        }
+    }
+}
+
+fn env_audit(state: &AppState) -> Result<()> {
+    let env_path = state.env_path()?;
+    let root = env_path
+        .parent()
+        .ok_or_else(|| anyhow!("cannot determine parent directory of {}", env_path))?;
+    let references = envaudit::scan(root)?;
+    let defined: std::collections::BTreeSet<String> =
+        state.env_entries(&env_path)?.into_iter().map(|(key, _)| key).collect();
+    let env = state.config.env.as_ref();
+    let required = env.and_then(|env| env.required.as_deref()).unwrap_or_default();
+    let optional = env.and_then(|env| env.optional.as_deref()).unwrap_or_default();
+
+    let missing: Vec<&String> = references
+        .keys()
+        .filter(|key| !defined.contains(*key) && !envaudit::AMBIENT_KEYS.contains(&key.as_str()))
+        .collect();
+    let declared: std::collections::BTreeSet<&String> =
+        defined.iter().chain(required).chain(optional).collect();
+    let unused: Vec<&&String> = declared
+        .iter()
+        .filter(|key| !references.contains_key(key.as_str()))
+        .collect();
+
+    println!(
+        "Scanned {} for env lookups: {} keys referenced, {} defined in {}",
+        root,
+        references.len(),
+        defined.len(),
+        env_path
+    );
+    if missing.is_empty() && unused.is_empty() {
+        println!("[ok] Every referenced key is defined and every defined key is used.");
+        return Ok(());
+    }
+    if !unused.is_empty() {
+        println!("[warn] Defined but never referenced:");
+        for key in &unused {
+            let source = if defined.contains(key.as_str()) { ".env" } else { "[env] config" };
+            println!("  - {} ({})", key, source);
+        }
+    }
+    if !missing.is_empty() {
+        println!("[error] Referenced but missing from .env:");
+        for key in &missing {
+            let first = &references[key.as_str()][0];
+            let more = references[key.as_str()].len() - 1;
+            let required_note = if required.contains(key) { ", required in config" } else { "" };
+            if more > 0 {
+                println!("  - {} ({}:{} and {} more{})", key, first.path, first.line, more, required_note);
+            } else {
+                println!("  - {} ({}:{}{})", key, first.path, first.line, required_note);
+            }
+        }
+        bail!("{} referenced env keys are not defined", missing.len());
+    }
+    Ok(())
+}
+
+fn env_edit(state: &AppState) -> Result<()> {
+    envedit::run(&state.env_path()?, state.config.env.as_ref())
+}
+
+fn env_history(state: &AppState) -> Result<()> {
+    let env_path = state.env_path()?;
+    let snapshots = envhistory::list(&env_path)?;
+    if snapshots.is_empty() {
+        println!("No snapshots of {} yet.", env_path);
+        return Ok(());
+    }
+    println!("Snapshots of {} (newest first):", env_path);
+    for (idx, snapshot) in snapshots.iter().enumerate() {
+        println!(
+            "  {:>2}. {}  before `{}`",
+            idx + 1,
+            snapshot.taken_display(),
+            snapshot.operation
+        );
+    }
+    println!("Restore one with `dev env rollback <n>`.");
+    Ok(())
+}
+
+fn env_rollback(state: &AppState, n: usize) -> Result<()> {
+    let env_path = state.env_path()?;
+    if state.ctx.dry_run {
+        let snapshots = envhistory::list(&env_path)?;
+        let chosen = snapshots
+            .get(n.wrapping_sub(1))
+            .ok_or_else(|| anyhow!("no snapshot #{} for {}", n, env_path))?;
+        println!("[dry-run] would restore {} to {}", chosen.path, env_path);
+        return Ok(());
+    }
+    let restored = envhistory::rollback(&env_path, n)?;
+    println!(
+        "[ok] restored {} from {} (before `{}`); `dev env rollback 1` undoes this",
+        env_path,
+        restored.taken_display(),
+        restored.operation
+    );
+    Ok(())
+}
+
+fn env_exec(state: &AppState, command: &[String]) -> Result<()> {
+    let env_path = state.env_path()?;
+    let entries = state.env_entries(&env_path)?;
+    let render = format_command(command);
+    if state.ctx.dry_run {
+        println!("[dry-run] {} ({} variables from {})", render, entries.len(), env_path);
+        return Ok(());
+    }
+
+    let status = run_process_with_env(command, &entries)
+        .with_context(|| format!("executing `{}`", render))?;
+    if !status.success() {
+        bail!("command `{}` failed with exit code {:?}", render, status.code());
+    }
+    Ok(())
+}
+
+fn env_export(state: &AppState, format: EnvExportFormat) -> Result<()> {
+    let env_path = state.env_path()?;
+    if !env_path.exists() && !envcrypt::encrypted_path(&env_path).exists() {
+        bail!("no .env found at {}", env_path);
+    }
+    let mut entries = state.env_entries(&env_path)?;
+    // stdout is meant for `eval` or redirection; anything else goes to stderr.
+    if !matches!(format, EnvExportFormat::Json | EnvExportFormat::Yaml) {
+        entries.retain(|(key, _)| {
+            let valid = envconvert::is_valid_key(key);
+            if !valid {
+                eprintln!("[warn] skipping `{}`: not a valid variable name", key);
+            }
+            valid
+        });
+    }
+    print!("{}", envconvert::render(&entries, format));
+    Ok(())
+}
+
+fn env_import(
+    state: &AppState,
+    file: PathBuf,
+    format: Option<EnvImportFormat>,
+    overwrite: bool,
+) -> Result<()> {
+    let file = Utf8PathBuf::from_path_buf(file).map_err(|_| anyhow!("import path must be valid UTF-8"))?;
+    let raw = fs::read_to_string(&file).with_context(|| format!("reading {}", file))?;
+    let format = format.unwrap_or_else(|| envconvert::detect_format(file.as_str()));
+    let imported = envconvert::parse(&raw, format).with_context(|| format!("parsing {}", file))?;
+
+    merge_env_entries(state, &imported, overwrite, "import", file.as_str())
+}
+
+/// Upsert `entries` into `.env`, printing `+`/`~`/`=` per key. Existing values are only
+/// replaced with `overwrite`; the previous file is snapshotted first.
+fn merge_env_entries(
+    state: &AppState,
+    entries: &[(String, String)],
+    overwrite: bool,
+    operation: &str,
+    source: &str,
+) -> Result<()> {
+    let env_path = state.env_path()?;
+    let mut env = envfile::EnvFile::load(&env_path)?;
+    let (mut added, mut updated, mut kept) = (0, 0, 0);
+    for (key, value) in entries {
+        let existing = env.entries().find(|(k, _)| k == key).map(|(_, v)| envfile::unquote(v));
+        match existing {
+            Some(current) if current == *value => continue,
+            Some(_) if !overwrite => {
+                kept += 1;
+                println!("  = {} (kept existing value; --overwrite to replace)", key);
+                continue;
+            }
+            Some(_) => {
+                updated += 1;
+                println!("  ~ {}", key);
+            }
+            None => {
+                added += 1;
+                println!("  + {}", key);
+            }
+        }
+        if !state.ctx.dry_run {
+            env.upsert(key, &envconvert::dotenv_value(value));
+        }
+    }
+
+    if state.ctx.dry_run {
+        println!("[dry-run] would add {} and update {} keys in {}", added, updated, env_path);
+        return Ok(());
+    }
+    if added + updated > 0 {
+        envhistory::snapshot(&env_path, operation)?;
+        env.save()?;
+    }
+    println!(
+        "Merged {} keys from {} into {} ({} added, {} updated, {} kept)",
+        entries.len(),
+        source,
+        env_path,
+        added,
+        updated,
+        kept
+    );
+    Ok(())
+}
+
+/// Where `dev env pull` / `dev env push` read and write secrets.
+struct SyncTarget<'a> {
+    backend: SecretBackend,
+    path: String,
+    keys: Option<&'a std::collections::BTreeMap<String, String>>,
+}
+
+/// Backend and path for pull/push: flags first, then `[env.sync]`.
+fn env_sync_target(
+    state: &AppState,
+    backend: Option<SecretBackend>,
+    path: Option<String>,
+) -> Result<SyncTarget<'_>> {
+    let sync = state.config.env.as_ref().and_then(|env| env.sync.as_ref());
+    let backend = match backend {
+        Some(backend) => backend,
+        None => {
+            let name = sync
+                .and_then(|sync| sync.backend.as_deref())
+                .ok_or_else(|| anyhow!("no secrets backend; pass --backend or set [env.sync] backend"))?;
+            SecretBackend::from_str(name, true).map_err(|_| {
+                anyhow!("unknown [env.sync] backend `{}` (expected aws, vault, or 1password)", name)
+            })?
+        }
+    };
+    let path = path
+        .or_else(|| sync.and_then(|sync| sync.path.clone()))
+        .ok_or_else(|| anyhow!("no secret path; pass --path or set [env.sync] path"))?;
+    Ok(SyncTarget {
+        backend,
+        path,
+        keys: sync.and_then(|sync| sync.keys.as_ref()),
+    })
+}
+
+fn env_pull(state: &AppState, backend: Option<SecretBackend>, path: Option<String>) -> Result<()> {
+    let SyncTarget { backend, path, keys } = env_sync_target(state, backend, path)?;
+    let remote = envsync::pull(backend, &path)?;
+    let entries = envsync::map_pulled(remote, keys);
+    // The secrets manager is the source of truth, so pulled values win.
+    merge_env_entries(
+        state,
+        &entries,
+        true,
+        "pull",
+        &format!("{} `{}`", backend.as_str(), path),
+    )
+}
+
+fn env_push(state: &AppState, backend: Option<SecretBackend>, path: Option<String>) -> Result<()> {
+    let SyncTarget { backend, path, keys } = env_sync_target(state, backend, path)?;
+    let env_path = state.env_path()?;
+    if !env_path.exists() && !envcrypt::encrypted_path(&env_path).exists() {
+        bail!("no .env found at {}", env_path);
+    }
+    let local = envfile::resolved_entries(&env_path, None)?;
+    let entries = envsync::map_pushed(local, keys);
+    if entries.is_empty() {
+        println!("Nothing to push from {}.", env_path);
+        return Ok(());
+    }
+
+    for (key, _) in &entries {
+        println!("  > {}", key);
+    }
+    if state.ctx.dry_run {
+        println!(
+            "[dry-run] would push {} keys to {} `{}`",
+            entries.len(),
+            backend.as_str(),
+            path
+        );
+        return Ok(());
+    }
+    envsync::push(backend, &path, &entries)?;
+    println!("[ok] pushed {} keys to {} `{}`", entries.len(), backend.as_str(), path);
+    Ok(())
+}
+
+fn env_encrypt(
+    state: &AppState,
+    recipients: Vec<String>,
+    sops: bool,
+    remove_plain: bool,
+) -> Result<()> {
+    let env_path = state.env_path()?;
+    if !env_path.exists() {
+        bail!("no .env found at {}", env_path);
+    }
+    let encrypted_path = envcrypt::encrypted_path(&env_path);
+    let sops_config = env_path.with_file_name(".sops.yaml");
+    let backend = if sops || sops_config.exists() {
+        envcrypt::Backend::Sops
+    } else {
+        envcrypt::Backend::Age
+    };
+    let recipients = if recipients.is_empty() {
+        state
+            .config
+            .env
+            .as_ref()
+            .and_then(|env| env.recipients.clone())
+            .unwrap_or_default()
+    } else {
+        recipients
+    };
+
+    if state.ctx.dry_run {
+        println!(
+            "[dry-run] would encrypt {} to {} with {}",
+            env_path,
+            encrypted_path,
+            backend.as_str()
+        );
+        if remove_plain {
+            println!("[dry-run] would remove {}", env_path);
+        }
+        return Ok(());
+    }
+
+    envcrypt::encrypt(&env_path, &encrypted_path, backend, &recipients)?;
+    println!(
+        "[ok] encrypted {} to {} with {}",
+        env_path,
+        encrypted_path,
+        backend.as_str()
+    );
+    if remove_plain {
+        fs::remove_file(&env_path).with_context(|| format!("removing {}", env_path))?;
+        println!("[ok] removed {}; commands now read {}", env_path, encrypted_path);
+    }
+    Ok(())
+}
+
+fn env_decrypt(state: &AppState, identity: Option<PathBuf>, force: bool) -> Result<()> {
+    let env_path = state.env_path()?;
+    let encrypted_path = envcrypt::encrypted_path(&env_path);
+    if !encrypted_path.exists() {
+        bail!("no encrypted env found at {}", encrypted_path);
+    }
+    if env_path.exists() && !force {
+        bail!("{} already exists; rerun with --force to overwrite", env_path);
+    }
+    let identity = identity
+        .map(|path| {
+            Utf8PathBuf::from_path_buf(path).map_err(|_| anyhow!("identity path must be valid UTF-8"))
+        })
+        .transpose()?;
+
+    if state.ctx.dry_run {
+        println!("[dry-run] would decrypt {} to {}", encrypted_path, env_path);
+        return Ok(());
+    }
+
+    let contents = envcrypt::decrypt(&encrypted_path, identity.as_deref())?;
+    envhistory::snapshot(&env_path, "decrypt")?;
+    fs::write(&env_path, contents).with_context(|| format!("writing {}", env_path))?;
+    println!("[ok] decrypted {} to {}", encrypted_path, env_path);
+    Ok(())
+}
+
+fn handle_config_only(ctx: &CliContext, command: Option<ConfigCommand>) -> Result<()> {
+    // An explicit generate target needs no discovery walk.
+    if let Some(ConfigCommand::Generate {
+        path: Some(path),
+        force,
+    }) = command
+    {
+        let target = Utf8PathBuf::from_path_buf(path)
+            .map_err(|_| anyhow!("config generate path must be valid UTF-8"))?;
+        return config_generate(&target, force);
+    }
+    if let Some(ConfigCommand::Sync { from }) = command {
+        return config_sync(ctx, from);
+    }
+
+    let resolved = ctx.resolve_config_path()?;
+    let config_path = resolved.path;
+    match command {
+        Some(ConfigCommand::Path) => {
+            println!("Config path: {} ({})", config_path, resolved.source.as_str());
+            Ok(())
+        }
+        None | Some(ConfigCommand::Show) => {
+            if !config_path.exists() {
+                println!("No config found at {}.", config_path);
+                println!("Use `dev config generate` to scaffold a default configuration.");
+                return Ok(());
+            }
+
+            let config = ctx.load_config(&config_path)?;
+            println!("Config path: {} ({})", config_path, resolved.source.as_str());
+            println!("{}", config::format_summary(&config));
+            Ok(())
+        }
+        Some(ConfigCommand::Check) => {
+            let config = ctx.load_config(&config_path)?;
+            let _ = TaskIndex::from_config(&config)?;
+            config::validate_pipeline_refs(&config)?;
+            println!("Config OK: {} ({})", config_path, resolved.source.as_str());
+            println!("{}", config::format_summary(&config));
+            Ok(())
+        }
+        Some(ConfigCommand::Generate { force, .. }) => config_generate(&config_path, force),
+        Some(ConfigCommand::Reload { watch: true, interval }) => {
+            config_watch(ctx, &config_path, Duration::from_secs(interval.max(1)))
+        }
+        Some(ConfigCommand::Reload { watch: false, .. }) => {
+            if !config_path.exists() {
+                println!("No config found at {}. Nothing to reload.", config_path);
+                return Ok(());
+            }
+            let config = ctx.load_config(&config_path)?;
+            println!("Reloaded config from {} ({})", config_path, resolved.source.as_str());
+            println!("{}", config::format_summary(&config));
+            Ok(())
+        }
+        Some(ConfigCommand::Migrate { xdg: true }) => config_migrate_xdg(ctx),
+        Some(ConfigCommand::Migrate { xdg: false }) => config_migrate(ctx, &config_path),
+        Some(ConfigCommand::Diff { against }) => config_diff(&config_path, against),
+        Some(ConfigCommand::Lint { fix }) => config_lint(ctx, &config_path, fix),
+        Some(ConfigCommand::Export { resolved: export_resolved, format }) => {
+            let exists = config_path.exists();
+            if !exists && resolved.source != ConfigPathSource::HomeDefault {
+                bail!("no config found at {}", config_path);
+            }
+            config_export(ctx, exists.then_some(&config_path), export_resolved, format)
+        }
+        Some(ConfigCommand::Get { key }) => config_get(&config_path, &key),
+        Some(ConfigCommand::Rm { name, force }) => config_rm(ctx, &config_path, &name, force),
+        Some(ConfigCommand::Rename { old, new }) => config_rename(ctx, &config_path, &old, &new),
+        Some(ConfigCommand::Set { key, value }) => config_set(ctx, &config_path, &key, &value),
+        Some(ConfigCommand::Sync { .. }) => unreachable!("sync is handled before config discovery"),
+        Some(ConfigCommand::Add {
+            name,
+            command,
+            force,
+            append,
+        }) => config_add(&config_path, name, command, force, append),
+    }
+}
+
+fn config_diff(config_path: &Utf8PathBuf, against: Option<PathBuf>) -> Result<()> {
+    if !config_path.exists() {
+        bail!(
+            "no config found at {}; use `dev config generate` to scaffold one",
+            config_path
+        );
+    }
+
+    let raw = fs::read_to_string(config_path)
+        .with_context(|| format!("reading config {}", config_path))?;
+    let active: toml_edit::DocumentMut = raw
+        .parse()
+        .with_context(|| format!("parsing config {}", config_path))?;
+
+    let (label, reference_raw) = match against {
+        Some(path) => {
+            let raw = fs::read_to_string(&path)
+                .with_context(|| format!("reading {}", path.display()))?;
+            (path.display().to_string(), raw)
+        }
+        None => ("embedded template".to_owned(), config::example_config()?),
+    };
+    let reference: toml_edit::DocumentMut = reference_raw
+        .parse()
+        .with_context(|| format!("parsing {}", label))?;
+
+    let diff = config::diff_documents(&active, &reference);
+    println!("Comparing {} against {}:", config_path, label);
+    if diff.is_empty() {
+        println!("[ok] No differences found.");
+        return Ok(());
+    }
+
+    if !diff.missing_sections.is_empty() {
+        println!("Missing sections (in {}):", label);
+        for section in &diff.missing_sections {
+            println!("  - [{}]", section);
+        }
+    }
+    if !diff.missing_keys.is_empty() {
+        println!("Missing keys (in {}):", label);
+        for key in &diff.missing_keys {
+            println!("  - {}", key);
+        }
+    }
+    if !diff.deprecated.is_empty() {
+        println!("[warn] Deprecated keys:");
+        for (key, hint) in &diff.deprecated {
+            println!("  ! {} ({})", key, hint);
+        }
+    }
+    if !diff.extra_sections.is_empty() {
+        println!("Extra sections (not in {}):", label);
+        for section in &diff.extra_sections {
+            println!("  + [{}]", section);
+        }
+    }
+    Ok(())
+}
+
+fn config_export(
+    ctx: &CliContext,
+    config_path: Option<&Utf8PathBuf>,
+    resolved: bool,
+    format: ExportFormat,
+) -> Result<()> {
+    let doc = if resolved {
+        let mut doc =
+            config::export_resolved(config_path.map(|path| path.as_path()), ctx.profile.as_deref())?;
+        for (name, value) in &ctx.set {
+            config::set_path(&mut doc, &format!("vars.{}", name), value.as_str().into())?;
+        }
+        doc
+    } else {
+        let path = config_path.ok_or_else(|| {
+            anyhow!("no config file found; use --resolved to see the built-in defaults")
+        })?;
+        read_config_document(path)?
+    };
+
+    match format {
+        ExportFormat::Toml => print!("{}", doc),
+        ExportFormat::Json => {
+            let value: toml::Value =
+                toml::from_str(&doc.to_string()).context("converting config to JSON")?;
+            println!("{}", serde_json::to_string_pretty(&value)?);
+        }
+    }
+    Ok(())
+}
+
+fn read_config_document(config_path: &Utf8PathBuf) -> Result<toml_edit::DocumentMut> {
+    if !config_path.exists() {
+        bail!(
+            "no config found at {}; use `dev config generate` to scaffold one",
+            config_path
+        );
+    }
+    let raw = fs::read_to_string(config_path)
+        .with_context(|| format!("reading config {}", config_path))?;
+    raw.parse()
+        .with_context(|| format!("parsing config {}", config_path))
+}
+
+fn config_rm(ctx: &CliContext, config_path: &Utf8PathBuf, name: &str, force: bool) -> Result<()> {
+    let mut doc = read_config_document(config_path)?;
+    let stripped = config::remove_task(&mut doc, name, force)?;
+    for location in &stripped {
+        println!("[warn] dropped reference to `{}` from {}", name, location);
+    }
+    if ctx.dry_run {
+        println!("[dry-run] would remove task `{}` from {}", name, config_path);
+        return Ok(());
+    }
+    fs::write(config_path, doc.to_string())
+        .with_context(|| format!("writing config {}", config_path))?;
+    println!("Removed task `{}` from {}", name, config_path);
+    Ok(())
+}
+
+fn config_rename(ctx: &CliContext, config_path: &Utf8PathBuf, old: &str, new: &str) -> Result<()> {
+    let mut doc = read_config_document(config_path)?;
+    let updated = config::rename_task(&mut doc, old, new)?;
+    for location in &updated {
+        println!("  ~ {}", location);
+    }
+    if ctx.dry_run {
+        println!("[dry-run] would rename task `{}` to `{}` in {}", old, new, config_path);
+        return Ok(());
+    }
+    fs::write(config_path, doc.to_string())
+        .with_context(|| format!("writing config {}", config_path))?;
+    println!(
+        "Renamed task `{}` to `{}` ({} reference(s) updated)",
+        old,
+        new,
+        updated.len()
+    );
+    Ok(())
+}
+
+fn config_get(config_path: &Utf8PathBuf, key: &str) -> Result<()> {
+    if !config_path.exists() {
+        bail!(
+            "no config found at {}; use `dev config generate` to scaffold one",
+            config_path
+        );
+    }
+    let raw = fs::read_to_string(config_path)
+        .with_context(|| format!("reading config {}", config_path))?;
+    let doc: toml_edit::DocumentMut = raw
+        .parse()
+        .with_context(|| format!("parsing config {}", config_path))?;
+    let item = config::get_path(&doc, key)
+        .filter(|item| !item.is_none())
+        .ok_or_else(|| anyhow!("`{}` is not set in {}", key, config_path))?;
+
+    match item {
+        // Print bare strings unquoted so scripts can use the output directly.
+        toml_edit::Item::Value(toml_edit::Value::String(value)) => println!("{}", value.value()),
+        toml_edit::Item::Value(value) => println!("{}", value.to_string().trim()),
+        table => {
+            let mut doc = toml_edit::DocumentMut::new();
+            if let Some(table) = table.as_table_like() {
+                for (key, value) in table.iter() {
+                    doc.insert(key, value.clone());
+                }
+            }
+            print!("{}", doc);
+        }
+    }
+    Ok(())
+}
+
+fn config_set(ctx: &CliContext, config_path: &Utf8PathBuf, key: &str, raw_value: &str) -> Result<()> {
+    let raw = if config_path.exists() {
+        fs::read_to_string(config_path)
+            .with_context(|| format!("reading config {}", config_path))?
+    } else {
+        String::new()
+    };
+    let mut doc: toml_edit::DocumentMut = raw
+        .parse()
+        .with_context(|| format!("parsing config {}", config_path))?;
+    let new_value = config::parse_cli_value(raw_value);
+    let rendered = new_value.to_string();
+    config::set_path(&mut doc, key, new_value)?;
+
+    // Reject edits that would leave the config unloadable (e.g. a number where a list is expected).
+    let updated = doc.to_string();
+    config::parse_checked(config_path, &updated)
+        .with_context(|| format!("`{}` = {} is not valid here", key, rendered.trim()))?;
+
+    if ctx.dry_run {
+        println!("[dry-run] would set {} = {} in {}", key, rendered.trim(), config_path);
+        return Ok(());
+    }
+    if let Some(parent) = config_path.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("creating directory {}", parent))?;
+    }
+    fs::write(config_path, updated).with_context(|| format!("writing config {}", config_path))?;
+    println!("Set {} = {} in {}", key, rendered.trim(), config_path);
+    Ok(())
+}
+
+fn config_sync(ctx: &CliContext, from: Option<String>) -> Result<()> {
+    let dest = config::shared_config_dir()?;
+    let is_checkout = dest.join(".git").exists();
+
+    let mut steps: Vec<Vec<String>> = Vec::new();
+    if is_checkout {
+        if let Some(url) = &from {
+            steps.push(vec![
+                "git".into(),
+                "-C".into(),
+                dest.to_string(),
+                "remote".into(),
+                "set-url".into(),
+                "origin".into(),
+                url.clone(),
+            ]);
+        }
+        steps.push(vec![
+            "git".into(),
+            "-C".into(),
+            dest.to_string(),
+            "pull".into(),
+            "--ff-only".into(),
+        ]);
+    } else {
+        let Some(url) = from else {
+            bail!(
+                "no shared config at {}; pass `--from <git-url>` to clone one",
+                dest
+            );
+        };
+        if dest.exists() && fs::read_dir(&dest)?.next().is_some() {
+            bail!("{} exists and is not a git checkout; move it aside first", dest);
+        }
+        steps.push(vec![
+            "git".into(),
+            "clone".into(),
+            url,
+            dest.to_string(),
+        ]);
+    }
+
+    for argv in &steps {
+        if ctx.dry_run {
+            println!("[dry-run] {}", format_command(argv));
+            continue;
+        }
+        let status = run_process(argv)?;
+        if !status.success() {
+            bail!(
+                "`{}` failed with exit code {:?}",
+                format_command(argv),
+                status.code()
+            );
+        }
+    }
+    if ctx.dry_run {
+        return Ok(());
+    }
+
+    match config::shared_config_path()? {
+        Some(path) => {
+            // Parse it now so a broken team config fails here rather than on the next task run.
+            config::load_from_path(&path)?;
+            println!("[ok] Shared config synced: {}", path);
+        }
+        None => println!(
+            "[warn] {} has no config.toml or .dev/config.toml; nothing will be layered",
+            dest
+        ),
+    }
+    Ok(())
+}
+
+fn config_lint(ctx: &CliContext, config_path: &Utf8PathBuf, fix: bool) -> Result<()> {
+    if !config_path.exists() {
+        bail!(
+            "no config found at {}; use `dev config generate` to scaffold one",
+            config_path
+        );
+    }
+
+    // Lint what the user wrote, not the shared or built-in layers beneath it.
+    let config = config::load_file(config_path)?;
+    let findings = configlint::lint(&config);
+    if findings.is_empty() {
+        println!("[ok] No lint findings in {}", config_path);
+        return Ok(());
+    }
+
+    for finding in &findings {
+        let location = match finding.line {
+            Some(line) => format!("{}:{}", config_path, line),
+            None => config_path.to_string(),
+        };
+        let fixable = if finding.fixable() { " (fixable)" } else { "" };
+        println!(
+            "[warn] {}: {} [{}] {}{}",
+            location,
+            finding.path,
+            finding.rule.as_str(),
+            finding.message,
+            fixable
+        );
+        println!("       help: {}", finding.suggestion);
+    }
+
+    if !fix {
+        let fixable = findings.iter().filter(|finding| finding.fixable()).count();
+        if fixable > 0 {
+            println!("{} finding(s) can be fixed with `dev config lint --fix`.", fixable);
+        }
+        return Ok(());
+    }
+
+    let raw = fs::read_to_string(config_path)
+        .with_context(|| format!("reading config {}", config_path))?;
+    let mut doc: toml_edit::DocumentMut = raw
+        .parse()
+        .with_context(|| format!("parsing config {}", config_path))?;
+    let fixed = configlint::apply_fixes(&mut doc, &findings)?;
+    if fixed.is_empty() {
+        println!("Nothing to fix automatically.");
+        return Ok(());
+    }
+
+    for finding in &fixed {
+        if ctx.dry_run {
+            println!("[dry-run] would fix {} [{}]", finding.path, finding.rule.as_str());
+        } else {
+            println!("[fixed] {} [{}]", finding.path, finding.rule.as_str());
+        }
+    }
+    if !ctx.dry_run {
+        fs::write(config_path, doc.to_string())
+            .with_context(|| format!("writing config {}", config_path))?;
+    }
+    Ok(())
+}
+
+fn config_generate(target: &Utf8PathBuf, force: bool) -> Result<()> {
+    config::write_example_config(target, force)?;
+    if force {
+        println!("Overwrote config at {}", target);
+    } else {
+        println!("Wrote example config to {}", target);
+    }
+    Ok(())
+}
+
+fn config_migrate(ctx: &CliContext, config_path: &Utf8PathBuf) -> Result<()> {
+    if !config_path.exists() {
+        println!("No config found at {}. Nothing to migrate.", config_path);
+        return Ok(());
+    }
+
+    let raw = fs::read_to_string(config_path)
+        .with_context(|| format!("reading config {}", config_path))?;
+    let mut doc: toml_edit::DocumentMut = raw
+        .parse()
+        .with_context(|| format!("parsing config {}", config_path))?;
+    let mut changes = config::migrate_document(&mut doc)?;
+
+    // `tools/dev/config.toml` is the pre-`.dev` layout; move it alongside the new default.
+    let target = if is_legacy_config_path(config_path) {
+        let root = Utf8PathBuf::from_path_buf(config_root_dir(config_path))
+            .map_err(|_| anyhow!("config path must be valid UTF-8"))?;
+        let target = root.join(".dev").join("config.toml");
+        if target.exists() {
+            bail!(
+                "cannot move legacy config {} to {}: destination already exists",
+                config_path,
+                target
+            );
+        }
+        changes.push(format!("moved {} to {}", config_path, target));
+        target
+    } else {
+        config_path.clone()
+    };
+
+    if changes.is_empty() {
+        println!(
+            "Config {} is already at version {}.",
+            config_path,
+            config::CONFIG_VERSION
+        );
+        return Ok(());
+    }
+
+    println!("Migrating {}:", config_path);
+    for change in &changes {
+        println!("  - {}", change);
+    }
+
+    if ctx.dry_run {
+        println!("[dry-run] would write {}", target);
+        return Ok(());
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("creating directory {}", parent))?;
+    }
+    fs::write(&target, doc.to_string()).with_context(|| format!("writing config {}", target))?;
+    if &target != config_path {
+        fs::remove_file(config_path)
+            .with_context(|| format!("removing legacy config {}", config_path))?;
+    }
+    println!("Wrote migrated config to {}", target);
+    Ok(())
+}
+
+/// Rebuilds `AppState` when the config file (or the shared layer beneath it) changes on
+/// disk, for modes that keep running instead of exiting after one command.
+struct ConfigReloader {
+    ctx: CliContext,
+    watched: Vec<(Utf8PathBuf, Option<SystemTime>)>,
+}
+
+impl ConfigReloader {
+    fn new(ctx: CliContext, state: &AppState) -> Self {
+        let mut reloader = Self {
+            ctx,
+            watched: Vec::new(),
+        };
+        reloader.track(state);
+        reloader
+    }
+
+    fn track(&mut self, state: &AppState) {
+        self.watched = std::iter::once(state.config_path.clone())
+            .chain(state.config.shared.clone())
+            .map(|path| {
+                let modified = modified_time(&path);
+                (path, modified)
+            })
+            .collect();
+    }
+
+    /// Whether any watched file was created, modified, or removed since the last check.
+    fn changed(&mut self) -> bool {
+        let mut changed = false;
+        for (path, last) in &mut self.watched {
+            let current = modified_time(path);
+            if current != *last {
+                *last = current;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Load a fresh state and report how its tasks differ from `current`. On error the
+    /// caller keeps `current`, so a half-saved file never takes the running mode down.
+    fn reload(&mut self, current: &AppState) -> Result<(AppState, TaskDiff)> {
+        let next = AppState::new(self.ctx.clone())?;
+        let diff = current.tasks()?.diff(next.tasks()?);
+        self.track(&next);
+        Ok((next, diff))
+    }
+}
+
+fn modified_time(path: &Utf8Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+fn print_task_diff(diff: &TaskDiff) {
+    if diff.is_empty() {
+        println!("  (no task changes)");
+    }
+    for name in &diff.added {
+        println!("  + {}", name);
+    }
+    for name in &diff.removed {
+        println!("  - {}", name);
+    }
+    for name in &diff.changed {
+        println!("  ~ {}", name);
+    }
+}
+
+/// `dev config reload --watch`: poll the resolved config and reload it on every change.
+fn config_watch(ctx: &CliContext, config_path: &Utf8Path, interval: Duration) -> Result<()> {
+    // Pin the file so project chdirs during reloads cannot change which config is found;
+    // with no file yet, keep resolving so creating one is picked up.
+    let ctx = if config_path.exists() {
+        CliContext {
+            file: Some(config_path.as_std_path().to_path_buf()),
+            ..ctx.clone()
+        }
+    } else {
+        ctx.clone()
+    };
+    let mut state = AppState::new(ctx.clone())?;
+    let mut reloader = ConfigReloader::new(ctx, &state);
+    println!(
+        "Watching {} ({} tasks); press Ctrl-C to stop.",
+        state.config_path,
+        state.tasks()?.task_names().count()
+    );
+
+    loop {
+        thread::sleep(interval);
+        if !reloader.changed() {
+            continue;
+        }
+        match reloader.reload(&state) {
+            Ok((next, diff)) => {
+                println!("[ok] reloaded {}", next.config_path);
+                print_task_diff(&diff);
+                state = next;
+            }
+            Err(err) => println!("[error] reload failed, keeping previous config: {:#}", err),
+        }
+    }
+}
+
+/// Candidate user-level config files outside any project.
+struct HomeConfigPaths {
+    /// `~/.dev/config.toml`.
+    legacy: Utf8PathBuf,
+    /// `$XDG_CONFIG_HOME/dev/config.toml`, or `~/.config/dev/config.toml`.
+    xdg: Utf8PathBuf,
+    xdg_config_home_set: bool,
+}
+
+impl HomeConfigPaths {
+    fn detect() -> Result<Self> {
+        let home = dirs::home_dir().ok_or_else(|| anyhow!("unable to determine home directory"))?;
+        let home =
+            Utf8PathBuf::from_path_buf(home).map_err(|_| anyhow!("config path must be valid UTF-8"))?;
+        let xdg_home = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|value| !value.is_empty())
+            .map(|value| {
+                Utf8PathBuf::from_path_buf(PathBuf::from(value))
+                    .map_err(|_| anyhow!("XDG_CONFIG_HOME must be valid UTF-8"))
+            })
+            .transpose()?;
+        Ok(Self {
+            legacy: home.join(".dev").join("config.toml"),
+            xdg_config_home_set: xdg_home.is_some(),
+            xdg: xdg_home
+                .unwrap_or_else(|| home.join(".config"))
+                .join("dev")
+                .join("config.toml"),
+        })
+    }
+
+    /// An existing XDG config wins over the legacy one. With neither present, new configs
+    /// go to the XDG location only when `XDG_CONFIG_HOME` is set, so existing setups keep `~/.dev`.
+    fn pick(self) -> Utf8PathBuf {
+        if self.xdg.exists() || (self.xdg_config_home_set && !self.legacy.exists()) {
+            self.xdg
+        } else {
+            self.legacy
+        }
+    }
+}
+
+/// `dev config migrate --xdg`: move `~/.dev/config.toml` to the XDG location.
+/// `~/.dev/shared` (see `dev config sync`) is left where it is.
+fn config_migrate_xdg(ctx: &CliContext) -> Result<()> {
+    let HomeConfigPaths { legacy, xdg, .. } = HomeConfigPaths::detect()?;
+    if !legacy.exists() {
+        println!("No config at {}. Nothing to move.", legacy);
+        return Ok(());
+    }
+    if xdg.exists() {
+        bail!("cannot move {} to {}: destination already exists", legacy, xdg);
+    }
+    if ctx.dry_run {
+        println!("[dry-run] would move {} to {}", legacy, xdg);
+        return Ok(());
+    }
+    if let Some(parent) = xdg.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("creating directory {}", parent))?;
     }
+    // Copy then remove so a cross-device move (e.g. separate /home mounts) still works.
+    fs::copy(&legacy, &xdg).with_context(|| format!("copying {} to {}", legacy, xdg))?;
+    fs::remove_file(&legacy).with_context(|| format!("removing legacy config {}", legacy))?;
+    println!("[ok] moved {} to {}", legacy, xdg);
+    Ok(())
+}
+
+fn is_legacy_config_path(config_path: &Utf8PathBuf) -> bool {
+    let Some(parent) = config_path.parent() else {
+        return false;
+    };
+    parent.file_name() == Some("dev")
+        && parent.parent().and_then(|p| p.file_name()) == Some("tools")
 }
 
 fn config_add(
@@ -1030,12 +2815,29 @@ fn task_exists(path: &Utf8PathBuf, task_name: &str) -> Result<bool> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::sync::{Mutex, OnceLock};
+    use crate::testutil::cwd_lock;
     use std::time::{SystemTime, UNIX_EPOCH};
 
-    fn cwd_lock() -> &'static Mutex<()> {
-        static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
-        LOCK.get_or_init(|| Mutex::new(()))
+    #[test]
+    fn home_config_prefers_existing_xdg() {
+        let root = unique_temp_dir();
+        let paths = |xdg_config_home_set| HomeConfigPaths {
+            legacy: root.join(".dev/config.toml"),
+            xdg: root.join(".config/dev/config.toml"),
+            xdg_config_home_set,
+        };
+        assert!(paths(false).pick().ends_with(".dev/config.toml"));
+        assert!(paths(true).pick().ends_with(".config/dev/config.toml"));
+
+        fs::create_dir_all(root.join(".dev")).unwrap();
+        fs::write(root.join(".dev/config.toml"), "").unwrap();
+        assert!(paths(true).pick().ends_with(".dev/config.toml"));
+
+        fs::create_dir_all(root.join(".config/dev")).unwrap();
+        fs::write(root.join(".config/dev/config.toml"), "").unwrap();
+        assert!(paths(false).pick().ends_with(".config/dev/config.toml"));
+
+        let _ = fs::remove_dir_all(root.as_std_path());
     }
 
     fn unique_temp_dir() -> Utf8PathBuf {
@@ -1050,7 +2852,7 @@ mod tests {
 
     #[test]
     fn resolve_config_prefers_nearest_discovered() {
-        let _guard = cwd_lock().lock().unwrap();
+        let _guard = cwd_lock();
         let root = unique_temp_dir();
         let nested = root.join("a").join("b");
         fs::create_dir_all(nested.as_std_path()).unwrap();
@@ -1066,6 +2868,8 @@ mod tests {
             file: None,
             project: None,
             language: None,
+            profile: None,
+            set: Vec::new(),
             dry_run: false,
             verbose: 0,
             no_color: false,
@@ -1080,7 +2884,7 @@ mod tests {
 
     #[test]
     fn resolve_config_prefers_legacy_when_no_dotdev() {
-        let _guard = cwd_lock().lock().unwrap();
+        let _guard = cwd_lock();
         let root = unique_temp_dir();
         let nested = root.join("a").join("b");
         fs::create_dir_all(nested.as_std_path()).unwrap();
@@ -1096,6 +2900,8 @@ mod tests {
             file: None,
             project: None,
             language: None,
+            profile: None,
+            set: Vec::new(),
             dry_run: false,
             verbose: 0,
             no_color: false,
@@ -1120,6 +2926,8 @@ mod tests {
             file: Some(cfg.as_std_path().to_path_buf()),
             project: None,
             language: None,
+            profile: None,
+            set: Vec::new(),
             dry_run: false,
             verbose: 0,
             no_color: false,
@@ -1133,7 +2941,7 @@ mod tests {
 
     #[test]
     fn project_applies_chdir_and_language() {
-        let _guard = cwd_lock().lock().unwrap();
+        let _guard = cwd_lock();
         let root = unique_temp_dir();
         let proj_dir = root.join("web");
         fs::create_dir_all(proj_dir.as_std_path()).unwrap();
@@ -1158,6 +2966,8 @@ language = 'typescript'
             file: None,
             project: Some("web".to_owned()),
             language: None,
+            profile: None,
+            set: Vec::new(),
             dry_run: false,
             verbose: 0,
             no_color: false,
@@ -1172,6 +2982,70 @@ language = 'typescript'
         std::env::set_current_dir(old).unwrap();
         let _ = fs::remove_dir_all(root.as_std_path());
     }
+
+    #[test]
+    fn project_detected_from_cwd() {
+        let _guard = cwd_lock();
+        let root = unique_temp_dir();
+        let nested = root.join("apps").join("web").join("src");
+        fs::create_dir_all(nested.as_std_path()).unwrap();
+        fs::create_dir_all(root.join(".dev").as_std_path()).unwrap();
+        fs::write(
+            root.join(".dev").join("config.toml").as_std_path(),
+            r#"default_language = 'python'
+
+[projects.apps]
+chdir = 'apps'
+language = 'rust'
+
+[projects.web]
+chdir = 'apps/web'
+language = 'typescript'
+"#,
+        )
+        .unwrap();
+
+        let old = std::env::current_dir().unwrap();
+        std::env::set_current_dir(nested.as_std_path()).unwrap();
+
+        let ctx = CliContext {
+            chdir: None,
+            file: None,
+            project: None,
+            language: None,
+            profile: None,
+            set: Vec::new(),
+            dry_run: false,
+            verbose: 0,
+            no_color: false,
+        };
+        let state = AppState::new(ctx).unwrap();
+        assert_eq!(state.effective_language(None).as_deref(), Some("typescript"));
+
+        std::env::set_current_dir(old).unwrap();
+        let _ = fs::remove_dir_all(root.as_std_path());
+    }
+
+    #[test]
+    fn removes_only_the_parts_an_index_lists() {
+        let root = unique_temp_dir();
+        fs::create_dir_all(&root).unwrap();
+        for file in ["manifest-01.md", "manifest-02.md", "manifest-03.md", "manifest-2024.md", "keep.md"] {
+            fs::write(root.join(file), "").unwrap();
+        }
+        let output = root.join("manifest.md");
+        fs::write(&output, "").unwrap();
+
+        let listed = ["manifest-01.md", "manifest-02.md", "../keep.md", "manifest.md", "missing.md"].map(String::from);
+        remove_listed_parts(output.as_std_path(), &listed).unwrap();
+        let mut left: Vec<String> = fs::read_dir(&root)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        assert_eq!(left, ["keep.md", "manifest-03.md", "manifest-2024.md", "manifest.md"]);
+        let _ = fs::remove_dir_all(root.as_std_path());
+    }
 }
 
 fn run_task_sequence(state: &AppState, tasks: &[String]) -> Result<()> {
@@ -1188,8 +3062,18 @@ fn execute_commands(state: &AppState, task: &str, commands: &[CommandSpec]) -> R
     }
 
     let total = commands.len();
+    let mut secrets = SecretResolver::new();
     for (idx, spec) in commands.iter().enumerate() {
-        let render = format_command(&spec.argv);
+        let display = spec
+            .argv
+            .iter()
+            .map(|arg| match arg {
+                CommandArg::Literal(value) => state.vars.expand(value),
+                CommandArg::Secret(_) => Ok(arg.display()),
+            })
+            .collect::<Result<Vec<_>>>()
+            .with_context(|| format!("expanding [vars] in task `{}`", spec.origin))?;
+        let render = format_command(&display);
         println!("[{}/{}] {} :: {}", idx + 1, total, spec.origin, render);
 
         if state.ctx.dry_run {
@@ -1197,8 +3081,30 @@ fn execute_commands(state: &AppState, task: &str, commands: &[CommandSpec]) -> R
             continue;
         }
 
+        // Secrets are resolved only now so dry-runs and listings never touch them.
+        let argv = spec
+            .argv
+            .iter()
+            .map(|arg| match arg {
+                CommandArg::Literal(value) => state.vars.expand(value),
+                CommandArg::Secret(name) => secrets.resolve(name),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let env = spec
+            .env
+            .iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    ConfigValue::Plain(plain) => state.vars.expand(plain)?,
+                    _ => secrets.resolve_value(value)?,
+                };
+                Ok((key.clone(), value))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
         let start = Instant::now();
-        let status = run_process(&spec.argv)?;
+        let status = run_process_with_env(&argv, &env)
+            .with_context(|| format!("executing `{}`", render))?;
         if status.success() {
             println!("[ok] {} (completed in {:.2?})", render, start.elapsed());
         } else if spec.allow_fail {
@@ -1235,6 +3141,20 @@ fn run_process(argv: &[String]) -> Result<std::process::ExitStatus> {
         .with_context(|| format!("executing `{}`", format_command(argv)))
 }
 
+/// Like `run_process`, but with extra environment variables and without echoing
+/// argv in errors, since it may contain resolved secrets.
+fn run_process_with_env(
+    argv: &[String],
+    env: &[(String, String)],
+) -> Result<std::process::ExitStatus> {
+    let mut command = ProcessCommand::new(&argv[0]);
+    if argv.len() > 1 {
+        command.args(&argv[1..]);
+    }
+    command.envs(env.iter().map(|(key, value)| (key, value)));
+    Ok(command.status()?)
+}
+
 fn format_command(argv: &[String]) -> String {
     argv.iter()
         .map(|arg| {
@@ -1413,6 +3333,8 @@ struct CliContext {
     file: Option<PathBuf>,
     project: Option<String>,
     language: Option<String>,
+    profile: Option<String>,
+    set: Vec<(String, String)>,
     dry_run: bool,
     verbose: u8,
     no_color: bool,
@@ -1437,6 +3359,15 @@ impl CliContext {
             });
         }
 
+        if let Some(path) = std::env::var_os("DEV_CONFIG").filter(|value| !value.is_empty()) {
+            let path = Utf8PathBuf::from_path_buf(PathBuf::from(path))
+                .map_err(|_| anyhow!("DEV_CONFIG must be valid UTF-8"))?;
+            return Ok(ResolvedConfigPath {
+                path,
+                source: ConfigPathSource::Env,
+            });
+        }
+
         if let Ok(cwd) = std::env::current_dir() {
             if let Ok(mut dir) = Utf8PathBuf::from_path_buf(cwd) {
                 loop {
@@ -1464,17 +3395,16 @@ impl CliContext {
             }
         }
 
-        let home = dirs::home_dir().ok_or_else(|| anyhow!("unable to determine home directory"))?;
-        let mut path = home;
-        path.push(".dev");
-        path.push("config.toml");
-        let path = Utf8PathBuf::from_path_buf(path).map_err(|_| anyhow!("config path must be valid UTF-8"))?;
         Ok(ResolvedConfigPath {
-            path,
+            path: HomeConfigPaths::detect()?.pick(),
             source: ConfigPathSource::HomeDefault,
         })
     }
 
+    fn load_config(&self, path: &Utf8PathBuf) -> Result<DevConfig> {
+        config::load_with_profile(path, self.profile.as_deref())
+    }
+
     fn effective_language(
         &self,
         config: &DevConfig,
@@ -1485,6 +3415,10 @@ impl CliContext {
             .or_else(|| self.language.clone())
             .or_else(|| project_language.map(|s| s.to_owned()))
             .or_else(|| config.default_language.clone())
+            .or_else(|| {
+                let cwd = std::env::current_dir().ok()?;
+                config::detect_languages(&cwd).first().map(|name| (*name).to_owned())
+            })
     }
 }
 
@@ -1495,6 +3429,8 @@ impl From<&Cli> for CliContext {
             file: cli.file.clone(),
             project: cli.project.clone(),
             language: cli.language.clone(),
+            profile: cli.profile.clone(),
+            set: cli.set.clone(),
             dry_run: cli.dry_run,
             verbose: cli.verbose,
             no_color: cli.no_color,
@@ -1507,21 +3443,37 @@ struct AppState {
     config_path: Utf8PathBuf,
     config_source: ConfigPathSource,
     config: DevConfig,
+    /// Project selected by `--project`, the working directory, or `default_project`.
+    project: Option<String>,
     project_language: Option<String>,
-    tasks: TaskIndex,
+    vars: Vars,
+    /// `dev env --env-profile`: the `.env.<name>` layer for this run.
+    env_profile: Option<String>,
+    /// Built on first use; most non-task commands never need it.
+    tasks: OnceCell<TaskIndex>,
 }
 
 impl AppState {
     fn new(ctx: CliContext) -> Result<Self> {
         let resolved = ctx.resolve_config_path()?;
         let config_path = resolved.path;
-        let config_source = resolved.source;
-        let config = config::load_from_path(&config_path)?;
+        // A fresh repo with no config anywhere still gets working pipelines.
+        let (config, config_source) =
+            if resolved.source == ConfigPathSource::HomeDefault && !config_path.exists() {
+                let cwd = std::env::current_dir().context("reading current directory")?;
+                let languages = config::detect_languages(&cwd);
+                (config::builtin_config(&languages)?, ConfigPathSource::BuiltIn)
+            } else {
+                (ctx.load_config(&config_path)?, resolved.source)
+            };
+        config::validate_pipeline_refs(&config)?;
+        let vars = Vars::from_config(&config, &ctx.set)?;
         let config_root = config_root_dir(&config_path);
 
         let requested_project = ctx
             .project
             .clone()
+            .or_else(|| detect_project(&config, &config_root))
             .or_else(|| config.default_project.clone());
         let mut project_language: Option<String> = None;
 
@@ -1553,17 +3505,27 @@ impl AppState {
             project_language = spec.language.clone();
         }
 
-        let tasks = TaskIndex::from_config(&config)?;
         Ok(Self {
             ctx,
             config_path,
             config_source,
             config,
+            project: requested_project,
             project_language,
-            tasks,
+            vars,
+            env_profile: None,
+            tasks: OnceCell::new(),
         })
     }
 
+    fn tasks(&self) -> Result<&TaskIndex> {
+        if let Some(tasks) = self.tasks.get() {
+            return Ok(tasks);
+        }
+        let tasks = TaskIndex::from_config(&self.config)?;
+        Ok(self.tasks.get_or_init(|| tasks))
+    }
+
     fn effective_language(&self, override_lang: Option<String>) -> Option<String> {
         self.ctx
             .effective_language(&self.config, self.project_language.as_deref(), override_lang)
@@ -1573,7 +3535,61 @@ impl AppState {
         let cwd = envfile::current_working_dir()?;
         envfile::locate(&cwd)
     }
+
+    fn env_layered(&self) -> bool {
+        self.config.env.as_ref().and_then(|env| env.layered).unwrap_or(false)
+    }
+
+    /// Files that make up the effective env: `.env` plus `.env.<name>` for
+    /// `--env-profile`; in layered mode also `.env.local` and the `dev env switch` profile.
+    fn env_layers(&self, env_path: &Utf8Path) -> Result<Vec<Utf8PathBuf>> {
+        if !self.env_layered() {
+            return Ok(envfile::layer_paths(env_path, false, self.env_profile.as_deref()));
+        }
+        let profile = match &self.env_profile {
+            Some(profile) => Some(profile.clone()),
+            None => envfile::active_profile(env_path)?,
+        };
+        Ok(envfile::layer_paths(env_path, true, profile.as_deref()))
+    }
+
+    /// Effective `(key, value)` pairs across [`Self::env_layers`], values unquoted.
+    fn env_entries(&self, env_path: &Utf8Path) -> Result<Vec<(String, String)>> {
+        Ok(envfile::resolve_layers(&self.env_layers(env_path)?)?
+            .into_iter()
+            .map(|entry| (entry.key, entry.value))
+            .collect())
+    }
+
+    /// Whether `[env] mask` marks `key` as sensitive (everything is, without `mask`).
+    fn env_masked(&self, key: &str) -> bool {
+        self.config.env.as_ref().is_none_or(|env| env.is_sensitive(key))
+    }
+}
+
+/// Project whose `chdir` contains the current directory; the deepest match wins
+/// so nested projects (`apps/web` inside `apps`) resolve to the inner one.
+fn detect_project(config: &DevConfig, config_root: &Path) -> Option<String> {
+    let cwd = std::env::current_dir().ok()?.canonicalize().ok()?;
+    config
+        .projects
+        .iter()
+        .flatten()
+        .filter_map(|(name, spec)| {
+            let chdir = Path::new(spec.chdir.as_deref()?);
+            let dir = if chdir.is_absolute() {
+                chdir.to_path_buf()
+            } else {
+                config_root.join(chdir)
+            };
+            let dir = dir.canonicalize().ok()?;
+            cwd.starts_with(&dir)
+                .then(|| (dir.components().count(), name.clone()))
+        })
+        .max_by_key(|(depth, _)| *depth)
+        .map(|(_, name)| name)
 }
+
 fn handle_language_set(ctx: &CliContext, name: String) -> Result<()> {
     let resolved = ctx.resolve_config_path()?;
     let path = resolved.path;
@@ -1588,77 +3604,458 @@ fn handle_language_set(ctx: &CliContext, name: String) -> Result<()> {
     Ok(())
 }
 
+/// Directory holding the project's `.dev/`: the config root when a project
+/// config was found, otherwise the current directory.
+fn project_root(ctx: &CliContext) -> Result<Utf8PathBuf> {
+    let resolved = ctx.resolve_config_path()?;
+    let root = match resolved.source {
+        ConfigPathSource::Explicit | ConfigPathSource::Env | ConfigPathSource::Discovered => {
+            config_root_dir(&resolved.path)
+        }
+        ConfigPathSource::HomeDefault | ConfigPathSource::BuiltIn => {
+            std::env::current_dir().context("reading current directory")?
+        }
+    };
+    Utf8PathBuf::from_path_buf(root).map_err(|_| anyhow!("project root must be valid UTF-8"))
+}
+
+fn record_tool_lock(ctx: &CliContext) -> Result<()> {
+    let path = toollock::record(&project_root(ctx)?, ctx.dry_run)?;
+    if !ctx.dry_run {
+        println!("Recorded tool versions in {}", path);
+    }
+    Ok(())
+}
+
+/// `dev setup` is machine-wide; only pin versions when run inside a configured project.
+fn record_project_tool_lock(ctx: &CliContext) -> Result<()> {
+    let resolved = ctx.resolve_config_path()?;
+    match resolved.source {
+        ConfigPathSource::Explicit | ConfigPathSource::Env | ConfigPathSource::Discovered => {
+            record_tool_lock(ctx)
+        }
+        ConfigPathSource::HomeDefault | ConfigPathSource::BuiltIn => Ok(()),
+    }
+}
+
+fn handle_check_tools(ctx: &CliContext, update: bool) -> Result<()> {
+    if update {
+        return record_tool_lock(ctx);
+    }
+    let drifted = toollock::check(&project_root(ctx)?)?;
+    if drifted > 0 {
+        println!(
+            "{} tool(s) differ from the lockfile; reinstall them or run `dev check-tools --update`.",
+            drifted
+        );
+    }
+    Ok(())
+}
+
+fn handle_doctor(ctx: &CliContext, args: DoctorArgs) -> Result<()> {
+    use crate::doctor::{DoctorOptions, run};
+
+    // Doctor must work before a config exists, so only consult it when present.
+    let resolved = ctx.resolve_config_path()?;
+    let language = if resolved.path.exists() {
+        let config = ctx.load_config(&resolved.path)?;
+        ctx.effective_language(&config, None, None)
+    } else {
+        ctx.language.clone()
+    };
+
+    run(&DoctorOptions {
+        language,
+        fix: args.fix,
+        dry_run: ctx.dry_run,
+    })
+}
+
+fn handle_debug(ctx: &CliContext, command: DebugCommand) -> Result<()> {
+    match command {
+        DebugCommand::Timings => debug_timings(ctx),
+    }
+}
+
+/// Replay each startup phase in isolation and report how long it took, to help
+/// track down slow config discovery on network filesystems.
+fn debug_timings(ctx: &CliContext) -> Result<()> {
+    let total = Instant::now();
+    let mut phases: Vec<(&str, Duration, String)> = Vec::new();
+
+    let start = Instant::now();
+    let _ = Cli::try_parse_from(std::env::args_os());
+    phases.push(("parse args", start.elapsed(), String::new()));
+
+    let start = Instant::now();
+    let resolved = ctx.resolve_config_path()?;
+    phases.push((
+        "config discovery",
+        start.elapsed(),
+        format!("{} ({})", resolved.path, resolved.source.as_str()),
+    ));
+
+    if resolved.path.exists() {
+        let start = Instant::now();
+        let config = ctx.load_config(&resolved.path)?;
+        phases.push(("config load", start.elapsed(), String::new()));
+
+        let start = Instant::now();
+        let tasks = TaskIndex::from_config(&config)?;
+        phases.push((
+            "task index",
+            start.elapsed(),
+            format!("{} tasks", tasks.task_names().count()),
+        ));
+    } else {
+        phases.push(("config load", Duration::ZERO, "no config file".to_owned()));
+    }
+
+    let start = Instant::now();
+    let cwd = envfile::current_working_dir()?;
+    let env_path = envfile::locate(&cwd)?;
+    phases.push(("env discovery", start.elapsed(), env_path.to_string()));
+
+    println!("Startup timings:");
+    for (name, elapsed, detail) in &phases {
+        let line = format!("  {:<18} {:>10.2?}  {}", name, elapsed, detail);
+        println!("{}", line.trim_end());
+    }
+    println!("  {:<18} {:>10.2?}", "total", total.elapsed());
+    Ok(())
+}
+
+/// What `dev walk` writes and where.
+struct WalkOutput {
+    path: Option<PathBuf>,
+    format: WalkFormat,
+    max_tokens: Option<usize>,
+    split_size: Option<crate::walk::SplitSize>,
+    clipboard: bool,
+}
+
 fn handle_walk(
     ctx: &CliContext,
     directory: PathBuf,
-    output: PathBuf,
-    _format: String,
-    max_depth: u32,
-    no_content: bool,
-    extensions: Option<Vec<String>>,
-    include_hidden: bool,
+    opts: crate::walk::WalkOptions,
+    output: WalkOutput,
 ) -> Result<()> {
-    use crate::walk::{WalkOptions, generate_manifest};
+    use crate::walk::{Manifest, fit_budget, render_json, render_markdown, render_yaml, scan, split};
 
+    let WalkOutput { path, format, max_tokens, split_size, clipboard } = output;
+    if max_tokens == Some(0) {
+        bail!("--max-tokens must be at least 1");
+    }
+    let output = path.unwrap_or_else(|| match format {
+        WalkFormat::Markdown => PathBuf::from("manifest.md"),
+        WalkFormat::Json => PathBuf::from("manifest.json"),
+        WalkFormat::Yaml => PathBuf::from("manifest.yaml"),
+    });
+    // `-o -` streams the manifest to stdout, so everything else goes to stderr.
+    let to_stdout = output == Path::new("-");
+    if to_stdout && split_size.is_some() {
+        bail!("--split-size writes numbered files; it cannot be combined with `-o -`");
+    }
+    if to_stdout && opts.record_hashes {
+        bail!("--record-hashes writes next to the manifest; it cannot be combined with `-o -`");
+    }
+    let log = |message: String| {
+        if to_stdout {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    };
     if ctx.dry_run {
-        println!("[dry-run] Generate manifest for {} -> {}", directory.display(), output.display());
+        let target = if to_stdout { "stdout".to_string() } else { output.display().to_string() };
+        log(format!("[dry-run] Generate manifest for {} -> {}", directory.display(), target));
+        if let Some(size) = split_size {
+            log(format!("[dry-run] Split into parts of at most {}, indexed in {}", size, output.display()));
+        }
+        if opts.record_hashes {
+            log(format!("[dry-run] Record file hashes in {}", crate::walk::hashes_path(&output).display()));
+        }
+        if clipboard {
+            log("[dry-run] Copy manifest to clipboard".to_string());
+        }
         return Ok(());
     }
 
-    let opts = WalkOptions {
-        max_depth: max_depth as usize,
-        include_content: !no_content,
-        extensions,
-        ignore_hidden: !include_hidden,
+    let render = |manifest: &Manifest| -> Result<String> {
+        Ok(match format {
+            WalkFormat::Markdown => render_markdown(manifest),
+            WalkFormat::Json => render_json(manifest)?,
+            WalkFormat::Yaml => render_yaml(manifest),
+        })
     };
 
-    println!("Generating directory manifest...");
-    let manifest = generate_manifest(&directory, opts)?;
-    
-    std::fs::write(&output, manifest)?;
+    log("Generating directory manifest...".to_string());
+    let mut manifest = scan(&directory, &opts)?;
+    if let Some(max_tokens) = max_tokens {
+        fit_budget(&mut manifest, max_tokens, render)?;
+        if let Some(budget) = manifest.budget.as_ref().filter(|budget| !budget.omitted.is_empty() || !budget.truncated.is_empty()) {
+            log(format!(
+                "To fit {} tokens: omitted {} file(s), truncated {}",
+                max_tokens,
+                budget.omitted.len(),
+                budget.truncated.len()
+            ));
+            for path in &budget.omitted {
+                log(format!("  omitted   {}", path));
+            }
+            for path in &budget.truncated {
+                log(format!("  truncated {}", path));
+            }
+        }
+    }
+
+    // Rewriting a split manifest's index would orphan its parts.
+    if !to_stdout
+        && let Some(parts) = std::fs::read_to_string(&output).ok().as_deref().and_then(crate::walk::index_parts)
+    {
+        remove_listed_parts(&output, &parts)?;
+    }
+    if opts.record_hashes {
+        let hashes = crate::walk::hashes_path(&output);
+        std::fs::write(&hashes, serde_json::to_string_pretty(&manifest.hashes)? + "\n")
+            .with_context(|| format!("cannot write {}", hashes.display()))?;
+        log(format!("File hashes recorded: {}", hashes.display()));
+    }
+    if let Some(size) = split_size {
+        let parts = split(&manifest, size, render)?;
+        if parts.len() > 1 {
+            return write_manifest_parts(&manifest, &parts, size, &output, format, render);
+        }
+    }
+    let manifest = render(&manifest)?;
     
-    println!("Directory map generated successfully: {}", output.display());
+    if to_stdout {
+        write_stdout(&manifest)?;
+    } else {
+        std::fs::write(&output, &manifest)?;
+        log(format!("Directory map generated successfully: {}", output.display()));
+    }
+    if clipboard {
+        log(copy_to_clipboard("Manifest", &manifest)?);
+    }
     
     Ok(())
 }
 
+/// Writes a split manifest: `manifest-01.md`, `manifest-02.md`, ... next to `output`,
+/// which becomes their index.
+fn write_manifest_parts(
+    manifest: &crate::walk::Manifest,
+    parts: &[crate::walk::Manifest],
+    size: crate::walk::SplitSize,
+    output: &Path,
+    format: WalkFormat,
+    render: impl Fn(&crate::walk::Manifest) -> Result<String>,
+) -> Result<()> {
+    let stem = output
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "manifest".to_string());
+    let extension = output
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    let width = parts.len().to_string().len().max(2);
+
+    let mut written = Vec::new();
+    for (index, part) in parts.iter().enumerate() {
+        let name = format!("{}-{:0width$}{}", stem, index + 1, extension, width = width);
+        let text = render(part)?;
+        let path = output.with_file_name(&name);
+        std::fs::write(&path, &text)?;
+        println!("Manifest part {} of {}: {}", index + 1, parts.len(), path.display());
+        if size.measure(&text) > size.limit() {
+            println!("  warning: over {}; a single file in it is larger than that", size);
+        }
+        written.push((name, part, text));
+    }
+
+    let index = crate::walk::Index::new(manifest, size, &written);
+    let index = match format {
+        WalkFormat::Markdown => index.render_markdown(),
+        WalkFormat::Json => index.render_json()?,
+        WalkFormat::Yaml => index.render_yaml(),
+    };
+    std::fs::write(output, index)?;
+    println!("Manifest index: {}", output.display());
+    Ok(())
+}
+
+/// Removes the `parts` an index at `output` lists, leaving anything that is not a plain
+/// file name next to it.
+fn remove_listed_parts(output: &Path, parts: &[String]) -> Result<()> {
+    for part in parts {
+        let name = Some(std::ffi::OsStr::new(part));
+        if Path::new(part).file_name() != name || output.file_name() == name {
+            continue;
+        }
+        let path = output.with_file_name(part);
+        if path.is_file() {
+            std::fs::remove_file(&path).with_context(|| format!("cannot remove stale part {}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Copies `text` and returns the line reporting it.
+fn copy_to_clipboard(what: &str, text: &str) -> Result<String> {
+    let tool = crate::clipboard::copy(text)?;
+    Ok(format!("{} copied to clipboard ({}, ~{} tokens)", what, tool, crate::review::estimate_tokens(text)))
+}
+
+/// Writes `text` to stdout; a reader that stops early (`| head`) is not an error.
+fn write_stdout(text: &str) -> Result<()> {
+    use std::io::Write;
+
+    let mut stdout = io::stdout().lock();
+    match stdout.write_all(text.as_bytes()).and_then(|()| stdout.flush()) {
+        Err(err) if err.kind() != io::ErrorKind::BrokenPipe => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+/// `[review]` defaults; review works without a config file, so a missing one means none.
+fn review_config(ctx: &CliContext) -> Result<config::ReviewConfig> {
+    let resolved = ctx.resolve_config_path()?;
+    if !resolved.path.exists() {
+        return Ok(Default::default());
+    }
+    Ok(ctx.load_config(&resolved.path)?.review.unwrap_or_default())
+}
+
+/// The prompt a Markdown report is wrapped in: `--prompt`, else `[review] prompt`,
+/// else the inline `[review] preamble`/`epilogue`. Only an explicit flag is an error
+/// for other formats; configured prompts just don't apply to them.
+fn review_prompt(
+    flag: Option<PathBuf>,
+    defaults: &config::ReviewConfig,
+    format: ReviewFormat,
+) -> Result<Option<crate::review::Prompt>> {
+    use crate::review::Prompt;
+
+    if format != ReviewFormat::Md {
+        if flag.is_some() {
+            bail!("--prompt only applies to --format md");
+        }
+        return Ok(None);
+    }
+    let prompt = match flag.or_else(|| defaults.prompt.as_ref().map(PathBuf::from)) {
+        Some(path) => Prompt::parse(
+            &fs::read_to_string(&path)
+                .with_context(|| format!("failed to read prompt template {}", path.display()))?,
+        ),
+        None => Prompt {
+            preamble: defaults.preamble.as_deref().unwrap_or_default().trim().to_string(),
+            epilogue: defaults.epilogue.as_deref().unwrap_or_default().trim().to_string(),
+        },
+    };
+    Ok((!prompt.is_empty()).then_some(prompt))
+}
+
 fn handle_review(
     ctx: &CliContext,
     output: Option<PathBuf>,
-    include_working: bool,
-    main: bool,
+    format: ReviewFormat,
+    opts: crate::review::ReviewOptions,
+    max_tokens: Option<usize>,
+    prompt: Option<crate::review::Prompt>,
+    clipboard: bool,
 ) -> Result<()> {
-    use crate::review::{ReviewOptions, generate_review, get_repo_root};
+    use crate::review::{build_report, get_repo_root, render_markdown, render_markdown_parts};
+
+    if let Some(max_tokens) = max_tokens {
+        if max_tokens == 0 {
+            bail!("--max-tokens must be at least 1");
+        }
+        if format != ReviewFormat::Md {
+            bail!("--max-tokens only applies to --format md");
+        }
+        if let Some(prompt) = prompt.as_ref().filter(|prompt| prompt.tokens() >= max_tokens) {
+            bail!(
+                "--max-tokens {} leaves no room for the report after the ~{}-token prompt",
+                max_tokens,
+                prompt.tokens()
+            );
+        }
+    }
 
+    let output_path = output.unwrap_or_else(|| match format {
+        ReviewFormat::Md => PathBuf::from("review-report.md"),
+        ReviewFormat::Html => PathBuf::from("review-report.html"),
+        ReviewFormat::Json => PathBuf::from("review-report.json"),
+    });
     if ctx.dry_run {
-        let output_path = output.as_ref()
-            .map(|p| p.display().to_string())
-            .unwrap_or_else(|| ".bako/review-report.md".to_string());
-        println!("[dry-run] Generate review report -> {}", output_path);
+        println!("[dry-run] Generate review report -> {}", output_path.display());
+        if clipboard {
+            println!("[dry-run] Copy review report to clipboard");
+        }
         return Ok(());
     }
 
-    let opts = ReviewOptions {
-        include_working,
-        compare_main: main,
-    };
-
     let repo_root = get_repo_root()?;
     
     println!("Generating code review report...");
-    let report = generate_review(opts, &repo_root)?;
-    
-    let output_path = output.unwrap_or_else(|| {
-        PathBuf::from("review-report.md")
-    });
+    let report = build_report(&opts, &repo_root)?;
+    // The prompt's share of the budget is reserved in every part, to keep it simple.
+    let reserved = prompt.as_ref().map_or(0, |prompt| prompt.tokens());
+    let parts = match (format, max_tokens) {
+        (ReviewFormat::Md, Some(max_tokens)) => {
+            render_markdown_parts(&report, max_tokens - reserved)
+        }
+        (ReviewFormat::Md, None) => vec![render_markdown(&report)],
+        (ReviewFormat::Html, _) => vec![crate::reviewhtml::render(&report)],
+        (ReviewFormat::Json, _) => vec![crate::review::render_json(&report)?],
+    };
+    let parts = match &prompt {
+        Some(prompt) => prompt.wrap(parts),
+        None => parts,
+    };
     
     if let Some(parent) = output_path.parent() {
         std::fs::create_dir_all(parent)?;
     }
-    
-    std::fs::write(&output_path, report)?;
-    
-    println!("Review report generated successfully: {}", output_path.display());
+
+    // Rewriting a chunked report's index would orphan its parts.
+    if matches!(format, ReviewFormat::Md)
+        && max_tokens.is_some()
+        && let Some(stale) = std::fs::read_to_string(&output_path).ok().as_deref().and_then(crate::review::index_parts)
+    {
+        remove_listed_parts(&output_path, &stale)?;
+    }
+    if let [report] = parts.as_slice() {
+        std::fs::write(&output_path, report)?;
+        println!("Review report generated successfully: {}", output_path.display());
+        if clipboard {
+            println!("{}", copy_to_clipboard("Review report", report)?);
+        }
+        return Ok(());
+    }
+
+    // review-report.md -> review-report.1.md, review-report.2.md, ...
+    let stem = output_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "review-report".to_string());
+    let extension = output_path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    let mut written = Vec::new();
+    for (index, part) in parts.iter().enumerate() {
+        let name = format!("{}.{}{}", stem, index + 1, extension);
+        let part_path = output_path.with_file_name(&name);
+        std::fs::write(&part_path, part)?;
+        println!("Review report part {} of {}: {}", index + 1, parts.len(), part_path.display());
+        written.push((name, part.clone()));
+    }
+    let max_tokens = max_tokens.context("only --max-tokens splits a report")?;
+    std::fs::write(&output_path, crate::review::render_parts_index(&report, max_tokens, &written))?;
+    println!("Review report index: {}", output_path.display());
     
     Ok(())
 }
@@ -1698,6 +4095,7 @@ fn handle_setup(
             // Default to skip_installed=true unless explicitly set to false via root flag
             let skip = if root_skip_installed { true } else { true };
             crate::setup::run_setup(&setup_ctx, components, skip, root_no_deps)?;
+            record_project_tool_lock(ctx)?;
         }
         Some(SetupCommand::Run {
             components: component_names,
@@ -1712,6 +4110,7 @@ fn handle_setup(
             let components = components?;
             // Subcommand flags take precedence over root flags
             crate::setup::run_setup(&setup_ctx, components, skip_installed, no_deps)?;
+            record_project_tool_lock(ctx)?;
         }
         Some(SetupCommand::Inference {
             service,
@@ -1862,6 +4261,7 @@ fn handle_setup(
             let components = Component::all();
             // Subcommand flags take precedence over root flags
             crate::setup::run_setup(&setup_ctx, components, skip_installed, no_deps)?;
+            record_project_tool_lock(ctx)?;
         }
         Some(SetupCommand::Status) => {
             crate::setup::show_status(&setup_ctx)?;
@@ -15,6 +15,21 @@ pub fn install(language: &str) -> Result<()> {
     }
 }
 
+/// Scaffold files for `language` that are not present in the current directory.
+pub fn missing_files(language: &str) -> Result<Vec<&'static str>> {
+    let expected = match language {
+        "rust" => rust::FILES,
+        "python" => python::FILES,
+        "typescript" | "ts" => typescript::FILES,
+        other => bail!("unsupported language scaffold: {other}"),
+    };
+    Ok(expected
+        .iter()
+        .copied()
+        .filter(|path| !Utf8Path::new(path).exists())
+        .collect())
+}
+
 pub fn write_template(destination: &Utf8Path, template: &str) -> Result<()> {
     templates::write_template(destination, template)
 }
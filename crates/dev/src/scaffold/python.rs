@@ -9,6 +9,9 @@ const MYPY: &str = "mypy.ini";
 const PRECOMMIT: &str = ".pre-commit-config.yaml";
 const CI_WORKFLOW: &str = ".github/workflows/ci.yml";
 
+/// Files written by `install`, used by `dev doctor` to spot missing scaffolds.
+pub const FILES: &[&str] = &[RUFF, MYPY, PRECOMMIT, CI_WORKFLOW];
+
 pub fn install() -> Result<()> {
     ensure_file(RUFF, "python/ruff.toml")?;
     ensure_file(MYPY, "python/mypy.ini")?;
@@ -8,6 +8,9 @@ const CARGO_CONFIG: &str = ".cargo/config.toml";
 const DENY_FILE: &str = "deny.toml";
 const CI_WORKFLOW: &str = ".github/workflows/ci.yml";
 
+/// Files written by `install`, used by `dev doctor` to spot missing scaffolds.
+pub const FILES: &[&str] = &[CARGO_CONFIG, DENY_FILE, CI_WORKFLOW];
+
 pub fn install() -> Result<()> {
     ensure_file(CARGO_CONFIG, "rust/cargo-config.toml")?;
     ensure_file(DENY_FILE, "rust/deny.toml")?;
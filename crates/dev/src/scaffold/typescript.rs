@@ -10,6 +10,9 @@ const VITEST: &str = "vitest.config.ts";
 const PRETTIER: &str = ".prettierrc.json";
 const CI_WORKFLOW: &str = ".github/workflows/ci.yml";
 
+/// Files written by `install`, used by `dev doctor` to spot missing scaffolds.
+pub const FILES: &[&str] = &[ESLINT, TSCONFIG, VITEST, PRETTIER, CI_WORKFLOW];
+
 pub fn install() -> Result<()> {
     ensure_file(ESLINT, "typescript/eslint.config.ts")?;
     ensure_file(TSCONFIG, "typescript/tsconfig.json")?;
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::process::Command;
+
+use anyhow::{Result, bail};
+use camino::Utf8PathBuf;
+
+use crate::config::ConfigValue;
+use crate::{envcrypt, envfile};
+
+/// Service name used when looking secrets up in the OS keychain.
+const KEYCHAIN_SERVICE: &str = "dev";
+
+/// Resolves `{ secret = "NAME" }` references from the nearest `.env` file first,
+/// then the OS keychain. Lookups are cached for the lifetime of the resolver.
+#[derive(Default)]
+pub struct SecretResolver {
+    env: Option<HashMap<String, String>>,
+    env_path: Option<Utf8PathBuf>,
+    cache: HashMap<String, String>,
+}
+
+impl SecretResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn resolve(&mut self, name: &str) -> Result<String> {
+        if let Some(value) = self.cache.get(name) {
+            return Ok(value.clone());
+        }
+
+        let value = match self.env_value(name)? {
+            Some(value) => value,
+            None => match keychain_lookup(name) {
+                Some(value) => value,
+                None => {
+                    let env_display = self
+                        .env_path
+                        .as_ref()
+                        .map(|path| path.to_string())
+                        .unwrap_or_else(|| ".env".to_owned());
+                    bail!(
+                        "secret `{}` not found in {} or the OS keychain (service `{}`)",
+                        name,
+                        env_display,
+                        KEYCHAIN_SERVICE
+                    );
+                }
+            },
+        };
+
+        self.cache.insert(name.to_owned(), value.clone());
+        Ok(value)
+    }
+
+    pub fn resolve_value(&mut self, value: &ConfigValue) -> Result<String> {
+        match value {
+            ConfigValue::Plain(value) => Ok(value.clone()),
+            ConfigValue::Secret { secret } => self.resolve(secret),
+        }
+    }
+
+    fn env_value(&mut self, name: &str) -> Result<Option<String>> {
+        if self.env.is_none() {
+            let path = match self.env_path.clone() {
+                Some(path) => path,
+                None => envfile::locate(&envfile::current_working_dir()?)?,
+            };
+            // `EnvFile::load` creates missing files; secret lookups must not.
+            let entries = if path.exists() || envcrypt::encrypted_path(&path).exists() {
+                envfile::EnvFile::load(&path)?
+                    .entries()
+                    .map(|(key, value)| (key.to_owned(), envfile::unquote(value)))
+                    .collect()
+            } else {
+                HashMap::new()
+            };
+            self.env = Some(entries);
+            self.env_path = Some(path);
+        }
+
+        Ok(self
+            .env
+            .as_ref()
+            .and_then(|entries| entries.get(name))
+            .filter(|value| !value.is_empty())
+            .cloned())
+    }
+}
+
+fn keychain_lookup(name: &str) -> Option<String> {
+    let output = if cfg!(target_os = "macos") {
+        Command::new("security")
+            .args(["find-generic-password", "-s", KEYCHAIN_SERVICE, "-a", name, "-w"])
+            .output()
+    } else {
+        Command::new("secret-tool")
+            .args(["lookup", "service", KEYCHAIN_SERVICE, "account", name])
+            .output()
+    };
+
+    let output = output.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let value = String::from_utf8_lossy(&output.stdout)
+        .trim_end_matches(['\n', '\r'])
+        .to_owned();
+    if value.is_empty() { None } else { Some(value) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_secrets_from_the_env_file_and_reports_missing_ones() {
+        let dir = std::env::temp_dir().join(format!("dev-secrets-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = Utf8PathBuf::from_path_buf(dir.join(".env")).unwrap();
+        std::fs::write(&path, "API_TOKEN=abc123\nQUOTED=\"a b\"\nEMPTY=\n").unwrap();
+
+        let mut resolver = SecretResolver {
+            env_path: Some(path.clone()),
+            ..SecretResolver::new()
+        };
+        assert_eq!(resolver.resolve("API_TOKEN").unwrap(), "abc123");
+        assert_eq!(resolver.resolve("QUOTED").unwrap(), "a b");
+        let secret = ConfigValue::Secret {
+            secret: "API_TOKEN".to_owned(),
+        };
+        assert_eq!(resolver.resolve_value(&secret).unwrap(), "abc123");
+        assert_eq!(
+            resolver
+                .resolve_value(&ConfigValue::Plain("inline".to_owned()))
+                .unwrap(),
+            "inline"
+        );
+
+        // Cached: later changes to the file are not picked up mid-run.
+        std::fs::write(&path, "API_TOKEN=changed\n").unwrap();
+        assert_eq!(resolver.resolve("API_TOKEN").unwrap(), "abc123");
+
+        // Empty values count as missing, so the keychain is consulted and the error names
+        // both places that were searched.
+        for name in ["EMPTY", "DEV_TEST_MISSING_SECRET_7F3A"] {
+            let err = resolver.resolve(name).unwrap_err().to_string();
+            assert!(err.contains(&format!("secret `{}` not found", name)), "{}", err);
+            assert!(err.contains(path.as_str()) && err.contains("OS keychain"), "{}", err);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -2,23 +2,61 @@ use std::collections::BTreeMap;
 
 use anyhow::{Context, Result, bail};
 
-use crate::config::{DevConfig, Task as TaskConfig};
+use crate::config::{
+    ConfigKey, ConfigSource, ConfigValue, DevConfig, Task as TaskConfig, source_hint,
+};
 
 #[derive(Debug, Clone)]
 pub struct CommandSpec {
     pub origin: String,
-    pub argv: Vec<String>,
+    pub argv: Vec<CommandArg>,
+    pub env: BTreeMap<String, ConfigValue>,
     pub allow_fail: bool,
 }
 
+/// A single argv entry; secrets stay unresolved until the command is executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandArg {
+    Literal(String),
+    Secret(String),
+}
+
+impl CommandArg {
+    /// Display form that never reveals secret values.
+    pub fn display(&self) -> String {
+        match self {
+            CommandArg::Literal(value) => value.clone(),
+            CommandArg::Secret(name) => format!("<secret:{name}>"),
+        }
+    }
+}
+
+/// Task names that differ between two indexes, e.g. before and after a config reload.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TaskDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl TaskDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
 #[derive(Default)]
 pub struct TaskIndex {
     tasks: BTreeMap<String, Task>,
+    source: Option<ConfigSource>,
 }
 
 impl TaskIndex {
     pub fn from_config(config: &DevConfig) -> Result<Self> {
-        let mut index = TaskIndex::default();
+        let mut index = TaskIndex {
+            source: config.source.clone(),
+            ..TaskIndex::default()
+        };
         let Some(tasks) = &config.tasks else {
             return Ok(index);
         };
@@ -26,7 +64,8 @@ impl TaskIndex {
         for (name, task) in tasks {
             index.tasks.insert(
                 name.clone(),
-                parse_task(name, task).with_context(|| format!("parsing task `{name}`"))?,
+                parse_task(name, task, config.source.as_ref())
+                    .with_context(|| format!("parsing task `{name}`"))?,
             );
         }
 
@@ -41,15 +80,38 @@ impl TaskIndex {
         self.tasks.keys()
     }
 
+    pub fn diff(&self, newer: &TaskIndex) -> TaskDiff {
+        let mut diff = TaskDiff::default();
+        for (name, task) in &self.tasks {
+            match newer.tasks.get(name) {
+                None => diff.removed.push(name.clone()),
+                Some(updated) if updated != task => diff.changed.push(name.clone()),
+                Some(_) => {}
+            }
+        }
+        diff.added = newer
+            .tasks
+            .keys()
+            .filter(|name| !self.tasks.contains_key(*name))
+            .cloned()
+            .collect();
+        diff
+    }
+
+    pub fn description(&self, task: &str) -> Option<&str> {
+        self.tasks.get(task)?.description.as_deref()
+    }
+
     pub fn flatten(&self, task: &str) -> Result<Vec<CommandSpec>> {
         let mut stack = Vec::new();
-        self.flatten_internal(task, false, &mut stack)
+        self.flatten_internal(task, false, &BTreeMap::new(), &mut stack)
     }
 
     fn flatten_internal(
         &self,
         task: &str,
         inherited_allow_fail: bool,
+        inherited_env: &BTreeMap<String, ConfigValue>,
         stack: &mut Vec<String>,
     ) -> Result<Vec<CommandSpec>> {
         if stack.contains(&task.to_owned()) {
@@ -70,20 +132,39 @@ impl TaskIndex {
         stack.push(task.to_owned());
         let mut commands = Vec::new();
         let allow_fail = inherited_allow_fail || definition.allow_fail;
-        for step in &definition.steps {
+        // A task's own env entries win over those inherited from referencing tasks.
+        let mut env = inherited_env.clone();
+        env.extend(definition.env.clone());
+        for (idx, step) in definition.steps.iter().enumerate() {
+            let location = [
+                ConfigKey::Key("tasks"),
+                ConfigKey::Key(task),
+                ConfigKey::Key("commands"),
+                ConfigKey::Index(idx),
+            ];
             match step {
                 TaskStep::Command(argv) => {
                     if argv.is_empty() {
-                        bail!("task `{task}` contains an empty command");
+                        bail!(
+                            "task `{task}` contains an empty command{}",
+                            source_hint(self.source.as_ref(), &location)
+                        );
                     }
                     commands.push(CommandSpec {
                         origin: task.to_owned(),
                         argv: argv.clone(),
+                        env: env.clone(),
                         allow_fail,
                     });
                 }
                 TaskStep::TaskRef(name) => {
-                    let mut nested = self.flatten_internal(name, allow_fail, stack)?;
+                    if !self.tasks.contains_key(name) {
+                        bail!(
+                            "task `{task}` references unknown task `{name}`{}",
+                            source_hint(self.source.as_ref(), &location)
+                        );
+                    }
+                    let mut nested = self.flatten_internal(name, allow_fail, &env, stack)?;
                     commands.append(&mut nested);
                 }
             }
@@ -93,41 +174,157 @@ impl TaskIndex {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 struct Task {
+    pub description: Option<String>,
     pub allow_fail: bool,
+    pub env: BTreeMap<String, ConfigValue>,
     pub steps: Vec<TaskStep>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 enum TaskStep {
-    Command(Vec<String>),
+    Command(Vec<CommandArg>),
     TaskRef(String),
 }
 
-fn parse_task(name: &str, task: &TaskConfig) -> Result<Task> {
+fn parse_task(name: &str, task: &TaskConfig, source: Option<&ConfigSource>) -> Result<Task> {
     let mut steps = Vec::new();
-    for value in &task.commands {
+    for (idx, value) in task.commands.iter().enumerate() {
+        let location = [
+            ConfigKey::Key("tasks"),
+            ConfigKey::Key(name),
+            ConfigKey::Key("commands"),
+            ConfigKey::Index(idx),
+        ];
         match value {
             toml::Value::String(reference) => steps.push(TaskStep::TaskRef(reference.clone())),
             toml::Value::Array(items) => {
                 let mut command = Vec::new();
-                for item in items {
-                    let Some(arg) = item.as_str() else {
-                        bail!("task `{name}` contains non-string command argument: {item:?}");
+                for (arg_idx, item) in items.iter().enumerate() {
+                    if let Some(arg) = item.as_str() {
+                        command.push(CommandArg::Literal(arg.to_owned()));
+                        continue;
+                    }
+                    let secret = item
+                        .as_table()
+                        .filter(|table| table.len() == 1)
+                        .and_then(|table| table.get("secret"))
+                        .and_then(toml::Value::as_str);
+                    let Some(secret) = secret else {
+                        let mut arg_location = location.to_vec();
+                        arg_location.push(ConfigKey::Index(arg_idx));
+                        bail!(
+                            "task `{name}` contains non-string command argument: {item:?}{}",
+                            source_hint(source, &arg_location)
+                        );
                     };
-                    command.push(arg.to_owned());
+                    command.push(CommandArg::Secret(secret.to_owned()));
                 }
                 steps.push(TaskStep::Command(command));
             }
             other => {
-                bail!("task `{name}` contains unsupported command value: {other:?}");
+                bail!(
+                    "task `{name}` contains unsupported command value: {other:?}{}",
+                    source_hint(source, &location)
+                );
             }
         }
     }
 
     Ok(Task {
+        description: task.description.clone(),
         allow_fail: task.allow_fail,
+        env: task.env.clone(),
         steps,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(raw: &str) -> TaskIndex {
+        let config: DevConfig = toml::from_str(raw).unwrap();
+        TaskIndex::from_config(&config).unwrap()
+    }
+
+    #[test]
+    fn diff_reports_added_removed_and_changed_tasks() {
+        let before = index(
+            r#"
+            [tasks.build]
+            commands = [["cargo", "build"]]
+            [tasks.lint]
+            commands = [["cargo", "clippy"]]
+            [tasks.old]
+            commands = [["true"]]
+            "#,
+        );
+        let after = index(
+            r#"
+            [tasks.build]
+            commands = [["cargo", "build"]]
+            [tasks.lint]
+            commands = [["cargo", "clippy", "--all-targets"]]
+            [tasks.new]
+            commands = [["true"]]
+            "#,
+        );
+        assert_eq!(
+            before.diff(&after),
+            TaskDiff {
+                added: vec!["new".to_owned()],
+                removed: vec!["old".to_owned()],
+                changed: vec!["lint".to_owned()],
+            }
+        );
+    }
+
+    #[test]
+    fn parses_secret_arguments_without_resolving_them() {
+        let tasks = index(
+            r#"
+            [tasks.deploy]
+            commands = [["curl", "-H", { secret = "API_TOKEN" }], "notify"]
+            [tasks.deploy.env]
+            TOKEN = { secret = "API_TOKEN" }
+            REGION = "eu"
+            [tasks.notify]
+            commands = [["echo", "done"]]
+            "#,
+        );
+        let commands = tasks.flatten("deploy").unwrap();
+        assert_eq!(
+            commands[0].argv,
+            [
+                CommandArg::Literal("curl".to_owned()),
+                CommandArg::Literal("-H".to_owned()),
+                CommandArg::Secret("API_TOKEN".to_owned()),
+            ]
+        );
+        assert_eq!(commands[0].argv[2].display(), "<secret:API_TOKEN>");
+        // Referenced tasks inherit the env, secrets included, still unresolved.
+        assert_eq!(
+            commands[1].env["TOKEN"],
+            ConfigValue::Secret {
+                secret: "API_TOKEN".to_owned()
+            }
+        );
+
+        for arg in [
+            "{ secret = 1 }",
+            r#"{ secret = "A", extra = "b" }"#,
+            r#"{ name = "A" }"#,
+        ] {
+            let raw = format!("[tasks.bad]\ncommands = [[\"echo\", {}]]", arg);
+            let config: DevConfig = toml::from_str(&raw).unwrap();
+            let err = TaskIndex::from_config(&config).err().unwrap();
+            assert!(
+                format!("{:#}", err).contains("non-string command argument"),
+                "{:#}",
+                err
+            );
+        }
+    }
+}
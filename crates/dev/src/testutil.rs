@@ -0,0 +1,101 @@
+//! Fixtures for tests that drive real git repositories through the current directory.
+
+use camino::{Utf8Path, Utf8PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Serializes tests that change the process-wide current directory.
+pub fn cwd_lock() -> MutexGuard<'static, ()> {
+    static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| Mutex::new(()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+pub fn unique_temp_dir(label: &str) -> Utf8PathBuf {
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let dir = std::env::temp_dir().join(format!("devkit-{label}-{ts}"));
+    std::fs::create_dir_all(&dir).unwrap();
+    Utf8PathBuf::from_path_buf(dir).unwrap()
+}
+
+/// Run git in `dir` with a fixed identity and return its trimmed stdout.
+pub fn git(dir: &Utf8Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_AUTHOR_NAME", "Test")
+        .env("GIT_AUTHOR_EMAIL", "test@example.com")
+        .env("GIT_COMMITTER_NAME", "Test")
+        .env("GIT_COMMITTER_EMAIL", "test@example.com")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "git {} failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// Commit a file in `dir` and return the new commit's sha.
+pub fn commit_file(dir: &Utf8Path, path: &str, content: &str) -> String {
+    std::fs::write(dir.join(path), content).unwrap();
+    git(dir, &["add", path]);
+    git(
+        dir,
+        &[
+            "commit",
+            "--quiet",
+            "--no-gpg-sign",
+            "-m",
+            &format!("update {path}"),
+        ],
+    );
+    git(dir, &["rev-parse", "HEAD"])
+}
+
+/// A working clone of a bare `origin`, with `main` committed and pushed.
+pub fn repo_with_origin() -> Utf8PathBuf {
+    let root = unique_temp_dir("repo");
+    let origin = root.join("origin.git");
+    let work = root.join("work");
+    git(
+        &root,
+        &["init", "--quiet", "--bare", "-b", "main", origin.as_str()],
+    );
+    git(&root, &["clone", "--quiet", origin.as_str(), work.as_str()]);
+    git(&work, &["checkout", "--quiet", "-b", "main"]);
+    git(&work, &["config", "user.name", "Test"]);
+    git(&work, &["config", "user.email", "test@example.com"]);
+    git(&work, &["config", "commit.gpgsign", "false"]);
+    commit_file(&work, "README.md", "hello\n");
+    git(&work, &["push", "--quiet", "-u", "origin", "main"]);
+    work
+}
+
+/// Changes into `dir` until dropped, holding [`cwd_lock`] meanwhile.
+pub struct InDir {
+    old: std::path::PathBuf,
+    _guard: MutexGuard<'static, ()>,
+}
+
+impl InDir {
+    pub fn new(dir: &Utf8Path) -> Self {
+        let guard = cwd_lock();
+        let old = std::env::current_dir().unwrap();
+        std::env::set_current_dir(dir).unwrap();
+        Self { old, _guard: guard }
+    }
+}
+
+impl Drop for InDir {
+    fn drop(&mut self) {
+        let _ = std::env::set_current_dir(&self.old);
+    }
+}
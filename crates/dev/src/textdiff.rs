@@ -0,0 +1,156 @@
+/// Lines of unchanged context shown around each change, as in `git diff`.
+const CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// A `git diff`-style unified diff from `old` to `new`, labelled `a/<path>` and `b/<path>`;
+/// empty when the texts are identical.
+pub fn unified(path: &str, old: &str, new: &str) -> String {
+    if old == new {
+        return String::new();
+    }
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let ops = line_ops(&a, &b);
+
+    // Positions in `ops` of the old and new line each op starts at.
+    let mut starts = Vec::with_capacity(ops.len() + 1);
+    let (mut i, mut j) = (0, 0);
+    for op in &ops {
+        starts.push((i, j));
+        match op {
+            Op::Equal => {
+                i += 1;
+                j += 1;
+            }
+            Op::Delete => i += 1,
+            Op::Insert => j += 1,
+        }
+    }
+    starts.push((i, j));
+
+    let changes: Vec<usize> = (0..ops.len()).filter(|&k| ops[k] != Op::Equal).collect();
+    let mut out = format!("--- a/{}\n+++ b/{}\n", path, path);
+    let mut index = 0;
+    while index < changes.len() {
+        // Merge changes whose context would overlap into one hunk.
+        let mut last = index;
+        while last + 1 < changes.len() && changes[last + 1] - changes[last] <= 2 * CONTEXT + 1 {
+            last += 1;
+        }
+        let from = changes[index].saturating_sub(CONTEXT);
+        let to = (changes[last] + CONTEXT + 1).min(ops.len());
+        let (old_from, new_from) = starts[from];
+        let (old_to, new_to) = starts[to];
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(old_from, old_to - old_from),
+            range(new_from, new_to - new_from)
+        ));
+        for k in from..to {
+            let (i, j) = starts[k];
+            match ops[k] {
+                Op::Equal => out.push_str(&format!(" {}\n", a[i])),
+                Op::Delete => out.push_str(&format!("-{}\n", a[i])),
+                Op::Insert => out.push_str(&format!("+{}\n", b[j])),
+            }
+        }
+        index = last + 1;
+    }
+    out
+}
+
+/// `start,count` as in a hunk header: 1-based, and the line before an empty range.
+fn range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, count),
+    }
+}
+
+/// Edit script turning `a` into `b`. The common prefix and suffix are matched directly,
+/// so the quadratic LCS only runs over the changed middle (a version line, a new
+/// changelog section).
+fn line_ops(a: &[&str], b: &[&str]) -> Vec<Op> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (mid_a, mid_b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    // lcs[i][j]: longest common subsequence of mid_a[i..] and mid_b[j..].
+    let (n, m) = (mid_a.len(), mid_b.len());
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if mid_a[i] == mid_b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = vec![Op::Equal; prefix];
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && mid_a[i] == mid_b[j] {
+            ops.push(Op::Equal);
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            // Deletions come first on ties, as in `git diff`.
+            ops.push(Op::Delete);
+            i += 1;
+        } else {
+            ops.push(Op::Insert);
+            j += 1;
+        }
+    }
+    ops.extend(std::iter::repeat_n(Op::Equal, suffix));
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_hunks_with_context() {
+        let old = "[package]\nname = \"x\"\nversion = \"1.2.3\"\nedition = \"2024\"\n";
+        let new = "[package]\nname = \"x\"\nversion = \"1.3.0\"\nedition = \"2024\"\n";
+        assert_eq!(
+            unified("Cargo.toml", old, new),
+            "--- a/Cargo.toml\n+++ b/Cargo.toml\n@@ -1,4 +1,4 @@\n [package]\n name = \"x\"\n\
+             -version = \"1.2.3\"\n+version = \"1.3.0\"\n edition = \"2024\"\n"
+        );
+        assert_eq!(unified("Cargo.toml", old, old), "");
+
+        let lines: Vec<String> = (1..=20).map(|n| n.to_string()).collect();
+        let old = lines.join("\n");
+        let mut changed = lines.clone();
+        changed[1] = "two".into();
+        changed[17] = "eighteen".into();
+        let diff = unified("n.txt", &old, &changed.join("\n"));
+        assert!(
+            diff.contains("@@ -1,5 +1,5 @@\n 1\n-2\n+two\n 3\n"),
+            "{}",
+            diff
+        );
+        assert!(diff.contains("@@ -15,6 +15,6 @@\n"), "{}", diff);
+
+        assert_eq!(
+            unified("CHANGELOG.md", "", "# Changelog\n"),
+            "--- a/CHANGELOG.md\n+++ b/CHANGELOG.md\n@@ -0,0 +1 @@\n+# Changelog\n"
+        );
+    }
+}
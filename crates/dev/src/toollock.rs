@@ -0,0 +1,129 @@
+use std::fs;
+use std::process::Command;
+
+use anyhow::{Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use regex::Regex;
+use toml_edit::{DocumentMut, Item, Table, value};
+
+/// Tools whose versions are pinned in `.dev/lock.toml`, with the argv that reports them.
+const TOOLS: &[(&str, &[&str])] = &[
+    ("node", &["node", "--version"]),
+    ("pnpm", &["pnpm", "--version"]),
+    ("uv", &["uv", "--version"]),
+    ("rustc", &["rustc", "--version"]),
+];
+
+const HEADER: &str =
+    "# Tool versions recorded by `dev install` / `dev setup`; compare with `dev check-tools`.\n";
+
+pub fn lock_path(root: &Utf8Path) -> Utf8PathBuf {
+    root.join(".dev").join("lock.toml")
+}
+
+/// Version printed by `argv`, or `None` when the tool is not installed.
+fn probe(argv: &[&str]) -> Option<String> {
+    let output = Command::new(argv[0]).args(&argv[1..]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let pattern = Regex::new(r"\d+\.\d+(?:\.\d+)?").expect("valid version regex");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    pattern.find(&stdout).map(|found| found.as_str().to_owned())
+}
+
+/// Record the versions of every installed tool. Tools missing locally keep
+/// their previous entry so one machine without node does not unpin it.
+pub fn record(root: &Utf8Path, dry_run: bool) -> Result<Utf8PathBuf> {
+    let path = lock_path(root);
+    let mut doc: DocumentMut = if path.exists() {
+        fs::read_to_string(&path)
+            .with_context(|| format!("reading {}", path))?
+            .parse()
+            .with_context(|| format!("parsing {}", path))?
+    } else {
+        DocumentMut::new()
+    };
+    if !doc.contains_table("tools") {
+        let mut tools = Table::new();
+        tools.decor_mut().set_prefix(HEADER);
+        doc.insert("tools", Item::Table(tools));
+    }
+
+    for (name, argv) in TOOLS {
+        let Some(version) = probe(argv) else {
+            continue;
+        };
+        if dry_run {
+            println!("[dry-run] would lock {} = {}", name, version);
+        } else {
+            doc["tools"][name] = value(version);
+        }
+    }
+
+    if !dry_run {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("creating directory {}", parent))?;
+        }
+        fs::write(&path, doc.to_string()).with_context(|| format!("writing {}", path))?;
+    }
+    Ok(path)
+}
+
+/// `(tool, version)` pairs from the nearest `.dev/lock.toml` at or above `start`; empty
+/// when no lockfile exists.
+pub fn locked(start: &Utf8Path) -> Result<Vec<(String, String)>> {
+    let Some(path) = start.ancestors().map(lock_path).find(|path| path.is_file()) else {
+        return Ok(Vec::new());
+    };
+    let raw = fs::read_to_string(&path).with_context(|| format!("reading {}", path))?;
+    let doc: DocumentMut = raw.parse().with_context(|| format!("parsing {}", path))?;
+    let Some(tools) = doc.get("tools").and_then(Item::as_table_like) else {
+        return Ok(Vec::new());
+    };
+    Ok(tools
+        .iter()
+        .filter_map(|(name, version)| Some((name.to_owned(), version.as_str()?.to_owned())))
+        .collect())
+}
+
+/// Compare installed tool versions with the lockfile, printing one line per tool.
+/// Returns the number of tools that drifted or are missing.
+pub fn check(root: &Utf8Path) -> Result<usize> {
+    let path = lock_path(root);
+    let raw = fs::read_to_string(&path).with_context(|| {
+        format!("reading {}; run `dev check-tools --update` to create it", path)
+    })?;
+    let doc: DocumentMut = raw.parse().with_context(|| format!("parsing {}", path))?;
+    let Some(locked) = doc.get("tools").and_then(Item::as_table_like) else {
+        println!("No tools recorded in {}.", path);
+        return Ok(0);
+    };
+
+    println!("Checking tools against {}", path);
+    let mut drifted = 0;
+    for (name, locked_version) in locked.iter() {
+        let Some(locked_version) = locked_version.as_str() else {
+            continue;
+        };
+        let argv = TOOLS
+            .iter()
+            .find(|(tool, _)| *tool == name)
+            .map(|(_, argv)| argv.to_vec())
+            .unwrap_or_else(|| vec![name, "--version"]);
+        match probe(&argv) {
+            Some(found) if found == locked_version => {
+                println!("[ok] {} {}", name, found);
+            }
+            Some(found) => {
+                drifted += 1;
+                println!("[warn] {}: locked {}, found {}", name, locked_version, found);
+            }
+            None => {
+                drifted += 1;
+                println!("[warn] {}: locked {}, not installed", name, locked_version);
+            }
+        }
+    }
+    Ok(drifted)
+}
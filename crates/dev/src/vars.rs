@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use anyhow::{Result, anyhow, bail};
+use regex::{Captures, Regex};
+use toml::Value;
+
+use crate::config::DevConfig;
+
+/// Values from `[vars]` merged with `--set key=value` overrides, expanded into
+/// `{{name}}` placeholders in task argv, task env, docker init args, and `.env` templates.
+#[derive(Debug, Default, Clone)]
+pub struct Vars {
+    values: BTreeMap<String, String>,
+}
+
+fn placeholder() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    // Only bare identifiers, so Go-template strings like `{{.State}}` pass through untouched.
+    PATTERN.get_or_init(|| {
+        Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_-]*)\s*\}\}").expect("valid placeholder regex")
+    })
+}
+
+impl Vars {
+    pub fn from_config(config: &DevConfig, overrides: &[(String, String)]) -> Result<Self> {
+        let mut values = BTreeMap::new();
+        for (name, value) in config.vars.iter().flatten() {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                Value::Integer(value) => value.to_string(),
+                Value::Float(value) => value.to_string(),
+                Value::Boolean(value) => value.to_string(),
+                _ => bail!("[vars] entry `{}` must be a string, number, or boolean", name),
+            };
+            values.insert(name.clone(), value);
+        }
+        for (name, value) in overrides {
+            values.insert(name.clone(), value.clone());
+        }
+        Ok(Self { values })
+    }
+
+    pub fn insert(&mut self, name: &str, value: String) {
+        self.values.insert(name.to_owned(), value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    /// Replace every `{{name}}` in `input`; unknown names are an error rather than left in place.
+    pub fn expand(&self, input: &str) -> Result<String> {
+        if !input.contains("{{") {
+            return Ok(input.to_owned());
+        }
+        let mut missing = None;
+        let expanded = placeholder().replace_all(input, |caps: &Captures<'_>| {
+            let name = &caps[1];
+            match self.values.get(name) {
+                Some(value) => value.clone(),
+                None => {
+                    missing.get_or_insert_with(|| name.to_owned());
+                    caps[0].to_owned()
+                }
+            }
+        });
+        if let Some(name) = missing {
+            return Err(anyhow!(
+                "unknown variable `{{{{{}}}}}` in `{}`; define it under [vars] or pass --set {}=...",
+                name,
+                input,
+                name
+            ));
+        }
+        Ok(expanded.into_owned())
+    }
+}
+
+/// clap value parser for `--set key=value`.
+pub fn parse_assignment(raw: &str) -> Result<(String, String), String> {
+    let (key, value) = raw
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=VALUE, got `{}`", raw))?;
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(format!("missing variable name in `{}`", raw));
+    }
+    Ok((key.to_owned(), value.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vars {
+        Vars {
+            values: pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn expand_replaces_known_and_rejects_unknown() {
+        let vars = vars(&[("image", "app:1.2"), ("port", "8080")]);
+        assert_eq!(
+            vars.expand("docker run -p {{ port }}:80 {{image}}").unwrap(),
+            "docker run -p 8080:80 app:1.2"
+        );
+        assert_eq!(vars.expand("{{.State.Status}}").unwrap(), "{{.State.Status}}");
+        let error = vars.expand("{{tag}}").unwrap_err().to_string();
+        assert!(error.contains("unknown variable `{{tag}}`"), "{error}");
+    }
+}
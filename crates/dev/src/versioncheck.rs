@@ -0,0 +1,751 @@
+use std::fs;
+
+use anyhow::{Context, Result, anyhow, bail};
+use camino::{Utf8Path, Utf8PathBuf};
+use semver::{Version, VersionReq};
+use toml_edit::{DocumentMut, Item};
+
+use crate::config;
+
+/// A workspace member (or the root) that disagrees with the root version.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Finding {
+    pub manifest: Utf8PathBuf,
+    pub problem: String,
+}
+
+/// One detected workspace and what was found in it.
+pub struct Workspace {
+    pub kind: &'static str,
+    pub manifest: Utf8PathBuf,
+    pub version: Option<Version>,
+    pub members: usize,
+    pub findings: Vec<Finding>,
+}
+
+/// Check every Cargo, pnpm, and uv workspace rooted in the current directory and fail
+/// when a member's version or an internal dependency requirement is out of sync.
+pub fn run() -> Result<()> {
+    let cwd = std::env::current_dir().context("determining current directory")?;
+    let root = Utf8PathBuf::from_path_buf(cwd)
+        .map_err(|_| anyhow!("current directory is not valid UTF-8"))?;
+    let workspaces = check(&root)?;
+    if workspaces.is_empty() {
+        bail!("no Cargo, pnpm, or uv workspace found in {}", root);
+    }
+
+    let mut problems = 0;
+    for workspace in &workspaces {
+        let version = workspace
+            .version
+            .as_ref()
+            .map(|version| version.to_string())
+            .unwrap_or_else(|| "no root version".to_string());
+        println!(
+            "{} workspace ({}) at {}: {} member(s)",
+            workspace.kind,
+            relative(&root, &workspace.manifest),
+            version,
+            workspace.members
+        );
+        for finding in &workspace.findings {
+            println!(
+                "  {}: {}",
+                relative(&root, &finding.manifest),
+                finding.problem
+            );
+        }
+        if workspace.version.is_none() {
+            println!("  skipped: the root manifest sets no version to compare against");
+        } else if workspace.findings.is_empty() {
+            println!("  all in sync");
+        }
+        problems += workspace.findings.len();
+    }
+    if problems > 0 {
+        bail!("{} version mismatch(es) across workspace members", problems);
+    }
+    Ok(())
+}
+
+pub fn check(root: &Utf8Path) -> Result<Vec<Workspace>> {
+    let mut workspaces = Vec::new();
+    if let Some(workspace) = check_cargo(root)? {
+        workspaces.push(workspace);
+    }
+    if let Some(workspace) = check_pnpm(root)? {
+        workspaces.push(workspace);
+    }
+    if let Some(workspace) = check_uv(root)? {
+        workspaces.push(workspace);
+    }
+    Ok(workspaces)
+}
+
+fn check_cargo(root: &Utf8Path) -> Result<Option<Workspace>> {
+    let manifest = root.join("Cargo.toml");
+    if !manifest.exists() {
+        return Ok(None);
+    }
+    let doc = read_toml(&manifest)?;
+    let Some(workspace) = doc.get("workspace") else {
+        return Ok(None);
+    };
+    let version = workspace
+        .get("package")
+        .and_then(|package| package.get("version"))
+        .or_else(|| doc.get("package")?.get("version"))
+        .and_then(Item::as_str)
+        .map(|raw| parse_version(&manifest, raw))
+        .transpose()?;
+
+    let members = expand_members(
+        root,
+        &strings(workspace.get("members")),
+        &strings(workspace.get("exclude")),
+        "Cargo.toml",
+    )?;
+    let mut docs = Vec::new();
+    for dir in &members {
+        let path = dir.join("Cargo.toml");
+        let member = read_toml(&path)?;
+        docs.push((path, member));
+    }
+    // The root package (when the root is not virtual) can be depended on as well.
+    let names: Vec<String> = std::iter::once(&doc)
+        .chain(docs.iter().map(|(_, member)| member))
+        .filter_map(|member| member.get("package")?.get("name")?.as_str())
+        .map(String::from)
+        .collect();
+
+    let mut findings = Vec::new();
+    if let Some(version) = &version {
+        // `workspace = true` dependencies take their requirement from the root.
+        check_cargo_deps(
+            &manifest,
+            workspace.get("dependencies"),
+            &names,
+            version,
+            &mut findings,
+        );
+        for (path, member) in &docs {
+            let Some(package) = member.get("package") else {
+                continue;
+            };
+            if let Some(raw) = package.get("version").and_then(Item::as_str) {
+                let own = parse_version(path, raw)?;
+                if &own != version {
+                    findings.push(Finding {
+                        manifest: path.clone(),
+                        problem: format!("version {} differs from {}", own, version),
+                    });
+                }
+            }
+            for table in ["dependencies", "dev-dependencies", "build-dependencies"] {
+                check_cargo_deps(path, member.get(table), &names, version, &mut findings);
+            }
+        }
+    }
+    Ok(Some(Workspace {
+        kind: "Cargo",
+        manifest,
+        version,
+        members: members.len(),
+        findings,
+    }))
+}
+
+fn check_cargo_deps(
+    manifest: &Utf8Path,
+    table: Option<&Item>,
+    members: &[String],
+    version: &Version,
+    findings: &mut Vec<Finding>,
+) {
+    let Some(table) = table.and_then(Item::as_table_like) else {
+        return;
+    };
+    for (name, spec) in table.iter() {
+        // Renamed dependencies name the real crate in `package`.
+        let krate = spec.get("package").and_then(Item::as_str).unwrap_or(name);
+        if !members.iter().any(|member| member == krate) {
+            continue;
+        }
+        let requirement = match spec.as_str() {
+            Some(req) => Some(req),
+            None => spec.get("version").and_then(Item::as_str),
+        };
+        let Some(requirement) = requirement else {
+            continue;
+        };
+        let satisfied = VersionReq::parse(requirement)
+            .map(|req| req.matches(version))
+            .unwrap_or(false);
+        if !satisfied {
+            findings.push(Finding {
+                manifest: manifest.to_owned(),
+                problem: format!(
+                    "dependency `{}` requires `{}`, which {} does not satisfy",
+                    name, requirement, version
+                ),
+            });
+        }
+    }
+}
+
+fn check_pnpm(root: &Utf8Path) -> Result<Option<Workspace>> {
+    let listing = root.join("pnpm-workspace.yaml");
+    if !listing.exists() {
+        return Ok(None);
+    }
+    let raw = fs::read_to_string(&listing).with_context(|| format!("reading {}", listing))?;
+    let (include, exclude) = pnpm_packages(&raw);
+    let manifest = root.join("package.json");
+    let root_package = if manifest.exists() {
+        read_json(&manifest)?
+    } else {
+        serde_json::Value::Null
+    };
+    let version = root_package["version"]
+        .as_str()
+        .map(|raw| parse_version(&manifest, raw))
+        .transpose()?;
+
+    let members = expand_members(root, &include, &exclude, "package.json")?;
+    let mut docs = Vec::new();
+    for dir in &members {
+        let path = dir.join("package.json");
+        let member = read_json(&path)?;
+        docs.push((path, member));
+    }
+    let names: Vec<&str> = std::iter::once(&root_package)
+        .chain(docs.iter().map(|(_, member)| member))
+        .filter_map(|member| member["name"].as_str())
+        .collect();
+
+    let mut findings = Vec::new();
+    if let Some(version) = &version {
+        for (path, member) in &docs {
+            if let Some(raw) = member["version"].as_str() {
+                let own = parse_version(path, raw)?;
+                if &own != version {
+                    findings.push(Finding {
+                        manifest: path.clone(),
+                        problem: format!("version {} differs from {}", own, version),
+                    });
+                }
+            }
+            for table in [
+                "dependencies",
+                "devDependencies",
+                "peerDependencies",
+                "optionalDependencies",
+            ] {
+                let Some(deps) = member[table].as_object() else {
+                    continue;
+                };
+                for (name, requirement) in deps {
+                    let Some(requirement) = requirement.as_str() else {
+                        continue;
+                    };
+                    if names.contains(&name.as_str())
+                        && npm_requirement_matches(requirement, version) == Some(false)
+                    {
+                        findings.push(Finding {
+                            manifest: path.clone(),
+                            problem: format!(
+                                "dependency `{}` requires `{}`, which {} does not satisfy",
+                                name, requirement, version
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+    }
+    Ok(Some(Workspace {
+        kind: "pnpm",
+        manifest: listing,
+        version,
+        members: members.len(),
+        findings,
+    }))
+}
+
+fn check_uv(root: &Utf8Path) -> Result<Option<Workspace>> {
+    let manifest = root.join("pyproject.toml");
+    if !manifest.exists() {
+        return Ok(None);
+    }
+    let doc = read_toml(&manifest)?;
+    let Some(workspace) = doc
+        .get("tool")
+        .and_then(|tool| tool.get("uv"))
+        .and_then(|uv| uv.get("workspace"))
+    else {
+        return Ok(None);
+    };
+    let version = doc
+        .get("project")
+        .and_then(|project| project.get("version"))
+        .and_then(Item::as_str)
+        .map(|raw| parse_version(&manifest, raw))
+        .transpose()?;
+
+    let members = expand_members(
+        root,
+        &strings(workspace.get("members")),
+        &strings(workspace.get("exclude")),
+        "pyproject.toml",
+    )?;
+    let mut docs = Vec::new();
+    for dir in &members {
+        let path = dir.join("pyproject.toml");
+        let member = read_toml(&path)?;
+        docs.push((path, member));
+    }
+    let names: Vec<String> = std::iter::once(&doc)
+        .chain(docs.iter().map(|(_, member)| member))
+        .filter_map(|member| member.get("project")?.get("name")?.as_str())
+        .map(normalize_python_name)
+        .collect();
+
+    let mut findings = Vec::new();
+    if let Some(version) = &version {
+        for (path, member) in &docs {
+            let Some(project) = member.get("project") else {
+                continue;
+            };
+            if let Some(raw) = project.get("version").and_then(Item::as_str) {
+                let own = parse_version(path, raw)?;
+                if &own != version {
+                    findings.push(Finding {
+                        manifest: path.clone(),
+                        problem: format!("version {} differs from {}", own, version),
+                    });
+                }
+            }
+            for dependency in strings(project.get("dependencies")) {
+                let (name, specifier) = split_requirement(&dependency);
+                if names.contains(&normalize_python_name(name))
+                    && pep440_matches(specifier, version) == Some(false)
+                {
+                    findings.push(Finding {
+                        manifest: path.clone(),
+                        problem: format!(
+                            "dependency `{}` requires `{}`, which {} does not satisfy",
+                            name, specifier, version
+                        ),
+                    });
+                }
+            }
+        }
+    }
+    Ok(Some(Workspace {
+        kind: "uv",
+        manifest,
+        version,
+        members: members.len(),
+        findings,
+    }))
+}
+
+/// Member directories matching `include` (with `*` wildcards per path segment and `**`
+/// for any depth) that hold `manifest`, minus those matching `exclude`.
+fn expand_members(
+    root: &Utf8Path,
+    include: &[String],
+    exclude: &[String],
+    manifest: &str,
+) -> Result<Vec<Utf8PathBuf>> {
+    let mut members: Vec<Utf8PathBuf> = Vec::new();
+    for pattern in include {
+        let mut dirs = vec![root.to_owned()];
+        for segment in pattern.trim_end_matches('/').split('/') {
+            if segment.is_empty() || segment == "." {
+                continue;
+            }
+            let mut next = Vec::new();
+            for dir in &dirs {
+                if segment == "**" {
+                    next.push(dir.clone());
+                    subdirectories(dir, &mut next)?;
+                    continue;
+                }
+                if !segment.contains('*') {
+                    next.push(dir.join(segment));
+                    continue;
+                }
+                let Ok(entries) = dir.read_dir_utf8() else {
+                    continue;
+                };
+                for entry in entries {
+                    let entry = entry.with_context(|| format!("reading {}", dir))?;
+                    let name = entry.file_name();
+                    let wanted = segment.replace("**", "*");
+                    if entry.path().is_dir()
+                        && member_candidate(name)
+                        && config::glob_matches(&wanted, name)
+                    {
+                        next.push(entry.path().to_owned());
+                    }
+                }
+            }
+            dirs = next;
+        }
+        for dir in dirs {
+            if dir != root && dir.join(manifest).exists() && !members.contains(&dir) {
+                members.push(dir);
+            }
+        }
+    }
+    members.retain(|dir| {
+        let relative = dir
+            .strip_prefix(root)
+            .map(Utf8Path::as_str)
+            .unwrap_or_default();
+        !exclude.iter().any(|pattern| {
+            let pattern = pattern.strip_prefix("./").unwrap_or(pattern);
+            config::path_glob_matches(&format!("/{}", pattern), relative, true)
+        })
+    });
+    members.sort();
+    Ok(members)
+}
+
+fn member_candidate(name: &str) -> bool {
+    !name.starts_with('.') && name != "node_modules"
+}
+
+/// Every directory below `dir`, depth first, for a `**` member segment.
+fn subdirectories(dir: &Utf8Path, out: &mut Vec<Utf8PathBuf>) -> Result<()> {
+    let Ok(entries) = dir.read_dir_utf8() else {
+        return Ok(());
+    };
+    for entry in entries {
+        let entry = entry.with_context(|| format!("reading {}", dir))?;
+        if entry.path().is_dir() && member_candidate(entry.file_name()) {
+            out.push(entry.path().to_owned());
+            subdirectories(entry.path(), out)?;
+        }
+    }
+    Ok(())
+}
+
+/// `packages:` include and `!`-prefixed exclude globs from `pnpm-workspace.yaml`.
+fn pnpm_packages(raw: &str) -> (Vec<String>, Vec<String>) {
+    let mut include = Vec::new();
+    let mut exclude = Vec::new();
+    let mut in_packages = false;
+    for line in raw.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with([' ', '\t', '-']) {
+            in_packages = trimmed == "packages:";
+            continue;
+        }
+        let Some(item) = trimmed.strip_prefix('-').filter(|_| in_packages) else {
+            continue;
+        };
+        let item = item.trim().trim_matches(['\'', '"']);
+        match item.strip_prefix('!') {
+            Some(excluded) => exclude.push(excluded.to_string()),
+            None => include.push(item.to_string()),
+        }
+    }
+    (include, exclude)
+}
+
+/// Whether an npm range admits `version`; `None` for specs that are not version ranges
+/// (`file:`, `link:`, git URLs, aliases).
+fn npm_requirement_matches(requirement: &str, version: &Version) -> Option<bool> {
+    let requirement = requirement.trim();
+    let range = match requirement.strip_prefix("workspace:") {
+        // `workspace:*`, `workspace:^` and `workspace:~` always resolve to the local package.
+        Some("*" | "^" | "~" | "") => return Some(true),
+        Some(range) => range,
+        None => requirement,
+    };
+    if range.contains(':') || range.contains('/') {
+        return None;
+    }
+    if range == "*" || range == "latest" {
+        return Some(true);
+    }
+    let alternatives: Vec<&str> = range.split("||").collect();
+    let mut any_parsed = false;
+    for alternative in alternatives {
+        let alternative = alternative.trim();
+        let bare = alternative.strip_prefix('v').unwrap_or(alternative);
+        // npm treats a bare version as exact, unlike Cargo's implicit caret.
+        if bare.starts_with(|c: char| c.is_ascii_digit())
+            && !bare.contains(' ')
+            && let Ok(exact) = Version::parse(bare)
+        {
+            any_parsed = true;
+            if &exact == version {
+                return Some(true);
+            }
+            continue;
+        }
+        let comparators = alternative
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(", ");
+        if let Ok(req) = VersionReq::parse(&comparators) {
+            any_parsed = true;
+            if req.matches(version) {
+                return Some(true);
+            }
+        }
+    }
+    any_parsed.then_some(false)
+}
+
+/// Whether a PEP 440 specifier set (`>=1.2,<2`, `~=1.4`, `==1.2.*`) admits `version`;
+/// `None` when a clause cannot be read. An empty set admits everything.
+fn pep440_matches(specifier: &str, version: &Version) -> Option<bool> {
+    let version = Version::new(version.major, version.minor, version.patch);
+    for clause in specifier.split(',') {
+        let clause = clause.trim();
+        if clause.is_empty() {
+            continue;
+        }
+        let (op, raw) = ["~=", "==", "!=", ">=", "<=", ">", "<"]
+            .iter()
+            .find_map(|op| clause.strip_prefix(op).map(|rest| (*op, rest.trim())))?;
+        if let Some(prefix) = raw.strip_suffix(".*") {
+            let segments: Vec<u64> = prefix
+                .split('.')
+                .map(|part| part.parse().ok())
+                .collect::<Option<_>>()?;
+            let own = [version.major, version.minor, version.patch];
+            let matches = segments.iter().zip(own.iter()).all(|(a, b)| a == b);
+            let ok = match op {
+                "==" => matches,
+                "!=" => !matches,
+                _ => return None,
+            };
+            if !ok {
+                return Some(false);
+            }
+            continue;
+        }
+        let bound = loose_version(raw)?;
+        let ok = match op {
+            "==" => version == bound,
+            "!=" => version != bound,
+            ">=" => version >= bound,
+            "<=" => version <= bound,
+            ">" => version > bound,
+            "<" => version < bound,
+            "~=" => {
+                // `~=1.4.2` means `>=1.4.2, ==1.4.*`; `~=1.4` means `>=1.4, ==1.*`.
+                let depth = raw.split('.').count();
+                let upper = if depth >= 3 {
+                    Version::new(bound.major, bound.minor + 1, 0)
+                } else {
+                    Version::new(bound.major + 1, 0, 0)
+                };
+                version >= bound && version < upper
+            }
+            _ => return None,
+        };
+        if !ok {
+            return Some(false);
+        }
+    }
+    Some(true)
+}
+
+/// `name[extra]>=1.0; marker` -> (`name`, `>=1.0`).
+fn split_requirement(requirement: &str) -> (&str, &str) {
+    let requirement = requirement.split(';').next().unwrap_or_default().trim();
+    let end = requirement
+        .find(|c: char| !(c.is_ascii_alphanumeric() || "._-".contains(c)))
+        .unwrap_or(requirement.len());
+    let (name, rest) = requirement.split_at(end);
+    let rest = rest.trim();
+    let rest = match rest.strip_prefix('[') {
+        Some(after) => after
+            .split_once(']')
+            .map(|(_, tail)| tail)
+            .unwrap_or_default(),
+        None => rest,
+    };
+    let rest = rest.trim().trim_start_matches('(').trim_end_matches(')');
+    (name, rest.trim())
+}
+
+fn normalize_python_name(name: &str) -> String {
+    name.to_ascii_lowercase().replace(['_', '.'], "-")
+}
+
+/// Semver with missing minor/patch components filled in (`1.2` -> `1.2.0`).
+fn loose_version(raw: &str) -> Option<Version> {
+    let raw = raw.trim().trim_start_matches('v');
+    let mut parts: Vec<&str> = raw.split('.').collect();
+    while parts.len() < 3 {
+        parts.push("0");
+    }
+    Version::parse(&parts.join(".")).ok()
+}
+
+fn parse_version(manifest: &Utf8Path, raw: &str) -> Result<Version> {
+    Version::parse(raw)
+        .ok()
+        .or_else(|| loose_version(raw))
+        .ok_or_else(|| anyhow!("parsing version `{}` in {}", raw, manifest))
+}
+
+fn strings(item: Option<&Item>) -> Vec<String> {
+    item.and_then(Item::as_array)
+        .map(|array| {
+            array
+                .iter()
+                .filter_map(|value| value.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn read_toml(path: &Utf8Path) -> Result<DocumentMut> {
+    fs::read_to_string(path)
+        .with_context(|| format!("reading {}", path))?
+        .parse::<DocumentMut>()
+        .with_context(|| format!("parsing {}", path))
+}
+
+fn read_json(path: &Utf8Path) -> Result<serde_json::Value> {
+    let raw = fs::read_to_string(path).with_context(|| format!("reading {}", path))?;
+    serde_json::from_str(&raw).with_context(|| format!("parsing {}", path))
+}
+
+fn relative<'a>(root: &Utf8Path, path: &'a Utf8Path) -> &'a str {
+    path.strip_prefix(root)
+        .map(Utf8Path::as_str)
+        .unwrap_or(path.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_npm_and_pep440_requirements() {
+        let version = Version::new(1, 4, 0);
+        assert_eq!(npm_requirement_matches("workspace:*", &version), Some(true));
+        assert_eq!(npm_requirement_matches("^1.2.0", &version), Some(true));
+        assert_eq!(npm_requirement_matches("1.3.0", &version), Some(false));
+        assert_eq!(
+            npm_requirement_matches(">=1.0.0 <1.4.0", &version),
+            Some(false)
+        );
+        assert_eq!(
+            npm_requirement_matches("^0.9 || ^1.0", &version),
+            Some(true)
+        );
+        assert_eq!(npm_requirement_matches("file:../core", &version), None);
+
+        assert_eq!(pep440_matches(">=1.2,<2", &version), Some(true));
+        assert_eq!(pep440_matches("==1.3.*", &version), Some(false));
+        assert_eq!(pep440_matches("~=1.3", &version), Some(true));
+        assert_eq!(pep440_matches("~=1.3.1", &version), Some(false));
+        assert_eq!(pep440_matches("", &version), Some(true));
+        assert_eq!(
+            split_requirement("core[fast] >=1.0; python_version>'3.8'"),
+            ("core", ">=1.0")
+        );
+    }
+
+    #[test]
+    fn reports_cargo_members_out_of_sync() {
+        let root = Utf8PathBuf::from_path_buf(std::env::temp_dir())
+            .unwrap()
+            .join(format!("devkit-versioncheck-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for member in ["crates/core", "crates/cli", "crates/old"] {
+            fs::create_dir_all(root.join(member)).unwrap();
+        }
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/old\"]\n\n\
+             [workspace.package]\nversion = \"0.4.0\"\n\n\
+             [workspace.dependencies]\ncore = { path = \"crates/core\", version = \"0.4\" }\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("crates/core/Cargo.toml"),
+            "[package]\nname = \"core\"\nversion.workspace = true\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("crates/cli/Cargo.toml"),
+            "[package]\nname = \"cli\"\nversion = \"0.3.0\"\n\n\
+             [dependencies]\ncore = { path = \"../core\", version = \"0.3\" }\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("crates/old/Cargo.toml"),
+            "[package]\nname = \"old\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+
+        let workspaces = check(&root).unwrap();
+        assert_eq!(workspaces.len(), 1);
+        let cargo = &workspaces[0];
+        assert_eq!(cargo.members, 2);
+        let problems: Vec<&str> = cargo
+            .findings
+            .iter()
+            .map(|finding| finding.problem.as_str())
+            .collect();
+        assert_eq!(
+            problems,
+            [
+                "version 0.3.0 differs from 0.4.0",
+                "dependency `core` requires `0.3`, which 0.4.0 does not satisfy"
+            ]
+        );
+        assert!(
+            pnpm_packages("packages:\n  - 'apps/*'\n  - \"!apps/legacy\"\ncatalog:\n  - x\n")
+                == (vec!["apps/*".to_string()], vec!["apps/legacy".to_string()])
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn double_star_members_match_at_any_depth() {
+        let root = Utf8PathBuf::from_path_buf(std::env::temp_dir())
+            .unwrap()
+            .join(format!("devkit-versioncheck-glob-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for member in ["packages/a", "packages/group/b", "packages/group/legacy/c"] {
+            fs::create_dir_all(root.join(member)).unwrap();
+            fs::write(root.join(member).join("package.json"), "{}").unwrap();
+        }
+        fs::create_dir_all(root.join("packages/group/node_modules/dep")).unwrap();
+        fs::write(
+            root.join("packages/group/node_modules/dep/package.json"),
+            "{}",
+        )
+        .unwrap();
+
+        let members = expand_members(
+            &root,
+            &["packages/**".to_string()],
+            &["packages/group/legacy/**".to_string()],
+            "package.json",
+        )
+        .unwrap();
+        let members: Vec<&str> = members.iter().map(|dir| relative(&root, dir)).collect();
+        assert_eq!(members, ["packages/a", "packages/group/b"]);
+
+        let shallow =
+            expand_members(&root, &["packages/*".to_string()], &[], "package.json").unwrap();
+        assert_eq!(shallow.len(), 1);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -2,106 +2,1007 @@ use std::{fs, io::Write, process::Command};
 
 use anyhow::{Context, Result, anyhow, bail};
 use camino::{Utf8Path, Utf8PathBuf};
-use chrono::Utc;
-use semver::{Prerelease, Version};
+use chrono::{Local, Utc};
+use clap::ValueEnum;
+use semver::{BuildMetadata, Prerelease, Version};
 use toml_edit::{DocumentMut, value};
 
 use crate::{
-    cli::{ChangelogArgs, VersionBump, VersionCommand},
-    config::DevConfig,
+    calver, changelog,
+    cli::{
+        BumpLevel, ChangelogArgs, ChangelogFormat, ChangelogSource, ReleaseArgs, VersionBump,
+        VersionCommand, VersionNext, VersionPublish, VersionRollback,
+    },
+    config::{self, DevConfig},
+    gitjournal::{self, Action},
+    gitops, gitpr,
+    gitprovider::{self, PullRequest, Release},
+    provenance, textdiff, toollock, versioncheck,
 };
 
-pub fn handle(config: &DevConfig, dry_run: bool, command: VersionCommand) -> Result<()> {
+/// Tag format and changelog path filter for the active project, so monorepo projects
+/// get their own `web-v1.2.0` style tags and changelogs.
+pub struct Scope {
+    /// What comes before and after the version in a tag (`v` and nothing by default).
+    pub tag_prefix: String,
+    pub tag_suffix: String,
+    /// Limit changelog commits to the project directory (the current directory once a
+    /// project's `chdir` has been applied).
+    pub project_only: bool,
+}
+
+impl Scope {
+    /// Tags follow the project's `tag_prefix`, else `[git] tag_format`, else
+    /// `[git] tag_prefix`, else `v`.
+    pub fn resolve(config: &DevConfig, project: Option<&str>) -> Result<Self> {
+        let spec = project.and_then(|name| config.projects.as_ref()?.get(name));
+        let git = config.git.as_ref();
+        let format = match (
+            spec.and_then(|spec| spec.tag_prefix.as_deref()),
+            git.and_then(|git| git.tag_format.as_deref()),
+        ) {
+            (Some(prefix), _) => format!("{}{{version}}", prefix),
+            (None, Some(format)) => format.to_string(),
+            (None, None) => format!(
+                "{}{{version}}",
+                git.and_then(|git| git.tag_prefix.as_deref()).unwrap_or("v")
+            ),
+        };
+        let format = match project {
+            Some(name) => format.replace("{project}", name),
+            None if format.contains("{project}") => bail!(
+                "tag format `{}` uses {{project}}, but no project is active; pass --project",
+                format
+            ),
+            None => format,
+        };
+        let Some((prefix, suffix)) = format
+            .split_once("{version}")
+            .filter(|(_, suffix)| !suffix.contains("{version}"))
+        else {
+            bail!(
+                "tag format `{}` must contain {{version}} exactly once",
+                format
+            );
+        };
+        Ok(Scope {
+            tag_prefix: prefix.to_string(),
+            tag_suffix: suffix.to_string(),
+            project_only: spec.is_some_and(|spec| spec.chdir.is_some()),
+        })
+    }
+
+    fn tag(&self, version: &Version) -> String {
+        format!("{}{}{}", self.tag_prefix, version, self.tag_suffix)
+    }
+
+    /// The version in a tag of this scope, if it is one.
+    fn tag_version(&self, tag: &str) -> Option<Version> {
+        let version = tag
+            .strip_prefix(&self.tag_prefix)?
+            .strip_suffix(&self.tag_suffix)?;
+        Version::parse(version).ok()
+    }
+
+    fn paths(&self) -> &'static [&'static str] {
+        if self.project_only { &["."] } else { &[] }
+    }
+
+    /// Most recent tag in this scope's format.
+    pub fn latest_tag(&self) -> Result<Option<String>> {
+        let pattern = format!("{}[0-9]*{}", self.tag_prefix, self.tag_suffix);
+        let output = Command::new("git")
+            .args(["describe", "--tags", "--abbrev=0", "--match", &pattern])
+            .output();
+        match output {
+            Ok(out) if out.status.success() => Ok(Some(
+                String::from_utf8_lossy(&out.stdout).trim().to_string(),
+            )),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// The task runner, for what a bump runs around itself.
+pub struct Runner<'a> {
+    /// Run named tasks in order (`[git.hooks]`).
+    pub tasks: &'a dyn Fn(&[String]) -> Result<()>,
+    /// Run a pipeline (`check`, `test`, ...) for the active language (`[git] require_ci`).
+    pub pipeline: &'a dyn Fn(&str) -> Result<()>,
+}
+
+pub fn handle(
+    config: &DevConfig,
+    project: Option<&str>,
+    dry_run: bool,
+    command: VersionCommand,
+    runner: &Runner,
+) -> Result<()> {
+    // Only the subcommands that build or read tags need the tag format to resolve.
+    let scope = || Scope::resolve(config, project);
     match command {
         VersionCommand::Show => show_version(config),
-        VersionCommand::Bump(args) => bump_version(config, &args, dry_run),
-        VersionCommand::Changelog(args) => print_changelog(config, &args),
+        VersionCommand::Next(args) => next_version(config, &scope()?, &args, dry_run, runner),
+        VersionCommand::Check => versioncheck::run(),
+        VersionCommand::Verify => verify_versions(config),
+        VersionCommand::Rollback(args) => rollback_release(config, &args, dry_run),
+        VersionCommand::Publish(args) => publish_packages(config, &scope()?, &args, dry_run),
+        VersionCommand::Bump(args) => bump_version(config, &scope()?, &args, dry_run, runner),
+        VersionCommand::Changelog(args) => print_changelog(config, &scope()?, &args),
     }
 }
 
 fn show_version(config: &DevConfig) -> Result<()> {
-    let (path, kind) = locate_version_file(config)?;
-    let doc = read_manifest(&path, kind)?;
-    let version = current_version(&doc, kind)?;
+    let (path, kind) = locate_version_files(config)?.remove(0);
+    let doc = read_manifest(&path, &kind)?;
+    let version = current_version(&doc, &kind)?;
     println!("{}", version);
     Ok(())
 }
 
-fn bump_version(config: &DevConfig, args: &VersionBump, dry_run: bool) -> Result<()> {
-    let (path, kind) = locate_version_file(config)?;
-    let mut doc = read_manifest(&path, kind)?;
-    let current = current_version(&doc, kind)?;
+fn verify_versions(config: &DevConfig) -> Result<()> {
+    let cwd = current_dir()?;
+    let display = |path: &Utf8Path| path.strip_prefix(&cwd).unwrap_or(path).to_string();
+    let files = locate_version_files(config)?;
+    let mut expected: Option<String> = None;
+    let mut mismatches = 0;
+    for (path, kind) in &files {
+        // A pattern can occur several times in its file; every occurrence must agree.
+        let found: Vec<(String, String)> = match kind {
+            VersionFileKind::Pattern(pattern) => {
+                let content = match fs::read_to_string(path) {
+                    Ok(content) => content,
+                    Err(err) => {
+                        println!("{}: cannot read ({})", display(path), err);
+                        mismatches += 1;
+                        continue;
+                    }
+                };
+                let found = pattern_versions(&content, pattern)?;
+                if found.is_empty() {
+                    println!("{}: pattern `{}` not found", display(path), pattern);
+                    mismatches += 1;
+                }
+                found
+                    .into_iter()
+                    .map(|(line, version)| (format!("{}:{}", display(path), line), version))
+                    .collect()
+            }
+            _ => {
+                let version = read_manifest(path, kind)
+                    .and_then(|doc| current_version(&doc, kind))
+                    .with_context(|| format!("reading version from {}", path))?;
+                vec![(display(path), version.to_string())]
+            }
+        };
+        for (location, version) in found {
+            match &expected {
+                None => {
+                    println!("{}: {}", location, version);
+                    expected = Some(version);
+                }
+                Some(expected) if *expected == version => println!("{}: {}", location, version),
+                Some(expected) => {
+                    println!("{}: {} (expected {})", location, version, expected);
+                    mismatches += 1;
+                }
+            }
+        }
+    }
+    let expected = expected.ok_or_else(|| anyhow!("no version file found"))?;
+    if mismatches > 0 {
+        bail!(
+            "{} location(s) disagree with version {}",
+            mismatches,
+            expected
+        );
+    }
+    println!("All version locations agree on {}", expected);
+    Ok(())
+}
+
+/// Every occurrence of `pattern` in `content` as (1-based line, version found where
+/// `{version}` stands).
+fn pattern_versions(content: &str, pattern: &str) -> Result<Vec<(usize, String)>> {
+    let regex = pattern_regex(pattern)?;
+    Ok(version_spans(&regex, content)
+        .map(|span| {
+            let line = content[..span.start].matches('\n').count() + 1;
+            (line, content[span].to_string())
+        })
+        .collect())
+}
 
-    let target = if let Some(custom) = &args.custom {
+/// Compile a `[[git.version_patterns]]` pattern into a regex whose first capture group is
+/// the version where `{version}` stands.
+fn pattern_regex(pattern: &str) -> Result<regex::Regex> {
+    let Some((before, after)) = pattern.split_once("{version}") else {
+        bail!(
+            "version pattern `{}` has no {{version}} placeholder",
+            pattern
+        );
+    };
+    if after.contains("{version}") {
+        bail!(
+            "version pattern `{}` has more than one {{version}}",
+            pattern
+        );
+    }
+    regex::Regex::new(&format!(
+        "{}([0-9](?:[0-9A-Za-z.+-]*[0-9A-Za-z])?){}",
+        regex::escape(before),
+        regex::escape(after)
+    ))
+    .with_context(|| format!("compiling version pattern `{}`", pattern))
+}
+
+fn bump_version(
+    config: &DevConfig,
+    scope: &Scope,
+    args: &VersionBump,
+    dry_run: bool,
+    runner: &Runner,
+) -> Result<()> {
+    let options = &args.options;
+    let sign = options.sign
+        || config
+            .git
+            .as_ref()
+            .and_then(|git| git.sign)
+            .unwrap_or(false);
+    if sign && !dry_run && (options.tag || !options.no_commit) {
+        gitops::ensure_signing_configured()?;
+    }
+    // Parse every manifest up front so a broken one fails the bump before anything is written.
+    let mut manifests = Vec::new();
+    for (path, kind) in locate_version_files(config)? {
+        let doc = read_manifest(&path, &kind)?;
+        let version = current_version(&doc, &kind)
+            .with_context(|| format!("reading version from {}", path))?;
+        manifests.push((path, kind, doc, version));
+    }
+    // The first manifest is the source of truth; the rest follow it.
+    let current = manifests[0].3.clone();
+
+    let mut target = if let Some(custom) = &args.custom {
         Version::parse(custom).with_context(|| format!("parsing custom version `{}`", custom))?
+    } else if args.strip_build {
+        if current.build.is_empty() {
+            bail!("{} carries no build metadata to strip", current);
+        }
+        Version {
+            build: BuildMetadata::EMPTY,
+            ..current.clone()
+        }
     } else {
-        increment_version(&current, args.level)?
+        let level = args
+            .level
+            .ok_or_else(|| anyhow!("a bump level, --custom, or --strip-build is required"))?;
+        target_version(config, &current, level)?
     };
+    if let Some(build) = &args.build {
+        target.build = BuildMetadata::new(build)
+            .with_context(|| format!("invalid build metadata `{}`", build))?;
+    }
 
+    let required = config
+        .git
+        .as_ref()
+        .and_then(|git| git.require_ci.as_deref())
+        .unwrap_or_default();
+    if !required.is_empty() {
+        if options.remote_ci {
+            ensure_remote_ci_green(dry_run)?;
+        } else {
+            run_required_ci(required, &target, runner)?;
+        }
+    }
+    let hooks = config.git.as_ref().and_then(|git| git.hooks.as_ref());
+    if let Some(tasks) = hooks.and_then(|hooks| hooks.pre_bump.as_deref()) {
+        println!("Running pre_bump hooks: {}", tasks.join(", "));
+        (runner.tasks)(tasks).context("pre_bump hook failed; version not bumped")?;
+    }
+
+    // `--pr`: the release commit goes on its own branch, opened as a PR into this one. The
+    // branch is only created once everything is written, right before the commit.
+    let release_pr = match options.pr {
+        true => {
+            let base = gitops::current_branch()?.ok_or_else(|| {
+                anyhow!("--pr needs a branch to open the PR into; HEAD is detached")
+            })?;
+            let branch = format!("release/{}", scope.tag(&target));
+            if gitops::rev_parse(&format!("refs/heads/{}", branch))?.is_some() {
+                bail!(
+                    "branch `{}` already exists; delete it or bump to another version",
+                    branch
+                );
+            }
+            Some((base, branch))
+        }
+        false => None,
+    };
+
+    let mut updates = Vec::new();
+    for (path, kind, doc, _) in &mut manifests {
+        let original = render_manifest(doc, kind);
+        write_version(doc, kind, &target);
+        updates.push((path.clone(), original, render_manifest(doc, kind)));
+    }
     if dry_run {
-        println!(
-            "[dry-run] would update {} from {} to {}",
-            path, current, target
-        );
+        for (path, original, updated) in &updates {
+            println!("[dry-run] would update {} to {}:", path, target);
+            print!("{}", preview(path, original, updated)?);
+        }
     } else {
-        write_version(&mut doc, kind, &target);
-        let output = match kind {
-            VersionFileKind::PackageJson => doc["__raw_json"]
-                .as_str()
-                .map(|s| format!("{}\n", s))
-                .unwrap_or_default(),
-            _ => doc.to_string(),
+        write_all(&updates)?;
+        for (path, _, _) in &updates {
+            println!("Updated {} to {}", path, target);
+        }
+    }
+
+    let mut staged_paths: Vec<Utf8PathBuf> =
+        manifests.iter().map(|(path, ..)| path.clone()).collect();
+    let previous_tag = scope.latest_tag()?;
+
+    let mut changelog = None;
+    let mut package_changelogs = Vec::new();
+    if !options.no_changelog
+        && let Some(path) = changelog_path(config)?
+    {
+        update_changelog(config, &path, scope, &target, scope.paths(), dry_run)?;
+        staged_paths.push(path.clone());
+        if config
+            .git
+            .as_ref()
+            .and_then(|git| git.package_changelogs)
+            .unwrap_or(false)
+        {
+            let cwd = current_dir()?;
+            let name = path.file_name().unwrap_or("CHANGELOG.md");
+            let files: Vec<(&Utf8Path, &VersionFileKind)> = manifests
+                .iter()
+                .map(|(path, kind, ..)| (path.as_path(), kind))
+                .collect();
+            for dir in package_dirs(&cwd, &files) {
+                let package = cwd.join(&dir).join(name);
+                update_changelog(config, &package, scope, &target, &[dir.as_str()], dry_run)?;
+                staged_paths.push(package.clone());
+                package_changelogs.push(package.to_string());
+            }
+        }
+        changelog = Some(path);
+    }
+
+    // Artifacts built now carry the new version, and the provenance record sums them.
+    if let Some(tasks) = hooks.and_then(|hooks| hooks.post_write.as_deref()) {
+        println!("Running post_write hooks: {}", tasks.join(", "));
+        (runner.tasks)(tasks).with_context(|| {
+            format!(
+                "post_write hook failed; {} is written but nothing is committed",
+                target
+            )
+        })?;
+    }
+
+    let provenance = match config
+        .git
+        .as_ref()
+        .and_then(|git| git.provenance.as_deref())
+    {
+        Some(raw) => {
+            let path = resolve_path(&current_dir()?, raw)?;
+            write_provenance(config, &path, &scope.tag(&target), &target, dry_run)?;
+            staged_paths.push(path.clone());
+            Some(path)
+        }
+        None => None,
+    };
+
+    let released = Action::Released {
+        version: target.to_string(),
+        previous: current.to_string(),
+        tag: scope.tag(&target),
+        previous_tag,
+        manifests: manifests
+            .iter()
+            .map(|(path, ..)| path.to_string())
+            .collect(),
+        changelog: changelog.map(String::from),
+        package_changelogs,
+        provenance: provenance.map(String::from),
+    };
+    // A release PR is not a release until it merges, so rollback has nothing to undo yet.
+    let mut actions = match release_pr {
+        Some(_) => Vec::new(),
+        None => vec![released],
+    };
+    if let Some((base, branch)) = &release_pr {
+        let release = PrRelease {
+            base,
+            branch,
+            tag: &scope.tag(&target),
+            version: &target,
+            sign,
         };
-        fs::write(&path, output).with_context(|| format!("writing {}", path))?;
-        println!("Updated {} to {}", path, target);
+        commit_release_pr(config, &release, &staged_paths, dry_run)?;
+    } else if !options.no_commit {
+        let before = gitops::rev_parse("HEAD")?;
+        git_add(&staged_paths, dry_run)?;
+        let message = release_message(config, &scope.tag(&target), &target);
+        git_commit(&message, sign, dry_run)?;
+        // Only branch commits are journaled: a detached HEAD has no branch to reset.
+        if !dry_run
+            && let (Some(before), Some(after), Some(branch)) = (
+                before,
+                gitops::rev_parse("HEAD")?,
+                gitops::current_branch()?,
+            )
+        {
+            actions.push(Action::BranchMoved {
+                branch,
+                before,
+                after,
+                pushed: false,
+            });
+        }
+    }
+
+    if options.tag {
+        let tag_name = scope.tag(&target);
+        let notes = match changelog_path(config)? {
+            Some(path) if path.exists() => {
+                let content =
+                    fs::read_to_string(&path).with_context(|| format!("reading {}", path))?;
+                changelog_section(&content, &tag_name)
+            }
+            _ => None,
+        };
+        git_tag(&tag_name, notes.as_deref(), sign, dry_run)?;
+        // Annotated tags point at a tag object; the journal tracks the commit.
+        if !dry_run
+            && let Some(target) = gitops::rev_parse(&format!("refs/tags/{}^{{commit}}", tag_name))?
+        {
+            actions.push(Action::Tagged {
+                tag: tag_name,
+                target,
+            });
+        }
     }
 
-    let mut staged_paths = vec![path.clone()];
+    if !dry_run {
+        gitjournal::record("version bump", actions)?;
+    }
 
-    if !args.no_changelog
-        && let Some(changelog) = changelog_path(config)?
+    if let Some(tasks) = hooks.and_then(|hooks| hooks.post_bump.as_deref()) {
+        println!("Running post_bump hooks: {}", tasks.join(", "));
+        (runner.tasks)(tasks).with_context(|| {
+            format!(
+                "post_bump hook failed after releasing {} (`dev version rollback` reverts it)",
+                target
+            )
+        })?;
+    }
+    Ok(())
+}
+
+fn rollback_release(config: &DevConfig, args: &VersionRollback, dry_run: bool) -> Result<()> {
+    let entries = gitjournal::load()?;
+    let Some((index, release)) = entries.iter().enumerate().rev().find_map(|(index, entry)| {
+        entry
+            .actions
+            .iter()
+            .find(|action| matches!(action, Action::Released { .. }))
+            .map(|action| (index, action.clone()))
+    }) else {
+        bail!("no `dev version bump` recorded in the gitops journal; nothing to roll back");
+    };
+    let Action::Released {
+        version,
+        previous,
+        tag,
+        previous_tag,
+        manifests,
+        changelog,
+        package_changelogs,
+        provenance,
+    } = release
+    else {
+        unreachable!("filtered to releases above");
+    };
+    if !dry_run && gitops::worktree_dirty()? {
+        bail!("working tree has uncommitted changes; commit or stash them before rolling back");
+    }
+    let released = Version::parse(&version).with_context(|| format!("parsing {}", version))?;
+    let restored = Version::parse(&previous).with_context(|| format!("parsing {}", previous))?;
+
+    // Check everything before touching anything: each manifest must still be at the release.
+    let mut updates = Vec::new();
+    let located = locate_version_files(config)?;
+    for path in manifests.iter().map(Utf8PathBuf::from) {
+        let kind = match located.iter().find(|(located, _)| *located == path) {
+            Some((_, kind)) => kind.clone(),
+            None => detect_version_file(&path)?,
+        };
+        let mut doc = read_manifest(&path, &kind)?;
+        let now = current_version(&doc, &kind)
+            .with_context(|| format!("reading version from {}", path))?;
+        if now != released {
+            bail!(
+                "{} is at {} now, not {}; not rolling back",
+                path,
+                now,
+                released
+            );
+        }
+        let original = render_manifest(&doc, &kind);
+        write_version(&mut doc, &kind, &restored);
+        updates.push((path, original, render_manifest(&doc, &kind)));
+    }
+    let changelogs: Vec<&String> = changelog.iter().chain(&package_changelogs).collect();
+    for path in changelogs
+        .iter()
+        .map(Utf8PathBuf::from)
+        .filter(|path| path.exists())
     {
-        update_changelog(&changelog, &target, dry_run)?;
-        staged_paths.push(changelog);
+        let content = fs::read_to_string(&path).with_context(|| format!("reading {}", path))?;
+        let mut links = Vec::new();
+        if let (Some(web), Some(previous_tag)) = (gitprovider::repository_url(), &previous_tag) {
+            let provider = gitprovider::from_config(config)?;
+            links.push((
+                "Unreleased".to_string(),
+                provider.compare_url(&web, previous_tag, "HEAD"),
+            ));
+        }
+        match changelog::remove_release(&content, &tag, &links) {
+            Some(updated) => updates.push((path, content, updated)),
+            None => println!("[warn] {} has no {} section; leaving it as is", path, tag),
+        }
+    }
+    // The provenance file describes only the latest release: put back the previous
+    // release's record, or drop the file if this was the first one.
+    let mut removed = None;
+    if let Some(path) = provenance
+        .map(Utf8PathBuf::from)
+        .filter(|path| path.exists())
+    {
+        let content = fs::read_to_string(&path).with_context(|| format!("reading {}", path))?;
+        match previous_tag
+            .as_deref()
+            .map(|previous_tag| file_at(previous_tag, &path))
+            .transpose()?
+            .flatten()
+        {
+            Some(earlier) => updates.push((path, content, earlier)),
+            None => removed = Some(path),
+        }
+    }
+
+    let strings = |items: &[&str]| {
+        items
+            .iter()
+            .map(|item| item.to_string())
+            .collect::<Vec<_>>()
+    };
+    if args.remote {
+        run_git(
+            &strings(&["push", "origin", "--delete", &format!("refs/tags/{}", tag)]),
+            dry_run,
+        )?;
+    }
+    if gitops::rev_parse(&format!("refs/tags/{}", tag))?.is_some() {
+        run_git(&strings(&["tag", "-d", &tag]), dry_run)?;
     }
 
+    if !dry_run {
+        write_all(&updates)?;
+        if let Some(path) = &removed {
+            fs::remove_file(path).with_context(|| format!("removing {}", path))?;
+        }
+    }
+    for (path, ..) in &updates {
+        // Manifests and provenance return to `restored`; changelogs lose a section.
+        let changelog = changelogs.iter().any(|changelog| *changelog == path);
+        match (changelog, dry_run) {
+            (false, true) => println!("[dry-run] would restore {} to {}", path, restored),
+            (false, false) => println!("Restored {} to {}", path, restored),
+            (true, true) => println!("[dry-run] would remove the {} section from {}", tag, path),
+            (true, false) => println!("Removed the {} section from {}", tag, path),
+        }
+    }
+    if let Some(path) = &removed {
+        match dry_run {
+            true => println!("[dry-run] would remove {}", path),
+            false => println!("Removed {}", path),
+        }
+    }
     if !args.no_commit {
-        git_add(&staged_paths, dry_run)?;
-        let message = format!("chore: release {}", target);
-        git_commit(&message, dry_run)?;
+        let sign = config
+            .git
+            .as_ref()
+            .and_then(|git| git.sign)
+            .unwrap_or(false);
+        let paths: Vec<Utf8PathBuf> = updates
+            .iter()
+            .map(|(path, ..)| path.clone())
+            .chain(removed)
+            .collect();
+        git_add(&paths, dry_run)?;
+        git_commit(
+            &format!("chore: roll back release {}", released),
+            sign,
+            dry_run,
+        )?;
+    }
+    if !dry_run {
+        // The release commit (and a release PR branch) stay journaled for `dev git undo`.
+        gitjournal::retain(index, |action| match action {
+            Action::Released { .. } => false,
+            Action::Tagged { tag: tagged, .. } => *tagged != tag,
+            _ => true,
+        })?;
+        println!("Rolled back {} to {}.", released, restored);
+    }
+    Ok(())
+}
+
+/// Contents of `path` as committed at `rev`, or `None` when it did not exist there.
+fn file_at(rev: &str, path: &Utf8Path) -> Result<Option<String>> {
+    let dir = path.parent().unwrap_or(Utf8Path::new("."));
+    let name = path.file_name().unwrap_or_default();
+    let output = Command::new("git")
+        .args(["-C", dir.as_str(), "show", &format!("{}:./{}", rev, name)])
+        .output()
+        .with_context(|| format!("running git show {}:{}", rev, path))?;
+    Ok(output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+/// `[git] require_ci`: every pipeline must pass locally, in order, before anything is
+/// written.
+fn run_required_ci(required: &[String], target: &Version, runner: &Runner) -> Result<()> {
+    for pipeline in required {
+        (runner.pipeline)(pipeline).with_context(|| {
+            format!(
+                "`{}` pipeline failed; fix it before releasing {}",
+                pipeline, target
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// `--remote-ci`: the commit being released must have green checks on GitHub.
+fn ensure_remote_ci_green(dry_run: bool) -> Result<()> {
+    let sha = gitops::rev_parse("HEAD")?.ok_or_else(|| anyhow!("no commit to release"))?;
+    if dry_run {
+        println!("[dry-run] verify CI checks for HEAD ({})", &sha[..12]);
+        return Ok(());
     }
+    let checks = gitpr::commit_checks(&sha).context("verifying CI checks for HEAD")?;
+    remote_ci_verdict(&sha, &checks)
+}
 
-    if args.tag {
-        let tag_name = format!("v{}", target);
-        git_tag(&tag_name, dry_run)?;
+fn remote_ci_verdict(sha: &str, checks: &[gitpr::Check]) -> Result<()> {
+    match gitpr::summarize(checks) {
+        "passing" => {
+            println!("CI checks for HEAD are green ({} checks).", checks.len());
+            Ok(())
+        }
+        "none" => bail!(
+            "no CI checks reported for HEAD ({}); push it and wait for CI",
+            sha
+        ),
+        summary => {
+            let pending: Vec<String> = checks
+                .iter()
+                .filter(|check| check.result == "failing" || check.result == "pending")
+                .map(|check| format!("  {:<8} {}", check.result, check.name))
+                .collect();
+            bail!(
+                "CI checks for HEAD are {}:\n{}",
+                summary,
+                pending.join("\n")
+            )
+        }
     }
+}
 
+fn publish_packages(
+    config: &DevConfig,
+    scope: &Scope,
+    args: &VersionPublish,
+    dry_run: bool,
+) -> Result<()> {
+    // Only publish exactly what was released: every version file agrees and the
+    // release tag is checked out.
+    let files = locate_version_files(config)?;
+    let mut version: Option<Version> = None;
+    for (path, kind) in &files {
+        let found = read_manifest(path, kind)
+            .and_then(|doc| current_version(&doc, kind))
+            .with_context(|| format!("reading version from {}", path))?;
+        match &version {
+            None => version = Some(found),
+            Some(expected) if *expected != found => bail!(
+                "{} is at {} but {} is at {}; run `dev version verify`",
+                path,
+                found,
+                files[0].0,
+                expected
+            ),
+            Some(_) => {}
+        }
+    }
+    let version = version.ok_or_else(|| anyhow!("no version file found"))?;
+    let tag = scope.tag(&version);
+    let tagged = gitops::rev_parse(&format!("refs/tags/{}^{{commit}}", tag))?.ok_or_else(|| {
+        anyhow!(
+            "tag {} does not exist; release with `dev version bump --tag` first",
+            tag
+        )
+    })?;
+    let head = gitops::rev_parse("HEAD")?.unwrap_or_default();
+    if tagged != head {
+        bail!(
+            "tag {} points at {}, not HEAD ({}); check out the tag to publish it",
+            tag,
+            &tagged[..tagged.len().min(8)],
+            &head[..head.len().min(8)]
+        );
+    }
+    if !dry_run && gitops::worktree_dirty()? {
+        bail!(
+            "working tree has uncommitted changes; publish from a clean checkout of {}",
+            tag
+        );
+    }
+
+    let configured = config.git.as_ref().and_then(|git| git.publish.clone());
+    let tools = match (&args.tools[..], configured) {
+        ([], Some(configured)) => configured,
+        ([], None) => infer_publish_tools(&files),
+        (tools, _) => tools.to_vec(),
+    };
+    if tools.is_empty() {
+        bail!("nothing to publish: set `[git] publish` or pass --tool");
+    }
+    let cwd = current_dir()?;
+    for tool in &tools {
+        let (manifest, steps) = publish_steps(tool)?;
+        // Run where the tool's manifest lives (the project directory otherwise).
+        let dir = files
+            .iter()
+            .find(|(path, _)| path.file_name() == Some(manifest))
+            .and_then(|(path, _)| path.parent())
+            .map_or(cwd.clone(), Utf8Path::to_path_buf);
+        println!("Publishing {} with {} from {}", tag, tool, dir);
+        for step in steps {
+            run_in(&dir, &step, dry_run)?;
+        }
+    }
+    if !dry_run {
+        println!("Published {} ({}).", tag, tools.join(", "));
+    }
     Ok(())
 }
 
-fn print_changelog(_config: &DevConfig, args: &ChangelogArgs) -> Result<()> {
+/// Registry tools for the version files: cargo for `Cargo.toml`, pnpm or npm for
+/// `package.json` (pnpm when a `pnpm-lock.yaml` sits next to it), uv for `pyproject.toml`.
+fn infer_publish_tools(files: &[(Utf8PathBuf, VersionFileKind)]) -> Vec<String> {
+    let mut tools: Vec<String> = Vec::new();
+    for (path, kind) in files {
+        let tool = match kind {
+            VersionFileKind::CargoToml => "cargo",
+            VersionFileKind::PackageJson => {
+                let lock = path.with_file_name("pnpm-lock.yaml");
+                if lock.exists() { "pnpm" } else { "npm" }
+            }
+            VersionFileKind::PyprojectToml => "uv",
+            VersionFileKind::Pattern(_) => continue,
+        };
+        if !tools.iter().any(|known| known == tool) {
+            tools.push(tool.to_string());
+        }
+    }
+    tools
+}
+
+/// The manifest a publish tool works from and the commands it runs. A `*` argument is
+/// expanded when the step runs, after the build produced the files.
+fn publish_steps(tool: &str) -> Result<(&'static str, Vec<Vec<String>>)> {
+    let steps: &[&[&str]] = match tool {
+        "cargo" => &[&["cargo", "publish"]],
+        "npm" => &[&["npm", "publish"]],
+        "pnpm" => &[&["pnpm", "publish"]],
+        "uv" => &[&["uv", "build"], &["uv", "publish"]],
+        "twine" => &[&["uv", "build"], &["twine", "upload", "dist/*"]],
+        other => bail!(
+            "unknown publish tool `{}` (expected cargo, npm, pnpm, uv, or twine)",
+            other
+        ),
+    };
+    let manifest = match tool {
+        "cargo" => "Cargo.toml",
+        "npm" | "pnpm" => "package.json",
+        _ => "pyproject.toml",
+    };
+    let steps = steps
+        .iter()
+        .map(|step| step.iter().map(|arg| arg.to_string()).collect())
+        .collect();
+    Ok((manifest, steps))
+}
+
+fn run_in(dir: &Utf8Path, argv: &[String], dry_run: bool) -> Result<()> {
+    let display = argv.join(" ");
+    if dry_run {
+        println!("[dry-run] (in {}) {}", dir, display);
+        return Ok(());
+    }
+    let mut args = Vec::new();
+    for arg in &argv[1..] {
+        if arg.contains('*') {
+            let pattern = dir.join(arg).to_string();
+            args.extend(expand_assets(&[pattern])?.into_iter().map(String::from));
+        } else {
+            args.push(arg.clone());
+        }
+    }
+    let status = Command::new(&argv[0])
+        .args(&args)
+        .current_dir(dir)
+        .status()
+        .with_context(|| format!("running `{}`", display))?;
+    if !status.success() {
+        bail!(
+            "`{}` failed with status {}",
+            display,
+            status.code().unwrap_or(-1)
+        );
+    }
+    Ok(())
+}
+
+fn next_version(
+    config: &DevConfig,
+    scope: &Scope,
+    args: &VersionNext,
+    dry_run: bool,
+    runner: &Runner,
+) -> Result<()> {
+    let (path, kind) = locate_version_files(config)?.remove(0);
+    let current = current_version(&read_manifest(&path, &kind)?, &kind)?;
+    let latest = scope.latest_tag()?;
+    let range = match &latest {
+        Some(tag) => format!("{}..HEAD", tag),
+        None => "HEAD".to_string(),
+    };
+    let commits = collect_entries(changelog_source(config, None)?, &range, scope.paths())?;
+    let since = latest.as_deref().unwrap_or("the first commit");
+
+    let Some(level) = recommend(&commits, &current) else {
+        println!("No commits since {}; nothing to release.", since);
+        return Ok(());
+    };
+    let count = |section: changelog::Section| {
+        commits
+            .iter()
+            .filter(|commit| commit.section() == section)
+            .count()
+    };
+    let target = target_version(config, &current, level)?;
+    println!(
+        "{} commit(s) since {}: {} breaking, {} feature(s), {} fix(es), {} other",
+        commits.len(),
+        since,
+        count(changelog::Section::Breaking),
+        count(changelog::Section::Features),
+        count(changelog::Section::Fixes),
+        count(changelog::Section::Other)
+    );
+    if calver_pattern(config)?.is_some() {
+        println!("Next calver release: {} -> {}", current, target);
+    } else {
+        println!(
+            "Recommended bump: {} ({} -> {})",
+            level
+                .to_possible_value()
+                .expect("every bump level is a CLI value")
+                .get_name(),
+            current,
+            target
+        );
+    }
+
+    if args.apply {
+        let bump = VersionBump {
+            level: Some(level),
+            custom: None,
+            build: None,
+            strip_build: false,
+            options: args.options.clone(),
+        };
+        bump_version(config, scope, &bump, dry_run, runner)?;
+    }
+    Ok(())
+}
+
+/// Bump level implied by conventional commits: breaking changes are major, features minor,
+/// anything else patch. Before 1.0.0, breaking changes only bump the minor version.
+/// `None` when there is nothing to release.
+fn recommend(commits: &[changelog::Commit], current: &Version) -> Option<BumpLevel> {
+    if commits.is_empty() {
+        return None;
+    }
+    let sections: Vec<changelog::Section> = commits.iter().map(|commit| commit.section()).collect();
+    let level = if sections.contains(&changelog::Section::Breaking) {
+        if current.major == 0 {
+            BumpLevel::Minor
+        } else {
+            BumpLevel::Major
+        }
+    } else if sections.contains(&changelog::Section::Features) {
+        BumpLevel::Minor
+    } else {
+        BumpLevel::Patch
+    };
+    Some(level)
+}
+
+fn print_changelog(config: &DevConfig, scope: &Scope, args: &ChangelogArgs) -> Result<()> {
+    let source = changelog_source(config, args.source)?;
     let range = if let Some(since) = &args.since {
         format!("{}..HEAD", since)
     } else if args.unreleased {
-        let tag = latest_tag()?.unwrap_or_else(|| "HEAD^".to_string());
+        let tag = scope.latest_tag()?.unwrap_or_else(|| "HEAD^".to_string());
         format!("{}..HEAD", tag)
     } else {
         format!("{}..HEAD", DEFAULT_BASE_BRANCH)
     };
 
-    let commits = collect_commits(&range)?;
-    if commits.is_empty() {
+    let commits = collect_entries(source, &range, scope.paths())?;
+    if args.format == ChangelogFormat::Json {
+        println!("{}", changelog::render_json(&range, &commits)?);
+    } else if commits.is_empty() {
         println!("No commits for range {}", range);
     } else {
-        println!("Changelog for {}:", range);
-        for commit in commits {
-            println!("- {}", commit);
+        println!("Changelog for {}:\n", range);
+        print!("{}", changelog::render(&commits));
+    }
+    Ok(())
+}
+
+fn render_manifest(doc: &DocumentMut, kind: &VersionFileKind) -> String {
+    match kind {
+        VersionFileKind::PackageJson => doc["__raw_json"]
+            .as_str()
+            .map(|s| format!("{}\n", s))
+            .unwrap_or_default(),
+        VersionFileKind::Pattern(_) => doc["__raw_text"]
+            .as_str()
+            .map(str::to_owned)
+            .unwrap_or_default(),
+        _ => doc.to_string(),
+    }
+}
+
+/// Write `(path, original, updated)` triples, restoring the files already written when a
+/// later write fails so the manifests never disagree.
+fn write_all(updates: &[(Utf8PathBuf, String, String)]) -> Result<()> {
+    for (index, (path, _, updated)) in updates.iter().enumerate() {
+        if let Err(err) = fs::write(path, updated) {
+            for (written, original, _) in &updates[..index] {
+                let _ = fs::write(written, original);
+            }
+            return Err(err).with_context(|| format!("writing {}", path));
         }
     }
     Ok(())
 }
 
-fn read_manifest(path: &Utf8Path, kind: VersionFileKind) -> Result<DocumentMut> {
+fn read_manifest(path: &Utf8Path, kind: &VersionFileKind) -> Result<DocumentMut> {
     let contents = fs::read_to_string(path).with_context(|| format!("reading {}", path))?;
     match kind {
         VersionFileKind::CargoToml | VersionFileKind::PyprojectToml => contents
@@ -113,14 +1014,22 @@ fn read_manifest(path: &Utf8Path, kind: VersionFileKind) -> Result<DocumentMut>
             doc["__raw_json"] = toml_edit::value(contents);
             Ok(doc)
         }
+        VersionFileKind::Pattern(pattern) => {
+            pattern_regex(pattern)?;
+            let mut doc = DocumentMut::new();
+            doc["__raw_text"] = toml_edit::value(contents);
+            Ok(doc)
+        }
     }
 }
 
-fn current_version(doc: &DocumentMut, kind: VersionFileKind) -> Result<Version> {
+fn current_version(doc: &DocumentMut, kind: &VersionFileKind) -> Result<Version> {
     match kind {
-        VersionFileKind::CargoToml => doc["package"]["version"]
-            .as_str()
-            .ok_or_else(|| anyhow!("missing package.version in Cargo.toml"))
+        VersionFileKind::CargoToml => cargo_version_item(doc)
+            .and_then(|item| item.as_str())
+            .ok_or_else(|| {
+                anyhow!("missing package.version (or workspace.package.version) in Cargo.toml")
+            })
             .and_then(|s| Version::parse(s).with_context(|| format!("parsing version `{}`", s))),
         VersionFileKind::PyprojectToml => doc["project"]["version"]
             .as_str()
@@ -137,12 +1046,51 @@ fn current_version(doc: &DocumentMut, kind: VersionFileKind) -> Result<Version>
                 .ok_or_else(|| anyhow!("missing version in package.json"))?;
             Version::parse(ver_str).with_context(|| format!("parsing version `{}`", ver_str))
         }
+        VersionFileKind::Pattern(pattern) => {
+            let raw = doc["__raw_text"]
+                .as_str()
+                .ok_or_else(|| anyhow!("internal error: missing raw text"))?;
+            let regex = pattern_regex(pattern)?;
+            let found = version_spans(&regex, raw)
+                .next()
+                .map(|span| &raw[span])
+                .ok_or_else(|| anyhow!("version pattern `{}` matches nothing", pattern))?;
+            Version::parse(found).with_context(|| format!("parsing version `{}`", found))
+        }
     }
 }
 
-fn write_version(doc: &mut DocumentMut, kind: VersionFileKind, version: &Version) {
+/// Byte ranges of the version in every match of `regex`.
+fn version_spans<'a>(
+    regex: &'a regex::Regex,
+    text: &'a str,
+) -> impl Iterator<Item = std::ops::Range<usize>> + 'a {
+    regex
+        .captures_iter(text)
+        .filter_map(|caps| caps.get(1).map(|group| group.range()))
+}
+
+fn cargo_version_item(doc: &DocumentMut) -> Option<&toml_edit::Item> {
+    doc.get("package")
+        .and_then(|package| package.get("version"))
+        .or_else(|| doc.get("workspace")?.get("package")?.get("version"))
+}
+
+fn write_version(doc: &mut DocumentMut, kind: &VersionFileKind, version: &Version) {
     match kind {
-        VersionFileKind::CargoToml => doc["package"]["version"] = value(version.to_string()),
+        VersionFileKind::CargoToml => {
+            // Workspace roots keep the shared version under `[workspace.package]`.
+            if doc.get("package").is_none()
+                && doc
+                    .get("workspace")
+                    .and_then(|workspace| workspace.get("package"))
+                    .is_some()
+            {
+                doc["workspace"]["package"]["version"] = value(version.to_string());
+            } else {
+                doc["package"]["version"] = value(version.to_string());
+            }
+        }
         VersionFileKind::PyprojectToml => doc["project"]["version"] = value(version.to_string()),
         VersionFileKind::PackageJson => {
             // Update version in the stored raw JSON
@@ -155,6 +1103,55 @@ fn write_version(doc: &mut DocumentMut, kind: VersionFileKind, version: &Version
                 }
             }
         }
+        VersionFileKind::Pattern(pattern) => {
+            if let (Some(raw), Ok(regex)) = (doc["__raw_text"].as_str(), pattern_regex(pattern)) {
+                let mut updated = String::with_capacity(raw.len());
+                let mut last = 0;
+                for span in version_spans(&regex, raw) {
+                    updated.push_str(&raw[last..span.start]);
+                    updated.push_str(&version.to_string());
+                    last = span.end;
+                }
+                updated.push_str(&raw[last..]);
+                doc["__raw_text"] = value(updated);
+            }
+        }
+    }
+}
+
+/// The version a bump at `level` produces under the configured `[git] version_scheme`.
+/// CalVer follows the calendar whatever the level, except that `prerelease` still
+/// produces a pre-release (of the next calendar version when `current` is a release).
+fn target_version(config: &DevConfig, current: &Version, level: BumpLevel) -> Result<Version> {
+    let Some(pattern) = calver_pattern(config)? else {
+        return increment_version(current, level);
+    };
+    if level != BumpLevel::Prerelease {
+        return pattern.next(current, Local::now().date_naive());
+    }
+    if !current.pre.is_empty() {
+        return bump_prerelease(current);
+    }
+    let mut next = pattern.next(current, Local::now().date_naive())?;
+    next.pre = Prerelease::new("alpha.1")?;
+    Ok(next)
+}
+
+/// `Some` when `[git] version_scheme = "calver"`.
+fn calver_pattern(config: &DevConfig) -> Result<Option<calver::Pattern>> {
+    let git = config.git.as_ref();
+    match git.and_then(|git| git.version_scheme.as_deref()) {
+        None | Some("semver") => Ok(None),
+        Some("calver") => {
+            let raw = git
+                .and_then(|git| git.calver_pattern.as_deref())
+                .unwrap_or(calver::DEFAULT_PATTERN);
+            calver::Pattern::parse(raw).map(Some)
+        }
+        Some(other) => bail!(
+            "unknown [git] version_scheme `{}` (expected semver or calver)",
+            other
+        ),
     }
 }
 
@@ -172,6 +1169,8 @@ fn increment_version(version: &Version, level: crate::cli::BumpLevel) -> Result<
 
 fn bump_prerelease(version: &Version) -> Result<Version> {
     let mut new = version.clone();
+    // Build metadata describes one build; it never carries over to the next version.
+    new.build = BuildMetadata::EMPTY;
     if new.pre.is_empty() {
         new.pre = Prerelease::new("alpha.1")?;
     } else {
@@ -192,10 +1191,40 @@ fn bump_prerelease(version: &Version) -> Result<Version> {
     Ok(new)
 }
 
+/// Every manifest a bump updates: `[git] version_files` when set, else the single
+/// detected version file, followed by the `[[git.version_patterns]]` files. The first
+/// one is the source of the current version.
+fn locate_version_files(config: &DevConfig) -> Result<Vec<(Utf8PathBuf, VersionFileKind)>> {
+    let git = config.git.as_ref();
+    let listed = git
+        .and_then(|git| git.version_files.as_deref())
+        .unwrap_or_default();
+    let patterns = git
+        .and_then(|git| git.version_patterns.as_deref())
+        .unwrap_or_default();
+    let cwd = current_dir()?;
+    let mut files = Vec::new();
+    if listed.is_empty() {
+        let detected = locate_version_file(config)?;
+        // Projects versioned only through patterns have no manifest to detect.
+        if patterns.is_empty() || detected.0.exists() {
+            files.push(detected);
+        }
+    }
+    for raw in listed {
+        let path = resolve_path(&cwd, raw)?;
+        let kind = detect_version_file(&path)?;
+        files.push((path, kind));
+    }
+    for entry in patterns {
+        let path = resolve_path(&cwd, &entry.file)?;
+        files.push((path, VersionFileKind::Pattern(entry.pattern.clone())));
+    }
+    Ok(files)
+}
+
 fn locate_version_file(config: &DevConfig) -> Result<(Utf8PathBuf, VersionFileKind)> {
-    let cwd = std::env::current_dir().context("determining current directory")?;
-    let cwd = Utf8PathBuf::from_path_buf(cwd)
-        .map_err(|_| anyhow!("current directory is not valid UTF-8"))?;
+    let cwd = current_dir()?;
 
     // Explicit version_file in config takes precedence
     if let Some(raw) = config
@@ -214,7 +1243,52 @@ fn locate_version_file(config: &DevConfig) -> Result<(Utf8PathBuf, VersionFileKi
         Some("typescript" | "javascript") => ("package.json", VersionFileKind::PackageJson),
         _ => ("Cargo.toml", VersionFileKind::CargoToml),
     };
-    Ok((cwd.join(filename), kind))
+    let path = cwd.join(filename);
+    if !path.exists() {
+        // Monorepo projects often differ from the default language; use whichever
+        // manifest the (project) directory actually has.
+        for (candidate, kind) in [
+            ("Cargo.toml", VersionFileKind::CargoToml),
+            ("pyproject.toml", VersionFileKind::PyprojectToml),
+            ("package.json", VersionFileKind::PackageJson),
+        ] {
+            if cwd.join(candidate).exists() {
+                return Ok((cwd.join(candidate), kind));
+            }
+        }
+    }
+    Ok((path, kind))
+}
+
+/// Directories, relative to `cwd`, of the package manifests among `files` that get their
+/// own changelog: every manifest outside `cwd` itself, whose changelog stays the aggregate.
+/// `version_patterns` files are not packages.
+fn package_dirs(cwd: &Utf8Path, files: &[(&Utf8Path, &VersionFileKind)]) -> Vec<Utf8PathBuf> {
+    let mut dirs: Vec<Utf8PathBuf> = Vec::new();
+    for (path, kind) in files {
+        if matches!(kind, VersionFileKind::Pattern(_)) {
+            continue;
+        }
+        let Some(dir) = path.parent().and_then(|dir| dir.strip_prefix(cwd).ok()) else {
+            continue;
+        };
+        if !dir.as_str().is_empty() && !dirs.iter().any(|known| known == dir) {
+            dirs.push(dir.to_path_buf());
+        }
+    }
+    dirs
+}
+
+/// Unified diff of a pending change to `path`, labelled relative to the current directory.
+fn preview(path: &Utf8Path, original: &str, updated: &str) -> Result<String> {
+    let cwd = current_dir()?;
+    let label = path.strip_prefix(&cwd).unwrap_or(path);
+    Ok(textdiff::unified(label.as_str(), original, updated))
+}
+
+fn current_dir() -> Result<Utf8PathBuf> {
+    let cwd = std::env::current_dir().context("determining current directory")?;
+    Utf8PathBuf::from_path_buf(cwd).map_err(|_| anyhow!("current directory is not valid UTF-8"))
 }
 
 fn detect_version_file(path: &Utf8Path) -> Result<VersionFileKind> {
@@ -227,33 +1301,49 @@ fn detect_version_file(path: &Utf8Path) -> Result<VersionFileKind> {
     }
 }
 
-fn update_changelog(path: &Utf8Path, version: &Version, dry_run: bool) -> Result<()> {
-    let date = Utc::now().format("%Y-%m-%d");
-    let mut section = format!("## {} - v{}\n\n", date, version);
-    section.push_str("- Describe the notable changes here.\n\n");
+/// Add the `version` section to the changelog at `path`, from the commits since the
+/// previous tag that touch `paths` (all commits when empty).
+fn update_changelog(
+    config: &DevConfig,
+    path: &Utf8Path,
+    scope: &Scope,
+    version: &Version,
+    paths: &[&str],
+    dry_run: bool,
+) -> Result<()> {
+    let source = changelog_source(config, None)?;
+    let tag = scope.tag(version);
+    let heading = format!("[{}] - {}", tag, Utc::now().format("%Y-%m-%d"));
+    let previous = scope.latest_tag()?;
+    let range = match &previous {
+        Some(tag) => format!("{}..HEAD", tag),
+        None => "HEAD".to_string(),
+    };
+    let commits = collect_entries(source, &range, paths)?;
 
-    if dry_run {
-        println!("[dry-run] update {} with:\n{}", path, section);
-        return Ok(());
+    let mut links = Vec::new();
+    if let Some(web) = gitprovider::repository_url() {
+        let provider = gitprovider::from_config(config)?;
+        links.push((
+            "Unreleased".to_string(),
+            provider.compare_url(&web, &tag, "HEAD"),
+        ));
+        if let Some(previous) = &previous {
+            links.push((tag.clone(), provider.compare_url(&web, previous, &tag)));
+        }
     }
 
-    let mut content = if path.exists() {
+    let current = if path.exists() {
         fs::read_to_string(path).with_context(|| format!("reading {}", path))?
     } else {
-        String::from("# Changelog\n\n## Unreleased\n\n")
+        String::new()
     };
+    let content = changelog::add_release(&current, &heading, &commits, &links);
 
-    if let Some(idx) = content.find("## Unreleased") {
-        let insert_at = content[idx..]
-            .find('\n')
-            .map(|offset| idx + offset + 1)
-            .unwrap_or(content.len());
-        content.insert_str(insert_at, &format!("\n{}", section));
-    } else {
-        if !content.ends_with('\n') {
-            content.push('\n');
-        }
-        content.push_str(&section);
+    if dry_run {
+        println!("[dry-run] would update {}:", path);
+        print!("{}", preview(path, &current, &content)?);
+        return Ok(());
     }
 
     let mut file = fs::File::create(path).with_context(|| format!("opening {}", path))?;
@@ -262,6 +1352,376 @@ fn update_changelog(path: &Utf8Path, version: &Version, dry_run: bool) -> Result
     Ok(())
 }
 
+/// Release commit message from `[git] release_commit` (default `chore: release {version}`),
+/// which may also use `{tag}`.
+fn release_message(config: &DevConfig, tag: &str, version: &Version) -> String {
+    config
+        .git
+        .as_ref()
+        .and_then(|git| git.release_commit.as_deref())
+        .unwrap_or("chore: release {version}")
+        .replace("{version}", &version.to_string())
+        .replace("{tag}", tag)
+}
+
+/// Push the `--pr` release `branch`, open a PR for it into `base` with the version's
+/// changelog section as its body, and switch back to `base`.
+/// The release branch `dev version bump --pr` commits to and opens a PR from.
+struct PrRelease<'a> {
+    base: &'a str,
+    branch: &'a str,
+    tag: &'a str,
+    version: &'a Version,
+    sign: bool,
+}
+
+/// Commit the written release on a new branch, journal it, push it, and open the PR. The
+/// journal entry exists before anything reaches origin, so `dev git undo` can always clean
+/// up, and the checkout returns to the base branch whether or not a step fails.
+fn commit_release_pr(
+    config: &DevConfig,
+    release: &PrRelease,
+    staged: &[Utf8PathBuf],
+    dry_run: bool,
+) -> Result<()> {
+    let branch = release.branch;
+    run_git(&["checkout".into(), "-b".into(), branch.into()], dry_run)?;
+    let mut committed = false;
+    let result = (|| -> Result<()> {
+        git_add(staged, dry_run)?;
+        let message = release_message(config, release.tag, release.version);
+        git_commit(&message, release.sign, dry_run)?;
+        committed = true;
+        if dry_run {
+            run_git(
+                &["push".into(), "-u".into(), "origin".into(), branch.into()],
+                dry_run,
+            )?;
+            return open_release_pr(config, release, dry_run);
+        }
+        let tip = gitops::rev_parse(&format!("refs/heads/{}", branch))?
+            .ok_or_else(|| anyhow!("release branch `{}` vanished after the commit", branch))?;
+        gitjournal::record(
+            "version bump",
+            vec![Action::BranchCreated {
+                branch: branch.to_string(),
+                base: release.base.to_string(),
+                tip,
+                previous: None,
+                pushed: false,
+            }],
+        )?;
+        run_git(
+            &["push".into(), "-u".into(), "origin".into(), branch.into()],
+            dry_run,
+        )
+        .with_context(|| format!("pushing `{}`", branch))?;
+        gitjournal::amend_last(|actions| {
+            for action in actions {
+                if let Action::BranchCreated {
+                    branch: created,
+                    pushed,
+                    ..
+                } = action
+                    && created == branch
+                {
+                    *pushed = true;
+                }
+            }
+        })?;
+        open_release_pr(config, release, dry_run)
+    })();
+    let back = run_git(&["checkout".into(), release.base.into()], dry_run);
+    // A branch without the release commit is just noise; the changes stay in the worktree.
+    if !committed && back.is_ok() {
+        let _ = run_git(&["branch".into(), "-D".into(), branch.into()], dry_run);
+    }
+    match (result, back) {
+        (Err(err), Ok(())) => Err(err.context(format!("switched back to `{}`", release.base))),
+        (Err(err), Err(_)) => Err(err.context(format!(
+            "could not switch back to `{}`; still on `{}`",
+            release.base, branch
+        ))),
+        (Ok(()), back) => back,
+    }
+}
+
+fn open_release_pr(config: &DevConfig, release: &PrRelease, dry_run: bool) -> Result<()> {
+    let PrRelease {
+        base,
+        branch,
+        tag,
+        version,
+        ..
+    } = *release;
+    let notes = match changelog_path(config)? {
+        Some(path) if path.exists() && !dry_run => {
+            let content = fs::read_to_string(&path).with_context(|| format!("reading {}", path))?;
+            changelog_section(&content, tag)
+        }
+        _ => None,
+    };
+    let title = release_message(config, tag, version);
+    let git = config.git.as_ref();
+    let provider = gitprovider::from_config(config)?;
+    let create = provider.create_pr(&PullRequest {
+        base,
+        head: branch,
+        title: Some(&title),
+        body: Some(notes.as_deref().unwrap_or(&title)),
+        reviewers: &git
+            .and_then(|git| git.release_reviewers.clone())
+            .unwrap_or_default(),
+        labels: &git
+            .and_then(|git| git.release_labels.clone())
+            .unwrap_or_default(),
+        draft: git.and_then(|git| git.release_draft).unwrap_or(false),
+    })?;
+    gitops::run_steps(&[create], dry_run)?;
+    if !dry_run {
+        println!("Opened release PR from `{}` into `{}`.", branch, base);
+    }
+    Ok(())
+}
+
+/// Write the `[git] provenance` record for `tag`: the commit being released from, the
+/// tool versions in `.dev/lock.toml`, and checksums of `[git] release_assets`. Runs after
+/// the version writes and `post_write` hooks, so the assets are the ones being released.
+fn write_provenance(
+    config: &DevConfig,
+    path: &Utf8Path,
+    tag: &str,
+    version: &Version,
+    dry_run: bool,
+) -> Result<()> {
+    let cwd = current_dir()?;
+    let patterns = config
+        .git
+        .as_ref()
+        .and_then(|git| git.release_assets.clone())
+        .unwrap_or_default();
+    let mut checksums = Vec::new();
+    for asset in expand_assets(&patterns)? {
+        let sum = provenance::sha256(&asset)?;
+        let display = asset.strip_prefix(&cwd).unwrap_or(&asset).to_string();
+        checksums.push((sum, display));
+    }
+    let source = gitops::rev_parse("HEAD")?;
+    let content = provenance::render(&provenance::Provenance {
+        tag,
+        version: &version.to_string(),
+        source: source.as_deref(),
+        date: &Utc::now().format("%Y-%m-%d").to_string(),
+        tools: &toollock::locked(&cwd)?,
+        checksums: &checksums,
+    });
+
+    let current = if path.exists() {
+        fs::read_to_string(path).with_context(|| format!("reading {}", path))?
+    } else {
+        String::new()
+    };
+    if dry_run {
+        println!("[dry-run] would update {}:", path);
+        print!("{}", preview(path, &current, &content)?);
+        return Ok(());
+    }
+    fs::write(path, content).with_context(|| format!("writing {}", path))?;
+    println!("Recorded provenance for {} in {}", tag, path);
+    Ok(())
+}
+
+/// Publish `args.tag` (default: the latest version tag) as a release on the configured
+/// provider with its `CHANGELOG.md` section as notes and the build artifacts attached.
+pub fn publish_release(
+    config: &DevConfig,
+    scope: &Scope,
+    args: &ReleaseArgs,
+    dry_run: bool,
+) -> Result<()> {
+    let tag = match &args.tag {
+        Some(tag) => tag.clone(),
+        None => scope.latest_tag()?.ok_or_else(|| {
+            anyhow!(
+                "no `{}*{}` tag found; run `dev version bump --tag` first",
+                scope.tag_prefix,
+                scope.tag_suffix
+            )
+        })?,
+    };
+    let exists = Command::new("git")
+        .args([
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("refs/tags/{}", tag),
+        ])
+        .stdout(std::process::Stdio::null())
+        .status()
+        .context("checking tag")?;
+    if !exists.success() {
+        bail!("tag `{}` does not exist", tag);
+    }
+
+    let notes = match changelog_path(config)? {
+        Some(path) if path.exists() => {
+            let content = fs::read_to_string(&path).with_context(|| format!("reading {}", path))?;
+            changelog_section(&content, &tag)
+        }
+        _ => None,
+    };
+    let prerelease = scope
+        .tag_version(&tag)
+        .is_some_and(|version| !version.pre.is_empty());
+
+    let mut patterns = args.assets.clone();
+    if !args.no_assets
+        && let Some(declared) = config
+            .git
+            .as_ref()
+            .and_then(|git| git.release_assets.as_ref())
+    {
+        patterns.extend(declared.iter().cloned());
+    }
+    let assets = expand_assets(&patterns)?;
+
+    let provider = gitprovider::from_config(config)?;
+    let invocation = provider.create_release(&Release {
+        tag: &tag,
+        notes: notes.as_deref(),
+        draft: args.draft,
+        prerelease,
+        assets: &assets,
+    })?;
+
+    run_git(&["push".into(), "origin".into(), tag.clone()], dry_run)?;
+    let display = invocation.argv.join(" ");
+    if dry_run {
+        println!("[dry-run] {}", display);
+        if let (Some(notes), Some(_)) = (&notes, &invocation.stdin) {
+            println!("{}", notes);
+        }
+        return Ok(());
+    }
+
+    let mut child = Command::new(&invocation.argv[0])
+        .args(&invocation.argv[1..])
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+        .with_context(|| format!("running `{}`", invocation.argv[0]))?;
+    if let (Some(input), Some(mut stdin)) = (&invocation.stdin, child.stdin.take()) {
+        stdin
+            .write_all(input.as_bytes())
+            .context("writing release notes")?;
+    }
+    let status = child
+        .wait()
+        .with_context(|| format!("waiting for `{}`", display))?;
+    if !status.success() {
+        let code = status.code().unwrap_or(-1);
+        bail!("`{}` failed with status {}", display, code);
+    }
+    println!(
+        "Published {} release `{}`{}.",
+        provider.name(),
+        tag,
+        match assets.len() {
+            0 => String::new(),
+            count => format!(" with {} asset(s)", count),
+        }
+    );
+    Ok(())
+}
+
+/// Body of the `## ... <tag>` changelog section, without its heading.
+fn changelog_section(content: &str, tag: &str) -> Option<String> {
+    let mut lines = content.lines();
+    // Matches `## [v1.2.0] - 2026-01-02` as well as the older `## 2026-01-02 - v1.2.0`.
+    lines.find(|line| {
+        line.starts_with("## ") && line.split([' ', '[', ']']).any(|token| token == tag)
+    })?;
+    // The section ends at the next release or at the link reference definitions.
+    let body: Vec<&str> = lines
+        .take_while(|line| {
+            !line.starts_with("## ") && (!line.starts_with('[') || !line.contains("]: "))
+        })
+        .collect();
+    let body = body.join("\n").trim().to_string();
+    (!body.is_empty()).then_some(body)
+}
+
+/// Resolve asset paths; `*` may appear in the file name (not in directories).
+fn expand_assets(patterns: &[String]) -> Result<Vec<Utf8PathBuf>> {
+    let mut assets = Vec::new();
+    for pattern in patterns {
+        let path = Utf8PathBuf::from(pattern);
+        let name = path.file_name().unwrap_or_default();
+        if !name.contains('*') {
+            if !path.is_file() {
+                bail!("release asset `{}` does not exist; build it first", pattern);
+            }
+            assets.push(path);
+            continue;
+        }
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_str().is_empty() => dir.to_path_buf(),
+            _ => Utf8PathBuf::from("."),
+        };
+        let mut matched: Vec<Utf8PathBuf> = fs::read_dir(&dir)
+            .with_context(|| format!("reading {} for release asset `{}`", dir, pattern))?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|file| config::glob_matches(name, file))
+            .map(|file| dir.join(file))
+            .collect();
+        if matched.is_empty() {
+            bail!(
+                "release asset pattern `{}` matched no files; build them first",
+                pattern
+            );
+        }
+        matched.sort();
+        assets.append(&mut matched);
+    }
+    Ok(assets)
+}
+
+fn changelog_source(config: &DevConfig, flag: Option<ChangelogSource>) -> Result<ChangelogSource> {
+    if let Some(source) = flag {
+        return Ok(source);
+    }
+    match config
+        .git
+        .as_ref()
+        .and_then(|git| git.changelog_source.as_deref())
+    {
+        Some(name) => ChangelogSource::from_str(name, true).map_err(|_| {
+            anyhow!(
+                "unknown [git] changelog_source `{}` (expected commits or prs)",
+                name
+            )
+        }),
+        None => Ok(ChangelogSource::Commits),
+    }
+}
+
+fn collect_entries(
+    source: ChangelogSource,
+    range: &str,
+    paths: &[&str],
+) -> Result<Vec<changelog::Commit>> {
+    match source {
+        ChangelogSource::Commits => changelog::collect(range, paths),
+        ChangelogSource::Prs => {
+            let base = gitops::current_branch()?.ok_or_else(|| {
+                anyhow!("the `prs` changelog source needs a checked-out branch to find merged PRs")
+            })?;
+            changelog::collect_merged_prs(range, &base, paths)
+        }
+    }
+}
+
 fn changelog_path(config: &DevConfig) -> Result<Option<Utf8PathBuf>> {
     let cwd = Utf8PathBuf::from_path_buf(
         std::env::current_dir().context("determining current directory")?,
@@ -295,12 +1755,38 @@ fn git_add(paths: &[Utf8PathBuf], dry_run: bool) -> Result<()> {
     run_git(&args, dry_run)
 }
 
-fn git_commit(message: &str, dry_run: bool) -> Result<()> {
-    run_git(&["commit".into(), "-m".into(), message.into()], dry_run)
+fn git_commit(message: &str, sign: bool, dry_run: bool) -> Result<()> {
+    if !sign {
+        return run_git(&["commit".into(), "-m".into(), message.into()], dry_run);
+    }
+    run_git(
+        &["commit".into(), "-S".into(), "-m".into(), message.into()],
+        dry_run,
+    )
+    .context(gitops::SIGNING_HINT)
 }
 
-fn git_tag(tag: &str, dry_run: bool) -> Result<()> {
-    run_git(&["tag".into(), tag.into()], dry_run)
+/// Create an annotated tag whose message is `Release <tag>` followed by the version's
+/// changelog section, so `git tag -n` and the hosting UI show the release notes.
+fn git_tag(tag: &str, notes: Option<&str>, sign: bool, dry_run: bool) -> Result<()> {
+    let message = match notes {
+        Some(notes) => format!("Release {}\n\n{}", tag, notes.trim()),
+        None => format!("Release {}", tag),
+    };
+    let mut args: Vec<String> = vec!["tag".into(), if sign { "-s" } else { "-a" }.into()];
+    // `###` headings would be stripped as comments by the default cleanup mode.
+    args.extend([
+        "--cleanup=whitespace".into(),
+        tag.into(),
+        "-m".into(),
+        message,
+    ]);
+    let result = run_git(&args, dry_run);
+    if sign {
+        result.context(gitops::SIGNING_HINT)
+    } else {
+        result
+    }
 }
 
 fn run_git(args: &[String], dry_run: bool) -> Result<()> {
@@ -321,40 +1807,438 @@ fn run_git(args: &[String], dry_run: bool) -> Result<()> {
     Ok(())
 }
 
-fn collect_commits(range: &str) -> Result<Vec<String>> {
-    let output = Command::new("git")
-        .args(["log", range, "--pretty=format:%s"])
-        .output()
-        .with_context(|| format!("collecting commits for {}", range))?;
-    if !output.status.success() {
-        let code = output.status.code().unwrap_or(-1);
-        bail!("git log failed with status {}", code);
-    }
-    let commits = String::from_utf8_lossy(&output.stdout)
-        .lines()
-        .map(|line| line.trim().to_owned())
-        .filter(|line| !line.is_empty())
-        .collect();
-    Ok(commits)
-}
-
-fn latest_tag() -> Result<Option<String>> {
-    let output = Command::new("git")
-        .args(["describe", "--tags", "--abbrev=0"])
-        .output();
-    match output {
-        Ok(out) if out.status.success() => Ok(Some(
-            String::from_utf8_lossy(&out.stdout).trim().to_string(),
-        )),
-        _ => Ok(None),
-    }
-}
-
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 enum VersionFileKind {
     CargoToml,
     PyprojectToml,
     PackageJson,
+    /// A `[[git.version_patterns]]` file: `{version}` in the pattern marks the version.
+    Pattern(String),
 }
 
 const DEFAULT_BASE_BRANCH: &str = "release-candidate";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::BumpOptions;
+
+    #[test]
+    fn scope_prefers_project_tag_prefix() {
+        let config: DevConfig = toml::from_str(
+            r#"
+            [git]
+            tag_prefix = "release-"
+
+            [projects.web]
+            chdir = "apps/web"
+            tag_prefix = "web-v"
+
+            [projects.api]
+            chdir = "services/api"
+            "#,
+        )
+        .unwrap();
+        let version = Version::new(1, 2, 0);
+
+        let web = Scope::resolve(&config, Some("web")).unwrap();
+        assert_eq!(web.tag(&version), "web-v1.2.0");
+        assert_eq!(web.paths(), ["."]);
+
+        let api = Scope::resolve(&config, Some("api")).unwrap();
+        assert_eq!(api.tag(&version), "release-1.2.0");
+
+        let root = Scope::resolve(&config, None).unwrap();
+        assert_eq!(root.tag(&version), "release-1.2.0");
+        assert!(root.paths().is_empty());
+
+        let bare: DevConfig = toml::from_str("").unwrap();
+        assert_eq!(Scope::resolve(&bare, None).unwrap().tag(&version), "v1.2.0");
+    }
+
+    #[test]
+    fn tag_format_and_release_commit_templates() {
+        let config: DevConfig = toml::from_str(
+            r#"
+            [git]
+            tag_prefix = "ignored-"
+            tag_format = "{project}-v{version}-final"
+            release_commit = "chore(release): {version} ({tag})"
+
+            [projects.web]
+            chdir = "apps/web"
+            "#,
+        )
+        .unwrap();
+        let version = Version::new(1, 2, 0);
+        let web = Scope::resolve(&config, Some("web")).unwrap();
+        assert_eq!(web.tag(&version), "web-v1.2.0-final");
+        assert_eq!(web.tag_version("web-v1.2.0-final"), Some(version.clone()));
+        assert_eq!(web.tag_version("api-v1.2.0-final"), None);
+        assert_eq!(
+            release_message(&config, &web.tag(&version), &version),
+            "chore(release): 1.2.0 (web-v1.2.0-final)"
+        );
+        // `{project}` needs an active project.
+        assert!(Scope::resolve(&config, None).is_err());
+
+        let bare: DevConfig = toml::from_str("").unwrap();
+        assert_eq!(
+            release_message(&bare, "v1.2.0", &version),
+            "chore: release 1.2.0"
+        );
+        let broken: DevConfig = toml::from_str("[git]\ntag_format = \"release\"").unwrap();
+        assert!(Scope::resolve(&broken, None).is_err());
+    }
+
+    #[test]
+    fn changelog_section_for_tag() {
+        let content = "# Changelog\n\n## [Unreleased]\n\n## [v1.1.0] - 2026-01-02\n\n### Fixed\n\n- a\n\n\
+                       ## 2026-01-01 - v1.0.0\n\n- first\n\n\
+                       [Unreleased]: https://github.com/o/r/compare/v1.1.0...HEAD\n";
+        assert_eq!(
+            changelog_section(content, "v1.1.0").as_deref(),
+            Some("### Fixed\n\n- a")
+        );
+        assert_eq!(
+            changelog_section(content, "v1.0.0").as_deref(),
+            Some("- first")
+        );
+        assert_eq!(changelog_section(content, "v1.0"), None);
+        assert_eq!(changelog_section(content, "v2.0.0"), None);
+    }
+
+    #[test]
+    fn cargo_workspace_root_version() {
+        let mut doc: DocumentMut =
+            "[workspace]\nmembers = [\"a\"]\n\n[workspace.package]\nversion = \"0.3.0\"\n"
+                .parse()
+                .unwrap();
+        let kind = &VersionFileKind::CargoToml;
+        assert_eq!(current_version(&doc, kind).unwrap(), Version::new(0, 3, 0));
+        write_version(&mut doc, kind, &Version::new(0, 4, 0));
+        assert!(doc.get("package").is_none());
+        assert_eq!(current_version(&doc, kind).unwrap(), Version::new(0, 4, 0));
+
+        let empty: DocumentMut = "[workspace]\n".parse().unwrap();
+        assert!(current_version(&empty, kind).is_err());
+    }
+
+    #[test]
+    fn recommends_bump_from_commits() {
+        let commits = |subjects: &[&str]| -> Vec<changelog::Commit> {
+            subjects
+                .iter()
+                .map(|subject| changelog::Commit::parse(subject, ""))
+                .collect()
+        };
+        let stable = Version::new(1, 2, 3);
+        assert_eq!(recommend(&[], &stable), None);
+        assert_eq!(
+            recommend(&commits(&["fix: a", "docs: b"]), &stable),
+            Some(BumpLevel::Patch)
+        );
+        assert_eq!(
+            recommend(&commits(&["fix: a", "feat(env): b"]), &stable),
+            Some(BumpLevel::Minor)
+        );
+        assert_eq!(
+            recommend(&commits(&["feat!: drop flag"]), &stable),
+            Some(BumpLevel::Major)
+        );
+        assert_eq!(
+            recommend(&commits(&["feat!: drop flag"]), &Version::new(0, 4, 1)),
+            Some(BumpLevel::Minor)
+        );
+    }
+
+    #[test]
+    fn bumps_drop_build_metadata() {
+        let current = Version::parse("1.2.3-rc.1+abc123").unwrap();
+        assert_eq!(
+            increment_version(&current, BumpLevel::Patch).unwrap(),
+            Version::new(1, 2, 4)
+        );
+        assert_eq!(bump_prerelease(&current).unwrap().to_string(), "1.2.3-rc.2");
+        let doc: DocumentMut = "[package]\nversion = \"0.9.0+build.7\"\n".parse().unwrap();
+        let version = current_version(&doc, &VersionFileKind::CargoToml).unwrap();
+        assert_eq!(version.build.as_str(), "build.7");
+    }
+
+    #[test]
+    fn finds_versions_by_pattern() {
+        let chart = "apiVersion: v2\nversion: 0.4.0\nappVersion: \"1.2.3\"\n";
+        assert_eq!(
+            pattern_versions(chart, "appVersion: \"{version}\"").unwrap(),
+            vec![(3, "1.2.3".to_string())]
+        );
+        let readme = "Install devkit@1.2.3-rc.1.\n\nThen devkit@1.2.2 for old hosts.";
+        assert_eq!(
+            pattern_versions(readme, "devkit@{version}").unwrap(),
+            vec![(1, "1.2.3-rc.1".to_string()), (3, "1.2.2".to_string())]
+        );
+        assert!(pattern_versions(chart, "appVersion").is_err());
+    }
+
+    #[test]
+    fn pattern_locations_read_and_write() {
+        let kind = &VersionFileKind::Pattern(r#"__version__ = "{version}""#.to_string());
+        let mut doc = DocumentMut::new();
+        doc["__raw_text"] = value("\"\"\"Package.\"\"\"\n__version__ = \"0.3.1\"\n");
+        assert_eq!(current_version(&doc, kind).unwrap(), Version::new(0, 3, 1));
+        write_version(&mut doc, kind, &Version::new(0, 4, 0));
+        assert_eq!(
+            render_manifest(&doc, kind),
+            "\"\"\"Package.\"\"\"\n__version__ = \"0.4.0\"\n"
+        );
+
+        let gradle = &VersionFileKind::Pattern("version = '{version}'".to_string());
+        doc["__raw_text"] = value("group = 'x'\nversion = '1.2.0'\n");
+        assert_eq!(
+            current_version(&doc, gradle).unwrap(),
+            Version::new(1, 2, 0)
+        );
+        assert!(pattern_regex("version").is_err());
+    }
+
+    #[test]
+    fn publish_tools_follow_version_files() {
+        let files = vec![
+            (
+                Utf8PathBuf::from("/nowhere/Cargo.toml"),
+                VersionFileKind::CargoToml,
+            ),
+            (
+                Utf8PathBuf::from("/nowhere/web/package.json"),
+                VersionFileKind::PackageJson,
+            ),
+            (
+                Utf8PathBuf::from("/nowhere/py/pyproject.toml"),
+                VersionFileKind::PyprojectToml,
+            ),
+            (
+                Utf8PathBuf::from("/nowhere/VERSION"),
+                VersionFileKind::Pattern("{version}".into()),
+            ),
+        ];
+        assert_eq!(infer_publish_tools(&files), ["cargo", "npm", "uv"]);
+        let (manifest, steps) = publish_steps("twine").unwrap();
+        assert_eq!(manifest, "pyproject.toml");
+        assert_eq!(
+            steps,
+            [vec!["uv", "build"], vec!["twine", "upload", "dist/*"]]
+        );
+        assert!(publish_steps("gem").is_err());
+    }
+
+    #[test]
+    fn package_changelogs_skip_root_and_locations() {
+        let pattern = VersionFileKind::Pattern("{version}".into());
+        let files = [
+            (
+                Utf8Path::new("/repo/Cargo.toml"),
+                &VersionFileKind::CargoToml,
+            ),
+            (
+                Utf8Path::new("/repo/crates/core/Cargo.toml"),
+                &VersionFileKind::CargoToml,
+            ),
+            (
+                Utf8Path::new("/repo/web/package.json"),
+                &VersionFileKind::PackageJson,
+            ),
+            (Utf8Path::new("/repo/web/VERSION"), &pattern),
+            (Utf8Path::new("/repo/py/VERSION"), &pattern),
+        ];
+        assert_eq!(
+            package_dirs(Utf8Path::new("/repo"), &files),
+            [Utf8PathBuf::from("crates/core"), Utf8PathBuf::from("web")]
+        );
+    }
+
+    #[test]
+    fn required_ci_stops_at_the_first_failing_pipeline() {
+        let ran = std::cell::RefCell::new(Vec::new());
+        let tasks = |_: &[String]| Ok(());
+        let pipeline = |name: &str| {
+            ran.borrow_mut().push(name.to_string());
+            match name {
+                "test" => bail!("2 tests failed"),
+                _ => Ok(()),
+            }
+        };
+        let runner = Runner {
+            tasks: &tasks,
+            pipeline: &pipeline,
+        };
+        let required = ["lint".to_string(), "test".to_string(), "check".to_string()];
+        let err = run_required_ci(&required, &Version::new(1, 2, 0), &runner).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`test` pipeline failed; fix it before releasing 1.2.0"
+        );
+        assert_eq!(*ran.borrow(), ["lint", "test"]);
+    }
+
+    #[test]
+    fn remote_ci_needs_every_check_green() {
+        let check = |name: &str, result: &str| gitpr::Check {
+            name: name.to_string(),
+            result: result.to_string(),
+        };
+        let sha = "0123456789abcdef";
+        assert!(
+            remote_ci_verdict(sha, &[check("build", "passing"), check("docs", "skipped")]).is_ok()
+        );
+        let none = remote_ci_verdict(sha, &[]).unwrap_err().to_string();
+        assert!(none.contains("no CI checks reported"), "{none}");
+        let failing =
+            remote_ci_verdict(sha, &[check("build", "failing"), check("lint", "pending")])
+                .unwrap_err()
+                .to_string();
+        assert!(
+            failing.starts_with("CI checks for HEAD are failing"),
+            "{failing}"
+        );
+        assert!(failing.contains("pending  lint"), "{failing}");
+    }
+
+    #[test]
+    fn provenance_sums_assets_and_names_the_source_commit() {
+        let work = crate::testutil::repo_with_origin();
+        let _dir = crate::testutil::InDir::new(&work);
+        fs::create_dir_all(work.join("dist")).unwrap();
+        fs::write(work.join("dist/app.tar.gz"), "hello\n").unwrap();
+        fs::write(work.join("dist/notes.txt"), "skip\n").unwrap();
+        let config: DevConfig =
+            toml::from_str("[git]\nrelease_assets = [\"dist/*.tar.gz\"]\n").unwrap();
+        let path = work.join("RELEASE.md");
+
+        write_provenance(&config, &path, "v1.2.0", &Version::new(1, 2, 0), true).unwrap();
+        assert!(!path.exists());
+        write_provenance(&config, &path, "v1.2.0", &Version::new(1, 2, 0), false).unwrap();
+        let record = fs::read_to_string(&path).unwrap();
+        let head = crate::testutil::git(&work, &["rev-parse", "HEAD"]);
+        assert!(
+            record.contains(&format!("- Source commit: `{}`\n", head)),
+            "{record}"
+        );
+        assert!(
+            record.contains(
+                "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03  dist/app.tar.gz\n"
+            ),
+            "{record}"
+        );
+        assert!(!record.contains("notes.txt"));
+
+        fs::remove_file(work.join("dist/app.tar.gz")).unwrap();
+        let err = write_provenance(&config, &path, "v1.2.0", &Version::new(1, 2, 0), false)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("dist/*.tar.gz"), "{err}");
+    }
+
+    fn pr_bump(extra: &str, tasks: &dyn Fn(&[String]) -> Result<()>) -> Result<()> {
+        let config: DevConfig = toml::from_str(&format!(
+            "[git]\nprovider = \"nowhere\"\n\
+             version_patterns = [{{ file = \"VERSION\", pattern = \"{{version}}\" }}]\n{}",
+            extra
+        ))
+        .unwrap();
+        let scope = Scope::resolve(&config, None).unwrap();
+        let pipeline = |_: &str| Ok(());
+        let runner = Runner {
+            tasks,
+            pipeline: &pipeline,
+        };
+        let args = VersionBump {
+            level: Some(BumpLevel::Minor),
+            custom: None,
+            build: None,
+            strip_build: false,
+            options: BumpOptions {
+                no_changelog: true,
+                pr: true,
+                ..BumpOptions::default()
+            },
+        };
+        bump_version(&config, &scope, &args, false, &runner)
+    }
+
+    #[test]
+    fn pr_bump_creates_no_branch_when_writing_fails() {
+        let work = crate::testutil::repo_with_origin();
+        let _dir = crate::testutil::InDir::new(&work);
+        crate::testutil::commit_file(&work, "VERSION", "0.1.0\n");
+        let tasks = |_: &[String]| bail!("build broke");
+        let err = pr_bump("[git.hooks]\npost_write = [\"build\"]\n", &tasks).unwrap_err();
+        assert!(format!("{err:#}").contains("build broke"), "{err:#}");
+        let git = |args: &[&str]| crate::testutil::git(&work, args);
+        assert_eq!(git(&["branch", "--show-current"]), "main");
+        assert_eq!(git(&["branch", "--list", "release/*"]), "");
+        assert!(gitjournal::load().unwrap().is_empty());
+    }
+
+    #[test]
+    fn pr_bump_journals_the_pushed_branch_and_returns_to_base() {
+        let work = crate::testutil::repo_with_origin();
+        let _dir = crate::testutil::InDir::new(&work);
+        let base = crate::testutil::commit_file(&work, "VERSION", "0.1.0\n");
+        let tasks = |_: &[String]| Ok(());
+        // The unknown provider fails the PR step, after the push.
+        let err = pr_bump("", &tasks).unwrap_err();
+        assert!(
+            format!("{err:#}").contains("switched back to `main`"),
+            "{err:#}"
+        );
+
+        let git = |args: &[&str]| crate::testutil::git(&work, args);
+        assert_eq!(git(&["branch", "--show-current"]), "main");
+        assert_eq!(git(&["rev-parse", "main"]), base);
+        let tip = git(&["rev-parse", "release/v0.2.0"]);
+        assert_eq!(git(&["show", "release/v0.2.0:VERSION"]), "0.2.0");
+        assert_eq!(git(&["ls-remote", "origin", "release/v0.2.0"])[..40], tip);
+        let entries = gitjournal::load().unwrap();
+        assert_eq!(
+            entries.last().unwrap().actions,
+            [Action::BranchCreated {
+                branch: "release/v0.2.0".into(),
+                base: "main".into(),
+                tip,
+                previous: None,
+                pushed: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn project_tag_formats_resolve_only_for_tag_commands() {
+        let dir = crate::testutil::unique_temp_dir("version-scope");
+        let _dir = crate::testutil::InDir::new(&dir);
+        fs::write(dir.join("VERSION"), "1.4.0\n").unwrap();
+        let config: DevConfig = toml::from_str(
+            "[git]\ntag_format = \"{project}-v{version}\"\n\
+             version_patterns = [{ file = \"VERSION\", pattern = \"{version}\" }]\n",
+        )
+        .unwrap();
+        let tasks = |_: &[String]| Ok(());
+        let pipeline = |_: &str| Ok(());
+        let runner = Runner {
+            tasks: &tasks,
+            pipeline: &pipeline,
+        };
+        handle(&config, None, false, VersionCommand::Show, &runner).unwrap();
+        handle(&config, None, false, VersionCommand::Verify, &runner).unwrap();
+
+        let bump = VersionCommand::Bump(VersionBump {
+            level: Some(BumpLevel::Patch),
+            custom: None,
+            build: None,
+            strip_build: false,
+            options: BumpOptions::default(),
+        });
+        let err = handle(&config, None, true, bump, &runner).unwrap_err();
+        assert!(err.to_string().contains("no project is active"), "{err}");
+        assert_eq!(fs::read_to_string(dir.join("VERSION")).unwrap(), "1.4.0\n");
+    }
+}
@@ -1,14 +1,124 @@
-use anyhow::Result;
-use std::collections::HashSet;
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Condvar, Mutex};
+use std::thread;
 use std::time::SystemTime;
 
+use crate::devignore::DevIgnore;
+use crate::review::estimate_tokens;
+
 pub struct WalkOptions {
     pub max_depth: usize,
     pub include_content: bool,
     pub extensions: Option<Vec<String>>,
     pub ignore_hidden: bool,
+    /// Path globs (`src/**`, `*.rs`); when set, only matching files are listed.
+    pub include: Vec<String>,
+    /// Path globs of files and directories left out.
+    pub exclude: Vec<String>,
+    /// Only list files changed since an earlier manifest or git ref.
+    pub since: Option<Since>,
+    /// Hash every matched file into `Manifest::hashes`, for a later `--since`.
+    pub record_hashes: bool,
+    /// Prefix each line of embedded content with its line number.
+    pub line_numbers: bool,
+}
+
+/// Which files `--since` keeps.
+pub struct Since {
+    /// As given: the previous manifest's path or the git ref.
+    pub value: String,
+    changed: Changed,
+}
+
+enum Changed {
+    /// New, or with content other than these hashes, recorded with the previous manifest.
+    Hashes(BTreeMap<String, String>),
+    /// These paths, relative to the walked directory: changed or untracked per git.
+    Paths(HashSet<String>),
+}
+
+impl Since {
+    /// `value` is a previous manifest (or its hashes file), so anything whose content
+    /// differs from the hashes `--record-hashes` kept for it, or a git ref, so anything
+    /// committed, staged, modified or added since.
+    pub fn resolve(dir: &Path, value: &str) -> Result<Self> {
+        let path = Path::new(value);
+        if path.is_file() {
+            let hashes_file = if value.ends_with(HASHES_SUFFIX) { path.to_path_buf() } else { hashes_path(path) };
+            let text = fs::read_to_string(&hashes_file).with_context(|| {
+                format!("no file hashes recorded for {}; write it with `dev walk --record-hashes`", value)
+            })?;
+            let hashes = serde_json::from_str(&text)
+                .with_context(|| format!("{} is not a file hashes map", hashes_file.display()))?;
+            return Ok(Since {
+                value: value.to_string(),
+                changed: Changed::Hashes(hashes),
+            });
+        }
+
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .arg("-C")
+                .arg(dir)
+                .args(args)
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+        };
+        git(&["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", value)]).ok_or_else(|| {
+            anyhow!("--since `{}` is neither a manifest file nor a git ref in {}", value, dir.display())
+        })?;
+        let changed = git(&["diff", "--name-only", "-z", "--relative", value])
+            .with_context(|| format!("git diff against {} failed", value))?;
+        let untracked = git(&["ls-files", "--others", "--exclude-standard", "-z"])
+            .context("git ls-files failed")?;
+        Ok(Since {
+            value: value.to_string(),
+            changed: Changed::Paths(
+                changed
+                    .split('\0')
+                    .chain(untracked.split('\0'))
+                    .filter(|path| !path.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            ),
+        })
+    }
+
+    fn compares_hashes(&self) -> bool {
+        matches!(self.changed, Changed::Hashes(_))
+    }
+
+    /// `hash` is `None` when the file could not be read, which counts as changed.
+    fn keeps(&self, rel_path: &str, hash: Option<&str>) -> bool {
+        match &self.changed {
+            Changed::Hashes(hashes) => hash.is_none() || hashes.get(rel_path).map(String::as_str) != hash,
+            Changed::Paths(paths) => paths.contains(rel_path),
+        }
+    }
+}
+
+/// FNV-1a, 64-bit: enough to tell whether a file changed between two walks.
+fn content_hash(bytes: &[u8]) -> String {
+    let hash = bytes
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3));
+    format!("{:016x}", hash)
+}
+
+const HASHES_SUFFIX: &str = ".hashes.json";
+
+/// Where `--record-hashes` keeps the hashes for `manifest`: `manifest.md.hashes.json`.
+pub fn hashes_path(manifest: &Path) -> PathBuf {
+    let mut name = manifest.file_name().unwrap_or_default().to_os_string();
+    name.push(HASHES_SUFFIX);
+    manifest.with_file_name(name)
 }
 
 impl Default for WalkOptions {
@@ -18,6 +128,11 @@ impl Default for WalkOptions {
             include_content: true,
             extensions: None,
             ignore_hidden: true,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            since: None,
+            record_hashes: false,
+            line_numbers: false,
         }
     }
 }
@@ -85,6 +200,8 @@ fn get_ignore_patterns() -> HashSet<&'static str> {
     patterns.insert(".lock");
     patterns.insert("manifest.md");
     patterns.insert("manifest-2.md");
+    patterns.insert("manifest.json");
+    patterns.insert("manifest.yaml");
     // IDEs
     patterns.insert(".vscode");
     patterns.insert(".idea");
@@ -98,6 +215,21 @@ fn should_ignore(name: &str, ignore_hidden: bool, patterns: &HashSet<&str>) -> b
     patterns.iter().any(|pattern| name.contains(pattern))
 }
 
+/// The built-in name patterns plus the project's `.devignore`, whose rules win: a
+/// `!dist` line lists a `dist` directory the built-ins would skip.
+struct Ignore {
+    patterns: HashSet<&'static str>,
+    devignore: DevIgnore,
+}
+
+impl Ignore {
+    fn skips(&self, name: &str, rel_path: &str, is_dir: bool, ignore_hidden: bool) -> bool {
+        self.devignore
+            .decision(rel_path, is_dir)
+            .unwrap_or_else(|| should_ignore(name, ignore_hidden, &self.patterns))
+    }
+}
+
 fn format_timestamp(time: SystemTime) -> String {
     use std::time::UNIX_EPOCH;
     if let Ok(duration) = time.duration_since(UNIX_EPOCH) {
@@ -110,87 +242,1296 @@ fn format_timestamp(time: SystemTime) -> String {
     "unknown".to_string()
 }
 
-fn walk_directory(
-    path: &Path,
-    output: &mut String,
-    depth: usize,
-    opts: &WalkOptions,
-    patterns: &HashSet<&str>,
-) -> Result<()> {
-    if depth >= opts.max_depth {
-        return Ok(());
+fn rfc3339<S: Serializer>(time: &Option<SystemTime>, serializer: S) -> Result<S::Ok, S::Error> {
+    match time {
+        Some(time) => serializer.serialize_str(
+            &chrono::DateTime::<chrono::Utc>::from(*time)
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        ),
+        None => serializer.serialize_none(),
     }
+}
 
-    let indent = "  ".repeat(depth);
-    
+/// A scanned directory tree, rendered as Markdown, JSON or YAML.
+#[derive(Serialize)]
+pub struct Manifest {
+    /// The walked directory as given, which Markdown file paths are shown under.
+    #[serde(skip)]
+    pub dir: PathBuf,
+    pub root: String,
+    /// The `--since` manifest or ref, when only changed files are listed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+    /// What `fit_budget` cut to fit `--max-tokens`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub budget: Option<Budget>,
+    /// Which of the `--split-size` parts this is.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub part: Option<Part>,
+    pub entries: Vec<Entry>,
+    /// With `record_hashes`, the content hash of every file the walk matched, listed or
+    /// left out by `--since`, by path. Written beside the manifest, never in it.
+    #[serde(skip)]
+    pub hashes: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Part {
+    pub index: usize,
+    pub count: usize,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Budget {
+    pub max_tokens: usize,
+    /// Files whose content was dropped.
+    pub omitted: Vec<String>,
+    /// Files whose content was cut short.
+    pub truncated: Vec<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Entry {
+    pub name: String,
+    /// Relative to the walked directory, `/`-separated.
+    pub path: String,
+    #[serde(rename = "type")]
+    pub kind: EntryKind,
+    /// Bytes, for files.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(serialize_with = "rfc3339")]
+    pub modified: Option<SystemTime>,
+    /// Estimated LLM tokens in the file's full content, when it is included.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<usize>,
+    /// The file's text, when contents are included and its extension is selected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// Set when the content was dropped or cut short to fit `--max-tokens`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trimmed: Option<Trimmed>,
+    /// A directory's entries; `None` for files.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub children: Option<Vec<Entry>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    Dir,
+    File,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trimmed {
+    Omitted,
+    Truncated,
+}
+
+fn any_matches(patterns: &[String], path: &str, is_dir: bool) -> bool {
+    patterns
+        .iter()
+        .any(|pattern| crate::config::path_glob_matches(pattern, path, is_dir))
+}
+
+/// Whether `--include` selects a file: it matches itself, or one of its directories does.
+fn is_included(opts: &WalkOptions, rel_path: &str) -> bool {
+    opts.include.is_empty()
+        || any_matches(&opts.include, rel_path, false)
+        || rel_path
+            .match_indices('/')
+            .any(|(index, _)| any_matches(&opts.include, &rel_path[..index], true))
+}
+
+/// Most directories read at once; walks are disk- rather than CPU-bound, and more
+/// threads mostly add contention.
+const MAX_WORKERS: usize = 8;
+
+/// One directory's entries, sorted by name, with subdirectories not yet filled in.
+/// One directory's entries, with the content hashes of its files that passed the filters,
+/// whether or not `--since` kept them, when those are recorded.
+struct Listing {
+    entries: Vec<Entry>,
+    hashes: Vec<(String, String)>,
+}
+
+fn read_listing(
+    path: &Path,
+    prefix: &str,
+    opts: &WalkOptions,
+    ignore: &Ignore,
+) -> Result<Listing> {
     let mut entries: Vec<_> = fs::read_dir(path)?
         .filter_map(|e| e.ok())
         .collect();
     
     entries.sort_by_key(|e| e.file_name());
 
+    let mut scanned = Vec::new();
+    let mut hashes = Vec::new();
     for entry in entries {
         let file_name = entry.file_name();
-        let name = file_name.to_string_lossy();
+        let name = file_name.to_string_lossy().into_owned();
+        let rel_path = format!("{}{}", prefix, name);
+        let is_dir = entry.file_type().is_ok_and(|kind| kind.is_dir());
         
-        if should_ignore(&name, opts.ignore_hidden, patterns) {
+        if ignore.skips(&name, &rel_path, is_dir, opts.ignore_hidden) {
             continue;
         }
 
         let entry_path = entry.path();
         let metadata = entry.metadata()?;
+        let modified = metadata.modified().ok();
+        if any_matches(&opts.exclude, &rel_path, metadata.is_dir()) {
+            continue;
+        }
 
         if metadata.is_dir() {
-            output.push_str(&format!("{}- 📁 **{}/**\n", indent, name));
-            walk_directory(&entry_path, output, depth + 1, opts, patterns)?;
+            scanned.push(Entry {
+                name,
+                path: rel_path,
+                kind: EntryKind::Dir,
+                size: None,
+                modified,
+                tokens: None,
+                content: None,
+                trimmed: None,
+                children: Some(Vec::new()),
+            });
         } else {
-            output.push_str(&format!("{}- 📄 **{}**\n", indent, name));
+            if !is_included(opts, &rel_path) {
+                continue;
+            }
+            let ext = entry_path.extension()
+                .and_then(|e| e.to_str())
+                .map(|e| format!(".{}", e));
             
-            if opts.include_content {
-                let ext = entry_path.extension()
-                    .and_then(|e| e.to_str())
-                    .map(|e| format!(".{}", e));
-                
-                let should_include = if let Some(ref exts) = opts.extensions {
-                    ext.as_ref().map_or(false, |e| exts.contains(e))
-                } else {
-                    true
+            let should_include = if let Some(ref exts) = opts.extensions {
+                ext.as_ref().map_or(false, |e| exts.contains(e))
+            } else {
+                true
+            };
+            let embeds = opts.include_content && should_include;
+            let compares = opts.since.as_ref().is_some_and(Since::compares_hashes);
+            // Files are read only for content that is embedded or hashed.
+            let bytes = if embeds || compares || opts.record_hashes { fs::read(&entry_path).ok() } else { None };
+            let hash = bytes.as_deref().filter(|_| compares || opts.record_hashes).map(content_hash);
+            if let Some(hash) = hash.as_ref().filter(|_| opts.record_hashes) {
+                hashes.push((rel_path.clone(), hash.clone()));
+            }
+            if opts.since.as_ref().is_some_and(|since| !since.keeps(&rel_path, hash.as_deref())) {
+                continue;
+            }
+            let content = if embeds {
+                bytes
+                    .and_then(|bytes| String::from_utf8(bytes).ok())
+                    .map(|content| if opts.line_numbers { number_lines(&content) } else { content })
+            } else {
+                None
+            };
+
+            scanned.push(Entry {
+                name,
+                path: rel_path,
+                kind: EntryKind::File,
+                size: Some(metadata.len()),
+                modified,
+                tokens: content.as_deref().map(estimate_tokens),
+                content,
+                trimmed: None,
+                children: None,
+            });
+        }
+    }
+
+    Ok(Listing {
+        entries: scanned,
+        hashes,
+    })
+}
+
+/// `content` with each line led by its number, right-aligned in a gutter: `  7 | let x = 1;`.
+fn number_lines(content: &str) -> String {
+    let width = content.lines().count().to_string().len();
+    let mut numbered = String::with_capacity(content.len() + (width + 3) * content.lines().count());
+    for (index, line) in content.lines().enumerate() {
+        if line.is_empty() {
+            numbered.push_str(&format!("{:>width$} |\n", index + 1, width = width));
+        } else {
+            numbered.push_str(&format!("{:>width$} | {}\n", index + 1, line, width = width));
+        }
+    }
+    if !content.ends_with('\n') {
+        numbered.pop();
+    }
+    numbered
+}
+
+/// Directories waiting to be read, and how many workers are reading one.
+struct Queue {
+    dirs: Vec<(PathBuf, String, usize)>,
+    busy: usize,
+}
+
+/// Reads every directory under `dir` within `max_depth` on a pool of worker threads,
+/// keyed by their `/`-terminated relative path (`""` for `dir` itself).
+fn read_listings(
+    dir: &Path,
+    opts: &WalkOptions,
+    ignore: &Ignore,
+) -> HashMap<String, Result<Listing>> {
+    let workers = thread::available_parallelism().map_or(1, |n| n.get()).min(MAX_WORKERS);
+    let queue = Mutex::new(Queue {
+        dirs: if opts.max_depth > 1 { vec![(dir.to_path_buf(), String::new(), 1)] } else { Vec::new() },
+        busy: 0,
+    });
+    let changed = Condvar::new();
+    let listings = Mutex::new(HashMap::new());
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let (path, prefix, depth) = {
+                    let mut queue = queue.lock().unwrap();
+                    loop {
+                        if let Some(job) = queue.dirs.pop() {
+                            queue.busy += 1;
+                            break job;
+                        }
+                        // Nothing queued and nobody left to queue more: done.
+                        if queue.busy == 0 {
+                            return;
+                        }
+                        queue = changed.wait(queue).unwrap();
+                    }
                 };
 
-                if should_include {
-                    if let Ok(content) = fs::read_to_string(&entry_path) {
-                        let size = metadata.len();
-                        let modified = metadata.modified()
-                            .map(format_timestamp)
-                            .unwrap_or_else(|_| "unknown".to_string());
-                        
-                        output.push_str(&format!("\n{}  📄 *File Path*: `{}`\n", indent, entry_path.display()));
-                        output.push_str(&format!("{}  *Size*: {} bytes | *Modified*: {}\n\n", indent, size, modified));
-                        output.push_str(&format!("{}  ```\n", indent));
-                        for line in content.lines() {
-                            output.push_str(&format!("{}  {}\n", indent, line));
+                let listing = read_listing(&path, &prefix, opts, ignore);
+                {
+                    let mut queue = queue.lock().unwrap();
+                    if let Ok(listing) = &listing
+                        && depth + 1 < opts.max_depth
+                    {
+                        for entry in listing.entries.iter().filter(|entry| entry.kind == EntryKind::Dir) {
+                            queue.dirs.push((path.join(&entry.name), format!("{}/", entry.path), depth + 1));
                         }
-                        output.push_str(&format!("{}  ```\n\n", indent));
                     }
+                    queue.busy -= 1;
+                    changed.notify_all();
                 }
+                listings.lock().unwrap().insert(prefix, listing);
+            });
+        }
+    });
+
+    listings.into_inner().unwrap()
+}
+
+/// Nests the listings under `prefix` into a tree, in name order whatever order the
+/// workers finished in, collecting their hashes into `hashes`.
+fn assemble(
+    prefix: &str,
+    depth: usize,
+    listings: &mut HashMap<String, Result<Listing>>,
+    opts: &WalkOptions,
+    hashes: &mut BTreeMap<String, String>,
+) -> Result<Vec<Entry>> {
+    let Some(listing) = listings.remove(prefix) else {
+        return Ok(Vec::new());
+    };
+    let listing = listing?;
+    hashes.extend(listing.hashes);
+    let mut entries = Vec::new();
+    for mut entry in listing.entries {
+        if entry.kind == EntryKind::Dir {
+            let children = assemble(&format!("{}/", entry.path), depth + 1, listings, opts, hashes)?;
+            // Directories the globs filtered empty are noise; ones at the depth limit
+            // were never looked into.
+            let filtered = !opts.include.is_empty() || !opts.exclude.is_empty() || opts.since.is_some();
+            if filtered && children.is_empty() && depth + 1 < opts.max_depth {
+                continue;
             }
+            entry.children = Some(children);
         }
+        entries.push(entry);
     }
-
-    Ok(())
+    Ok(entries)
 }
 
-pub fn generate_manifest(dir: &Path, opts: WalkOptions) -> Result<String> {
-    let mut output = String::from("# Directory Structure\n\n");
-    
-    let dir_name = dir.file_name()
+/// Walks `dir` into a `Manifest`, honouring its `.devignore` and the depth, hidden-file
+/// and content options.
+pub fn scan(dir: &Path, opts: &WalkOptions) -> Result<Manifest> {
+    let root = dir.file_name()
         .and_then(|n| n.to_str())
-        .unwrap_or(".");
-    
-    output.push_str(&format!("- 📁 **{}/**\n", dir_name));
+        .unwrap_or(".")
+        .to_string();
     
-    let patterns = get_ignore_patterns();
-    walk_directory(dir, &mut output, 1, &opts, &patterns)?;
+    let ignore = Ignore {
+        patterns: get_ignore_patterns(),
+        devignore: DevIgnore::find(dir)?,
+    };
+    let mut listings = read_listings(dir, opts, &ignore);
+    let mut hashes = BTreeMap::new();
+    let entries = assemble("", 1, &mut listings, opts, &mut hashes)?;
     
-    Ok(output)
+    Ok(Manifest {
+        dir: dir.to_path_buf(),
+        root,
+        since: opts.since.as_ref().map(|since| since.value.clone()),
+        budget: None,
+        part: None,
+        entries,
+        hashes,
+    })
+}
+
+fn files_with_content<'a>(entries: &'a mut [Entry], files: &mut Vec<&'a mut Entry>) {
+    for entry in entries {
+        if entry.kind == EntryKind::Dir {
+            files_with_content(entry.children.as_deref_mut().unwrap_or_default(), files);
+        } else if entry.content.is_some() {
+            files.push(entry);
+        }
+    }
+}
+
+fn trimmed_paths(entries: &[Entry], budget: &mut Budget) {
+    for entry in entries {
+        match entry.trimmed {
+            Some(Trimmed::Omitted) => budget.omitted.push(entry.path.clone()),
+            Some(Trimmed::Truncated) => budget.truncated.push(entry.path.clone()),
+            None => {}
+        }
+        trimmed_paths(entry.children.as_deref().unwrap_or_default(), budget);
+    }
+}
+
+/// The leading lines of `content` that fit in `tokens`.
+fn truncate_to(content: &str, tokens: usize) -> String {
+    let mut room = tokens * 4;
+    let mut kept = String::new();
+    for line in content.split_inclusive('\n') {
+        let len = line.chars().count();
+        if len > room {
+            break;
+        }
+        room -= len;
+        kept.push_str(line);
+    }
+    kept
+}
+
+/// Cuts file contents, largest first, until `render` is at most `max_tokens`: a file
+/// much larger than the overflow is truncated, anything else is dropped whole.
+/// Overhead the estimate misses (indentation, escaping) is caught by re-rendering.
+pub fn fit_budget(
+    manifest: &mut Manifest,
+    max_tokens: usize,
+    render: impl Fn(&Manifest) -> Result<String>,
+) -> Result<()> {
+    manifest.budget = Some(Budget {
+        max_tokens,
+        ..Budget::default()
+    });
+    loop {
+        let tokens = estimate_tokens(&render(manifest)?);
+        if tokens <= max_tokens {
+            break;
+        }
+        let mut files = Vec::new();
+        files_with_content(&mut manifest.entries, &mut files);
+        // The overflow in content tokens: the rendering adds its overhead on top.
+        let content: usize = files
+            .iter()
+            .map(|file| file.content.as_deref().map_or(0, estimate_tokens))
+            .sum();
+        let mut overflow = ((tokens - max_tokens) * content).div_ceil(tokens).max(1);
+        if files.is_empty() {
+            bail!(
+                "the directory tree alone is ~{} tokens, over --max-tokens {}; lower --max-depth or raise the budget",
+                tokens,
+                max_tokens
+            );
+        }
+        files.sort_by_key(|file| std::cmp::Reverse(file.content.as_deref().map_or(0, estimate_tokens)));
+        for file in files {
+            let size = file.content.as_deref().map_or(0, estimate_tokens);
+            if size > overflow * 2 {
+                file.content = file.content.as_deref().map(|content| truncate_to(content, size - overflow));
+                file.trimmed = Some(Trimmed::Truncated);
+                break;
+            }
+            file.content = None;
+            file.trimmed = Some(Trimmed::Omitted);
+            overflow = overflow.saturating_sub(size);
+            if overflow == 0 {
+                break;
+            }
+        }
+        // Refresh the summary, which is part of what gets rendered.
+        let mut budget = Budget {
+            max_tokens,
+            ..Budget::default()
+        };
+        trimmed_paths(&manifest.entries, &mut budget);
+        manifest.budget = Some(budget);
+    }
+    Ok(())
+}
+
+/// `--split-size`: the most each part of a split manifest may hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SplitSize {
+    Bytes(usize),
+    /// Estimated tokens, four characters each.
+    Tokens(usize),
+}
+
+impl SplitSize {
+    /// Bytes by default (`200000`, `200k`, `2mb`), or tokens with a `t`/`tokens`
+    /// suffix (`8000t`, `8k tokens`). `k` and `m` are thousands and millions.
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let invalid = || format!("expected a size like 200k (bytes) or 8000t (tokens), got `{}`", raw);
+        let value = raw.trim().to_ascii_lowercase();
+        let digits = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+        let number: usize = value[..digits].parse().map_err(|_| invalid())?;
+        let unit = value[digits..].trim_start();
+        let (multiplier, unit) = match unit.strip_prefix('k') {
+            Some(unit) => (1_000, unit),
+            None => match unit.strip_prefix('m') {
+                Some(unit) => (1_000_000, unit),
+                None => (1, unit),
+            },
+        };
+        let size = number.checked_mul(multiplier).ok_or_else(invalid)?;
+        if size == 0 {
+            return Err("the split size must be at least 1".to_string());
+        }
+        match unit.trim_start() {
+            "" | "b" | "bytes" => Ok(SplitSize::Bytes(size)),
+            "t" | "tokens" => Ok(SplitSize::Tokens(size)),
+            _ => Err(invalid()),
+        }
+    }
+
+    pub fn limit(self) -> usize {
+        match self {
+            SplitSize::Bytes(limit) | SplitSize::Tokens(limit) => limit,
+        }
+    }
+
+    pub fn measure(self, text: &str) -> usize {
+        match self {
+            SplitSize::Bytes(_) => text.len(),
+            SplitSize::Tokens(_) => estimate_tokens(text),
+        }
+    }
+}
+
+impl std::fmt::Display for SplitSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SplitSize::Bytes(limit) => write!(f, "{} bytes", limit),
+            SplitSize::Tokens(limit) => write!(f, "{} tokens", limit),
+        }
+    }
+}
+
+/// Files and empty directories, in the order they are rendered: what a split divides up.
+fn leaf_paths<'a>(entries: &'a [Entry], leaves: &mut Vec<&'a Entry>) {
+    for entry in entries {
+        match entry.children.as_deref() {
+            Some(children) if !children.is_empty() => leaf_paths(children, leaves),
+            _ => leaves.push(entry),
+        }
+    }
+}
+
+/// `entries` cut down to `keep`, with the directories leading to them.
+fn entries_with(entries: &[Entry], keep: &HashSet<&str>) -> Vec<Entry> {
+    entries
+        .iter()
+        .filter_map(|entry| match entry.children.as_deref() {
+            Some(children) if !children.is_empty() => {
+                let children = entries_with(children, keep);
+                (!children.is_empty()).then(|| Entry {
+                    children: Some(children),
+                    ..entry.clone()
+                })
+            }
+            _ => keep.contains(entry.path.as_str()).then(|| entry.clone()),
+        })
+        .collect()
+}
+
+impl Manifest {
+    /// Part `index` of `count`, holding just `leaves`; the budget summary keeps to them too.
+    fn part(&self, leaves: &[&Entry], index: usize, count: usize) -> Manifest {
+        let keep: HashSet<&str> = leaves.iter().map(|leaf| leaf.path.as_str()).collect();
+        let budget = self.budget.as_ref().map(|budget| Budget {
+            max_tokens: budget.max_tokens,
+            omitted: budget.omitted.iter().filter(|path| keep.contains(path.as_str())).cloned().collect(),
+            truncated: budget.truncated.iter().filter(|path| keep.contains(path.as_str())).cloned().collect(),
+        });
+        Manifest {
+            dir: self.dir.clone(),
+            root: self.root.clone(),
+            since: self.since.clone(),
+            budget,
+            part: Some(Part { index, count }),
+            entries: entries_with(&self.entries, &keep),
+            hashes: BTreeMap::new(),
+        }
+    }
+}
+
+/// Splits the manifest into parts that each render within `size`, in tree order and
+/// never in the middle of a file; a file too large on its own gets a part to itself.
+pub fn split(
+    manifest: &Manifest,
+    size: SplitSize,
+    render: impl Fn(&Manifest) -> Result<String>,
+) -> Result<Vec<Manifest>> {
+    let mut leaves = Vec::new();
+    leaf_paths(&manifest.entries, &mut leaves);
+    let measure = |leaves: &[&Entry]| -> Result<usize> { Ok(size.measure(&render(&manifest.part(leaves, 1, 1))?)) };
+
+    // Pack greedily, costing each file as what it adds after the one before it, which
+    // shares most of its directories.
+    let mut groups = Vec::new();
+    let mut start = 0;
+    let mut used = 0;
+    for index in 0..leaves.len() {
+        if index == start {
+            used = measure(&leaves[index..=index])?;
+            continue;
+        }
+        let pair = &leaves[index - 1..=index];
+        let cost = measure(pair)?.saturating_sub(measure(&pair[..1])?);
+        if used + cost > size.limit() {
+            groups.push(start..index);
+            start = index;
+            used = measure(&leaves[index..=index])?;
+        } else {
+            used += cost;
+        }
+    }
+    groups.push(start..leaves.len());
+
+    // The costs are estimates, and part numbers grow with the count: halve any part
+    // that still renders over the limit until none do.
+    loop {
+        let count = groups.len();
+        let parts: Vec<Manifest> = groups
+            .iter()
+            .enumerate()
+            .map(|(index, group)| manifest.part(&leaves[group.clone()], index + 1, count))
+            .collect();
+        let mut next = Vec::new();
+        for (group, part) in groups.iter().zip(&parts) {
+            if group.len() > 1 && size.measure(&render(part)?) > size.limit() {
+                let middle = group.start + group.len() / 2;
+                next.push(group.start..middle);
+                next.push(middle..group.end);
+            } else {
+                next.push(group.clone());
+            }
+        }
+        if next.len() == count {
+            return Ok(parts);
+        }
+        groups = next;
+    }
+}
+
+/// The file written in place of a split manifest, listing its parts.
+#[derive(Serialize)]
+pub struct Index {
+    pub root: String,
+    pub split_size: SplitSize,
+    pub parts: Vec<IndexPart>,
+}
+
+#[derive(Serialize)]
+pub struct IndexPart {
+    /// The part's file name, next to the index.
+    pub file: String,
+    pub files: usize,
+    /// The part's first and last paths, in tree order.
+    pub first: String,
+    pub last: String,
+    pub bytes: usize,
+    pub tokens: usize,
+}
+
+impl Index {
+    /// `parts` with their file names and rendered text.
+    pub fn new(manifest: &Manifest, split_size: SplitSize, parts: &[(String, &Manifest, String)]) -> Self {
+        let parts = parts
+            .iter()
+            .map(|(file, part, text)| {
+                let mut leaves = Vec::new();
+                leaf_paths(&part.entries, &mut leaves);
+                let path = |leaf: Option<&&Entry>| leaf.map(|leaf| leaf.path.clone()).unwrap_or_default();
+                IndexPart {
+                    file: file.clone(),
+                    files: leaves.iter().filter(|leaf| leaf.kind == EntryKind::File).count(),
+                    first: path(leaves.first()),
+                    last: path(leaves.last()),
+                    bytes: text.len(),
+                    tokens: estimate_tokens(text),
+                }
+            })
+            .collect();
+        Index {
+            root: manifest.root.clone(),
+            split_size,
+            parts,
+        }
+    }
+
+    pub fn render_markdown(&self) -> String {
+        let mut output = format!(
+            "# Directory Structure (index)\n\n`{}/` is split into {} parts of at most {} each:\n\n",
+            self.root,
+            self.parts.len(),
+            self.split_size
+        );
+        for (index, part) in self.parts.iter().enumerate() {
+            let range = if part.first == part.last {
+                format!("`{}`", part.first)
+            } else {
+                format!("`{}` to `{}`", part.first, part.last)
+            };
+            output.push_str(&format!(
+                "{}. [{}]({}): {} file(s), {} (~{} tokens)\n",
+                index + 1,
+                part.file,
+                part.file,
+                part.files,
+                range,
+                part.tokens
+            ));
+        }
+        output
+    }
+
+    pub fn render_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)? + "\n")
+    }
+
+    pub fn render_yaml(&self) -> String {
+        let (unit, limit) = match self.split_size {
+            SplitSize::Bytes(limit) => ("bytes", limit),
+            SplitSize::Tokens(limit) => ("tokens", limit),
+        };
+        let mut output = format!("root: {}\nsplit_size:\n  {}: {}\nparts:\n", yaml_string(&self.root), unit, limit);
+        for part in &self.parts {
+            output.push_str(&format!("  - file: {}\n", yaml_string(&part.file)));
+            output.push_str(&format!("    files: {}\n", part.files));
+            output.push_str(&format!("    first: {}\n", yaml_string(&part.first)));
+            output.push_str(&format!("    last: {}\n", yaml_string(&part.last)));
+            output.push_str(&format!("    bytes: {}\n", part.bytes));
+            output.push_str(&format!("    tokens: {}\n", part.tokens));
+        }
+        output
+    }
+}
+
+/// The part files listed by a split index `Index` wrote, in any of its formats; `None`
+/// when `text` is not such an index.
+pub fn index_parts(text: &str) -> Option<Vec<String>> {
+    if text.trim_start().starts_with('{') {
+        let index: serde_json::Value = serde_json::from_str(text).ok()?;
+        index.get("split_size")?;
+        let parts = index.get("parts")?.as_array()?;
+        return Some(parts.iter().filter_map(|part| Some(part.get("file")?.as_str()?.to_string())).collect());
+    }
+    if let Some(list) = text.strip_prefix("# Directory Structure (index)\n") {
+        return Some(
+            list.lines()
+                .filter_map(|line| line.split_once(". [")?.1.split_once("](").map(|(file, _)| file.to_string()))
+                .collect(),
+        );
+    }
+    if text.lines().any(|line| line == "split_size:") {
+        return Some(
+            text.lines()
+                .filter_map(|line| line.strip_prefix("  - file: "))
+                .filter_map(|file| serde_json::from_str(file).ok())
+                .collect(),
+        );
+    }
+    None
+}
+
+/// The code fence language for a file, by name or extension; `None` leaves the fence bare.
+pub(crate) fn fence_language(name: &str) -> Option<&'static str> {
+    match name {
+        "Dockerfile" | "Containerfile" => return Some("dockerfile"),
+        "Makefile" | "GNUmakefile" => return Some("makefile"),
+        "CMakeLists.txt" => return Some("cmake"),
+        "Justfile" | "justfile" => return Some("just"),
+        _ => {}
+    }
+    let (_, extension) = name.rsplit_once('.')?;
+    Some(match extension.to_ascii_lowercase().as_str() {
+        "rs" => "rust",
+        "py" | "pyi" => "python",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "jsx",
+        "ts" | "mts" | "cts" => "typescript",
+        "tsx" => "tsx",
+        "json" => "json",
+        "toml" => "toml",
+        "yaml" | "yml" => "yaml",
+        "md" | "markdown" => "markdown",
+        "sh" | "bash" => "bash",
+        "zsh" => "zsh",
+        "fish" => "fish",
+        "ps1" => "powershell",
+        "html" | "htm" => "html",
+        "css" => "css",
+        "scss" => "scss",
+        "sql" => "sql",
+        "go" => "go",
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "swift" => "swift",
+        "c" | "h" => "c",
+        "cpp" | "cc" | "cxx" | "hpp" | "hh" => "cpp",
+        "cs" => "csharp",
+        "rb" => "ruby",
+        "php" => "php",
+        "lua" => "lua",
+        "dart" => "dart",
+        "scala" => "scala",
+        "ex" | "exs" => "elixir",
+        "hs" => "haskell",
+        "zig" => "zig",
+        "nix" => "nix",
+        "xml" | "svg" => "xml",
+        "vue" => "vue",
+        "svelte" => "svelte",
+        "proto" => "protobuf",
+        "graphql" | "gql" => "graphql",
+        "tf" | "hcl" => "hcl",
+        "ini" | "cfg" => "ini",
+        "diff" | "patch" => "diff",
+        _ => return None,
+    })
+}
+
+fn markdown_entries(manifest: &Manifest, entries: &[Entry], depth: usize, output: &mut String) {
+    let indent = "  ".repeat(depth);
+    for entry in entries {
+        match entry.kind {
+            EntryKind::Dir => {
+                output.push_str(&format!("{}- 📁 **{}/**\n", indent, entry.name));
+                markdown_entries(manifest, entry.children.as_deref().unwrap_or_default(), depth + 1, output);
+            }
+            EntryKind::File => {
+                output.push_str(&format!("{}- 📄 **{}**\n", indent, entry.name));
+                if entry.trimmed == Some(Trimmed::Omitted) {
+                    output.push_str(&format!(
+                        "{}  _Content omitted (~{} tokens) to fit the token budget._\n",
+                        indent,
+                        entry.tokens.unwrap_or(0)
+                    ));
+                }
+                if let Some(content) = &entry.content {
+                    let modified = entry.modified
+                        .map(format_timestamp)
+                        .unwrap_or_else(|| "unknown".to_string());
+                    
+                    output.push_str(&format!("\n{}  📄 *File Path*: `{}`\n", indent, manifest.dir.join(&entry.path).display()));
+                    output.push_str(&format!(
+                        "{}  *Size*: {} bytes | *Tokens*: ~{} | *Modified*: {}\n\n",
+                        indent,
+                        entry.size.unwrap_or(0),
+                        entry.tokens.unwrap_or(0),
+                        modified
+                    ));
+                    output.push_str(&format!("{}  ```{}\n", indent, fence_language(&entry.name).unwrap_or_default()));
+                    for line in content.lines() {
+                        output.push_str(&format!("{}  {}\n", indent, line));
+                    }
+                    output.push_str(&format!("{}  ```\n\n", indent));
+                    if entry.trimmed == Some(Trimmed::Truncated) {
+                        output.push_str(&format!(
+                            "{}  _Truncated to ~{} of ~{} tokens to fit the token budget._\n\n",
+                            indent,
+                            estimate_tokens(content),
+                            entry.tokens.unwrap_or(0)
+                        ));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The Markdown manifest: a nested list of the tree with file contents in code blocks.
+pub fn render_markdown(manifest: &Manifest) -> String {
+    let mut output = match manifest.part {
+        Some(part) => format!("# Directory Structure (part {} of {})\n\n", part.index, part.count),
+        None => String::from("# Directory Structure\n\n"),
+    };
+    if let Some(since) = &manifest.since {
+        output.push_str(&format!("_Only files added or changed since `{}`._\n\n", since));
+    }
+    if let Some(budget) = &manifest.budget {
+        output.push_str(&budget_note(budget));
+    }
+    output.push_str(&format!("- 📁 **{}/**\n", manifest.root));
+    markdown_entries(manifest, &manifest.entries, 1, &mut output);
+    output
+}
+
+fn budget_note(budget: &Budget) -> String {
+    if budget.omitted.is_empty() && budget.truncated.is_empty() {
+        return String::new();
+    }
+    let list = |paths: &[String]| {
+        paths
+            .iter()
+            .map(|path| format!("`{}`", path))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mut note = format!("_To fit {} tokens:", budget.max_tokens);
+    if !budget.omitted.is_empty() {
+        note.push_str(&format!(" contents omitted for {}.", list(&budget.omitted)));
+    }
+    if !budget.truncated.is_empty() {
+        note.push_str(&format!(" Truncated {}.", list(&budget.truncated)));
+    }
+    note + "_\n\n"
+}
+
+/// `{root, since, budget, part, entries}`, each entry with `name`, `path`, `type` (`dir`/`file`), `size`,
+/// `modified` (RFC 3339), `content` and `children`.
+pub fn render_json(manifest: &Manifest) -> Result<String> {
+    Ok(serde_json::to_string_pretty(manifest)? + "\n")
+}
+
+/// Scalars are written as JSON strings, which are valid YAML double-quoted scalars.
+fn yaml_string(value: &str) -> String {
+    serde_json::Value::String(value.to_string()).to_string()
+}
+
+fn yaml_entries(entries: &[Entry], depth: usize, output: &mut String) {
+    let indent = "  ".repeat(depth);
+    for entry in entries {
+        output.push_str(&format!("{}- name: {}\n", indent, yaml_string(&entry.name)));
+        output.push_str(&format!("{}  path: {}\n", indent, yaml_string(&entry.path)));
+        let kind = match entry.kind {
+            EntryKind::Dir => "dir",
+            EntryKind::File => "file",
+        };
+        output.push_str(&format!("{}  type: {}\n", indent, kind));
+        if let Some(size) = entry.size {
+            output.push_str(&format!("{}  size: {}\n", indent, size));
+        }
+        let modified = entry.modified.map(|time| {
+            chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+        });
+        output.push_str(&format!("{}  modified: {}\n", indent, modified.as_deref().map_or_else(|| "null".to_string(), yaml_string)));
+        if let Some(tokens) = entry.tokens {
+            output.push_str(&format!("{}  tokens: {}\n", indent, tokens));
+        }
+        if let Some(content) = &entry.content {
+            output.push_str(&format!("{}  content: {}\n", indent, yaml_string(content)));
+        }
+        match entry.trimmed {
+            Some(Trimmed::Omitted) => output.push_str(&format!("{}  trimmed: omitted\n", indent)),
+            Some(Trimmed::Truncated) => output.push_str(&format!("{}  trimmed: truncated\n", indent)),
+            None => {}
+        }
+        match &entry.children {
+            Some(children) if children.is_empty() => output.push_str(&format!("{}  children: []\n", indent)),
+            Some(children) => {
+                output.push_str(&format!("{}  children:\n", indent));
+                yaml_entries(children, depth + 2, output);
+            }
+            None => {}
+        }
+    }
+}
+
+/// The same tree as `render_json`, as block-style YAML.
+pub fn render_yaml(manifest: &Manifest) -> String {
+    let mut output = format!("root: {}\n", yaml_string(&manifest.root));
+    if let Some(since) = &manifest.since {
+        output.push_str(&format!("since: {}\n", yaml_string(since)));
+    }
+    if let Some(part) = manifest.part {
+        output.push_str(&format!("part:\n  index: {}\n  count: {}\n", part.index, part.count));
+    }
+    if let Some(budget) = &manifest.budget {
+        output.push_str(&format!("budget:\n  max_tokens: {}\n", budget.max_tokens));
+        for (key, paths) in [("omitted", &budget.omitted), ("truncated", &budget.truncated)] {
+            if paths.is_empty() {
+                output.push_str(&format!("  {}: []\n", key));
+                continue;
+            }
+            output.push_str(&format!("  {}:\n", key));
+            for path in paths {
+                output.push_str(&format!("    - {}\n", yaml_string(path)));
+            }
+        }
+    }
+    if manifest.entries.is_empty() {
+        output.push_str("entries: []\n");
+    } else {
+        output.push_str("entries:\n");
+        yaml_entries(&manifest.entries, 1, &mut output);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, content: Option<&str>) -> Entry {
+        Entry {
+            name: path.rsplit('/').next().unwrap().to_string(),
+            path: path.to_string(),
+            kind: EntryKind::File,
+            size: Some(6),
+            modified: None,
+            tokens: content.map(estimate_tokens),
+            content: content.map(str::to_string),
+            trimmed: None,
+            children: None,
+        }
+    }
+
+    #[test]
+    fn fits_manifests_to_token_budgets() {
+        let line = "x".repeat(39) + "\n";
+        let big = line.repeat(100);
+        let small = line.repeat(10);
+        let mut manifest = Manifest {
+            dir: PathBuf::from("."),
+            root: "app".to_string(),
+            since: None,
+            budget: None,
+            part: None,
+            hashes: BTreeMap::new(),
+            entries: vec![file("big.rs", Some(&big)), file("small.rs", Some(&small))],
+        };
+        let render = |manifest: &Manifest| Ok(render_markdown(manifest));
+        let untouched = estimate_tokens(&render_markdown(&manifest));
+
+        // Far over: the big file goes, the small one stays whole.
+        fit_budget(&mut manifest, 300, render).unwrap();
+        assert!(estimate_tokens(&render_markdown(&manifest)) <= 300);
+        let budget = manifest.budget.as_ref().unwrap();
+        assert_eq!(budget.omitted, ["big.rs"]);
+        assert!(budget.truncated.is_empty());
+        assert_eq!(manifest.entries[1].content.as_deref(), Some(small.as_str()));
+        assert!(render_markdown(&manifest).contains("_Content omitted (~1000 tokens)"));
+
+        // Slightly over: the big file is cut short instead.
+        manifest.entries[0] = file("big.rs", Some(&big));
+        fit_budget(&mut manifest, untouched - 100, render).unwrap();
+        assert!(estimate_tokens(&render_markdown(&manifest)) <= untouched - 100);
+        assert_eq!(manifest.entries[0].trimmed, Some(Trimmed::Truncated));
+        assert!(big.starts_with(manifest.entries[0].content.as_deref().unwrap()));
+
+        assert!(fit_budget(&mut manifest, 5, render).is_err());
+    }
+
+    #[test]
+    fn splits_manifests_into_parts_within_size() {
+        assert_eq!(SplitSize::parse("200000"), Ok(SplitSize::Bytes(200_000)));
+        assert_eq!(SplitSize::parse("200k"), Ok(SplitSize::Bytes(200_000)));
+        assert_eq!(SplitSize::parse("2MB"), Ok(SplitSize::Bytes(2_000_000)));
+        assert_eq!(SplitSize::parse("8000t"), Ok(SplitSize::Tokens(8000)));
+        assert_eq!(SplitSize::parse("8k tokens"), Ok(SplitSize::Tokens(8000)));
+        assert!(SplitSize::parse("0").is_err());
+        assert!(SplitSize::parse("8 pages").is_err());
+
+        let line = "x".repeat(39) + "\n";
+        let content = line.repeat(10);
+        let dir = |path: &str, children: Vec<Entry>| Entry {
+            name: path.rsplit('/').next().unwrap().to_string(),
+            path: path.to_string(),
+            kind: EntryKind::Dir,
+            size: None,
+            modified: None,
+            tokens: None,
+            content: None,
+            trimmed: None,
+            children: Some(children),
+        };
+        let manifest = Manifest {
+            dir: PathBuf::from("."),
+            root: "app".to_string(),
+            since: None,
+            budget: None,
+            part: None,
+            hashes: BTreeMap::new(),
+            entries: vec![
+                dir("a", (1..=4).map(|n| file(&format!("a/{}.rs", n), Some(&content))).collect()),
+                dir("b", vec![file("b/big.rs", Some(&content.repeat(3))), dir("b/empty", vec![])]),
+                file("c.rs", Some(&content)),
+            ],
+        };
+        let render = |manifest: &Manifest| Ok(render_markdown(manifest));
+
+        let size = SplitSize::Bytes(1000);
+        let parts = split(&manifest, size, render).unwrap();
+        assert!(parts.len() > 2);
+        let mut listed = Vec::new();
+        for (index, part) in parts.iter().enumerate() {
+            let text = render_markdown(part);
+            assert!(text.starts_with(&format!("# Directory Structure (part {} of {})", index + 1, parts.len())));
+            // Only the big file, alone in its part, may overflow.
+            assert!(text.len() <= 1000 || paths(&part.entries) == ["b", "b/big.rs"]);
+            listed.extend(paths(&part.entries));
+        }
+        // Parts repeat the directories leading to their files.
+        let mut seen = HashSet::new();
+        listed.retain(|path| seen.insert(path.clone()));
+        assert_eq!(listed, paths(&manifest.entries));
+
+        assert_eq!(split(&manifest, SplitSize::Tokens(100_000), render).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn reads_part_names_back_from_indexes() {
+        let manifest = Manifest {
+            dir: PathBuf::from("."),
+            root: "app".to_string(),
+            since: None,
+            budget: None,
+            part: None,
+            hashes: BTreeMap::new(),
+            entries: vec![file("a.rs", Some("a\n")), file("b.rs", Some("b\n"))],
+        };
+        let parts = split(&manifest, SplitSize::Bytes(1), |part| Ok(render_markdown(part))).unwrap();
+        let written: Vec<_> = parts
+            .iter()
+            .enumerate()
+            .map(|(index, part)| (format!("manifest-0{}.md", index + 1), part, render_markdown(part)))
+            .collect();
+        let index = Index::new(&manifest, SplitSize::Bytes(1), &written);
+
+        let names = ["manifest-01.md", "manifest-02.md"];
+        assert_eq!(index_parts(&index.render_markdown()).unwrap(), names);
+        assert_eq!(index_parts(&index.render_json().unwrap()).unwrap(), names);
+        assert_eq!(index_parts(&index.render_yaml()).unwrap(), names);
+        for manifest in [render_markdown(&manifest), render_json(&manifest).unwrap(), render_yaml(&manifest)] {
+            assert!(index_parts(&manifest).is_none());
+        }
+    }
+
+    #[test]
+    fn numbers_lines_in_a_gutter() {
+        let content: String = (1..=10).map(|n| if n == 5 { "\n".to_string() } else { format!("line {}\n", n) }).collect();
+        let numbered = number_lines(&content);
+        assert!(numbered.starts_with(" 1 | line 1\n 2 | line 2\n"));
+        assert!(numbered.contains("\n 5 |\n"));
+        assert!(numbered.ends_with("10 | line 10\n"));
+        assert_eq!(number_lines("a\nb"), "1 | a\n2 | b");
+        assert_eq!(number_lines(""), "");
+    }
+
+    #[test]
+    fn tags_fences_with_languages() {
+        assert_eq!(fence_language("main.rs"), Some("rust"));
+        assert_eq!(fence_language("setup.PY"), Some("python"));
+        assert_eq!(fence_language("Dockerfile"), Some("dockerfile"));
+        assert_eq!(fence_language("notes.txt"), None);
+        assert_eq!(fence_language("LICENSE"), None);
+
+        let manifest = Manifest {
+            dir: PathBuf::from("."),
+            root: "app".to_string(),
+            since: None,
+            budget: None,
+            part: None,
+            hashes: BTreeMap::new(),
+            entries: vec![file("lib.rs", Some("fn a() {}\n")), file("LICENSE", Some("MIT\n"))],
+        };
+        let markdown = render_markdown(&manifest);
+        assert!(markdown.contains("  ```rust\n    fn a() {}\n    ```\n"));
+        assert!(markdown.contains("  ```\n    MIT\n"));
+    }
+
+    fn paths(entries: &[Entry]) -> Vec<String> {
+        entries
+            .iter()
+            .flat_map(|entry| {
+                std::iter::once(entry.path.clone())
+                    .chain(paths(entry.children.as_deref().unwrap_or_default()))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn scans_trees_in_name_order() {
+        let root = std::env::temp_dir().join(format!("dev-walk-{}", std::process::id()));
+        for dir in ["b/y", "b/x", "a", "c/deep/er", "tests"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in ["b/y/2.rs", "b/x/1.rs", "b/0.py", "a/z.rs", "tests/t.rs", "top.md"] {
+            fs::write(root.join(file), file).unwrap();
+        }
+
+        let manifest = scan(&root, &WalkOptions::default()).unwrap();
+        assert_eq!(
+            paths(&manifest.entries),
+            [
+                "a", "a/z.rs", "b", "b/0.py", "b/x", "b/x/1.rs", "b/y", "b/y/2.rs", "c",
+                "c/deep", "c/deep/er", "tests", "tests/t.rs", "top.md"
+            ]
+        );
+        assert_eq!(manifest.entries[1].children.as_ref().unwrap()[0].content.as_deref(), Some("b/0.py"));
+
+        let scoped = WalkOptions {
+            include: vec!["*.rs".to_string()],
+            exclude: vec!["tests/".to_string()],
+            ..WalkOptions::default()
+        };
+        let manifest = scan(&root, &scoped).unwrap();
+        assert_eq!(
+            paths(&manifest.entries),
+            ["a", "a/z.rs", "b", "b/x", "b/x/1.rs", "b/y", "b/y/2.rs"]
+        );
+
+        let changed = WalkOptions {
+            since: Some(Since {
+                value: "main".to_string(),
+                changed: Changed::Paths(HashSet::from(["b/x/1.rs".to_string()])),
+            }),
+            ..WalkOptions::default()
+        };
+        let manifest = scan(&root, &changed).unwrap();
+        assert_eq!(paths(&manifest.entries), ["b", "b/x", "b/x/1.rs"]);
+        assert_eq!(manifest.since.as_deref(), Some("main"));
+
+        let shallow = WalkOptions {
+            max_depth: 2,
+            ..WalkOptions::default()
+        };
+        assert_eq!(paths(&scan(&root, &shallow).unwrap().entries), ["a", "b", "c", "tests", "top.md"]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn since_manifests_compare_recorded_hashes() {
+        let root = crate::testutil::unique_temp_dir("walk-since");
+        let dir = root.join("app");
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src/a.rs"), "a").unwrap();
+        fs::write(dir.join("src/b.rs"), "b").unwrap();
+
+        assert!(scan(dir.as_std_path(), &WalkOptions::default()).unwrap().hashes.is_empty());
+        let recording = WalkOptions {
+            include_content: false,
+            record_hashes: true,
+            ..WalkOptions::default()
+        };
+        let manifest = scan(dir.as_std_path(), &recording).unwrap();
+        assert_eq!(manifest.hashes.keys().collect::<Vec<_>>(), ["src/a.rs", "src/b.rs"]);
+        // The hashes stay out of the manifest itself.
+        let rendered = render_markdown(&manifest) + &render_json(&manifest).unwrap() + &render_yaml(&manifest);
+        assert!(!rendered.contains(&manifest.hashes["src/a.rs"]));
+        let written = root.join("manifest.md");
+        fs::write(&written, render_markdown(&manifest)).unwrap();
+        let hashes = hashes_path(written.as_std_path());
+        assert_eq!(hashes, root.join("manifest.md.hashes.json").as_std_path());
+        fs::write(&hashes, serde_json::to_string(&manifest.hashes).unwrap()).unwrap();
+
+        // Rewriting a file unchanged keeps it out, whatever its mtime.
+        fs::write(dir.join("src/a.rs"), "a").unwrap();
+        fs::write(dir.join("src/b.rs"), "b2").unwrap();
+        fs::write(dir.join("c.rs"), "c").unwrap();
+        for value in [written.as_std_path(), hashes.as_path()] {
+            let since = Since::resolve(dir.as_std_path(), value.to_str().unwrap()).unwrap();
+            let changed = WalkOptions {
+                since: Some(since),
+                record_hashes: true,
+                ..WalkOptions::default()
+            };
+            let manifest = scan(dir.as_std_path(), &changed).unwrap();
+            assert_eq!(paths(&manifest.entries), ["c.rs", "src", "src/b.rs"], "against {}", value.display());
+            // Files `--since` left out are still recorded for the next comparison.
+            assert_eq!(manifest.hashes.len(), 3);
+        }
+
+        fs::write(root.join("notes.md"), "# Notes\n").unwrap();
+        let error = Since::resolve(dir.as_std_path(), root.join("notes.md").as_str()).err().unwrap();
+        assert!(error.to_string().contains("--record-hashes"), "{}", error);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn since_refs_list_changes_from_git() {
+        use crate::testutil::{commit_file, git, unique_temp_dir};
+
+        let dir = unique_temp_dir("walk-since-ref");
+        git(&dir, &["init", "--quiet"]);
+        commit_file(&dir, "a.rs", "a");
+        commit_file(&dir, "b.rs", "b");
+        fs::write(dir.join("b.rs"), "b2").unwrap();
+        fs::write(dir.join("c.rs"), "c").unwrap();
+
+        let since = Since::resolve(dir.as_std_path(), "HEAD").unwrap();
+        let changed = WalkOptions {
+            since: Some(since),
+            ..WalkOptions::default()
+        };
+        assert_eq!(paths(&scan(dir.as_std_path(), &changed).unwrap().entries), ["b.rs", "c.rs"]);
+
+        let error = Since::resolve(dir.as_std_path(), "no-such-ref").err().unwrap();
+        assert!(error.to_string().contains("neither a manifest file nor a git ref"), "{}", error);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn renders_trees_as_json_and_yaml() {
+        let manifest = Manifest {
+            dir: PathBuf::from("."),
+            root: "app".to_string(),
+            since: None,
+            budget: None,
+            part: None,
+            hashes: BTreeMap::new(),
+            entries: vec![Entry {
+                name: "src".to_string(),
+                path: "src".to_string(),
+                kind: EntryKind::Dir,
+                size: None,
+                modified: Some(SystemTime::UNIX_EPOCH),
+                tokens: None,
+                content: None,
+                trimmed: None,
+                children: Some(vec![file("src/a.rs", Some("fn a()\n")), file("src/b.bin", None)]),
+            }],
+        };
+
+        let json: serde_json::Value = serde_json::from_str(&render_json(&manifest).unwrap()).unwrap();
+        let src = &json["entries"][0];
+        assert_eq!(src["type"], "dir");
+        assert_eq!(src["modified"], "1970-01-01T00:00:00Z");
+        assert_eq!(src["children"][0]["content"], "fn a()\n");
+        assert!(src["children"][1].get("content").is_none());
+
+        assert_eq!(
+            render_yaml(&manifest),
+            "root: \"app\"\nentries:\n  - name: \"src\"\n    path: \"src\"\n    type: dir\n    \
+             modified: \"1970-01-01T00:00:00Z\"\n    children:\n      - name: \"a.rs\"\n        \
+             path: \"src/a.rs\"\n        type: file\n        size: 6\n        modified: null\n        \
+             tokens: 2\n        content: \"fn a()\\n\"\n      - name: \"b.bin\"\n        path: \"src/b.bin\"\n        \
+             type: file\n        size: 6\n        modified: null\n"
+        );
+    }
 }